@@ -0,0 +1,556 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::fmt;
+use world_grid::{
+    CellCodec, EdgeMode, GridCell, GridSize, Loc, Neighborhood, Random, SnapshotError,
+    SnapshotReader, SnapshotWriter, StepStats, World, WorldGrid, WorldGridCells, WorldMetric,
+    WorldParams, WorldParamsCell,
+};
+
+const UPDATE_PHASE_NAMES: [&str; 2] = ["ants", "pheromone"];
+const NEST_RADIUS: u32 = 3;
+const NUM_FOOD_PATCHES: usize = 3;
+const FOOD_PATCH_RADIUS: u32 = 5;
+// The food an ant carries home on each trip, out of a full cell's 1.
+const FOOD_LOAD: f32 = 0.05;
+// The ways an ant can face, clockwise from north, as row and column offsets.
+const HEADINGS: [(i32, i32); 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+// How much a loaded ant's scent fades each step since it found food, so that the pheromone it
+// lays is strongest nearest the food.
+const SCENT_FADE: f32 = 0.99;
+// The fraction of a cell's pheromone that spreads to its neighbors each step.
+const PHEROMONE_DIFFUSION: f32 = 0.1;
+// Pheromone thinner than this evaporate entirely, so that cells settle back to inert.
+const MIN_PHEROMONE: f32 = 0.001;
+
+pub static PARAMS: WorldParamsCell<AntParams> = WorldParamsCell::new();
+
+// An ant colony foraging from its nest in the middle of the grid. Ants emerge from the nest
+// one per step and wander out. An ant that finds food picks some up and heads home, laying a
+// pheromone that fades the farther it gets from the food, which leads other ants to it. The
+// pheromone spreads and evaporates, so only the paths refreshed most often become trails.
+// When all the food is gone, new patches appear.
+#[derive(Clone, Debug)]
+pub struct AntWorld {
+    grid: WorldGrid<AntGridCell>,
+    rand: Option<Random>,
+    nest_locs: Vec<Loc>,
+    // Loads of food carried into the nest so far.
+    food_delivered: u64,
+}
+
+impl AntWorld {
+    pub fn new(grid_size: GridSize, rand: Random) -> Self {
+        let mut result = Self::new_empty(grid_size, rand);
+        result.add_contents();
+        result
+    }
+
+    fn new_empty(grid_size: GridSize, rand: Random) -> Self {
+        assert!(!grid_size.is_empty());
+        Self {
+            grid: WorldGrid::new(grid_size),
+            rand: Some(rand),
+            nest_locs: vec![],
+            food_delivered: 0,
+        }
+    }
+
+    fn add_contents(&mut self) {
+        self.add_nest();
+        self.add_food_patches();
+    }
+
+    fn nest_center(size: GridSize) -> Loc {
+        Loc::new(size.height / 2, size.width / 2)
+    }
+
+    fn add_nest(&mut self) {
+        let size = self.grid.size();
+        self.nest_locs = Self::disk_locs(Self::nest_center(size), NEST_RADIUS, size);
+        for loc in &self.nest_locs {
+            self.grid.cells[*loc].is_nest = true;
+        }
+        self.grid.mark_all_changed();
+    }
+
+    // Each patch is at least a quarter of the grid's smaller side from the nest.
+    fn add_food_patches(&mut self) {
+        let size = self.grid.size();
+        let nest = Self::nest_center(size);
+        let min_distance = size.width.min(size.height) as f64 / 4.0;
+        let rand = self.rand.as_mut().unwrap();
+        for _ in 0..NUM_FOOD_PATCHES {
+            let center = loop {
+                let center = Loc::new(
+                    rand.next_in_range(0..size.height),
+                    rand.next_in_range(0..size.width),
+                );
+                if center.distance(nest) >= min_distance {
+                    break center;
+                }
+            };
+            for loc in Self::disk_locs(center, FOOD_PATCH_RADIUS, size) {
+                let cell = &mut self.grid.cells[loc];
+                if !cell.is_nest {
+                    cell.food = 1.0;
+                }
+            }
+        }
+        self.grid.mark_all_changed();
+    }
+
+    // The locations within radius of center that are on the grid.
+    fn disk_locs(center: Loc, radius: u32, size: GridSize) -> Vec<Loc> {
+        let rows = center.row.saturating_sub(radius)..=(center.row + radius).min(size.height - 1);
+        rows.flat_map(|row| {
+            let cols =
+                center.col.saturating_sub(radius)..=(center.col + radius).min(size.width - 1);
+            cols.map(move |col| Loc::new(row, col))
+        })
+        .filter(|loc| loc.distance(center) <= radius as f64)
+        .collect()
+    }
+
+    // Moves each ant in turn, in random order, within next_cells, and then lets another ant
+    // emerge from the nest if the colony is short of ants.
+    fn move_ants(
+        grid: &mut WorldGrid<AntGridCell>,
+        rand: &mut Option<Random>,
+        nest_locs: &[Loc],
+        food_delivered: &mut u64,
+    ) {
+        let edge_mode = grid.edge_mode();
+        let cells = &mut grid.next_cells;
+        let width = cells.size().width;
+        let mut ant_locs: Vec<Loc> = cells
+            .cells_iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.ant.is_some())
+            .map(|(index, _)| Loc::new(index as u32 / width, index as u32 % width))
+            .collect();
+        let num_ants = ant_locs.len();
+        if let Some(rand) = rand.as_mut() {
+            rand.shuffle(&mut ant_locs);
+        }
+        // An ant that moves into a cell vacated by one that hasn't moved yet mustn't move again.
+        let mut moved = vec![false; cells.num_cells()];
+        for loc in ant_locs {
+            let index = (loc.row * width + loc.col) as usize;
+            if !moved[index] {
+                let loc = Self::move_ant(cells, loc, edge_mode, rand, food_delivered);
+                moved[(loc.row * width + loc.col) as usize] = true;
+            }
+        }
+        if num_ants < PARAMS.get().num_ants {
+            Self::add_ant(cells, nest_locs, rand);
+        }
+    }
+
+    // Returns where the ant ends up.
+    fn move_ant(
+        cells: &mut WorldGridCells<AntGridCell>,
+        loc: Loc,
+        edge_mode: EdgeMode,
+        rand: &mut Option<Random>,
+        food_delivered: &mut u64,
+    ) -> Loc {
+        let cell = &mut cells[loc];
+        let mut ant = cell.ant.take().unwrap();
+        if ant.carrying_food && cell.is_nest {
+            ant.carrying_food = false;
+            ant.turn_back();
+            *food_delivered += 1;
+        } else if !ant.carrying_food && cell.food > 0.0 {
+            cell.food = (cell.food - FOOD_LOAD).max(0.0);
+            ant.carrying_food = true;
+            ant.turn_back();
+        }
+        ant.lay_pheromone(cell);
+        let nest = Self::nest_center(cells.size());
+        let choices: Vec<(u8, Loc, f32)> = [7, 0, 1]
+            .iter()
+            .filter_map(|turn| {
+                let heading = (ant.heading + turn) % HEADINGS.len() as u8;
+                let (row_offset, col_offset) = HEADINGS[heading as usize];
+                let target = edge_mode.offset_loc(loc, row_offset, col_offset, cells.size())?;
+                let target_cell = &cells[target];
+                target_cell.ant.is_none().then(|| {
+                    (
+                        heading,
+                        target,
+                        ant.attraction(loc, target, target_cell, nest),
+                    )
+                })
+            })
+            .collect();
+        let result = match Self::choose(&choices, rand) {
+            Some((heading, target, _)) => {
+                ant.heading = heading;
+                target
+            }
+            // Blocked ahead, e.g. by other ants or the grid's edge, so turn aside and wait.
+            None => {
+                let turn = rand.as_mut().map_or(4, |rand| rand.next_in_range(2..=6));
+                ant.heading = (ant.heading + turn) % HEADINGS.len() as u8;
+                loc
+            }
+        };
+        cells[result].ant = Some(ant);
+        result
+    }
+
+    // Picks a choice at random, weighted by its weight, or the heaviest one without a random
+    // number stream.
+    fn choose(choices: &[(u8, Loc, f32)], rand: &mut Option<Random>) -> Option<(u8, Loc, f32)> {
+        let Some(rand) = rand.as_mut() else {
+            return choices.iter().copied().max_by(|a, b| a.2.total_cmp(&b.2));
+        };
+        let total: f32 = choices.iter().map(|choice| choice.2).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut pick = rand.next_in_range(0.0..total);
+        for choice in choices {
+            if pick < choice.2 {
+                return Some(*choice);
+            }
+            pick -= choice.2;
+        }
+        choices.last().copied()
+    }
+
+    fn add_ant(
+        cells: &mut WorldGridCells<AntGridCell>,
+        nest_locs: &[Loc],
+        rand: &mut Option<Random>,
+    ) {
+        let mut free_locs = nest_locs.iter().filter(|loc| cells[**loc].ant.is_none());
+        let (loc, heading) = match rand.as_mut() {
+            Some(rand) => {
+                let free_locs: Vec<&Loc> = free_locs.collect();
+                if free_locs.is_empty() {
+                    return;
+                }
+                (
+                    *free_locs[rand.next_in_range(0..free_locs.len())],
+                    rand.next_in_range(0..HEADINGS.len() as u8),
+                )
+            }
+            None => match free_locs.next() {
+                Some(loc) => (*loc, 0),
+                None => return,
+            },
+        };
+        cells[loc].ant = Some(Ant::new(heading));
+    }
+}
+
+impl World for AntWorld {
+    fn grid(&self) -> &WorldGrid<impl GridCell> {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut WorldGrid<impl GridCell> {
+        &mut self.grid
+    }
+
+    fn update(&mut self) {
+        self.update_phase(0);
+        self.update_phase(1);
+    }
+
+    fn reset(&mut self) {
+        self.grid.clear();
+        self.food_delivered = 0;
+        self.add_contents();
+    }
+
+    fn metrics(&self) -> Vec<WorldMetric> {
+        let ants = self.grid.cells_iter().filter_map(|cell| cell.ant);
+        let carrying = ants.clone().filter(|ant| ant.carrying_food).count();
+        let food: f64 = self.grid.cells_iter().map(|cell| cell.food as f64).sum();
+        vec![
+            WorldMetric::new("ants", ants.count() as f64),
+            WorldMetric::new("carrying_food", carrying as f64),
+            WorldMetric::new("food_delivered", self.food_delivered as f64),
+            WorldMetric::new("food_left", food),
+        ]
+    }
+
+    // The food is the ants' substance (see AntGridCell::substance_amount).
+    fn step_stats(&self) -> StepStats {
+        StepStats::new(self.generation(), &self.grid)
+            .with_layer_total("food", self.grid.substance_mass())
+    }
+
+    fn update_phase_names(&self) -> &'static [&'static str] {
+        &UPDATE_PHASE_NAMES
+    }
+
+    // The ants move one at a time, so that no two land in the same cell, and then the
+    // pheromone they laid spreads and evaporates in an ordinary grid update.
+    fn update_phase(&mut self, phase: usize) {
+        match phase {
+            0 => {
+                let mut ant_rand = self.rand.as_mut().map(|rand| rand.fork());
+                let nest_locs = &self.nest_locs;
+                let food_delivered = &mut self.food_delivered;
+                self.grid.update_with(
+                    &mut self.rand,
+                    |grid| Self::move_ants(grid, &mut ant_rand, nest_locs, food_delivered),
+                    |_cell, _neighborhood, _next_cell, _rand| {},
+                );
+                if self.grid.cells_iter().all(|cell| cell.food == 0.0) {
+                    self.add_food_patches();
+                }
+            }
+            _ => self.grid.update(&mut self.rand, |_grid| {}),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Ant {
+    // An index into HEADINGS.
+    heading: u8,
+    carrying_food: bool,
+    // From 1 when the ant last found food, fading by SCENT_FADE each step.
+    scent: f32,
+}
+
+impl Ant {
+    fn new(heading: u8) -> Self {
+        Self {
+            heading,
+            carrying_food: false,
+            scent: 1.0,
+        }
+    }
+
+    fn turn_back(&mut self) {
+        self.heading = (self.heading + 4) % HEADINGS.len() as u8;
+        self.scent = 1.0;
+    }
+
+    // Only loaded ants lay pheromone, marking the way back to the food.
+    fn lay_pheromone(&mut self, cell: &mut AntGridCell) {
+        if self.carrying_food {
+            cell.pheromone = cell.pheromone.max(self.scent);
+        }
+        self.scent *= SCENT_FADE;
+    }
+
+    // How strongly the ant is drawn to step from one cell into another. A loaded ant heads
+    // straight home, keeping track of where the nest is as real ants do, while a searching
+    // one follows the pheromone, and most of all the food itself. Every step draws a little,
+    // so that ants also wander.
+    fn attraction(&self, from: Loc, to: Loc, to_cell: &AntGridCell, nest: Loc) -> f32 {
+        let pull = if self.carrying_food {
+            (from.distance(nest) - to.distance(nest)).max(0.0) as f32
+        } else if to_cell.food > 0.0 {
+            1.0
+        } else {
+            to_cell.pheromone
+        };
+        let result = PARAMS.get().wander + pull;
+        result * result
+    }
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_u8(self.heading);
+        writer.write_bool(self.carrying_food);
+        writer.write_f32(self.scent);
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        let heading = reader.read_u8()?;
+        if heading as usize >= HEADINGS.len() {
+            return Err(SnapshotError::InvalidValue);
+        }
+        Ok(Self {
+            heading,
+            carrying_food: reader.read_bool()?,
+            scent: reader.read_fraction()?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AntGridCell {
+    pub is_nest: bool,
+    // From 0 to 1.
+    pub food: f32,
+    // Laid by ants carrying food, leading to it. From 0 to 1.
+    pub pheromone: f32,
+    pub ant: Option<Ant>,
+}
+
+impl AntGridCell {
+    fn diffused(amount: f32, neighbor_mean: f32) -> f32 {
+        let result = (1.0 - PARAMS.get().evaporation)
+            * ((1.0 - PHEROMONE_DIFFUSION) * amount + PHEROMONE_DIFFUSION * neighbor_mean);
+        if result < MIN_PHEROMONE { 0.0 } else { result }
+    }
+
+    fn blend(color: [u8; 3], tint: [u8; 3], amount: f32) -> [u8; 3] {
+        let amount = amount.clamp(0.0, 1.0);
+        [0, 1, 2].map(|i| (color[i] as f32 + (tint[i] as f32 - color[i] as f32) * amount) as u8)
+    }
+}
+
+impl CellCodec for AntGridCell {
+    const FORMAT_NAME: &'static str = "ant-colony";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_bool(self.is_nest);
+        writer.write_f32(self.food);
+        writer.write_f32(self.pheromone);
+        writer.write_option(&self.ant, |writer, ant| ant.encode(writer));
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        Ok(Self {
+            is_nest: reader.read_bool()?,
+            food: reader.read_fraction()?,
+            pheromone: reader.read_fraction()?,
+            ant: reader.read_option(Ant::decode)?,
+        })
+    }
+}
+
+impl GridCell for AntGridCell {
+    fn color_rgba(&self) -> [u8; 4] {
+        let params = PARAMS.get();
+        let color = match self.ant {
+            Some(ant) if ant.carrying_food => params.loaded_ant_color,
+            Some(_) => params.ant_color,
+            None => {
+                let ground = if self.is_nest {
+                    params.nest_color
+                } else {
+                    Self::blend(params.ground_color, params.food_color, self.food)
+                };
+                Self::blend(ground, params.trail_color, self.pheromone)
+            }
+        };
+        [color[0], color[1], color[2], 0xff]
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    // Spreads and evaporates the pheromone. The ants move in a phase of their own (see
+    // AntWorld::update_phase).
+    fn update(
+        &self,
+        neighborhood: &Neighborhood<AntGridCell>,
+        next_cell: &mut AntGridCell,
+        _rand: &mut Option<Random>,
+    ) {
+        let (mut pheromone, mut count) = (0.0, 0);
+        neighborhood.for_neighbor_cells(|neighbor| {
+            pheromone += neighbor.pheromone;
+            count += 1;
+        });
+        next_cell.pheromone = Self::diffused(self.pheromone, pheromone / count.max(1) as f32);
+    }
+
+    fn debug_print(&self, _row: u32, _col: u32) {}
+
+    fn is_inert(&self) -> bool {
+        self.ant.is_none() && self.pheromone == 0.0
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["nest", "food", "pheromone", "carrying_food"]
+    }
+
+    fn field_value(&self, field: usize) -> f32 {
+        match field {
+            0 => self.is_nest as u8 as f32,
+            1 => self.food,
+            2 => self.pheromone,
+            _ => self.ant.map_or(-1.0, |ant| ant.carrying_food as u8 as f32),
+        }
+    }
+
+    fn field_tolerance(field: usize) -> f32 {
+        match field {
+            1 | 2 => 1e-4,
+            _ => 0.0,
+        }
+    }
+
+    fn substance_amount(&self) -> f32 {
+        self.food
+    }
+
+    fn remove_creature(&mut self) {
+        self.ant = None;
+    }
+}
+
+#[derive(Debug)]
+pub struct AntParams {
+    // How many ants the colony grows to.
+    num_ants: usize,
+    // The attraction of every way ahead, pheromone or not. Higher makes ants wander more.
+    wander: f32,
+    // The fraction of the pheromone that evaporates each step.
+    evaporation: f32,
+    ground_color: [u8; 3],
+    nest_color: [u8; 3],
+    food_color: [u8; 3],
+    trail_color: [u8; 3],
+    ant_color: [u8; 3],
+    loaded_ant_color: [u8; 3],
+}
+
+impl Default for AntParams {
+    fn default() -> Self {
+        Self {
+            num_ants: 400,
+            wander: 0.05,
+            evaporation: 0.004,
+            ground_color: [0x30, 0x24, 0x18],
+            nest_color: [0x80, 0x30, 0x20],
+            food_color: [0x30, 0xc0, 0x30],
+            trail_color: [0xe0, 0xc0, 0x40],
+            ant_color: [0xf0, 0xf0, 0xf0],
+            loaded_ant_color: [0x80, 0xff, 0x80],
+        }
+    }
+}
+
+impl WorldParams for AntParams {
+    fn random(rand: &mut Random) -> Self {
+        Self {
+            num_ants: rand.next_in_range(100..=800),
+            wander: rand.next_in_range(0.01..=0.2),
+            evaporation: rand.next_in_range(0.003..=0.03),
+            ..Self::default()
+        }
+    }
+}
+
+impl fmt::Display for AntParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "num_ants={} wander={:.3} evaporation={:.3}",
+            self.num_ants, self.wander, self.evaporation
+        )
+    }
+}
@@ -0,0 +1,13 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use ant_colony_pixels::AntWorld;
+use pixels_main_support::{WorldApp, world_params};
+
+fn main() {
+    WorldApp::new(AntWorld::new)
+        .with_time_step_frames(2)
+        .with_cell_pixel_width(3)
+        .with_params(|rand| ant_colony_pixels::PARAMS.init(|| world_params(rand)))
+        .run();
+}
@@ -0,0 +1,349 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::fmt;
+use world_grid::{
+    CellCodec, GridCell, GridSize, Neighborhood, Random, SnapshotError, SnapshotReader,
+    SnapshotWriter, World, WorldGrid, WorldMetric, WorldParams, WorldParamsCell,
+};
+
+// Life-like and Generations rules that make interesting patterns from random soup, for
+// surprise mode.
+const SURPRISE_RULES: [&str; 8] = [
+    "B3/S23",
+    "B36/S23",
+    "B3678/S34678",
+    "B34/S34",
+    "B368/S245",
+    "B3/S238",
+    "B2/S",
+    "B2/S/C3",
+];
+
+// The rules that --preset can name (see ConwayParams::preset).
+pub const PRESET_NAMES: [&str; 3] = ["life", "seeds", "brians-brain"];
+
+// Steps that new worlds take to let their random soup settle.
+const SETTLING_STEPS: u64 = 5;
+
+pub static PARAMS: WorldParamsCell<ConwayParams> = WorldParamsCell::new();
+
+#[derive(Clone, Debug)]
+pub struct ConwayWorld {
+    grid: WorldGrid<ConwayGridCell>,
+    rand: Option<Random>,
+}
+
+impl ConwayWorld {
+    pub fn new(grid_size: GridSize, rand: Random) -> Self {
+        let mut result = Self::new_empty(grid_size, rand);
+        result.add_random_life();
+        result.fast_forward(SETTLING_STEPS);
+        result
+    }
+
+    fn new_empty(grid_size: GridSize, rand: Random) -> Self {
+        assert!(!grid_size.is_empty());
+        let mut grid = WorldGrid::new(grid_size);
+        grid.set_skip_quiescent_tiles(true);
+        Self {
+            grid,
+            rand: Some(rand),
+        }
+    }
+
+    fn add_random_life(&mut self) {
+        for cell in self.grid.cells.cells_iter_mut() {
+            if let Some(rand) = self.rand.as_mut()
+                && rand.next_bool(PARAMS.get().density)
+            {
+                cell.alive = true;
+            }
+        }
+    }
+}
+
+impl World for ConwayWorld {
+    fn grid(&self) -> &WorldGrid<impl GridCell> {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut WorldGrid<impl GridCell> {
+        &mut self.grid
+    }
+
+    fn update(&mut self) {
+        self.grid.update(&mut self.rand, |_grid| {});
+    }
+
+    fn reset(&mut self) {
+        self.grid.clear();
+        self.add_random_life();
+        self.fast_forward(SETTLING_STEPS);
+    }
+
+    fn metrics(&self) -> Vec<WorldMetric> {
+        let population = self.grid.cells_iter().filter(|cell| cell.alive).count();
+        let mut result = vec![WorldMetric::new("population", population as f64)];
+        if PARAMS.get().num_states > 2 {
+            let dying = self.grid.cells_iter().filter(|cell| cell.dying > 0).count();
+            result.push(WorldMetric::new("dying", dying as f64));
+        }
+        result
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConwayGridCell {
+    pub alive: bool,
+    // Steps left in the dying states that follow life under Generations rules (see
+    // ConwayParams::num_states), during which a cell is neither live nor can be born.
+    pub dying: u8,
+}
+
+impl ConwayGridCell {
+    fn num_live_neighbors(neighborhood: &Neighborhood<ConwayGridCell>) -> u32 {
+        neighborhood
+            .neighbors()
+            .filter(|neighbor| neighbor.alive)
+            .count() as u32
+    }
+}
+
+impl CellCodec for ConwayGridCell {
+    const FORMAT_NAME: &'static str = "conway";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_bool(self.alive);
+        writer.write_u8(self.dying);
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        Ok(Self {
+            alive: reader.read_bool()?,
+            dying: reader.read_u8()?,
+        })
+    }
+}
+
+impl GridCell for ConwayGridCell {
+    fn color_rgba(&self) -> [u8; 4] {
+        if self.alive {
+            PARAMS.get().alive_color
+        } else if self.dying > 0 {
+            PARAMS.get().dying_color
+        } else {
+            PARAMS.get().dead_color
+        }
+    }
+
+    fn clear(&mut self) {
+        self.alive = false;
+        self.dying = 0;
+    }
+
+    fn update(
+        &self,
+        neighborhood: &Neighborhood<ConwayGridCell>,
+        next_cell: &mut ConwayGridCell,
+        _rand: &mut Option<Random>,
+    ) {
+        if self.dying > 0 {
+            next_cell.dying = self.dying - 1;
+            return;
+        }
+        let neighbors = Self::num_live_neighbors(neighborhood) as usize;
+        if !self.alive {
+            next_cell.alive = PARAMS.get().birth_counts[neighbors];
+        } else if !PARAMS.get().survival_counts[neighbors] {
+            next_cell.alive = false;
+            next_cell.dying = PARAMS.get().num_states - 2;
+        }
+    }
+
+    fn debug_print(&self, _row: u32, _col: u32) {}
+
+    fn is_inert(&self) -> bool {
+        !self.alive && self.dying == 0
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["alive", "dying"]
+    }
+
+    fn field_value(&self, field: usize) -> f32 {
+        match field {
+            0 => self.alive as u8 as f32,
+            _ => self.dying as f32,
+        }
+    }
+
+    fn genome(&self) -> Option<u64> {
+        self.alive.then_some(0)
+    }
+
+    fn set_genome(&mut self, _genome: u64) {
+        self.alive = true;
+        self.dying = 0;
+    }
+
+    fn remove_creature(&mut self) {
+        self.alive = false;
+    }
+
+    // Numbered as in Golly's Generations rules: 0 for dead, 1 for live, and 2 on up for the
+    // dying states, in order.
+    fn num_pattern_states() -> u8 {
+        PARAMS.get().num_states
+    }
+
+    fn pattern_state(&self) -> u8 {
+        match (self.alive, self.dying) {
+            (true, _) => 1,
+            (false, 0) => 0,
+            (false, dying) => PARAMS.get().num_states.saturating_sub(dying),
+        }
+    }
+
+    // States beyond the rule's are read as dead.
+    fn set_pattern_state(&mut self, state: u8, _genome: u64) {
+        let num_states = PARAMS.get().num_states;
+        self.alive = state == 1;
+        self.dying = if (2..num_states).contains(&state) {
+            num_states - state
+        } else {
+            0
+        };
+    }
+}
+
+#[derive(Debug)]
+pub struct ConwayParams {
+    rule: &'static str,
+    // Indexed by number of live neighbors.
+    birth_counts: [bool; 9],
+    survival_counts: [bool; 9],
+    // Counting dead, live, and the dying states between them, as in Generations rules. Life-like
+    // rules have just 2.
+    num_states: u8,
+    density: f64,
+    alive_color: [u8; 4],
+    dying_color: [u8; 4],
+    dead_color: [u8; 4],
+}
+
+impl ConwayParams {
+    // Rules are Life-like, e.g. "B3/S23", or Generations rules with a state count, e.g.
+    // "B2/S/C3".
+    fn new(
+        rule: &'static str,
+        density: f64,
+        alive_color: [u8; 4],
+        dying_color: [u8; 4],
+        dead_color: [u8; 4],
+    ) -> Self {
+        let mut parts = rule.split('/');
+        let birth = parts.next().unwrap().strip_prefix('B').unwrap();
+        let survival = parts.next().unwrap().strip_prefix('S').unwrap();
+        let num_states = parts.next().map_or(2, |states| {
+            states.strip_prefix('C').unwrap().parse().unwrap()
+        });
+        Self {
+            rule,
+            birth_counts: Self::neighbor_counts(birth),
+            survival_counts: Self::neighbor_counts(survival),
+            num_states,
+            density,
+            alive_color,
+            dying_color,
+            dead_color,
+        }
+    }
+
+    // A classic rule with its own palette, by name (see PRESET_NAMES).
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "life" => Some(Self::default()),
+            // Every live cell dies at once, and two live neighbors give birth, so nearly any
+            // start explodes.
+            "seeds" => Some(Self::new(
+                "B2/S",
+                0.02,
+                [0xff, 0xa0, 0x20, 0xff],
+                [0xff, 0xa0, 0x20, 0xff],
+                [0x10, 0x08, 0x00, 0xff],
+            )),
+            // Like seeds, but a cell spends a step dying before it can be born again, which
+            // sends spaceships streaming in all directions.
+            "brians-brain" => Some(Self::new(
+                "B2/S/C3",
+                0.2,
+                [0xe0, 0xf0, 0xff, 0xff],
+                [0x20, 0x60, 0xe0, 0xff],
+                [0x00, 0x00, 0x10, 0xff],
+            )),
+            _ => None,
+        }
+    }
+
+    // Parses e.g. "23" from rule "B3/S23".
+    fn neighbor_counts(digits: &str) -> [bool; 9] {
+        let mut result = [false; 9];
+        for digit in digits.chars() {
+            result[digit.to_digit(10).unwrap() as usize] = true;
+        }
+        result
+    }
+}
+
+impl Default for ConwayParams {
+    fn default() -> Self {
+        Self::new(
+            "B3/S23",
+            0.3,
+            [0x80, 0x80, 0x80, 0xff],
+            [0x40, 0x40, 0x60, 0xff],
+            [0x00, 0x00, 0x40, 0xff],
+        )
+    }
+}
+
+impl WorldParams for ConwayParams {
+    fn random(rand: &mut Random) -> Self {
+        let rule = SURPRISE_RULES[rand.next_in_range(0..SURPRISE_RULES.len())];
+        let density = rand.next_in_range(0.1..0.5);
+        let alive_color = [
+            0xff,
+            rand.next_in_range(0x40..=0xff),
+            rand.next_in_range(0..0x80),
+        ];
+        let [red, green, blue] = rand.shuffle_color_rgb(alive_color);
+        let dark = rand.next_in_range(0..0x40);
+        Self::new(
+            rule,
+            density,
+            [red, green, blue, 0xff],
+            [red / 2, green / 2, blue / 2, 0xff],
+            [dark / 2, dark / 2, dark, 0xff],
+        )
+    }
+}
+
+impl fmt::Display for ConwayParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [alive_red, alive_green, alive_blue, _] = self.alive_color;
+        let [dead_red, dead_green, dead_blue, _] = self.dead_color;
+        write!(
+            f,
+            "rule={} density={:.2} alive_color=#{:02x}{:02x}{:02x} dead_color=#{:02x}{:02x}{:02x}",
+            self.rule,
+            self.density,
+            alive_red,
+            alive_green,
+            alive_blue,
+            dead_red,
+            dead_green,
+            dead_blue
+        )
+    }
+}
@@ -1,119 +1,23 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
-use pixels_main_support::{animate, window_size_to_grid_size};
-use world_grid::{GridCell, GridSize, Neighborhood, Random, World, WorldGrid};
-
-const TIME_STEP_FRAMES: u32 = 4;
-const CELL_PIXEL_WIDTH: u32 = 4;
+use conway_life_pixels::{ConwayParams, ConwayWorld, PRESET_NAMES};
+use pixels_main_support::{AppOptions, WorldApp, world_params};
 
+// Runs the rule named by --preset, if any, else the params' rule.
 fn main() {
-    animate(TIME_STEP_FRAMES, |window_size| {
-        ConwayWorld::new(
-            window_size_to_grid_size(window_size, CELL_PIXEL_WIDTH),
-            Random::new(),
-        )
-    });
-}
-
-#[derive(Debug)]
-pub struct ConwayWorld {
-    grid: WorldGrid<ConwayGridCell>,
-    rand: Option<Random>,
-}
-
-impl ConwayWorld {
-    pub fn new(grid_size: GridSize, rand: Random) -> Self {
-        let mut result = Self::new_empty(grid_size, rand);
-        result.add_random_life();
-        result.skip_initial_steps();
-        result
-    }
-
-    fn new_empty(grid_size: GridSize, rand: Random) -> Self {
-        assert!(!grid_size.is_empty());
-        Self {
-            grid: WorldGrid::new(grid_size),
-            rand: Some(rand),
-        }
-    }
-
-    fn add_random_life(&mut self) {
-        for cell in self.grid.cells.cells_iter_mut() {
-            if let Some(rand) = self.rand.as_mut()
-                && rand.next_bool(0.3)
-            {
-                cell.alive = true;
-            }
-        }
-    }
-
-    fn skip_initial_steps(&mut self) {
-        for _ in 0..5 {
-            self.update();
-        }
-    }
-}
-
-impl World for ConwayWorld {
-    fn grid(&self) -> &WorldGrid<impl GridCell> {
-        &self.grid
-    }
-
-    fn update(&mut self) {
-        self.grid.update(&mut self.rand, |_grid| {});
-    }
-
-    fn reset(&mut self) {
-        self.grid.clear();
-        self.add_random_life();
-        self.skip_initial_steps();
-    }
-}
-
-#[derive(Clone, Copy, Debug, Default)]
-pub struct ConwayGridCell {
-    pub alive: bool,
-}
-
-impl ConwayGridCell {
-    fn num_live_neighbors(neighborhood: &Neighborhood<ConwayGridCell>) -> u32 {
-        let mut result = 0;
-        neighborhood.for_neighbor_cells(|neighbor| {
-            if neighbor.alive {
-                result += 1;
-            }
-        });
-        result
-    }
-}
-
-impl GridCell for ConwayGridCell {
-    fn color_rgba(&self) -> [u8; 4] {
-        if self.alive {
-            [0x80, 0x80, 0x80, 0xff]
-        } else {
-            [0x00, 0x00, 0x40, 0xff]
-        }
-    }
-
-    fn clear(&mut self) {
-        self.alive = false;
-    }
-
-    fn update(
-        &self,
-        neighborhood: &Neighborhood<ConwayGridCell>,
-        next_cell: &mut ConwayGridCell,
-        _rand: &mut Option<Random>,
-    ) {
-        let neighbors = Self::num_live_neighbors(neighborhood);
-        next_cell.alive = if self.alive {
-            (2..=3).contains(&neighbors)
-        } else {
-            neighbors == 3
-        };
-    }
-
-    fn debug_print(&self, _row: u32, _col: u32) {}
+    WorldApp::new(ConwayWorld::new)
+        .with_params(|rand| {
+            conway_life_pixels::PARAMS.init(|| match AppOptions::from_args().preset {
+                Some(name) => ConwayParams::preset(&name).unwrap_or_else(|| {
+                    panic!(
+                        "Unknown preset {}; choose from {}",
+                        name,
+                        PRESET_NAMES.join(", ")
+                    )
+                }),
+                None => world_params(rand),
+            })
+        })
+        .run();
 }
@@ -0,0 +1,650 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use arrayvec::ArrayVec;
+use std::fmt::{self, Debug};
+use world_grid::{
+    BitSet8, BitSet8Gene, CellCodec, GeneDescriptor, GeneFormat, GridCell, GridSize, Loc,
+    Neighborhood, Random, SnapshotError, SnapshotReader, SnapshotWriter, World, WorldGrid,
+    WorldMetric, WorldParams, WorldParamsCell,
+};
+
+const EMPTY_CELL_COLOR: [u8; 4] = [0, 0, 0, 0xff];
+const MAX_GENE_SLOTS: usize = 6;
+// Each gene slot packs into genome() as its 8 bits plus a role bit, with the number of slots
+// in the top bits.
+const GENE_SLOT_BITS: u32 = 9;
+const GENE_SLOT_COUNT_SHIFT: u32 = 60;
+
+pub static PARAMS: WorldParamsCell<EvoConwayParams> = WorldParamsCell::new();
+
+#[derive(Clone, Debug)]
+pub struct EvoConwayWorld {
+    grid: WorldGrid<EvoConwayGridCell>,
+    rand: Option<Random>,
+    conway_steps: usize,
+    // Whether steps are Moran-process steps instead of Conway steps (see with_moran_process).
+    moran_process: bool,
+}
+
+impl EvoConwayWorld {
+    pub fn new(grid_size: GridSize, rand: Random) -> Self {
+        let mut result = Self::new_empty(grid_size, rand);
+        result.add_random_life();
+        result
+    }
+
+    fn new_empty(grid_size: GridSize, rand: Random) -> Self {
+        assert!(!grid_size.is_empty());
+        Self {
+            grid: WorldGrid::new(grid_size),
+            rand: Some(rand),
+            conway_steps: PARAMS.get().conway_steps,
+            moran_process: false,
+        }
+    }
+
+    // Holds the population constant, as in the Moran process of evolutionary dynamics: each
+    // step, one random creature dies and a random pair of its neighbors reproduces into its
+    // cell, in place of the Conway rules.
+    pub fn with_moran_process(mut self) -> Self {
+        self.moran_process = true;
+        self
+    }
+
+    fn moran_update(&mut self) {
+        let birth = Self::moran_birth(&self.grid, &mut self.rand);
+        self.grid.update_with(
+            &mut None,
+            |grid| {
+                if let Some((loc, genome)) = birth {
+                    grid.next_cells[loc].creature = Some(Creature::new(genome));
+                }
+            },
+            |_cell, _neighborhood, _next_cell, _rand| {},
+        );
+    }
+
+    // The cell of the creature that dies and the genome of the child that replaces it. The
+    // parents are those of the cell's neighbors whose repro genes match its neighbor count,
+    // or else any of its neighbors, or else, for a lone creature, any creatures.
+    fn moran_birth(
+        grid: &WorldGrid<EvoConwayGridCell>,
+        rand: &mut Option<Random>,
+    ) -> Option<(Loc, Genome)> {
+        let width = grid.size().width;
+        let creatures: Vec<(Loc, Genome)> = grid
+            .cells_iter()
+            .enumerate()
+            .filter_map(|(index, cell)| {
+                let loc = Loc::new(index as u32 / width, index as u32 % width);
+                cell.creature.map(|creature| (loc, creature.genome))
+            })
+            .collect();
+        if creatures.is_empty() {
+            return None;
+        }
+        let (loc, _) = creatures[Self::random_index(creatures.len(), rand)];
+        let neighborhood = grid.neighborhood(loc);
+        let num_neighbors = EvoConwayGridCell::num_neighbor_creatures(&neighborhood);
+        let mut neighbors = ArrayVec::<Creature, 8>::new();
+        neighborhood.for_neighbor_cells(|neighbor| {
+            if let Some(creature) = neighbor.creature {
+                neighbors.push(creature);
+            }
+        });
+        let mut candidates: Vec<Genome> = neighbors
+            .iter()
+            .filter(|creature| creature.can_reproduce(num_neighbors, rand))
+            .map(|creature| creature.genome)
+            .collect();
+        if candidates.is_empty() {
+            candidates = neighbors.iter().map(|creature| creature.genome).collect();
+        }
+        if candidates.is_empty() {
+            candidates = creatures.iter().map(|(_, genome)| *genome).collect();
+        }
+        let mut parents = ArrayVec::<Genome, 8>::new();
+        parents.push(candidates.swap_remove(Self::random_index(candidates.len(), rand)));
+        if !candidates.is_empty() {
+            parents.push(candidates[Self::random_index(candidates.len(), rand)]);
+        }
+        Some((loc, Genome::merge(&parents, rand)))
+    }
+
+    // Without a random number stream, always the first.
+    fn random_index(len: usize, rand: &mut Option<Random>) -> usize {
+        rand.as_mut().map_or(0, |rand| rand.next_in_range(0..len))
+    }
+
+    fn add_random_life(&mut self) {
+        for cell in self.grid.cells.cells_iter_mut() {
+            if let Some(rand) = self.rand.as_mut()
+                && rand.next_bool(PARAMS.get().density)
+            {
+                cell.creature = Some(Creature::conway());
+            }
+        }
+    }
+}
+
+impl World for EvoConwayWorld {
+    fn grid(&self) -> &WorldGrid<impl GridCell> {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut WorldGrid<impl GridCell> {
+        &mut self.grid
+    }
+
+    fn update(&mut self) {
+        if self.moran_process {
+            self.moran_update();
+        } else if self.conway_steps > 0 {
+            self.conway_steps -= 1;
+            self.grid.update(&mut None, |_grid| {});
+        } else {
+            self.grid.update(&mut self.rand, |_grid| {});
+        };
+    }
+
+    fn reset(&mut self) {
+        self.grid.clear();
+        self.add_random_life();
+    }
+
+    fn metrics(&self) -> Vec<WorldMetric> {
+        let mut population = 0;
+        let mut gene_slots = 0;
+        for creature in self.grid.cells_iter().filter_map(|cell| cell.creature) {
+            population += 1;
+            gene_slots += creature.genome.slots().len();
+        }
+        vec![
+            WorldMetric::new("population", population as f64),
+            WorldMetric::new(
+                "mean_gene_slots",
+                gene_slots as f64 / population.max(1) as f64,
+            ),
+        ]
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EvoConwayGridCell {
+    creature: Option<Creature>,
+}
+
+impl EvoConwayGridCell {
+    fn num_neighbor_creatures(neighborhood: &Neighborhood<EvoConwayGridCell>) -> usize {
+        let mut result = 0;
+        neighborhood.for_neighbor_cells(|neighbor| {
+            if neighbor.creature.is_some() {
+                result += 1;
+            }
+        });
+        result
+    }
+
+    fn format_neighbor_count_gene(neighbor_counts: BitSet8) -> String {
+        GeneFormat::NeighborCounts.format(neighbor_counts.bits)
+    }
+}
+
+impl CellCodec for EvoConwayGridCell {
+    const FORMAT_NAME: &'static str = "evo-conway";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_option(&self.creature, |writer, creature| {
+            let slots = creature.genome.slots();
+            writer.write_u8(slots.len() as u8);
+            for slot in slots {
+                writer.write_bool(slot.role == GeneRole::Repro);
+                writer.write_u8(slot.gene.value.bits);
+            }
+        });
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        let creature = reader.read_option(|reader| {
+            let len = reader.read_u8()? as usize;
+            if !(1..=MAX_GENE_SLOTS).contains(&len) {
+                return Err(SnapshotError::InvalidValue);
+            }
+            let mut slots = ArrayVec::<GeneSlot, MAX_GENE_SLOTS>::new();
+            for _ in 0..len {
+                let role = if reader.read_bool()? {
+                    GeneRole::Repro
+                } else {
+                    GeneRole::Survival
+                };
+                slots.push(GeneSlot::new(role, reader.read_u8()?));
+            }
+            Ok(Creature::new(Genome::new(&slots)))
+        })?;
+        Ok(Self { creature })
+    }
+}
+
+impl GridCell for EvoConwayGridCell {
+    fn color_rgba(&self) -> [u8; 4] {
+        if let Some(creature) = self.creature {
+            creature.color_rgba()
+        } else {
+            EMPTY_CELL_COLOR
+        }
+    }
+
+    fn clear(&mut self) {
+        self.creature = None;
+    }
+
+    fn update(
+        &self,
+        neighborhood: &Neighborhood<EvoConwayGridCell>,
+        next_cell: &mut EvoConwayGridCell,
+        rand: &mut Option<Random>,
+    ) {
+        let num_neighbors = Self::num_neighbor_creatures(neighborhood);
+        if let Some(creature) = self.creature {
+            if !creature.survives(num_neighbors, rand) {
+                next_cell.creature = None;
+            }
+        } else {
+            next_cell.creature = Creature::maybe_reproduce(neighborhood, num_neighbors, rand);
+        };
+    }
+
+    fn debug_print(&self, row: u32, col: u32) {
+        if let Some(creature) = self.creature {
+            let color = self.color_rgba();
+            let slots: Vec<_> = creature
+                .genome
+                .slots()
+                .iter()
+                .map(|slot| {
+                    format!(
+                        "{:?}{}",
+                        slot.role,
+                        Self::format_neighbor_count_gene(slot.gene.value)
+                    )
+                })
+                .collect();
+            println!(
+                "({}, {}): Survival: {}, Repro: {}, Slots: {}, Color: [0x{:X},0x{:X},0x{:X}]",
+                row,
+                col,
+                Self::format_neighbor_count_gene(creature.survival_counts()),
+                Self::format_neighbor_count_gene(creature.repro_counts()),
+                slots.join(" "),
+                color[0],
+                color[1],
+                color[2]
+            );
+        } else {
+            println!("({}, {}): No creature", row, col);
+        }
+    }
+
+    fn is_inert(&self) -> bool {
+        self.creature.is_none()
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["occupied", "survival_gene", "repro_gene", "gene_slots"]
+    }
+
+    fn field_value(&self, field: usize) -> f32 {
+        match (field, self.creature) {
+            (0, _) => self.creature.is_some() as u8 as f32,
+            (1, Some(creature)) => creature.survival_counts().bits as f32,
+            (2, Some(creature)) => creature.repro_counts().bits as f32,
+            (3, Some(creature)) => creature.genome.slots().len() as f32,
+            _ => -1.0,
+        }
+    }
+
+    fn genome(&self) -> Option<u64> {
+        self.creature.map(|creature| creature.genome.packed())
+    }
+
+    fn set_genome(&mut self, genome: u64) {
+        self.creature = Some(Creature::new(Genome::unpacked(genome)));
+    }
+
+    fn remove_creature(&mut self) {
+        self.creature = None;
+    }
+
+    fn genes() -> &'static [GeneDescriptor] {
+        const fn slot(name: &'static str, index: u32) -> GeneDescriptor {
+            GeneDescriptor::new(name, index * GENE_SLOT_BITS, GeneFormat::NeighborCounts)
+        }
+        const GENES: [GeneDescriptor; 6] = [
+            slot("gene_slot_0", 0),
+            slot("gene_slot_1", 1),
+            slot("gene_slot_2", 2),
+            slot("gene_slot_3", 3),
+            slot("gene_slot_4", 4),
+            slot("gene_slot_5", 5),
+        ];
+        &GENES
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum GeneRole {
+    // bits[n] == 1 means will survive if own cell has n-1 neighbor creatures
+    #[default]
+    Survival,
+    // bits[n] == 1 means will reproduce if target cell has n-1 neighbor creatures
+    Repro,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct GeneSlot {
+    role: GeneRole,
+    gene: BitSet8Gene,
+}
+
+impl GeneSlot {
+    fn new(role: GeneRole, bits: u8) -> Self {
+        Self {
+            role,
+            gene: BitSet8Gene::new(BitSet8::new(bits)),
+        }
+    }
+}
+
+// A variable number of gene slots, each playing a role. A creature's neighbor counts for a
+// role are the union of those of all its slots in that role, so duplicated slots are
+// redundant copies that can diverge. Fixed capacity, so that cells stay Copy.
+#[derive(Clone, Copy, Debug, Default)]
+struct Genome {
+    slots: [GeneSlot; MAX_GENE_SLOTS],
+    len: usize,
+}
+
+impl Genome {
+    fn new(slots: &[GeneSlot]) -> Self {
+        let mut result = Self::default();
+        result.slots[..slots.len()].copy_from_slice(slots);
+        result.len = slots.len();
+        result
+    }
+
+    fn slots(&self) -> &[GeneSlot] {
+        &self.slots[..self.len]
+    }
+
+    // With a random number stream, each slot's gene is read with expression noise.
+    fn expressed(&self, role: GeneRole, rand: &mut Option<Random>) -> BitSet8 {
+        self.slots()
+            .iter()
+            .filter(|slot| slot.role == role)
+            .map(|slot| match rand {
+                Some(rand) => slot.gene.expressed(PARAMS.get().expression_noise, rand),
+                None => slot.gene.value,
+            })
+            .fold(BitSet8::empty(), |result, value| result.union(value))
+    }
+
+    fn num_set_bits(&self) -> usize {
+        self.slots()
+            .iter()
+            .map(|slot| slot.gene.value.count_ones())
+            .sum()
+    }
+
+    fn packed(&self) -> u64 {
+        let mut result = (self.len as u64) << GENE_SLOT_COUNT_SHIFT;
+        for (index, slot) in self.slots().iter().enumerate() {
+            let role_bit = (slot.role == GeneRole::Repro) as u64;
+            let slot_bits = (role_bit << 8) | slot.gene.value.bits as u64;
+            result |= slot_bits << (index as u32 * GENE_SLOT_BITS);
+        }
+        result
+    }
+
+    // Genomes packed without a slot count are from before gene slots, with the survival gene
+    // in the second byte and the repro gene in the first.
+    fn unpacked(packed: u64) -> Self {
+        let len = (packed >> GENE_SLOT_COUNT_SHIFT) as usize;
+        if len == 0 {
+            return Self::new(&[
+                GeneSlot::new(GeneRole::Survival, (packed >> 8) as u8),
+                GeneSlot::new(GeneRole::Repro, packed as u8),
+            ]);
+        }
+        let slots: ArrayVec<GeneSlot, MAX_GENE_SLOTS> = (0..len.min(MAX_GENE_SLOTS))
+            .map(|index| {
+                let slot_bits = packed >> (index as u32 * GENE_SLOT_BITS);
+                let role = if slot_bits & (1 << 8) != 0 {
+                    GeneRole::Repro
+                } else {
+                    GeneRole::Survival
+                };
+                GeneSlot::new(role, slot_bits as u8)
+            })
+            .collect();
+        Self::new(&slots)
+    }
+
+    // The child takes its slot layout from one of its parents, picked at random, and each
+    // slot's gene is merged from those of the parents that have a slot in the same place and
+    // role. Then a slot may be duplicated or deleted.
+    fn merge(parents: &ArrayVec<Genome, 8>, rand: &mut Option<Random>) -> Self {
+        let template = match rand {
+            Some(rand) => parents[rand.next_in_range(0..parents.len())],
+            None => parents[0],
+        };
+        let mut result = template;
+        for (index, slot) in result.slots[..result.len].iter_mut().enumerate() {
+            let parent_genes: ArrayVec<BitSet8Gene, 8> = parents
+                .iter()
+                .filter_map(|parent| parent.slots().get(index))
+                .filter(|parent_slot| parent_slot.role == slot.role)
+                .map(|parent_slot| parent_slot.gene)
+                .collect();
+            slot.gene = BitSet8Gene::merge(&parent_genes, rand, PARAMS.get().mutation_odds);
+        }
+        if let Some(rand) = rand {
+            result.maybe_duplicate_slot(rand);
+            result.maybe_delete_slot(rand);
+        }
+        result
+    }
+
+    fn maybe_duplicate_slot(&mut self, rand: &mut Random) {
+        if self.len < MAX_GENE_SLOTS && rand.next_bool(PARAMS.get().slot_duplication_odds) {
+            self.slots[self.len] = self.slots[rand.next_in_range(0..self.len)];
+            self.len += 1;
+        }
+    }
+
+    fn maybe_delete_slot(&mut self, rand: &mut Random) {
+        if self.len > 1 && rand.next_bool(PARAMS.get().slot_deletion_odds) {
+            let index = rand.next_in_range(0..self.len);
+            self.slots.copy_within(index + 1..self.len, index);
+            self.len -= 1;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Creature {
+    genome: Genome,
+}
+
+impl Creature {
+    pub fn new(genome: Genome) -> Self {
+        Self { genome }
+    }
+
+    pub fn conway() -> Self {
+        Self::new(Genome::new(&[
+            GeneSlot::new(GeneRole::Survival, 0b110),
+            GeneSlot::new(GeneRole::Repro, 0b100),
+        ]))
+    }
+
+    fn survival_counts(&self) -> BitSet8 {
+        self.genome.expressed(GeneRole::Survival, &mut None)
+    }
+
+    fn repro_counts(&self) -> BitSet8 {
+        self.genome.expressed(GeneRole::Repro, &mut None)
+    }
+
+    pub fn color_rgba(&self) -> [u8; 4] {
+        let survival_bitset = self.survival_counts();
+        let repro_bitset = self.repro_counts();
+
+        let counts_bits_union = survival_bitset.union(repro_bitset).bits;
+        let red = counts_bits_union; // >> 1 + counts_bits_union >> 2;
+
+        let num_survival_bits = survival_bitset.count_ones() as u8;
+        let num_survival_bits_squeezed = (num_survival_bits & 0b1000) | (num_survival_bits << 1);
+        let green = num_survival_bits_squeezed << 4;
+
+        let num_repro_bits = repro_bitset.count_ones() as u8;
+        let num_repro_bits_squeezed = (num_repro_bits & 0b1000) | (num_repro_bits << 1);
+        let blue = num_repro_bits_squeezed << 4;
+
+        PARAMS.get().color_rgba([red, green, blue])
+    }
+
+    pub fn survives(&self, num_neighbors: usize, rand: &mut Option<Random>) -> bool {
+        num_neighbors > 0
+            && self
+                .genome
+                .expressed(GeneRole::Survival, rand)
+                .is_bit_set(num_neighbors - 1)
+            && self.has_small_genome(rand)
+    }
+
+    // Every set bit in every slot costs the same, so bigger genomes survive and reproduce
+    // less often, and a genome of 16 set bits never does.
+    fn has_small_genome(&self, rand: &mut Option<Random>) -> bool {
+        if let Some(rand) = rand {
+            let num_genome_bits = self.genome.num_set_bits();
+            rand.next_bool((1.0 - num_genome_bits as f64 / 16.0).max(0.0))
+        } else {
+            true
+        }
+    }
+
+    pub fn maybe_reproduce(
+        neighborhood: &Neighborhood<EvoConwayGridCell>,
+        num_neighbors: usize,
+        rand: &mut Option<Random>,
+    ) -> Option<Creature> {
+        if num_neighbors > 0
+            && let Some(child_genome) =
+                Self::merge_parent_genomes(neighborhood, num_neighbors, rand)
+        {
+            let child = Creature::new(child_genome);
+            if child.has_small_genome(rand) {
+                return Some(child);
+            }
+        }
+
+        None
+    }
+
+    fn merge_parent_genomes(
+        neighborhood: &Neighborhood<EvoConwayGridCell>,
+        num_neighbors: usize,
+        rand: &mut Option<Random>,
+    ) -> Option<Genome> {
+        let mut parent_genomes = ArrayVec::<Genome, 8>::new();
+        neighborhood.for_neighbor_cells(|neighbor| {
+            if let Some(creature) = neighbor.creature
+                && creature.can_reproduce(num_neighbors, rand)
+            {
+                parent_genomes.push(creature.genome);
+            }
+        });
+
+        if parent_genomes.is_empty() {
+            None
+        } else {
+            Some(Genome::merge(&parent_genomes, rand))
+        }
+    }
+
+    fn can_reproduce(&self, num_neighbors: usize, rand: &mut Option<Random>) -> bool {
+        num_neighbors > 0
+            && self
+                .genome
+                .expressed(GeneRole::Repro, rand)
+                .is_bit_set(num_neighbors - 1)
+    }
+}
+
+#[derive(Debug)]
+pub struct EvoConwayParams {
+    density: f64,
+    mutation_odds: f64,
+    // Odds that a newborn's genome gains a copy of one of its gene slots, or loses one.
+    slot_duplication_odds: f64,
+    slot_deletion_odds: f64,
+    // Odds of each gene bit reading flipped each time it's read.
+    expression_noise: f64,
+    // Number of initial steps that follow Conway's rules exactly, without mutation.
+    conway_steps: usize,
+    // Which of the creature color's computed channels goes into each of red, green, and blue.
+    channel_order: [u8; 3],
+}
+
+impl EvoConwayParams {
+    fn color_rgba(&self, channels: [u8; 3]) -> [u8; 4] {
+        let [red, green, blue] = self.channel_order.map(|channel| channels[channel as usize]);
+        [red, green, blue, 0xff]
+    }
+}
+
+impl Default for EvoConwayParams {
+    fn default() -> Self {
+        Self {
+            density: 0.3,
+            mutation_odds: 0.001,
+            slot_duplication_odds: 0.001,
+            slot_deletion_odds: 0.001,
+            expression_noise: 0.0,
+            conway_steps: 30,
+            channel_order: [0, 1, 2],
+        }
+    }
+}
+
+impl WorldParams for EvoConwayParams {
+    fn random(rand: &mut Random) -> Self {
+        Self {
+            density: rand.next_in_range(0.1..0.5),
+            mutation_odds: 10f64.powf(rand.next_in_range(-4.0..-2.0)),
+            slot_duplication_odds: 10f64.powf(rand.next_in_range(-4.0..-2.0)),
+            slot_deletion_odds: 10f64.powf(rand.next_in_range(-4.0..-2.0)),
+            expression_noise: 10f64.powf(rand.next_in_range(-4.0..-1.0)),
+            conway_steps: rand.next_in_range(0..=60),
+            channel_order: rand.shuffle_color_rgb([0, 1, 2]),
+        }
+    }
+}
+
+impl fmt::Display for EvoConwayParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "density={:.2} mutation_odds={:.5} slot_duplication_odds={:.5} \
+             slot_deletion_odds={:.5} expression_noise={:.5} conway_steps={} channel_order={:?}",
+            self.density,
+            self.mutation_odds,
+            self.slot_duplication_odds,
+            self.slot_deletion_odds,
+            self.expression_noise,
+            self.conway_steps,
+            self.channel_order
+        )
+    }
+}
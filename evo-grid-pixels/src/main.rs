@@ -1,18 +1,34 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
-use evo_grid::EvoWorld;
-use pixels_main_support::{animate, window_size_to_grid_size};
-use world_grid::Random;
-
-const TIME_STEP_FRAMES: u32 = 60;
-const CELL_PIXEL_WIDTH: u32 = 3;
+use evo_grid::{EvoParams, EvoWorld, SourceLayout, Wind};
+use pixels_main_support::{AppOptions, WorldApp, world_params};
 
+// Runs with the substance sources given by --sources, else random clusters of them, and with
+// the wind given by --wind, else the params' wind.
 fn main() {
-    animate(TIME_STEP_FRAMES, |window_size| {
-        EvoWorld::new(
-            window_size_to_grid_size(window_size, CELL_PIXEL_WIDTH),
-            Random::new(),
-        )
-    });
+    let source_layout = AppOptions::from_args()
+        .source_layout_path
+        .map(|path| SourceLayout::load(&path));
+    WorldApp::new(|grid_size, rand| {
+        let world = EvoWorld::new(grid_size, rand);
+        match &source_layout {
+            Some(source_layout) => world.with_source_layout(source_layout.clone()),
+            None => world,
+        }
+    })
+    .with_time_step_frames(60)
+    .with_cell_pixel_width(3)
+    .with_params(|rand| {
+        evo_grid::PARAMS.init(|| {
+            let params: EvoParams = world_params(rand);
+            match AppOptions::from_args().wind {
+                Some(wind) => {
+                    params.with_wind(wind.parse::<Wind>().unwrap_or_else(|err| panic!("{}", err)))
+                }
+                None => params,
+            }
+        })
+    })
+    .run();
 }
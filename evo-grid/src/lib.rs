@@ -1,15 +1,46 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
+mod sources;
+mod wind;
+
+pub use sources::{EmissionProfile, SinkSpec, SourceLayout, SourceShape, SourceSpec};
+pub use wind::Wind;
+
+use sources::{SubstanceSink, SubstanceSource, TerrainCell};
+use std::fmt;
 use world_grid::{
-    GridCell, GridSize, Loc, Neighborhood, Random, World, WorldGrid, WorldGridCells, alpha_blend,
+    CellCodec, GeneDescriptor, GeneFormat, GridCell, GridSettings, GridSize, LayerCell,
+    LayeredWorldGrid, LayersBelow, Loc, Neighborhood, Random, SnapshotError, SnapshotReader,
+    SnapshotWriter, StepStats, World, WorldGrid, WorldMetric, WorldParams, WorldParamsCell,
+    WorldSources, alpha_blend,
 };
 
-#[derive(Debug)]
+const SUBSTANCE_AMOUNT_TOLERANCE: f32 = 1e-4;
+const TERRAIN: &str = "terrain";
+const SUBSTANCES: &str = "substances";
+const CREATURES: &str = "creatures";
+// Bottom first, which is also the order of the phases of an update.
+const LAYER_NAMES: [&str; 3] = [TERRAIN, SUBSTANCES, CREATURES];
+
+pub static PARAMS: WorldParamsCell<EvoParams> = WorldParamsCell::new();
+
+// Runs on a layered grid: the terrain, painted with the substance sources and sinks, under the
+// substances, under the creatures that feed on them. The world's grid holds each cell's
+// creature and substance together, to show and edit. It's split into the layers whenever it
+// has been edited, and put back together from them after each update.
+#[derive(Clone, Debug)]
 pub struct EvoWorld {
     grid: WorldGrid<EvoGridCell>,
+    layers: LayeredWorldGrid,
     sources: Vec<SubstanceSource>,
+    sinks: Vec<SubstanceSink>,
+    // The sources to start with, if not random clusters.
+    source_layout: Option<SourceLayout>,
     rand: Option<Random>,
+    // The grids and random number stream as of the start of an update being run a phase at a
+    // time (see update_phase).
+    phase_start: Option<(WorldGrid<EvoGridCell>, LayeredWorldGrid, Option<Random>)>,
 }
 
 impl EvoWorld {
@@ -23,27 +54,63 @@ impl EvoWorld {
         assert!(!grid_size.is_empty());
         Self {
             grid: WorldGrid::new(grid_size),
+            layers: LayeredWorldGrid::new(grid_size)
+                .with_layer(TERRAIN, WorldGrid::<TerrainCell>::new(grid_size))
+                .with_layer(SUBSTANCES, WorldGrid::<SubstanceCell>::new(grid_size))
+                .with_layer(CREATURES, WorldGrid::<CreatureCell>::new(grid_size)),
             sources: vec![],
+            sinks: vec![],
+            source_layout: None,
             rand: Some(rand),
+            phase_start: None,
         }
     }
 
     fn add_contents(&mut self) {
         self.add_substances();
         self.add_creatures();
-        // self.cells[(1 + height / 4, width / 2)].debug_selected = true;
+    }
+
+    // Starts with the layout's sources and sinks in place of the random clusters, also on reset.
+    pub fn with_source_layout(mut self, source_layout: SourceLayout) -> Self {
+        self.source_layout = Some(source_layout);
+        self.reset();
+        self
     }
 
     fn add_substances(&mut self) {
-        self.add_substance_source_clusters(40, 5, 10);
+        match self.source_layout.clone() {
+            Some(source_layout) => self.add_layout_sources(&source_layout),
+            None => self.add_substance_source_clusters(
+                PARAMS.get().source_cluster_count,
+                PARAMS.get().source_cluster_radius,
+                PARAMS.get().source_cluster_size,
+            ),
+        }
+        self.paint_terrain();
     }
 
+    // Repaints the terrain with the sources and sinks, after any change to them.
+    fn paint_terrain(&mut self) {
+        let terrain = self.layers.layer_mut::<TerrainCell>(TERRAIN).unwrap();
+        terrain.clear();
+        for source in &self.sources {
+            source.paint(&mut terrain.cells);
+        }
+        for sink in &self.sinks {
+            sink.paint(&mut terrain.cells);
+        }
+    }
+
+    // Grids too small for clusters of the given radius get clusters as wide as they can hold.
     fn add_substance_source_clusters(&mut self, count: usize, radius: u32, size: u32) {
+        let grid_size = self.grid().size();
+        let radius = radius.min((grid_size.width.min(grid_size.height) - 1) / 2);
         for _ in 0..count {
-            let row_range = radius..(self.grid().size().height - radius);
+            let row_range = radius..(grid_size.height - radius);
             let row = self.rand.as_mut().unwrap().next_in_range(row_range);
 
-            let col_range = radius..(self.grid().size().width - radius);
+            let col_range = radius..(grid_size.width - radius);
             let col = self.rand.as_mut().unwrap().next_in_range(col_range);
 
             self.add_substance_source_cluster(Loc::new(row, col), radius, size);
@@ -57,10 +124,30 @@ impl EvoWorld {
                 self.random_offset(center.row, radius),
                 self.random_offset(center.col, radius),
             );
-            self.sources.push(SubstanceSource::new(loc, substance));
+            self.sources
+                .push(SubstanceSource::from_params(loc, substance));
         }
     }
 
+    fn add_layout_sources(&mut self, source_layout: &SourceLayout) {
+        for spec in &source_layout.specs {
+            let color = spec.color.unwrap_or_else(|| self.random_color());
+            self.sources.push(SubstanceSource::new(
+                spec.shape,
+                spec.profile,
+                Substance::new(color, 1.0),
+                spec.rate.unwrap_or(PARAMS.get().source_rate),
+                spec.saturation.unwrap_or(PARAMS.get().source_saturation),
+            ));
+        }
+        self.sinks.extend(
+            source_layout
+                .sinks
+                .iter()
+                .map(|spec| SubstanceSink::new(spec.shape, spec.profile, spec.rate)),
+        );
+    }
+
     fn random_color(&mut self) -> [u8; 3] {
         let rand = self.rand.as_mut().unwrap();
         let result = [
@@ -77,12 +164,75 @@ impl EvoWorld {
         (index as i32 + rand.next_in_range(offset_range)) as u32
     }
 
+    // Starts a creature on the source whose substance it feeds on best, since creatures need
+    // matching substance to survive and reproduce. Sources laid out past the grid's edges are
+    // passed over.
     fn add_creatures(&mut self) {
-        let loc = Loc::new(
-            20 + self.grid().size().height / 4,
-            self.grid().size().width / 3,
-        );
-        self.grid.cells[loc].creature = Some(Creature::new([0, 0xff, 0]));
+        let creature = Creature::new(PARAMS.get().creature_color);
+        let grid_size = self.grid.size();
+        let loc = self
+            .sources
+            .iter()
+            .filter(|source| source.shape.anchor().grid_index(grid_size).is_some())
+            .max_by(|source1, source2| {
+                creature
+                    .feeding(Some(source1.substance))
+                    .total_cmp(&creature.feeding(Some(source2.substance)))
+            })
+            .map_or(
+                Loc::new(
+                    (20 + grid_size.height / 4).min(grid_size.height - 1),
+                    grid_size.width / 3,
+                ),
+                |source| source.shape.anchor(),
+            );
+        self.grid.cells[loc].creature = Some(creature);
+    }
+
+    // Copies the grid's cells, which have been edited or restored since the last update, into
+    // the layers.
+    fn split_grid(&mut self) {
+        self.layers.set_step(self.grid.step());
+        let substances = self.layers.layer_mut::<SubstanceCell>(SUBSTANCES).unwrap();
+        for (layer_cell, cell) in substances
+            .cells
+            .cells_iter_mut()
+            .zip(self.grid.cells_iter())
+        {
+            layer_cell.substance = cell.substance;
+        }
+        substances.mark_all_changed();
+        let creatures = self.layers.layer_mut::<CreatureCell>(CREATURES).unwrap();
+        for (layer_cell, cell) in creatures.cells.cells_iter_mut().zip(self.grid.cells_iter()) {
+            layer_cell.creature = cell.creature;
+        }
+        creatures.mark_all_changed();
+    }
+
+    // Puts the updated layers back together as the grid's cells, as an update of the grid, so
+    // that it counts the step and keeps track of which cells changed. Only the cells that
+    // changed in the substance or creature layers need copying.
+    fn composite_grid(&mut self) {
+        let substances = self.layers.layer::<SubstanceCell>(SUBSTANCES).unwrap();
+        let creatures = self.layers.layer::<CreatureCell>(CREATURES).unwrap();
+        let changed = substances
+            .changed_cells()
+            .zip(creatures.changed_cells())
+            .map(|(substances_changed, creatures_changed)| {
+                substances_changed
+                    .iter()
+                    .zip(creatures_changed)
+                    .map(|(substance_changed, creature_changed)| {
+                        *substance_changed || *creature_changed
+                    })
+                    .collect()
+            });
+        let substance_cells = substances.cells_iter().as_slice();
+        let creature_cells = creatures.cells_iter().as_slice();
+        self.grid.record_update(changed, |index, cell| {
+            cell.substance = substance_cells[index].substance;
+            cell.creature = creature_cells[index].creature;
+        });
     }
 }
 
@@ -91,37 +241,122 @@ impl World for EvoWorld {
         &self.grid
     }
 
+    fn grid_mut(&mut self) -> &mut WorldGrid<impl GridCell> {
+        &mut self.grid
+    }
+
     fn update(&mut self) {
-        self.grid.update(&mut self.rand, |grid| {
-            self.sources
-                .iter()
-                .for_each(|source| source.update_cells(&mut grid.next_cells));
-        });
+        if self.grid.changed_outside_update() {
+            self.split_grid();
+        }
+        self.layers.set_step(self.grid.step());
+        self.layers.set_pinned_locs(self.grid.pinned_locs());
+        self.layers.update(&mut self.rand);
+        self.composite_grid();
     }
 
     fn reset(&mut self) {
+        self.phase_start = None;
         self.grid.clear();
+        self.layers.clear();
+        self.sources.clear();
+        self.sinks.clear();
         self.add_contents();
     }
-}
 
-#[derive(Clone, Copy, Debug)]
-struct SubstanceSource {
-    loc: Loc,
-    substance: Substance,
+    fn metrics(&self) -> Vec<WorldMetric> {
+        let mut population = 0;
+        let mut substance_cells = 0;
+        let mut substance_amount = 0.0;
+        for cell in self.grid.cells_iter() {
+            if cell.creature.is_some() {
+                population += 1;
+            }
+            if let Some(substance) = cell.substance {
+                substance_cells += 1;
+                substance_amount += substance.amount as f64;
+            }
+        }
+        vec![
+            WorldMetric::new("population", population as f64),
+            WorldMetric::new("substance_cells", substance_cells as f64),
+            WorldMetric::new("substance_amount", substance_amount),
+        ]
+    }
+
+    fn step_stats(&self) -> StepStats {
+        StepStats::new(self.generation(), &self.grid)
+            .with_layer_total("substance", self.grid.substance_mass())
+    }
+
+    // A phase for each layer.
+    fn update_phase_names(&self) -> &'static [&'static str] {
+        &LAYER_NAMES
+    }
+
+    // Each phase reruns the update from its start through that phase's layer, with the same
+    // random numbers, so the last phase leaves the same result as update.
+    fn update_phase(&mut self, phase: usize) {
+        if phase == 0 || self.phase_start.is_none() {
+            self.split_grid();
+            self.layers.set_pinned_locs(self.grid.pinned_locs());
+            self.phase_start = Some((self.grid.clone(), self.layers.clone(), self.rand.clone()));
+        }
+        (self.grid, self.layers, self.rand) = self.phase_start.clone().unwrap();
+        for index in 0..=phase.min(LAYER_NAMES.len() - 1) {
+            self.layers.update_layer(index, &mut self.rand);
+        }
+        self.composite_grid();
+        self.grid.mark_all_changed();
+        if phase + 1 >= LAYER_NAMES.len() {
+            self.phase_start = None;
+        }
+    }
+
+    // The layers run the updates, so they get the settings too.
+    fn apply_grid_settings(&mut self, settings: &GridSettings) {
+        settings.apply(&mut self.grid);
+        self.layers.apply_settings(settings);
+    }
+
+    fn sources(&self) -> Option<&dyn WorldSources> {
+        Some(self)
+    }
+
+    fn sources_mut(&mut self) -> Option<&mut dyn WorldSources> {
+        Some(self)
+    }
 }
 
-impl SubstanceSource {
-    fn new(loc: Loc, substance: Substance) -> Self {
-        Self { loc, substance }
+// The substance sources, by their anchors.
+impl WorldSources for EvoWorld {
+    fn source_locs(&self) -> Vec<Loc> {
+        self.sources
+            .iter()
+            .map(|source| source.shape.anchor())
+            .collect()
+    }
+
+    fn add_source(&mut self, loc: Loc) {
+        let substance = Substance::new(self.random_color(), 1.0);
+        self.sources
+            .push(SubstanceSource::from_params(loc, substance));
+        self.paint_terrain();
+    }
+
+    fn move_source(&mut self, index: usize, loc: Loc) {
+        let source = &mut self.sources[index];
+        source.shape = source.shape.moved_to(loc);
+        self.paint_terrain();
     }
 
-    fn update_cells(&self, cells: &mut WorldGridCells<EvoGridCell>) {
-        let substance = cells[self.loc].substance.get_or_insert_default();
-        *substance = self.substance;
+    fn remove_source(&mut self, index: usize) {
+        self.sources.remove(index);
+        self.paint_terrain();
     }
 }
 
+// A cell of the world's grid, with the creature and substance of the layers' cells.
 #[derive(Clone, Copy, Debug, Default)]
 pub struct EvoGridCell {
     pub creature: Option<Creature>,
@@ -129,26 +364,6 @@ pub struct EvoGridCell {
 }
 
 impl EvoGridCell {
-    fn update_next_creature(
-        &self,
-        neighborhood: &Neighborhood<EvoGridCell>,
-        next_cell: &mut EvoGridCell,
-    ) {
-        if let Some(creature) = self.creature {
-            creature.update_next_cell(neighborhood, next_cell);
-        }
-    }
-
-    fn update_next_substance(
-        &self,
-        neighborhood: &Neighborhood<EvoGridCell>,
-        next_cell: &mut EvoGridCell,
-    ) {
-        if let Some(substance) = self.substance {
-            substance.update_next_cell(neighborhood, next_cell);
-        }
-    }
-
     fn render_creature(&self) -> [u8; 4] {
         self.creature
             .map_or([0, 0, 0, 0], |creature| creature.color_rgba())
@@ -160,6 +375,40 @@ impl EvoGridCell {
     }
 }
 
+impl CellCodec for EvoGridCell {
+    const FORMAT_NAME: &'static str = "evo-grid";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_option(&self.creature, |writer, creature| {
+            writer.write_bytes(&creature.color);
+            writer.write_u64(creature.age);
+        });
+        writer.write_option(&self.substance, |writer, substance| {
+            writer.write_bytes(&substance.color);
+            writer.write_f32(substance.amount);
+        });
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        let creature = reader.read_option(|reader| {
+            Ok(Creature {
+                color: reader.read_array()?,
+                age: reader.read_u64()?,
+            })
+        })?;
+        let substance = reader.read_option(|reader| {
+            Ok(Substance {
+                color: reader.read_array()?,
+                amount: reader.read_f32()?,
+            })
+        })?;
+        Ok(Self {
+            creature,
+            substance,
+        })
+    }
+}
+
 impl GridCell for EvoGridCell {
     fn color_rgba(&self) -> [u8; 4] {
         alpha_blend(self.render_substance(), self.render_creature())
@@ -170,17 +419,244 @@ impl GridCell for EvoGridCell {
         self.substance = None;
     }
 
+    // The layers update the cells; see EvoWorld::update.
+    fn update(
+        &self,
+        _neighborhood: &Neighborhood<EvoGridCell>,
+        _next_cell: &mut EvoGridCell,
+        _rand: &mut Option<Random>,
+    ) {
+    }
+
+    fn debug_print(&self, _row: u32, _col: u32) {}
+
+    fn is_inert(&self) -> bool {
+        self.creature.is_none() && self.substance.is_none()
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["occupied", "creature_age", "substance_amount"]
+    }
+
+    fn field_value(&self, field: usize) -> f32 {
+        match (field, self.creature, self.substance) {
+            (0, _, _) => self.creature.is_some() as u8 as f32,
+            (1, Some(creature), _) => creature.age as f32,
+            (2, _, Some(substance)) => substance.amount,
+            _ => -1.0,
+        }
+    }
+
+    fn field_tolerance(field: usize) -> f32 {
+        if field == 2 {
+            SUBSTANCE_AMOUNT_TOLERANCE
+        } else {
+            0.0
+        }
+    }
+
+    fn genome(&self) -> Option<u64> {
+        self.creature.map(|creature| creature.genome())
+    }
+
+    fn substance_amount(&self) -> f32 {
+        self.substance.map_or(0.0, |substance| substance.amount)
+    }
+
+    fn set_genome(&mut self, genome: u64) {
+        let [_, red, green, blue] = (genome as u32).to_be_bytes();
+        self.creature = Some(Creature::new([red, green, blue]));
+    }
+
+    fn remove_creature(&mut self) {
+        self.creature = None;
+    }
+
+    fn genes() -> &'static [GeneDescriptor] {
+        const GENES: [GeneDescriptor; 3] = [
+            GeneDescriptor::new("red", 16, GeneFormat::Number),
+            GeneDescriptor::new("green", 8, GeneFormat::Number),
+            GeneDescriptor::new("blue", 0, GeneFormat::Number),
+        ];
+        &GENES
+    }
+}
+
+// A cell of the substance layer, fed and drained by the terrain below it.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct SubstanceCell {
+    substance: Option<Substance>,
+}
+
+impl CellCodec for SubstanceCell {
+    const FORMAT_NAME: &'static str = "evo-grid-substances";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_option(&self.substance, |writer, substance| {
+            writer.write_bytes(&substance.color);
+            writer.write_f32(substance.amount);
+        });
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        let substance = reader.read_option(|reader| {
+            Ok(Substance {
+                color: reader.read_array()?,
+                amount: reader.read_f32()?,
+            })
+        })?;
+        Ok(Self { substance })
+    }
+}
+
+impl GridCell for SubstanceCell {
+    fn color_rgba(&self) -> [u8; 4] {
+        self.substance
+            .map_or([0, 0, 0, 0], |substance| substance.color_rgba())
+    }
+
+    fn clear(&mut self) {
+        self.substance = None;
+    }
+
     fn update(
         &self,
-        neighborhood: &Neighborhood<EvoGridCell>,
-        next_cell: &mut EvoGridCell,
+        neighborhood: &Neighborhood<SubstanceCell>,
+        next_cell: &mut SubstanceCell,
+        rand: &mut Option<Random>,
+    ) {
+        self.update_over(neighborhood, &LayersBelow::none(), next_cell, rand);
+    }
+
+    fn debug_print(&self, _row: u32, _col: u32) {}
+
+    fn is_inert(&self) -> bool {
+        self.substance.is_none()
+    }
+
+    fn substance_amount(&self) -> f32 {
+        self.substance.map_or(0.0, |substance| substance.amount)
+    }
+}
+
+impl LayerCell for SubstanceCell {
+    // The terrain's source or sink, if any, feeds or drains the substance before it diffuses.
+    fn update_over(
+        &self,
+        neighborhood: &Neighborhood<SubstanceCell>,
+        below: &LayersBelow,
+        next_cell: &mut SubstanceCell,
         _rand: &mut Option<Random>,
     ) {
-        self.update_next_creature(neighborhood, next_cell);
-        self.update_next_substance(neighborhood, next_cell);
+        if let Some(terrain) = below.cell::<TerrainCell, _>(neighborhood) {
+            terrain.feed(&mut next_cell.substance);
+        }
+        match self.substance {
+            Some(substance) => substance.update_next_cell(neighborhood, next_cell),
+            // Unless a source just filled it.
+            None if next_cell.substance.is_none() => {
+                next_cell.substance = Substance::blown_in(neighborhood)
+            }
+            None => (),
+        }
+    }
+}
+
+// A cell of the creature layer, whose creature feeds on the substance below it.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct CreatureCell {
+    creature: Option<Creature>,
+}
+
+impl CellCodec for CreatureCell {
+    const FORMAT_NAME: &'static str = "evo-grid-creatures";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_option(&self.creature, |writer, creature| {
+            writer.write_bytes(&creature.color);
+            writer.write_u64(creature.age);
+        });
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        let creature = reader.read_option(|reader| {
+            Ok(Creature {
+                color: reader.read_array()?,
+                age: reader.read_u64()?,
+            })
+        })?;
+        Ok(Self { creature })
+    }
+}
+
+impl GridCell for CreatureCell {
+    fn color_rgba(&self) -> [u8; 4] {
+        self.creature
+            .map_or([0, 0, 0, 0], |creature| creature.color_rgba())
+    }
+
+    fn clear(&mut self) {
+        self.creature = None;
+    }
+
+    fn update(
+        &self,
+        neighborhood: &Neighborhood<CreatureCell>,
+        next_cell: &mut CreatureCell,
+        rand: &mut Option<Random>,
+    ) {
+        self.update_over(neighborhood, &LayersBelow::none(), next_cell, rand);
     }
 
     fn debug_print(&self, _row: u32, _col: u32) {}
+
+    fn is_inert(&self) -> bool {
+        self.creature.is_none()
+    }
+
+    // For the population cap (see GridSettings).
+    fn genome(&self) -> Option<u64> {
+        self.creature.map(|creature| creature.genome())
+    }
+
+    fn remove_creature(&mut self) {
+        self.creature = None;
+    }
+}
+
+impl LayerCell for CreatureCell {
+    // Creatures are born only next to other creatures, wherever the substances are.
+    const UPDATES_OVER_BELOW: bool = false;
+
+    fn update_over(
+        &self,
+        neighborhood: &Neighborhood<CreatureCell>,
+        below: &LayersBelow,
+        next_cell: &mut CreatureCell,
+        rand: &mut Option<Random>,
+    ) {
+        if !neighborhood.is_update_step(PARAMS.get().creature_update_period) {
+            return;
+        }
+        let substances = below.neighborhood::<SubstanceCell, _>(neighborhood);
+        match self.creature {
+            Some(creature) => {
+                creature.update_next_cell(substance_at(substances.as_ref(), 0, 0), next_cell)
+            }
+            None => {
+                Creature::update_next_empty_cell(neighborhood, substances.as_ref(), next_cell, rand)
+            }
+        }
+    }
+}
+
+// The substance in the cell at the given offsets from the neighborhood's center, if any.
+fn substance_at(
+    substances: Option<&Neighborhood<SubstanceCell>>,
+    row_offset: i32,
+    col_offset: i32,
+) -> Option<Substance> {
+    substances?.cell_at(row_offset, col_offset)?.substance
 }
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -190,16 +666,39 @@ pub struct Creature {
 }
 
 impl Creature {
+    const MIN_PARENT_AGE: u64 = 1;
+    const STARVING_MAX_AGE: u64 = 3;
+
     fn new(color: [u8; 3]) -> Self {
         Self { color, age: 0 }
     }
 
-    fn update_next_cell(
-        &self,
-        _neighborhood: &Neighborhood<EvoGridCell>,
-        next_cell: &mut EvoGridCell,
-    ) {
-        if self.age > 3 {
+    fn genome(&self) -> u64 {
+        let [red, green, blue] = self.color;
+        u32::from_be_bytes([0, red, green, blue]) as u64
+    }
+
+    // How well the creature feeds on a substance, from 0 to 1: the substance amount, scaled
+    // down the further its color is from the creature's.
+    fn feeding(&self, substance: Option<Substance>) -> f32 {
+        substance.map_or(0.0, |substance| {
+            let color_distance: u32 = self
+                .color
+                .iter()
+                .zip(substance.color)
+                .map(|(value1, value2)| value1.abs_diff(value2) as u32)
+                .sum();
+            substance.amount * (1.0 - color_distance as f32 / (3 * 0xff) as f32)
+        })
+    }
+
+    // Well fed creatures live longer; starving ones die young.
+    fn max_age(&self, substance: Option<Substance>) -> u64 {
+        Self::STARVING_MAX_AGE + (self.feeding(substance) * PARAMS.get().fed_extra_age) as u64
+    }
+
+    fn update_next_cell(&self, substance: Option<Substance>, next_cell: &mut CreatureCell) {
+        if self.age > self.max_age(substance) {
             next_cell.creature = None;
         } else {
             let next_creature = next_cell.creature.as_mut().unwrap();
@@ -207,6 +706,42 @@ impl Creature {
         }
     }
 
+    // An empty cell gets a newborn copy of one of its neighboring creatures that is old enough
+    // to be a parent, picked at random. The better the parent feeds on the substance under it,
+    // the likelier the birth, so creatures spread mostly across substance that matches their
+    // color and barely hold on anywhere else.
+    fn update_next_empty_cell(
+        neighborhood: &Neighborhood<CreatureCell>,
+        substances: Option<&Neighborhood<SubstanceCell>>,
+        next_cell: &mut CreatureCell,
+        rand: &mut Option<Random>,
+    ) {
+        let mut parent = None;
+        let mut num_parents = 0;
+        neighborhood.for_neighbor_cells_with_offsets(|row_offset, col_offset, neighbor| {
+            if let Some(creature) = neighbor.creature
+                && creature.age >= Self::MIN_PARENT_AGE
+            {
+                num_parents += 1;
+                // Reservoir sampling, so each parent is equally likely to be picked.
+                if rand
+                    .as_mut()
+                    .is_none_or(|rand| rand.next_in_range(0..num_parents) == 0)
+                {
+                    let substance = substance_at(substances, row_offset, col_offset);
+                    parent = Some((creature, creature.feeding(substance)));
+                }
+            }
+        });
+        let Some((parent, parent_feeding)) = parent else {
+            return;
+        };
+        let birth_odds = (parent_feeding as f64).max(PARAMS.get().min_birth_odds);
+        if rand.as_mut().is_none_or(|rand| rand.next_bool(birth_odds)) {
+            next_cell.creature = Some(Self::new(parent.color));
+        }
+    }
+
     fn color_rgba(&self) -> [u8; 4] {
         let color_rgb = self.color;
         [color_rgb[0], color_rgb[1], color_rgb[2], 0xff]
@@ -223,6 +758,24 @@ impl Substance {
     const DONATE_FRACTION: f32 = 0.1;
     const DECAY_FRACTION: f32 = 0.01;
     const MIN_AMOUNT: f32 = 0.01;
+    // Each neighbor donates an equal share of DONATE_FRACTION when there's no wind.
+    const CALM_DONATION_KERNEL: [[f32; 3]; 3] = [
+        [
+            Self::DONATE_FRACTION / 8.0,
+            Self::DONATE_FRACTION / 8.0,
+            Self::DONATE_FRACTION / 8.0,
+        ],
+        [
+            Self::DONATE_FRACTION / 8.0,
+            0.0,
+            Self::DONATE_FRACTION / 8.0,
+        ],
+        [
+            Self::DONATE_FRACTION / 8.0,
+            Self::DONATE_FRACTION / 8.0,
+            Self::DONATE_FRACTION / 8.0,
+        ],
+    ];
 
     fn new(color: [u8; 3], amount: f32) -> Self {
         Self {
@@ -233,10 +786,13 @@ impl Substance {
 
     fn update_next_cell(
         &self,
-        neighborhood: &Neighborhood<EvoGridCell>,
-        next_cell: &mut EvoGridCell,
+        neighborhood: &Neighborhood<SubstanceCell>,
+        next_cell: &mut SubstanceCell,
     ) {
-        let next_substance = next_cell.substance.as_mut().unwrap();
+        // Unless a sink just emptied it.
+        let Some(next_substance) = next_cell.substance.as_mut() else {
+            return;
+        };
 
         next_substance.amount += Self::sum_donations(neighborhood, self.color);
 
@@ -247,17 +803,58 @@ impl Substance {
         }
     }
 
-    fn sum_donations(neighborhood: &Neighborhood<EvoGridCell>, color: [u8; 3]) -> f32 {
-        let mut donated: f32 = 0.0;
-        neighborhood.for_neighbor_cells(|neighbor| {
+    fn sum_donations(neighborhood: &Neighborhood<SubstanceCell>, color: [u8; 3]) -> f32 {
+        let wind = PARAMS.get().wind;
+        let kernel = if wind.is_calm() {
+            Self::CALM_DONATION_KERNEL
+        } else {
+            Wind::donation_kernel(
+                wind.vector(neighborhood.step()),
+                Self::DONATE_FRACTION / 8.0,
+            )
+        };
+        neighborhood.weighted_sum(&kernel, |neighbor| match neighbor.substance {
+            Some(neighbor_substance)
+                if neighbor_substance.amount >= Self::MIN_AMOUNT
+                    && neighbor_substance.color == color =>
+            {
+                neighbor_substance.amount
+            }
+            _ => 0.0,
+        })
+    }
+
+    // Calm air leaves empty cells empty, but wind carries the upwind neighbors' extra
+    // donations (see Wind::donation_weight) into them, which is what makes plumes drift. An
+    // empty cell takes the color that would blow in the most.
+    fn blown_in(neighborhood: &Neighborhood<SubstanceCell>) -> Option<Self> {
+        let wind = PARAMS.get().wind;
+        if wind.is_calm() {
+            return None;
+        }
+        let wind_vector = wind.vector(neighborhood.step());
+        let mut blown: Vec<Self> = Vec::with_capacity(8);
+        neighborhood.for_neighbor_cells_with_offsets(|row_offset, col_offset, neighbor| {
             if let Some(neighbor_substance) = neighbor.substance
                 && neighbor_substance.amount >= Self::MIN_AMOUNT
-                && neighbor_substance.color == color
             {
-                donated += (Self::DONATE_FRACTION / 8.0) * neighbor_substance.amount;
+                let extra_weight =
+                    (Wind::donation_weight(wind_vector, row_offset, col_offset) - 1.0).max(0.0);
+                let amount =
+                    extra_weight * (Self::DONATE_FRACTION / 8.0) * neighbor_substance.amount;
+                match blown
+                    .iter_mut()
+                    .find(|substance| substance.color == neighbor_substance.color)
+                {
+                    Some(substance) => substance.amount += amount,
+                    None => blown.push(Self::new(neighbor_substance.color, amount)),
+                }
             }
         });
-        donated
+        blown
+            .into_iter()
+            .max_by(|substance1, substance2| substance1.amount.total_cmp(&substance2.amount))
+            .filter(|substance| substance.amount >= Self::MIN_AMOUNT)
     }
 
     fn color_rgba(&self) -> [u8; 4] {
@@ -266,3 +863,97 @@ impl Substance {
         [color_rgb[0], color_rgb[1], color_rgb[2], color_alpha]
     }
 }
+
+#[derive(Debug)]
+pub struct EvoParams {
+    source_cluster_count: usize,
+    source_cluster_radius: u32,
+    source_cluster_size: u32,
+    creature_color: [u8; 3],
+    // Birth odds for parents that don't feed on the substance under them, if any.
+    min_birth_odds: f64,
+    // How much longer than a starving creature a fully fed one lives.
+    fed_extra_age: f32,
+    // Creatures act only every this many steps, while substances diffuse every step.
+    creature_update_period: u64,
+    // How much substance each source adds to its cell per step, and the most it fills it to
+    // (see SubstanceSource).
+    source_rate: f32,
+    source_saturation: f32,
+    // Blows diffusing substances downwind.
+    wind: Wind,
+}
+
+impl EvoParams {
+    pub fn with_wind(mut self, wind: Wind) -> Self {
+        self.wind = wind;
+        self
+    }
+}
+
+impl Default for EvoParams {
+    fn default() -> Self {
+        Self {
+            source_cluster_count: 40,
+            source_cluster_radius: 5,
+            source_cluster_size: 10,
+            creature_color: [0, 0xff, 0],
+            min_birth_odds: 0.05,
+            fed_extra_age: 20.0,
+            creature_update_period: 1,
+            source_rate: 1.0,
+            source_saturation: 1.0,
+            wind: Wind::default(),
+        }
+    }
+}
+
+impl WorldParams for EvoParams {
+    fn random(rand: &mut Random) -> Self {
+        let creature_color = [0xff, rand.next_in_range(0..=0xff), 0];
+        Self {
+            source_cluster_count: rand.next_in_range(10..=80),
+            source_cluster_radius: rand.next_in_range(3..=10),
+            source_cluster_size: rand.next_in_range(5..=20),
+            creature_color: rand.shuffle_color_rgb(creature_color),
+            min_birth_odds: rand.next_in_range(0.03..0.07),
+            fed_extra_age: rand.next_in_range(10.0..30.0),
+            creature_update_period: rand.next_in_range(1..=2),
+            source_rate: rand.next_in_range(0.05..=1.0),
+            source_saturation: rand.next_in_range(0.5..=1.0),
+            wind: Wind::new(
+                rand.next_in_range(0.0..=0.5),
+                rand.next_in_range(0.0..360.0),
+                if rand.next_bool(0.5) {
+                    0
+                } else {
+                    rand.next_in_range(500..=5000)
+                },
+            ),
+        }
+    }
+}
+
+impl fmt::Display for EvoParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [red, green, blue] = self.creature_color;
+        write!(
+            f,
+            "source_clusters={}x{} source_cluster_radius={} creature_color=#{:02x}{:02x}{:02x} \
+             min_birth_odds={:.3} fed_extra_age={:.1} creature_update_period={} source_rate={:.2} \
+             source_saturation={:.2} wind={}",
+            self.source_cluster_count,
+            self.source_cluster_size,
+            self.source_cluster_radius,
+            red,
+            green,
+            blue,
+            self.min_birth_odds,
+            self.fed_extra_age,
+            self.creature_update_period,
+            self.source_rate,
+            self.source_saturation,
+            self.wind
+        )
+    }
+}
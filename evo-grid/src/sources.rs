@@ -0,0 +1,490 @@
+use crate::{PARAMS, Substance};
+use std::fs;
+use std::path::Path;
+use world_grid::{
+    CellCodec, GridCell, GridSize, LayerCell, Loc, Neighborhood, Random, SnapshotError,
+    SnapshotReader, SnapshotWriter, WorldGridCells,
+};
+
+// How much substance a sink removes from each of its cells per step, unless given.
+const DEFAULT_SINK_RATE: f32 = 0.1;
+
+// The cells a source emits into.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SourceShape {
+    Point(Loc),
+    // The cells along the segment between two ends.
+    Line(Loc, Loc),
+    // The cells between two opposite corners, inclusive.
+    Rect(Loc, Loc),
+    // The cells about radius away from the center.
+    Ring { center: Loc, radius: u32 },
+}
+
+impl SourceShape {
+    // The cell that stands for the whole shape, e.g. for its marker and for dragging it.
+    pub fn anchor(&self) -> Loc {
+        match *self {
+            Self::Point(loc) | Self::Line(loc, _) | Self::Rect(loc, _) => loc,
+            Self::Ring { center, .. } => center,
+        }
+    }
+
+    // The same shape with its anchor moved to loc. Parts moved past the top or left edges of
+    // the grid are cut off there.
+    pub fn moved_to(&self, loc: Loc) -> Self {
+        let anchor = self.anchor();
+        let offset = |other: Loc| {
+            Loc::new(
+                (other.row as i64 + loc.row as i64 - anchor.row as i64).max(0) as u32,
+                (other.col as i64 + loc.col as i64 - anchor.col as i64).max(0) as u32,
+            )
+        };
+        match *self {
+            Self::Point(_) => Self::Point(loc),
+            Self::Line(start, end) => Self::Line(offset(start), offset(end)),
+            Self::Rect(corner, other_corner) => Self::Rect(offset(corner), offset(other_corner)),
+            Self::Ring { radius, .. } => Self::Ring {
+                center: loc,
+                radius,
+            },
+        }
+    }
+
+    fn locs(&self) -> Vec<Loc> {
+        match *self {
+            Self::Point(loc) => vec![loc],
+            Self::Line(start, end) => {
+                let num_steps = start.row.abs_diff(end.row).max(start.col.abs_diff(end.col));
+                (0..=num_steps)
+                    .map(|step| {
+                        let fraction = step as f64 / num_steps.max(1) as f64;
+                        let along = |from: u32, to: u32| {
+                            (from as f64 + (to as f64 - from as f64) * fraction).round() as u32
+                        };
+                        Loc::new(along(start.row, end.row), along(start.col, end.col))
+                    })
+                    .collect()
+            }
+            Self::Rect(corner, other_corner) => {
+                let rows = corner.row.min(other_corner.row)..=corner.row.max(other_corner.row);
+                let cols = corner.col.min(other_corner.col)..=corner.col.max(other_corner.col);
+                rows.flat_map(|row| cols.clone().map(move |col| Loc::new(row, col)))
+                    .collect()
+            }
+            Self::Ring { center, radius } => {
+                let rows = center.row.saturating_sub(radius)..=center.row + radius;
+                let cols = center.col.saturating_sub(radius)..=center.col + radius;
+                rows.flat_map(|row| cols.clone().map(move |col| Loc::new(row, col)))
+                    .filter(|loc| (loc.distance(center) - radius as f64).abs() < 0.5)
+                    .collect()
+            }
+        }
+    }
+
+    fn center(&self) -> (f64, f64) {
+        match *self {
+            Self::Point(loc) | Self::Ring { center: loc, .. } => (loc.row as f64, loc.col as f64),
+            Self::Line(start, end) | Self::Rect(start, end) => (
+                (start.row + end.row) as f64 / 2.0,
+                (start.col + end.col) as f64 / 2.0,
+            ),
+        }
+    }
+}
+
+// How a source's emission varies across its shape.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum EmissionProfile {
+    // Every cell gets the full rate.
+    #[default]
+    Uniform,
+    // The rate falls off from the middle of a line or rectangle towards its ends or corners.
+    // All of a ring's cells are about as far from its center, so it emits about evenly.
+    Tapered,
+}
+
+impl EmissionProfile {
+    // The shape's cells within the grid, with their fractions of the full rate.
+    fn weighted_locs(&self, shape: &SourceShape, size: GridSize) -> Vec<(Loc, f32)> {
+        let locs = shape.locs();
+        let weights = self.weights(shape, &locs);
+        locs.into_iter()
+            .zip(weights)
+            .filter(|(loc, _)| loc.grid_index(size).is_some())
+            .collect()
+    }
+
+    // Each loc's fraction of the full rate.
+    fn weights(&self, shape: &SourceShape, locs: &[Loc]) -> Vec<f32> {
+        if *self == Self::Uniform {
+            return vec![1.0; locs.len()];
+        }
+        let (center_row, center_col) = shape.center();
+        let distances: Vec<f64> = locs
+            .iter()
+            .map(|loc| (loc.row as f64 - center_row).hypot(loc.col as f64 - center_col))
+            .collect();
+        let min = distances.iter().copied().fold(f64::MAX, f64::min);
+        let max = distances.iter().copied().fold(0.0, f64::max);
+        distances
+            .iter()
+            .map(|distance| (1.0 - (distance - min) / (max - min + 1.0)) as f32)
+            .collect()
+    }
+}
+
+// Each step, a source adds rate (scaled by its profile) to the amount of its substance in each
+// of its cells, up to saturation, replacing any other substance there. A rate and saturation
+// of 1 keep the cells full.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SubstanceSource {
+    pub shape: SourceShape,
+    pub profile: EmissionProfile,
+    pub substance: Substance,
+    rate: f32,
+    saturation: f32,
+}
+
+impl SubstanceSource {
+    pub fn new(
+        shape: SourceShape,
+        profile: EmissionProfile,
+        substance: Substance,
+        rate: f32,
+        saturation: f32,
+    ) -> Self {
+        Self {
+            shape,
+            profile,
+            substance,
+            rate,
+            saturation: saturation.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn from_params(loc: Loc, substance: Substance) -> Self {
+        Self::new(
+            SourceShape::Point(loc),
+            EmissionProfile::Uniform,
+            substance,
+            PARAMS.get().source_rate,
+            PARAMS.get().source_saturation,
+        )
+    }
+
+    pub fn paint(&self, terrain: &mut WorldGridCells<TerrainCell>) {
+        for (loc, weight) in self.profile.weighted_locs(&self.shape, terrain.size()) {
+            terrain[loc].add_source(Emission {
+                color: self.substance.color,
+                rate: weight * self.rate,
+                saturation: self.saturation,
+            });
+        }
+    }
+}
+
+// Each step, a sink removes rate (scaled by its profile) from the amount of whatever substance
+// is in each of its cells, emptying the cells it leaves with too little. Sinks next to sources
+// make steady gradients between them.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SubstanceSink {
+    pub shape: SourceShape,
+    pub profile: EmissionProfile,
+    rate: f32,
+}
+
+impl SubstanceSink {
+    pub fn new(shape: SourceShape, profile: EmissionProfile, rate: f32) -> Self {
+        Self {
+            shape,
+            profile,
+            rate: rate.max(0.0),
+        }
+    }
+
+    pub fn paint(&self, terrain: &mut WorldGridCells<TerrainCell>) {
+        for (loc, weight) in self.profile.weighted_locs(&self.shape, terrain.size()) {
+            terrain[loc].add_sink(weight * self.rate);
+        }
+    }
+}
+
+// A cell of the terrain layer under the substances (see EvoWorld), painted with the sources
+// and sinks that cover it, which feed and drain the substance above it each step. Where
+// sources of the same substance overlap their rates add up, and otherwise the last one
+// painted covers the others.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct TerrainCell {
+    source: Option<Emission>,
+    sink_rate: Option<f32>,
+}
+
+impl TerrainCell {
+    fn add_source(&mut self, emission: Emission) {
+        self.source = Some(match self.source {
+            Some(source) if source.color == emission.color => Emission {
+                rate: source.rate + emission.rate,
+                saturation: source.saturation.max(emission.saturation),
+                ..emission
+            },
+            _ => emission,
+        });
+    }
+
+    fn add_sink(&mut self, rate: f32) {
+        self.sink_rate = Some(self.sink_rate.unwrap_or(0.0) + rate);
+    }
+
+    // The source fills the substance above first, and then the sink drains it.
+    pub fn feed(&self, substance: &mut Option<Substance>) {
+        if let Some(source) = self.source {
+            source.feed(substance);
+        }
+        if let Some(sink_rate) = self.sink_rate
+            && let Some(sunk) = substance.as_mut()
+        {
+            sunk.amount -= sink_rate;
+            if sunk.amount < Substance::MIN_AMOUNT {
+                *substance = None;
+            }
+        }
+    }
+}
+
+impl CellCodec for TerrainCell {
+    const FORMAT_NAME: &'static str = "evo-grid-terrain";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_option(&self.source, |writer, source| {
+            writer.write_bytes(&source.color);
+            writer.write_f32(source.rate);
+            writer.write_f32(source.saturation);
+        });
+        writer.write_option(&self.sink_rate, |writer, sink_rate| {
+            writer.write_f32(*sink_rate);
+        });
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        let source = reader.read_option(|reader| {
+            Ok(Emission {
+                color: reader.read_array()?,
+                rate: reader.read_f32()?,
+                saturation: reader.read_f32()?,
+            })
+        })?;
+        let sink_rate = reader.read_option(|reader| reader.read_f32())?;
+        Ok(Self { source, sink_rate })
+    }
+}
+
+impl GridCell for TerrainCell {
+    // The sources' markers show where they are instead.
+    fn color_rgba(&self) -> [u8; 4] {
+        [0, 0, 0, 0]
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    // The terrain only changes when the sources and sinks do; see EvoWorld::paint_terrain.
+    fn update(
+        &self,
+        _neighborhood: &Neighborhood<TerrainCell>,
+        _next_cell: &mut TerrainCell,
+        _rand: &mut Option<Random>,
+    ) {
+    }
+
+    fn debug_print(&self, _row: u32, _col: u32) {}
+
+    fn is_inert(&self) -> bool {
+        self.source.is_none() && self.sink_rate.is_none()
+    }
+}
+
+// The terrain changes only when sources or sinks are added, moved, or removed, which
+// repaints it (see EvoWorld::paint_terrain).
+impl LayerCell for TerrainCell {
+    const IS_STATIC: bool = true;
+}
+
+// How much of its substance a source adds to a cell per step, and the most it fills it to.
+#[derive(Clone, Copy, Debug)]
+struct Emission {
+    color: [u8; 3],
+    rate: f32,
+    saturation: f32,
+}
+
+impl Emission {
+    fn feed(&self, substance: &mut Option<Substance>) {
+        match substance.as_mut() {
+            Some(substance) if substance.color == self.color => {
+                // Donations from neighbors can take it past saturation, but the source doesn't
+                // take any away.
+                if substance.amount < self.saturation {
+                    substance.amount = (substance.amount + self.rate).min(self.saturation);
+                }
+            }
+            _ => {
+                *substance = Some(Substance {
+                    color: self.color,
+                    amount: self.rate.min(self.saturation),
+                })
+            }
+        }
+    }
+}
+
+// One source of a layout, with the settings left out of its line as None.
+#[derive(Clone, Debug)]
+pub struct SourceSpec {
+    pub shape: SourceShape,
+    pub profile: EmissionProfile,
+    pub color: Option<[u8; 3]>,
+    pub rate: Option<f32>,
+    pub saturation: Option<f32>,
+}
+
+// One sink of a layout.
+#[derive(Clone, Debug)]
+pub struct SinkSpec {
+    pub shape: SourceShape,
+    pub profile: EmissionProfile,
+    pub rate: f32,
+}
+
+// Sources given in a file in place of the random point source clusters, one per line, e.g.
+//
+//     point 10,20
+//     line 10,20 10,80 tapered rate=0.2
+//     rect 30,30 40,60 color=ff8000
+//     ring 60,100 12 saturation=0.5
+//     sink rect 0,150 100,160 rate=0.05
+//
+// with locs as row,col. A line can end with a profile (uniform or tapered) and settings for
+// the color (as RRGGBB), rate, and saturation, which otherwise are random and from the params.
+// A line starting with "sink" gives a sink (see SubstanceSink) instead, with a shape, profile,
+// and rate in the same form, its rate defaulting to DEFAULT_SINK_RATE. Everything from a '#'
+// to the end of its line is ignored.
+#[derive(Clone, Debug, Default)]
+pub struct SourceLayout {
+    pub specs: Vec<SourceSpec>,
+    pub sinks: Vec<SinkSpec>,
+}
+
+impl SourceLayout {
+    pub fn load(path: &Path) -> Self {
+        let text = fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("Can't read {}: {}", path.display(), err));
+        let mut result = Self::default();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let parsed = match fields[..] {
+                ["sink", ref fields @ ..] => Self::parse_sink(fields).map(|sink| {
+                    result.sinks.push(sink);
+                }),
+                _ => Self::parse_spec(&fields).map(|spec| {
+                    result.specs.push(spec);
+                }),
+            };
+            if parsed.is_none() {
+                panic!(
+                    "Invalid source at line {} of {}: {}",
+                    index + 1,
+                    path.display(),
+                    line
+                );
+            }
+        }
+        result
+    }
+
+    fn parse_sink(fields: &[&str]) -> Option<SinkSpec> {
+        let (shape, settings) = Self::parse_shape(fields)?;
+        let mut result = SinkSpec {
+            shape,
+            profile: EmissionProfile::default(),
+            rate: DEFAULT_SINK_RATE,
+        };
+        for setting in settings {
+            match setting.split_once('=') {
+                None => result.profile = Self::parse_profile(setting)?,
+                Some(("rate", value)) => result.rate = value.parse().ok()?,
+                _ => return None,
+            }
+        }
+        Some(result)
+    }
+
+    fn parse_spec(fields: &[&str]) -> Option<SourceSpec> {
+        let (shape, settings) = Self::parse_shape(fields)?;
+        let mut result = SourceSpec {
+            shape,
+            profile: EmissionProfile::default(),
+            color: None,
+            rate: None,
+            saturation: None,
+        };
+        for setting in settings {
+            match setting.split_once('=') {
+                None => result.profile = Self::parse_profile(setting)?,
+                Some(("color", value)) => {
+                    let [_, red, green, blue] = u32::from_str_radix(value, 16).ok()?.to_be_bytes();
+                    result.color = Some([red, green, blue]);
+                }
+                Some(("rate", value)) => result.rate = Some(value.parse().ok()?),
+                Some(("saturation", value)) => result.saturation = Some(value.parse().ok()?),
+                _ => return None,
+            }
+        }
+        Some(result)
+    }
+
+    // Returns the shape and the settings fields after it.
+    fn parse_shape<'a>(fields: &'a [&'a str]) -> Option<(SourceShape, &'a [&'a str])> {
+        let (shape, settings) = match *fields {
+            ["point", loc, ref settings @ ..] => {
+                (SourceShape::Point(Self::parse_loc(loc)?), settings)
+            }
+            ["line", start, end, ref settings @ ..] => (
+                SourceShape::Line(Self::parse_loc(start)?, Self::parse_loc(end)?),
+                settings,
+            ),
+            ["rect", corner, other_corner, ref settings @ ..] => (
+                SourceShape::Rect(Self::parse_loc(corner)?, Self::parse_loc(other_corner)?),
+                settings,
+            ),
+            ["ring", center, radius, ref settings @ ..] => (
+                SourceShape::Ring {
+                    center: Self::parse_loc(center)?,
+                    radius: radius.parse().ok()?,
+                },
+                settings,
+            ),
+            _ => return None,
+        };
+        Some((shape, settings))
+    }
+
+    fn parse_profile(value: &str) -> Option<EmissionProfile> {
+        match value {
+            "uniform" => Some(EmissionProfile::Uniform),
+            "tapered" => Some(EmissionProfile::Tapered),
+            _ => None,
+        }
+    }
+
+    // E.g. "10,20" for row 10, column 20.
+    fn parse_loc(value: &str) -> Option<Loc> {
+        let (row, col) = value.split_once(',')?;
+        Some(Loc::new(row.trim().parse().ok()?, col.trim().parse().ok()?))
+    }
+}
@@ -0,0 +1,100 @@
+use std::fmt;
+use std::str::FromStr;
+
+// A wind that biases substance diffusion downwind, so that plumes drift away from their
+// sources. It can hold steady or turn clockwise at a steady pace.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Wind {
+    // From 0 for calm to 1 for no diffusion upwind at all.
+    pub speed: f32,
+    // The direction the wind blows towards, in degrees clockwise from east, i.e. from
+    // increasing columns towards increasing rows.
+    pub direction: f32,
+    // Steps per full turn of the direction, or 0 for a steady wind.
+    pub turn_period: u64,
+}
+
+impl Wind {
+    pub fn new(speed: f32, direction: f32, turn_period: u64) -> Self {
+        Self {
+            speed: speed.clamp(0.0, 1.0),
+            direction,
+            turn_period,
+        }
+    }
+
+    pub fn is_calm(&self) -> bool {
+        self.speed == 0.0
+    }
+
+    // The wind's (row, col) vector at a step, as long as its speed.
+    pub fn vector(&self, step: u64) -> (f32, f32) {
+        let turned = if self.turn_period == 0 {
+            0.0
+        } else {
+            360.0 * (step % self.turn_period) as f32 / self.turn_period as f32
+        };
+        let (sin, cos) = (self.direction + turned).to_radians().sin_cos();
+        (self.speed * sin, self.speed * cos)
+    }
+
+    // How much of a neighbor's donation reaches the center, relative to a calm day, given the
+    // neighbor's offsets from the center and the wind's vector. Donations blown straight
+    // downwind get 1 + speed, those blown straight upwind get 1 - speed. Since the weights of
+    // all eight neighbors average 1, the wind moves substance without adding or removing any.
+    pub fn donation_weight(vector: (f32, f32), row_offset: i32, col_offset: i32) -> f32 {
+        let (row_offset, col_offset) = (row_offset as f32, col_offset as f32);
+        // Donations flow from the neighbor towards the center.
+        1.0 - (vector.0 * row_offset + vector.1 * col_offset) / row_offset.hypot(col_offset)
+    }
+
+    // The donation weights of the eight neighbors, scaled by the given factor, as a kernel for
+    // Neighborhood::weighted_sum. The center donates nothing to itself.
+    pub fn donation_kernel(vector: (f32, f32), scale: f32) -> [[f32; 3]; 3] {
+        let mut result = [[0.0; 3]; 3];
+        for (row, weights) in result.iter_mut().enumerate() {
+            for (col, weight) in weights.iter_mut().enumerate() {
+                let (row_offset, col_offset) = (row as i32 - 1, col as i32 - 1);
+                if (row_offset, col_offset) != (0, 0) {
+                    *weight = Self::donation_weight(vector, row_offset, col_offset) * scale;
+                }
+            }
+        }
+        result
+    }
+}
+
+impl FromStr for Wind {
+    type Err = String;
+
+    // Parses "SPEED,DIRECTION" or "SPEED,DIRECTION,TURN_PERIOD", e.g. "0.5,90" for a wind
+    // blowing towards increasing rows at half speed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid wind: {}", s);
+        let fields: Vec<&str> = s.split(',').map(str::trim).collect();
+        let (speed, direction, turn_period) = match fields[..] {
+            [speed, direction] => (speed, direction, "0"),
+            [speed, direction, turn_period] => (speed, direction, turn_period),
+            _ => return Err(invalid()),
+        };
+        let speed: f32 = speed.parse().map_err(|_| invalid())?;
+        if !(0.0..=1.0).contains(&speed) {
+            return Err(invalid());
+        }
+        Ok(Self::new(
+            speed,
+            direction.parse().map_err(|_| invalid())?,
+            turn_period.parse().map_err(|_| invalid())?,
+        ))
+    }
+}
+
+impl fmt::Display for Wind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.2},{:.0},{}",
+            self.speed, self.direction, self.turn_period
+        )
+    }
+}
@@ -0,0 +1,756 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use arrayvec::ArrayVec;
+use std::fmt::{self, Debug};
+use world_grid::{
+    BitSet8, BitSet8Gene, CellCodec, FractionGene, GeneDescriptor, GeneFormat, GridCell, GridSize,
+    Loc, Neighborhood, Random, SnapshotError, SnapshotReader, SnapshotWriter, StepStats, World,
+    WorldGrid, WorldMetric, WorldParams, WorldParamsCell, alpha_blend_with_background, rotate_hue,
+    strict_math,
+};
+
+const EMPTY_CELL_COLOR: [u8; 4] = [0, 0, 0, 0xff];
+const MATCH_WEIGHT_TOLERANCE: f32 = 1e-6;
+// Creatures whose enzyme genes differ in at most this many bits count as kin (see
+// Creature::is_kin).
+const KIN_MAX_BIT_DIFFERENCE: usize = 1;
+
+pub static PARAMS: WorldParamsCell<EvoSubstanceParams> = WorldParamsCell::new();
+
+#[derive(Clone, Debug)]
+pub struct EvoSubstanceWorld {
+    grid: WorldGrid<EvoSubstanceCell>,
+    rand: Option<Random>,
+}
+
+impl EvoSubstanceWorld {
+    pub fn new(grid_size: GridSize, rand: Random) -> Self {
+        let mut result = Self::new_empty(grid_size, rand);
+        result.add_contents();
+        result
+    }
+
+    fn new_empty(grid_size: GridSize, rand: Random) -> Self {
+        Self {
+            grid: WorldGrid::new(grid_size),
+            rand: Some(rand),
+        }
+    }
+
+    fn add_contents(&mut self) {
+        self.add_temperature_gradient();
+        self.add_random_creature(Loc::new(150, 300));
+        self.add_random_substance(Loc::new(150, 250), 50);
+        self.add_random_substance(Loc::new(80, 150), 30);
+        self.add_random_substance(Loc::new(80, 350), 30);
+        self.add_random_substance(Loc::new(220, 150), 30);
+        self.add_random_substance(Loc::new(220, 350), 30);
+        // self._add_random_substances();
+        // self._add_random_life();
+    }
+
+    // Cold on the left edge, hot on the right.
+    fn add_temperature_gradient(&mut self) {
+        let width = self.grid.size().width;
+        for (index, cell) in self.grid.cells.cells_iter_mut().enumerate() {
+            let col = index as u32 % width;
+            cell.temperature = col as f32 / (width - 1).max(1) as f32;
+        }
+        self.grid.mark_all_changed();
+    }
+
+    fn _add_random_substances(&mut self) {
+        for _ in 0..=5 {
+            let center = self._random_loc();
+            let radius = self._random_blob_radius();
+            self.add_random_substance(center, radius);
+        }
+    }
+
+    fn _random_loc(&mut self) -> Loc {
+        let rand = self.rand.as_mut().unwrap();
+        let row = rand.next_in_range(0..=self.grid.size().height);
+        let col = rand.next_in_range(0..=self.grid.size().width);
+        Loc::new(row, col)
+    }
+
+    fn _random_blob_radius(&mut self) -> u32 {
+        let max_radius = self.grid.size().width.min(self.grid.size().height) / 4;
+        let rand = self.rand.as_mut().unwrap();
+        rand.next_in_range(10..=max_radius)
+    }
+
+    fn add_random_substance(&mut self, center: Loc, radius: u32) {
+        let substance = self.random_substance();
+        self.add_substance_blob(center, radius, substance);
+    }
+
+    fn random_substance(&mut self) -> Substance {
+        let rand = self.rand.as_mut().unwrap();
+        Substance::new(BitSet8::random(0.5, rand))
+    }
+
+    fn add_substance_blob(&mut self, center: Loc, radius: u32, substance: Substance) {
+        let (upper_left, lower_right) = self.cell_circle_bounding_box(center, radius);
+        let rand = self.rand.as_mut().unwrap();
+        for row in upper_left.row..=lower_right.row {
+            for col in upper_left.col..=lower_right.col {
+                let loc = Loc::new(row, col);
+                let fraction_of_radius = loc.distance(center) / radius as f64;
+                if fraction_of_radius < 1.0
+                    && rand.next_bool(1.0 - fraction_of_radius)
+                    && let Some(cell) = self.grid.cell_mut(loc)
+                {
+                    cell.substance = Some(substance);
+                }
+            }
+        }
+    }
+
+    fn cell_circle_bounding_box(&mut self, center: Loc, radius: u32) -> (Loc, Loc) {
+        let min_row = center.row.saturating_sub(radius);
+        let max_row = (center.row + radius).min(self.grid.size().height - 1);
+        let min_col = center.col.saturating_sub(radius);
+        let max_col = (center.col + radius).min(self.grid.size().width - 1);
+        (Loc::new(min_row, min_col), Loc::new(max_row, max_col))
+    }
+
+    fn _add_random_life(&mut self) {
+        let rand = self.rand.as_mut().unwrap();
+        for cell in self.grid.cells.cells_iter_mut() {
+            if rand.next_bool(0.1) {
+                cell.creature = Some(Self::random_creature(rand));
+            }
+        }
+    }
+
+    // Grids too small to hold the loc get the creature at their nearest cell.
+    fn add_random_creature(&mut self, loc: Loc) {
+        let size = self.grid.size();
+        let loc = Loc::new(loc.row.min(size.height - 1), loc.col.min(size.width - 1));
+        let rand = self.rand.as_mut().unwrap();
+        let cell = self.grid.cell_mut(loc).unwrap();
+        cell.creature = Some(Self::random_creature(rand));
+    }
+
+    fn random_creature(rand: &mut Random) -> Creature {
+        let enzyme = BitSet8::random(0.5, rand);
+        let vision = BitSet8::random(0.5, rand);
+        let sharing = BitSet8::random(0.5, rand);
+        let toxin = BitSet8::random(0.25, rand);
+        Creature::new(
+            BitSet8Gene::new(enzyme),
+            FractionGene::new(0.5),
+            BitSet8Gene::new(vision),
+            BitSet8Gene::new(sharing),
+            BitSet8Gene::new(toxin),
+        )
+    }
+}
+
+impl World for EvoSubstanceWorld {
+    fn grid(&self) -> &WorldGrid<impl GridCell> {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut WorldGrid<impl GridCell> {
+        &mut self.grid
+    }
+
+    fn update(&mut self) {
+        self.grid.update(&mut self.rand, |_grid| {});
+    }
+
+    fn reset(&mut self) {
+        self.grid.clear();
+        self.add_contents();
+    }
+
+    fn metrics(&self) -> Vec<WorldMetric> {
+        let mut population = 0;
+        let mut substance_cells = 0;
+        for cell in self.grid.cells_iter() {
+            if cell.creature.is_some() {
+                population += 1;
+            }
+            if cell.substance.is_some() {
+                substance_cells += 1;
+            }
+        }
+        vec![
+            WorldMetric::new("population", population as f64),
+            WorldMetric::new("substance_cells", substance_cells as f64),
+        ]
+    }
+
+    fn step_stats(&self) -> StepStats {
+        StepStats::new(self.generation(), &self.grid)
+            .with_layer_total("substance", self.grid.substance_mass())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EvoSubstanceCell {
+    creature: Option<Creature>,
+    substance: Option<Substance>,
+    // From 0 (coldest) to 1 (hottest). Creatures born in hotter cells mutate more.
+    temperature: f32,
+}
+
+impl CellCodec for EvoSubstanceCell {
+    const FORMAT_NAME: &'static str = "evo-substance";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_option(&self.creature, |writer, creature| {
+            writer.write_u8(creature.enzyme_gene.value.bits);
+            writer.write_f32(creature.match_weight_gene.value);
+            writer.write_u8(creature.vision_gene.value.bits);
+            writer.write_u8(creature.sharing_gene.value.bits);
+            writer.write_u8(creature.toxin_gene.value.bits);
+            writer.write_f32(creature.energy);
+        });
+        writer.write_option(&self.substance, |writer, substance| {
+            writer.write_u8(substance.code.bits);
+        });
+        writer.write_f32(self.temperature);
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        let creature = reader.read_option(|reader| {
+            let creature = Creature::new(
+                BitSet8Gene::new(BitSet8::new(reader.read_u8()?)),
+                FractionGene::new(reader.read_fraction()?),
+                BitSet8Gene::new(BitSet8::new(reader.read_u8()?)),
+                BitSet8Gene::new(BitSet8::new(reader.read_u8()?)),
+                BitSet8Gene::new(BitSet8::new(reader.read_u8()?)),
+            );
+            Ok(Creature {
+                energy: reader.read_fraction()?,
+                ..creature
+            })
+        })?;
+        let substance =
+            reader.read_option(|reader| Ok(Substance::new(BitSet8::new(reader.read_u8()?))))?;
+        let temperature = reader.read_fraction()?;
+        Ok(Self {
+            creature,
+            substance,
+            temperature,
+        })
+    }
+}
+
+impl EvoSubstanceCell {
+    // The creature's color, if any, blended over the given substance color.
+    fn color_over_substance(&self, substance_color: Option<[u8; 4]>) -> [u8; 4] {
+        let mut result = substance_color;
+        if let Some(creature) = self.creature {
+            let mut creature_color = creature.color_rgba();
+            result = result.map_or(Some(creature_color), |color| {
+                creature_color[3] = 0x80;
+                Some(alpha_blend_with_background(creature_color, color))
+            });
+        }
+        result.unwrap_or(EMPTY_CELL_COLOR)
+    }
+}
+
+impl GridCell for EvoSubstanceCell {
+    fn color_rgba(&self) -> [u8; 4] {
+        self.color_over_substance(self.substance.map(|substance| substance.color_rgba()))
+    }
+
+    fn hue_shifted_color_rgba(&self, turns: f32) -> [u8; 4] {
+        self.color_over_substance(
+            self.substance
+                .map(|substance| rotate_hue(substance.color_rgba(), turns)),
+        )
+    }
+
+    fn clear(&mut self) {
+        self.creature = None;
+        self.substance = None;
+    }
+
+    fn update(
+        &self,
+        neighborhood: &Neighborhood<EvoSubstanceCell>,
+        next_cell: &mut EvoSubstanceCell,
+        rand: &mut Option<Random>,
+    ) {
+        if let Some(creature) = self.creature {
+            next_cell.creature =
+                creature.next_state(&self.substance, neighborhood, rand.as_mut().unwrap());
+        } else {
+            next_cell.creature = Creature::maybe_reproduce(
+                neighborhood,
+                &self.substance,
+                rand,
+                PARAMS.get().mutation_odds_at(self.temperature),
+            );
+        };
+    }
+
+    fn debug_print(&self, _row: u32, _col: u32) {}
+
+    fn is_inert(&self) -> bool {
+        self.creature.is_none()
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &[
+            "occupied",
+            "enzyme_gene",
+            "match_weight_gene",
+            "substance_code",
+            "temperature",
+            "vision_gene",
+            "sharing_gene",
+            "energy",
+            "toxin_gene",
+        ]
+    }
+
+    fn field_value(&self, field: usize) -> f32 {
+        match (field, self.creature, self.substance) {
+            (0, _, _) => self.creature.is_some() as u8 as f32,
+            (1, Some(creature), _) => creature.enzyme_gene.value.bits as f32,
+            (2, Some(creature), _) => creature.match_weight_gene.value,
+            (3, _, Some(substance)) => substance.code.bits as f32,
+            (4, _, _) => self.temperature,
+            (5, Some(creature), _) => creature.vision_gene.value.bits as f32,
+            (6, Some(creature), _) => creature.sharing_gene.value.bits as f32,
+            (7, Some(creature), _) => creature.energy,
+            (8, Some(creature), _) => creature.toxin_gene.value.bits as f32,
+            _ => -1.0,
+        }
+    }
+
+    fn field_tolerance(field: usize) -> f32 {
+        if field == 2 || field == 4 || field == 7 {
+            MATCH_WEIGHT_TOLERANCE
+        } else {
+            0.0
+        }
+    }
+
+    fn genome(&self) -> Option<u64> {
+        self.creature.map(|creature| {
+            ((creature.toxin_gene.value.bits as u64) << 56)
+                | ((creature.sharing_gene.value.bits as u64) << 48)
+                | ((creature.vision_gene.value.bits as u64) << 40)
+                | ((creature.enzyme_gene.value.bits as u64) << 32)
+                | creature.match_weight_gene.value.to_bits() as u64
+        })
+    }
+
+    // Substances fill their cells or don't.
+    fn substance_amount(&self) -> f32 {
+        self.substance.is_some() as u8 as f32
+    }
+
+    fn set_genome(&mut self, genome: u64) {
+        let match_weight = f32::from_bits(genome as u32);
+        if (0.0..=1.0).contains(&match_weight) {
+            self.creature = Some(Creature::new(
+                BitSet8Gene::new(BitSet8::new((genome >> 32) as u8)),
+                FractionGene::new(match_weight),
+                BitSet8Gene::new(BitSet8::new((genome >> 40) as u8)),
+                BitSet8Gene::new(BitSet8::new((genome >> 48) as u8)),
+                BitSet8Gene::new(BitSet8::new((genome >> 56) as u8)),
+            ));
+        }
+    }
+
+    fn remove_creature(&mut self) {
+        self.creature = None;
+    }
+
+    fn genes() -> &'static [GeneDescriptor] {
+        const GENES: [GeneDescriptor; 4] = [
+            GeneDescriptor::new("enzyme_gene", 32, GeneFormat::Bits),
+            GeneDescriptor::new("vision_gene", 40, GeneFormat::Bits),
+            GeneDescriptor::new("sharing_gene", 48, GeneFormat::Bits),
+            GeneDescriptor::new("toxin_gene", 56, GeneFormat::Bits),
+        ];
+        &GENES
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Creature {
+    enzyme_gene: BitSet8Gene,
+    match_weight_gene: FractionGene,
+    // How much the creature's reproduction decisions rely on what it sees around the cell it
+    // would reproduce into (see Sighting), from none with no bits set to fully with all of
+    // them. Seeing costs some survival odds (see EvoSubstanceParams::vision_cost), so how
+    // much to use it is left to evolve.
+    vision_gene: BitSet8Gene,
+    // How much of its energy the creature gives to needy kin around it (see gift_to), from
+    // none with no bits set to MAX_SHARED_FRACTION with all of them.
+    sharing_gene: BitSet8Gene,
+    // Its low four bits are the toxins the creature makes, one kind per bit, which harm the
+    // creatures around it (see toxin_damage_to), and its high four bits are the kinds it
+    // resists, in the same order. Making and resisting toxins both cost energy, so makers beat
+    // the sensitive, resisters outlast makers by not paying to make, and the sensitive outgrow
+    // resisters by not paying to resist.
+    toxin_gene: BitSet8Gene,
+    // From 0 to 1. Creatures gain it by feeding on substances that match their enzymes and
+    // spend it just living, and die when they run out.
+    energy: f32,
+}
+
+impl Creature {
+    const NEWBORN_ENERGY: f32 = 0.5;
+    // Energy gained per step on a fully matching substance.
+    const FEEDING_ENERGY: f32 = 0.1;
+    // Energy spent per step.
+    const METABOLIC_ENERGY: f32 = 0.04;
+    // Creatures with less energy than this are needy.
+    const NEEDY_ENERGY: f32 = 0.25;
+    // The fraction of its energy a fully sharing creature would give away per step if all of
+    // its neighbors were needy kin.
+    const MAX_SHARED_FRACTION: f32 = 0.5;
+    // Energy a creature loses per step for each kind of toxin it doesn't resist that each of
+    // its neighbors makes.
+    const TOXIN_DAMAGE: f32 = 0.05;
+    // Energy spent per step for each kind of toxin made or resisted.
+    const TOXIN_COST: f32 = 0.01;
+    const RESISTANCE_COST: f32 = 0.005;
+
+    pub fn new(
+        enzyme_gene: BitSet8Gene,
+        match_weight_gene: FractionGene,
+        vision_gene: BitSet8Gene,
+        sharing_gene: BitSet8Gene,
+        toxin_gene: BitSet8Gene,
+    ) -> Self {
+        Self {
+            enzyme_gene,
+            match_weight_gene,
+            vision_gene,
+            sharing_gene,
+            toxin_gene,
+            energy: Self::NEWBORN_ENERGY,
+        }
+    }
+
+    // From 0 to 1.
+    fn vision(&self) -> f64 {
+        self.vision_gene.value.count_ones() as f64 / 8.0
+    }
+
+    // From 0 to 1.
+    fn sharing(&self) -> f32 {
+        self.sharing_gene.value.count_ones() as f32 / 8.0
+    }
+
+    fn toxins(&self) -> u8 {
+        self.toxin_gene.value.bits & 0x0f
+    }
+
+    fn resistances(&self) -> u8 {
+        self.toxin_gene.value.bits >> 4
+    }
+
+    fn toxin_upkeep(&self) -> f32 {
+        Self::TOXIN_COST * self.toxins().count_ones() as f32
+            + Self::RESISTANCE_COST * self.resistances().count_ones() as f32
+    }
+
+    fn toxin_damage_to(&self, other: &Self) -> f32 {
+        Self::TOXIN_DAMAGE * (self.toxins() & !other.resistances()).count_ones() as f32
+    }
+
+    fn is_kin(&self, other: &Self) -> bool {
+        8 - self
+            .enzyme_gene
+            .value
+            .count_matching_bits(other.enzyme_gene.value)
+            <= KIN_MAX_BIT_DIFFERENCE
+    }
+
+    // None if the creature dies.
+    fn next_state(
+        &self,
+        substance: &Option<Substance>,
+        neighborhood: &Neighborhood<EvoSubstanceCell>,
+        rand: &mut Random,
+    ) -> Option<Self> {
+        if !self.survives(substance, rand) {
+            return None;
+        }
+        let mut energy = self.energy - Self::METABOLIC_ENERGY - self.toxin_upkeep()
+            + Self::FEEDING_ENERGY * self.enzyme_match_odds(substance, 0.0, rand) as f32;
+        neighborhood.for_neighbor_cells(|neighbor| {
+            if let Some(other) = neighbor.creature {
+                energy += other.gift_to(self) - self.gift_to(&other) - other.toxin_damage_to(self);
+            }
+        });
+        (energy > 0.0).then_some(Self {
+            energy: energy.min(1.0),
+            ..*self
+        })
+    }
+
+    // The energy this creature gives another one next to it each step, if the other is needy
+    // kin and this one isn't needy itself. Cells can only write their own next states, so
+    // the giver and the receiver each work out the gift from their current states, the giver
+    // taking it out of its own energy and the receiver adding it to its own. (Updates that
+    // don't run all the cells off the same current states, e.g. random sequential ones, can
+    // make the two disagree.)
+    fn gift_to(&self, other: &Self) -> f32 {
+        if self.energy >= Self::NEEDY_ENERGY
+            && other.energy < Self::NEEDY_ENERGY
+            && self.is_kin(other)
+        {
+            self.sharing() * Self::MAX_SHARED_FRACTION / 8.0 * self.energy
+        } else {
+            0.0
+        }
+    }
+
+    pub fn color_rgba(&self) -> [u8; 4] {
+        let (high, low) = self.enzyme_gene.value.nybbles();
+        let red = high;
+        let green = low;
+        let blue = 0x10;
+        PARAMS.get().color_rgba([red, green, blue])
+    }
+
+    pub fn survives(&self, substance: &Option<Substance>, rand: &mut Random) -> bool {
+        let odds = self.enzyme_match_odds(substance, PARAMS.get().survival_odds, rand)
+            * (1.0 - PARAMS.get().vision_cost * self.vision());
+        rand.next_bool(odds)
+    }
+
+    pub fn maybe_reproduce(
+        neighborhood: &Neighborhood<EvoSubstanceCell>,
+        center_substance: &Option<Substance>,
+        rand: &mut Option<Random>,
+        mutation_odds: f64,
+    ) -> Option<Creature> {
+        Self::merge_parent_genes(neighborhood, center_substance, rand, mutation_odds)
+    }
+
+    fn merge_parent_genes(
+        neighborhood: &Neighborhood<EvoSubstanceCell>,
+        center_substance: &Option<Substance>,
+        rand: &mut Option<Random>,
+        mutation_odds: f64,
+    ) -> Option<Creature> {
+        let neighbors: ArrayVec<EvoSubstanceCell, 8> = neighborhood.neighbors().copied().collect();
+        let mut parent_enzyme_genes = ArrayVec::<BitSet8Gene, 8>::new();
+        let mut parent_match_weight_genes = ArrayVec::<FractionGene, 8>::new();
+        let mut parent_vision_genes = ArrayVec::<BitSet8Gene, 8>::new();
+        let mut parent_sharing_genes = ArrayVec::<BitSet8Gene, 8>::new();
+        let mut parent_toxin_genes = ArrayVec::<BitSet8Gene, 8>::new();
+        for neighbor in &neighbors {
+            if let Some(creature) = neighbor.creature
+                && creature.chooses_to_reproduce(
+                    &neighbor.substance,
+                    center_substance,
+                    &neighbors,
+                    rand.as_mut().unwrap(),
+                )
+            {
+                parent_enzyme_genes.push(creature.enzyme_gene);
+                parent_match_weight_genes.push(creature.match_weight_gene);
+                parent_vision_genes.push(creature.vision_gene);
+                parent_sharing_genes.push(creature.sharing_gene);
+                parent_toxin_genes.push(creature.toxin_gene);
+            }
+        }
+
+        if parent_enzyme_genes.is_empty() {
+            None
+        } else {
+            Some(Creature::new(
+                BitSet8Gene::merge(&parent_enzyme_genes, rand, mutation_odds),
+                FractionGene::merge(&parent_match_weight_genes, rand, mutation_odds),
+                BitSet8Gene::merge(&parent_vision_genes, rand, mutation_odds),
+                BitSet8Gene::merge(&parent_sharing_genes, rand, mutation_odds),
+                BitSet8Gene::merge(&parent_toxin_genes, rand, mutation_odds),
+            ))
+        }
+    }
+
+    // Blind creatures go by the substances in their own cell and the target cell alone. The
+    // more a creature sees, the more its odds also rise or fall with how promising the
+    // target's surroundings look (see Sighting::prospect). Creatures with less energy than a
+    // newborn are less likely to reproduce.
+    fn chooses_to_reproduce(
+        &self,
+        own_cell_substance: &Option<Substance>,
+        target_cell_substance: &Option<Substance>,
+        target_neighbors: &[EvoSubstanceCell],
+        rand: &mut Random,
+    ) -> bool {
+        let own_odds = self.enzyme_match_odds(own_cell_substance, PARAMS.get().repro_odds, rand);
+        let mut odds =
+            own_odds * self.enzyme_match_odds(target_cell_substance, PARAMS.get().repro_odds, rand);
+        let vision = self.vision();
+        if vision > 0.0 {
+            let sighting = Sighting::sense(self, target_neighbors, rand);
+            odds = (odds * (1.0 + vision * (2.0 * sighting.prospect(own_odds) - 1.0))).min(1.0);
+        }
+        odds *= (self.energy / Self::NEWBORN_ENERGY).min(1.0) as f64;
+        rand.next_bool(odds)
+    }
+
+    fn enzyme_match_odds(
+        &self,
+        substance: &Option<Substance>,
+        default_odds: f64,
+        rand: &mut Random,
+    ) -> f64 {
+        substance.map_or(default_odds, |substance| {
+            let enzyme = self
+                .enzyme_gene
+                .expressed(PARAMS.get().expression_noise, rand);
+            substance.match_fraction(enzyme)
+        })
+    }
+}
+
+// A summary of the cells around a cell a creature might reproduce into.
+#[derive(Clone, Copy, Debug)]
+struct Sighting {
+    // Creatures there that are kin to the viewer (see Creature::is_kin), which includes the
+    // viewer itself.
+    kin: u32,
+    strangers: u32,
+    // How well the substances there match the viewer's enzyme, on average, as odds (see
+    // Creature::enzyme_match_odds).
+    substance_odds: f64,
+}
+
+impl Sighting {
+    fn sense(viewer: &Creature, cells: &[EvoSubstanceCell], rand: &mut Random) -> Self {
+        let mut result = Self {
+            kin: 0,
+            strangers: 0,
+            substance_odds: 0.0,
+        };
+        for cell in cells {
+            if let Some(creature) = cell.creature {
+                if creature.is_kin(viewer) {
+                    result.kin += 1;
+                } else {
+                    result.strangers += 1;
+                }
+            }
+            result.substance_odds +=
+                viewer.enzyme_match_odds(&cell.substance, PARAMS.get().repro_odds, rand);
+        }
+        result.substance_odds /= cells.len().max(1) as f64;
+        result
+    }
+
+    // From 0 to 1, with 0.5 for no better or worse than staying put: higher the further up
+    // the gradient of matching substance from the viewer's own cell, whose odds are given, and
+    // the more kin outnumber strangers.
+    fn prospect(&self, own_odds: f64) -> f64 {
+        let uphill = 0.5 + (self.substance_odds - own_odds) / 2.0;
+        let kinship = (self.kin + 1) as f64 / (self.kin + self.strangers + 2) as f64;
+        (uphill + kinship) / 2.0
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Substance {
+    code: BitSet8,
+}
+
+impl Substance {
+    pub fn new(code: BitSet8) -> Self {
+        Self { code }
+    }
+
+    pub fn color_rgba(&self) -> [u8; 4] {
+        let (high, low) = self.code.nybbles();
+        let red = 0x40;
+        let green = high >> 1;
+        let blue = low >> 1;
+        PARAMS.get().color_rgba([red, green, blue])
+    }
+
+    fn match_fraction(&self, bits: BitSet8) -> f64 {
+        self.code.count_matching_bits(bits) as f64 / 8.0
+    }
+}
+
+#[derive(Debug)]
+pub struct EvoSubstanceParams {
+    // Odds of survival and reproduction in cells without substance.
+    survival_odds: f64,
+    repro_odds: f64,
+    // Mutation odds in the coldest cells, which rise geometrically with temperature to
+    // hot_mutation_factor times that in the hottest cells.
+    mutation_odds: f64,
+    hot_mutation_factor: f64,
+    // Odds of each enzyme gene bit reading flipped each time it's read.
+    expression_noise: f64,
+    // Which of a computed color's channels goes into each of red, green, and blue.
+    channel_order: [u8; 3],
+    // How much a fully sighted creature's survival odds drop (see Creature::vision_gene).
+    vision_cost: f64,
+}
+
+impl EvoSubstanceParams {
+    fn mutation_odds_at(&self, temperature: f32) -> f64 {
+        (self.mutation_odds * strict_math::powf(self.hot_mutation_factor, temperature as f64))
+            .min(1.0)
+    }
+
+    fn color_rgba(&self, channels: [u8; 3]) -> [u8; 4] {
+        let [red, green, blue] = self.channel_order.map(|channel| channels[channel as usize]);
+        [red, green, blue, 0xff]
+    }
+}
+
+impl Default for EvoSubstanceParams {
+    fn default() -> Self {
+        Self {
+            survival_odds: 0.5,
+            repro_odds: 0.3,
+            mutation_odds: 0.001,
+            hot_mutation_factor: 10.0,
+            expression_noise: 0.0,
+            channel_order: [0, 1, 2],
+            vision_cost: 0.05,
+        }
+    }
+}
+
+impl WorldParams for EvoSubstanceParams {
+    fn random(rand: &mut Random) -> Self {
+        Self {
+            survival_odds: rand.next_in_range(0.3..0.7),
+            repro_odds: rand.next_in_range(0.1..0.5),
+            mutation_odds: 10f64.powf(rand.next_in_range(-4.0..-2.0)),
+            hot_mutation_factor: 10f64.powf(rand.next_in_range(0.0..2.0)),
+            expression_noise: 10f64.powf(rand.next_in_range(-4.0..-1.0)),
+            channel_order: rand.shuffle_color_rgb([0, 1, 2]),
+            vision_cost: rand.next_in_range(0.0..0.2),
+        }
+    }
+}
+
+impl fmt::Display for EvoSubstanceParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "survival_odds={:.2} repro_odds={:.2} mutation_odds={:.5} hot_mutation_factor={:.1} \
+             expression_noise={:.5} channel_order={:?} vision_cost={:.3}",
+            self.survival_odds,
+            self.repro_odds,
+            self.mutation_odds,
+            self.hot_mutation_factor,
+            self.expression_noise,
+            self.channel_order,
+            self.vision_cost
+        )
+    }
+}
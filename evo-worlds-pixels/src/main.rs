@@ -0,0 +1,135 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use ant_colony_pixels::AntWorld;
+use conway_life_pixels::ConwayWorld;
+use evo_conway_life_pixels::EvoConwayWorld;
+use evo_grid::EvoWorld;
+use evo_substance_pixels::EvoSubstanceWorld;
+use pixels_main_support::{AppOptions, WorldApp, world_params};
+use prisoners_dilemma_pixels::PdWorld;
+use rock_paper_scissors_pixels::RpsWorld;
+use std::io::{self, BufRead, Write};
+use wireworld_pixels::WireworldWorld;
+use world_grid::{AnyWorld, DynWorld, GridSize, Random};
+
+// The worlds to choose from, with the settings their own binaries use.
+const WORLD_CHOICES: [WorldChoice; 8] = [
+    WorldChoice {
+        name: "conway",
+        time_step_frames: 4,
+        cell_pixel_width: 4,
+        init_params: |rand| conway_life_pixels::PARAMS.init(|| world_params(rand)),
+        build: |grid_size, rand| Box::new(ConwayWorld::new(grid_size, rand)),
+    },
+    WorldChoice {
+        name: "evo-conway",
+        time_step_frames: 20,
+        cell_pixel_width: 4,
+        init_params: |rand| evo_conway_life_pixels::PARAMS.init(|| world_params(rand)),
+        build: |grid_size, rand| Box::new(EvoConwayWorld::new(grid_size, rand)),
+    },
+    WorldChoice {
+        name: "evo-substance",
+        time_step_frames: 2,
+        cell_pixel_width: 4,
+        init_params: |rand| evo_substance_pixels::PARAMS.init(|| world_params(rand)),
+        build: |grid_size, rand| Box::new(EvoSubstanceWorld::new(grid_size, rand)),
+    },
+    WorldChoice {
+        name: "evo-grid",
+        time_step_frames: 60,
+        cell_pixel_width: 3,
+        init_params: |rand| evo_grid::PARAMS.init(|| world_params(rand)),
+        build: |grid_size, rand| Box::new(EvoWorld::new(grid_size, rand)),
+    },
+    WorldChoice {
+        name: "rock-paper-scissors",
+        time_step_frames: 2,
+        cell_pixel_width: 2,
+        init_params: |rand| rock_paper_scissors_pixels::PARAMS.init(|| world_params(rand)),
+        build: |grid_size, rand| Box::new(RpsWorld::new(grid_size, rand)),
+    },
+    WorldChoice {
+        name: "prisoners-dilemma",
+        time_step_frames: 10,
+        cell_pixel_width: 4,
+        init_params: |rand| prisoners_dilemma_pixels::PARAMS.init(|| world_params(rand)),
+        build: |grid_size, rand| Box::new(PdWorld::new(grid_size, rand)),
+    },
+    WorldChoice {
+        name: "ant-colony",
+        time_step_frames: 2,
+        cell_pixel_width: 3,
+        init_params: |rand| ant_colony_pixels::PARAMS.init(|| world_params(rand)),
+        build: |grid_size, rand| Box::new(AntWorld::new(grid_size, rand)),
+    },
+    WorldChoice {
+        name: "wireworld",
+        time_step_frames: 4,
+        cell_pixel_width: 4,
+        init_params: |rand| wireworld_pixels::PARAMS.init(|| world_params(rand)),
+        build: |grid_size, rand| Box::new(WireworldWorld::new(grid_size, rand)),
+    },
+];
+
+struct WorldChoice {
+    name: &'static str,
+    time_step_frames: u32,
+    cell_pixel_width: u32,
+    init_params: fn(&mut Random),
+    build: fn(GridSize, Random) -> Box<dyn AnyWorld>,
+}
+
+// Runs the world named by --world, or else the one picked from a menu, in place of the
+// separate binaries for each world.
+fn main() {
+    let choice = match AppOptions::from_args().world_name {
+        Some(name) => find_choice(&name).unwrap_or_else(|| {
+            panic!(
+                "Unknown world {}; choose from {}",
+                name,
+                choice_names().join(", ")
+            )
+        }),
+        None => choose_from_menu(),
+    };
+    WorldApp::new(|grid_size, rand| DynWorld::new((choice.build)(grid_size, rand)))
+        .with_time_step_frames(choice.time_step_frames)
+        .with_cell_pixel_width(choice.cell_pixel_width)
+        .with_params(choice.init_params)
+        .run();
+}
+
+fn find_choice(name: &str) -> Option<&'static WorldChoice> {
+    WORLD_CHOICES.iter().find(|choice| choice.name == name)
+}
+
+fn choice_names() -> Vec<&'static str> {
+    WORLD_CHOICES.iter().map(|choice| choice.name).collect()
+}
+
+// Asks on the console until given a world's number or name.
+fn choose_from_menu() -> &'static WorldChoice {
+    println!("Worlds:");
+    for (index, choice) in WORLD_CHOICES.iter().enumerate() {
+        println!("  {}. {}", index + 1, choice.name);
+    }
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        print!("Choose a world: ");
+        io::stdout().flush().unwrap();
+        let line = lines.next().expect("No world chosen").unwrap();
+        let answer = line.trim();
+        let choice = match answer.parse::<usize>() {
+            Ok(number) => number
+                .checked_sub(1)
+                .and_then(|index| WORLD_CHOICES.get(index)),
+            Err(_) => find_choice(answer),
+        };
+        match choice {
+            Some(choice) => return choice,
+            None => println!("No world {}", answer),
+        }
+    }
+}
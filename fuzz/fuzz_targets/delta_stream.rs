@@ -0,0 +1,14 @@
+#![no_main]
+
+use evo_grid::EvoGridCell;
+use libfuzzer_sys::fuzz_target;
+use world_grid::DeltaStream;
+
+// Malformed delta streams should be rejected with errors, not panics or runaway allocations.
+fuzz_target!(|bytes: &[u8]| {
+    if let Ok(stream) = DeltaStream::parse::<EvoGridCell>(bytes) {
+        stream
+            .for_each_grid::<EvoGridCell, _>(|_grid| ())
+            .unwrap();
+    }
+});
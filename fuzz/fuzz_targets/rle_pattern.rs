@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use world_grid::Pattern;
+
+// Malformed RLE files should be rejected with errors, not panics or runaway allocations.
+fuzz_target!(|text: &str| {
+    if let Ok(pattern) = Pattern::parse_rle(text) {
+        pattern.trimmed();
+    }
+});
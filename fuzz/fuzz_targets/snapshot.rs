@@ -0,0 +1,12 @@
+#![no_main]
+
+use evo_grid::EvoGridCell;
+use libfuzzer_sys::fuzz_target;
+use world_grid::WorldGrid;
+
+// Malformed snapshots should be rejected with errors, not panics or runaway allocations.
+fuzz_target!(|bytes: &[u8]| {
+    if let Ok(grid) = WorldGrid::<EvoGridCell>::decode_snapshot(bytes) {
+        assert_eq!(grid.encode_snapshot(), bytes);
+    }
+});
@@ -0,0 +1,724 @@
+mod quick_save;
+mod selection;
+mod tools;
+mod view;
+
+use crate::breakpoint::{Breakpoints, report_breakpoint};
+use crate::camera::Camera;
+use crate::edit_checks::{allows_action, check_edit_options};
+use crate::frontend::{Frame, Frontend};
+use crate::heredity::{HeredityView, modal_genome};
+use crate::input::{InputEvent, KeyAction};
+use crate::metrics::{RunMetrics, world_stats};
+use crate::mouse_tool::{self, MouseTool};
+use crate::options::{AppOptions, exit_with_usage_error, load_option_file};
+use crate::pixel_buffer::PixelBuffer;
+use crate::probes::{
+    add_selection_pressure, after_selection_update, initial_selection_probes,
+    report_expired_selection_pressures,
+};
+use crate::remote::{RemoteCommand, RemoteControl, RemoteResponse};
+use crate::replay::{ReplayPlayer, ReplayRecorder, UserAction};
+use crate::run_outputs::RunOutputs;
+use crate::save_slots::SaveSlots;
+use crate::speed_schedule::{SpeedChange, SpeedSchedule};
+use crate::watch::CellWatch;
+use crate::window_frontend::WindowFrontend;
+use crate::{grid_size, place_initial_pattern, warm_up};
+use pixels::wgpu::Color;
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use winit::dpi::PhysicalPosition;
+use winit::event_loop::ActiveEventLoop;
+use winit::window::{Cursor, CursorIcon, Fullscreen, Window};
+use world_grid::{EditHistory, GridSize, Loc, Random, Seed, SelectionProbes, World};
+
+const BACKGROUND_COLOR: Color = Color::BLACK;
+// Time for one frame at 60 FPS.
+const FRAME_BUDGET: Duration = Duration::from_micros(16_667);
+
+// The state of a windowed run: the world, how it's shown, and what the user is doing to it.
+// Its handling of the mouse tools, the selection, the save slots, and the view options is in
+// the modules below.
+pub struct App<W: World> {
+    pub world: W,
+    pub frontend: WindowFrontend,
+    pixel_buffer: PixelBuffer,
+    time_step_frame: u32,
+    time_step_frames: u32,
+    paused: bool,
+    fast_forward: bool,
+    pub run_metrics: RunMetrics,
+    recorder: Option<ReplayRecorder>,
+    player: Option<ReplayPlayer>,
+    remote: Option<RemoteControl>,
+    pub run_outputs: RunOutputs,
+    seed_bank_dir: PathBuf,
+    save_slots: SaveSlots,
+    seed: Option<Seed>,
+    seed_count: usize,
+    mouse_tool: MouseTool,
+    last_drag_loc: Option<Loc>,
+    dragged_source: Option<usize>,
+    // The corner where the selection drag started, and the opposite corner.
+    selection: Option<(Loc, Loc)>,
+    // Edits since the last update, for undo and redo.
+    edit_history: EditHistory,
+    speed_schedule: SpeedSchedule,
+    fast_forward_steps: u64,
+    // Updates since the world was last loaded into the cross-fade buffer.
+    steps_since_load: u32,
+    // Whether the last update took longer than a frame.
+    lagging: bool,
+    camera: Camera,
+    selection_probes: SelectionProbes,
+    // Whether single steps go a phase of the update at a time (see World::update_phase).
+    phase_stepping: bool,
+    // The next phase to step to, or 0 if not partway through an update.
+    next_phase: usize,
+    breakpoints: Breakpoints,
+    // The cell of the breakpoint that last paused the simulation, until the next update.
+    highlighted: Option<Loc>,
+    pub watch: CellWatch,
+    // The most common genome when the world started, for the heredity view.
+    initial_genome: Option<u64>,
+    // Seconds per full rotation of substance hues, if they cycle, and when the cycling began.
+    palette_cycle_secs: Option<f32>,
+    palette_cycle_start: Instant,
+}
+
+impl<W: World> App<W> {
+    pub fn new<F>(
+        event_loop: &ActiveEventLoop,
+        options: &AppOptions,
+        time_step_frames: u32,
+        cell_pixel_width: u32,
+        selection_rand: Random,
+        build_world: &F,
+    ) -> Self
+    where
+        F: Fn(GridSize) -> W,
+    {
+        let window = Arc::new(Self::build_window(event_loop, options));
+        let window_size = options.world_size.unwrap_or(window.inner_size());
+        let mut world = build_world(grid_size(options, window_size, cell_pixel_width));
+        check_edit_options(&world, options);
+        let seed = options
+            .seed_path
+            .as_deref()
+            .map(|path| load_option_file(path, Seed::load));
+        place_initial_pattern(&mut world, options, seed.as_ref());
+        let pixel_buffer = PixelBuffer::new(world.grid().size(), window.inner_size());
+        let frontend = WindowFrontend::new(window, pixel_buffer.size, BACKGROUND_COLOR);
+        let run_outputs = RunOutputs::new(options, world.grid());
+        let breakpoints = Breakpoints::new(&options.breakpoints, &world);
+        let watch = CellWatch::new(options, world.grid());
+        let initial_genome = modal_genome(world.grid().cells_iter().as_slice());
+        Self {
+            world,
+            frontend,
+            pixel_buffer,
+            time_step_frame: 0,
+            time_step_frames,
+            paused: false,
+            fast_forward: false,
+            run_metrics: RunMetrics::new(),
+            recorder: options
+                .record_path
+                .as_deref()
+                .map(|path| load_option_file(path, ReplayRecorder::create)),
+            player: options
+                .replay_path
+                .as_deref()
+                .map(|path| load_option_file(path, ReplayPlayer::load)),
+            remote: options.http_port.map(RemoteControl::start),
+            run_outputs,
+            seed_bank_dir: options.seed_bank_dir.clone(),
+            save_slots: SaveSlots::new(&options.save_slots_dir),
+            seed,
+            seed_count: options.seed_count,
+            mouse_tool: MouseTool::default(),
+            last_drag_loc: None,
+            dragged_source: None,
+            selection: None,
+            edit_history: EditHistory::default(),
+            speed_schedule: SpeedSchedule::new(&options.speed_changes),
+            fast_forward_steps: options.fast_forward_steps,
+            steps_since_load: 0,
+            lagging: false,
+            camera: Camera::new(options.zoom, options.follow),
+            selection_probes: initial_selection_probes(options, selection_rand),
+            phase_stepping: false,
+            next_phase: 0,
+            breakpoints,
+            highlighted: None,
+            watch,
+            initial_genome,
+            palette_cycle_secs: options.palette_cycle_secs,
+            palette_cycle_start: Instant::now(),
+        }
+    }
+
+    fn build_window(event_loop: &ActiveEventLoop, options: &AppOptions) -> Window {
+        let window_attributes = Window::default_attributes()
+            .with_cursor(Cursor::Icon(CursorIcon::Crosshair))
+            .with_fullscreen(Some(Self::fullscreen_mode(event_loop, options)))
+            .with_visible(false);
+        event_loop.create_window(window_attributes).unwrap()
+    }
+
+    fn fullscreen_mode(event_loop: &ActiveEventLoop, options: &AppOptions) -> Fullscreen {
+        let monitor = options.monitor_index.map(|index| {
+            event_loop
+                .available_monitors()
+                .nth(index)
+                .unwrap_or_else(|| exit_with_usage_error(&format!("No monitor {}", index)))
+        });
+        if !options.exclusive_fullscreen {
+            return Fullscreen::Borderless(monitor);
+        }
+
+        let monitor = monitor
+            .or_else(|| event_loop.primary_monitor())
+            .or_else(|| event_loop.available_monitors().next())
+            .expect("No monitor for exclusive fullscreen");
+        // The largest mode, at the requested refresh rate if any, else the highest one.
+        let video_mode = monitor
+            .video_modes()
+            .filter(|mode| {
+                options.refresh_rate_hz.is_none_or(|refresh_rate_hz| {
+                    (mode.refresh_rate_millihertz() + 500) / 1000 == refresh_rate_hz
+                })
+            })
+            .max_by_key(|mode| {
+                let size = mode.size();
+                (
+                    size.width * size.height,
+                    mode.refresh_rate_millihertz(),
+                    mode.bit_depth(),
+                )
+            })
+            .unwrap_or_else(|| {
+                exit_with_usage_error(&format!(
+                    "Monitor {} has no video mode at {} Hz",
+                    monitor.name().unwrap_or_default(),
+                    options.refresh_rate_hz.unwrap_or_default()
+                ))
+            });
+        Fullscreen::Exclusive(video_mode)
+    }
+
+    pub fn on_create(&mut self) {
+        self.update_world();
+        self.load_world();
+        self.frontend.fade_to(1.0);
+        self.frontend.init(self.world.grid().size());
+    }
+
+    fn toggle_paused(&mut self) {
+        self.paused ^= true;
+    }
+
+    fn toggle_fast_forward(&mut self) {
+        self.fast_forward ^= true;
+    }
+
+    fn toggle_phase_stepping(&mut self) {
+        let phase_names = self.world.update_phase_names();
+        if phase_names.is_empty() {
+            println!("This world's updates have no phases to step through");
+            return;
+        }
+        self.phase_stepping ^= true;
+        if self.phase_stepping {
+            println!("Single-stepping phases: {}", phase_names.join(", "));
+        } else {
+            println!("Single-stepping whole updates");
+        }
+    }
+
+    // Quitting is up to the caller (see handle_frontend_input).
+    fn handle_input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Key { action, pointer } => self.on_key_action(action, pointer),
+            InputEvent::PointerDragged(loc) => self.on_pointer_drag(loc),
+            InputEvent::PointerReleased(pointer) => self.on_pointer_release(pointer),
+        }
+    }
+
+    fn on_key_action(&mut self, action: KeyAction, pointer: Option<Loc>) {
+        if let Some(user_action) = action.user_action() {
+            self.perform(user_action);
+            return;
+        }
+        match action {
+            KeyAction::SaveSeed => self.on_save_seed(pointer),
+            KeyAction::CopyPattern => self.on_copy_pattern(pointer),
+            KeyAction::PastePattern => self.on_paste_pattern(pointer),
+            KeyAction::SavePattern => self.on_save_pattern(pointer),
+            KeyAction::InjectSeed => self.on_inject_seed(pointer),
+            KeyAction::RemoveSource => self.on_remove_source(pointer),
+            KeyAction::CycleCoarseGrain => self.cycle_coarse_grain(),
+            KeyAction::ToggleCoarseGrainMode => self.toggle_coarse_grain_mode(),
+            KeyAction::CycleHeredity => self.cycle_heredity(),
+            KeyAction::ReportPerformance => self.report_performance(),
+            KeyAction::CycleMouseTool => self.cycle_mouse_tool(),
+            KeyAction::CycleZoom => self.cycle_zoom(),
+            KeyAction::ToggleFollowing => self.toggle_following(),
+            KeyAction::QuickSave => self.quick_save(),
+            KeyAction::NextSaveSlot => self.next_save_slot(),
+            KeyAction::QuickLoad => self.quick_load(),
+            _ => (),
+        }
+    }
+
+    pub fn perform(&mut self, action: UserAction) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(self.run_metrics.steps(), &action);
+        }
+        self.apply(action);
+    }
+
+    fn apply(&mut self, action: UserAction) {
+        if !allows_action(&self.world, &action) {
+            eprintln!("Can't edit this world's cells");
+            return;
+        }
+        match action {
+            UserAction::TogglePaused => self.toggle_paused(),
+            UserAction::ToggleFastForward => self.toggle_fast_forward(),
+            UserAction::Restart => self.restart(),
+            UserAction::SingleStep => self.on_single_step(),
+            UserAction::TogglePhaseStepping => self.toggle_phase_stepping(),
+            UserAction::Inspect(loc) => self.inspect(loc),
+            UserAction::ToggleWatch(loc) => self.watch.toggle(loc),
+            UserAction::TogglePinned(loc) => self.toggle_pinned(loc),
+            UserAction::PastePattern {
+                loc,
+                pattern,
+                genome,
+            } => self.edit(|app| {
+                let placed = app.world.grid_mut().place_pattern(&pattern, loc, genome);
+                println!("Pasted {} live cells", placed);
+            }),
+            UserAction::InjectSeed { loc, seed, count } => {
+                self.edit(|app| app.inject_seed(loc, &seed, count))
+            }
+            UserAction::Erase(loc) => self.edit(|app| mouse_tool::erase(app.world.grid_mut(), loc)),
+            UserAction::Perturb(loc) => {
+                self.edit(|app| mouse_tool::perturb(app.world.grid_mut(), loc))
+            }
+            UserAction::EndStroke => self.edit_history.close(),
+            UserAction::Undo => {
+                if self.edit_history.undo(self.world.grid_mut()) {
+                    self.draw_world();
+                } else {
+                    println!("Nothing to undo");
+                }
+            }
+            UserAction::Redo => {
+                if self.edit_history.redo(self.world.grid_mut()) {
+                    self.draw_world();
+                } else {
+                    println!("Nothing to redo");
+                }
+            }
+            UserAction::AddSource(loc) => {
+                match self.world.sources_mut() {
+                    Some(sources) => sources.add_source(loc),
+                    None => println!("This world has no sources"),
+                }
+                self.draw_world();
+            }
+            UserAction::MoveSource { index, loc } => {
+                if let Some(sources) = self.world.sources_mut() {
+                    sources.move_source(index, loc);
+                }
+                self.draw_world();
+            }
+            UserAction::RemoveSource(index) => {
+                if let Some(sources) = self.world.sources_mut() {
+                    sources.remove_source(index);
+                }
+                self.draw_world();
+            }
+            UserAction::ApplySelection(pressure) => {
+                add_selection_pressure(&mut self.selection_probes, pressure);
+            }
+        }
+    }
+
+    // Applies an edit of the grid's cells, recording it for undo. It stays open, so that the
+    // edits of a paint stroke undo together, until an EndStroke.
+    fn edit<F: FnOnce(&mut Self)>(&mut self, f: F) {
+        self.world.grid_mut().begin_edit();
+        f(self);
+        let edit = self.world.grid_mut().end_edit();
+        self.edit_history.push(edit, true);
+        self.draw_world();
+    }
+
+    fn end_stroke(&mut self) {
+        if self.edit_history.is_open() {
+            self.perform(UserAction::EndStroke);
+        }
+    }
+
+    fn apply_replay_actions(&mut self) {
+        while let Some(action) = self
+            .player
+            .as_mut()
+            .and_then(|player| player.next_due(self.run_metrics.steps()))
+        {
+            self.apply(action);
+        }
+    }
+
+    fn handle_remote_requests(&mut self) {
+        while let Some(request) = self
+            .remote
+            .as_ref()
+            .and_then(|remote| remote.next_request())
+        {
+            let response = self.execute_remote_command(&request.command);
+            request.respond(response);
+        }
+    }
+
+    fn execute_remote_command(&mut self, command: &RemoteCommand) -> RemoteResponse {
+        match command {
+            RemoteCommand::Pause => {
+                if !self.paused {
+                    self.perform(UserAction::TogglePaused);
+                }
+                RemoteResponse::ok()
+            }
+            RemoteCommand::Resume => {
+                if self.paused {
+                    self.perform(UserAction::TogglePaused);
+                }
+                RemoteResponse::ok()
+            }
+            RemoteCommand::Step => {
+                self.perform(UserAction::SingleStep);
+                RemoteResponse::ok()
+            }
+            RemoteCommand::SetParam { name, value } => match self.set_param(name, value) {
+                Ok(()) => RemoteResponse::ok(),
+                Err(message) => RemoteResponse::error(&message),
+            },
+            RemoteCommand::Snapshot => RemoteResponse::json(self.status_json()),
+            RemoteCommand::Metrics => RemoteResponse::prometheus(
+                self.run_metrics.prometheus_text(&world_stats(&self.world)),
+            ),
+            RemoteCommand::Select(pressure) => {
+                self.perform(UserAction::ApplySelection(*pressure));
+                RemoteResponse::ok()
+            }
+            RemoteCommand::AddBreakpoint(breakpoint) => {
+                self.breakpoints.add(breakpoint.clone(), &self.world);
+                RemoteResponse::ok()
+            }
+            RemoteCommand::Watch(loc) => {
+                self.perform(UserAction::ToggleWatch(*loc));
+                RemoteResponse::ok()
+            }
+            RemoteCommand::Unknown(target) => {
+                RemoteResponse::error(&format!("Unknown command {}", target))
+            }
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let invalid_value = || format!("Invalid value for {}: {}", name, value);
+        match name {
+            "time_step_frames" => {
+                self.time_step_frames = value.parse().map_err(|_| invalid_value())?;
+            }
+            "fast_forward" => {
+                if value.parse::<bool>().map_err(|_| invalid_value())? != self.fast_forward {
+                    self.perform(UserAction::ToggleFastForward);
+                }
+            }
+            _ => return Err(format!("Unknown param {}", name)),
+        }
+        Ok(())
+    }
+
+    fn status_json(&self) -> String {
+        let size = self.world.grid().size();
+        let stats = self.world.step_stats();
+        format!(
+            "{{\"steps\":{},\"generation\":{},\"live_cells\":{},\"paused\":{},\"fast_forward\":{},\"time_step_frames\":{},\"lagging\":{},\"width\":{},\"height\":{}}}",
+            self.run_metrics.steps(),
+            stats.generation,
+            stats.live_cells,
+            self.paused,
+            self.fast_forward,
+            self.time_step_frames,
+            self.lagging,
+            size.width,
+            size.height
+        )
+    }
+
+    // Returns false if the frontend quit.
+    pub fn on_frame(&mut self) -> bool {
+        if !self.handle_frontend_input() {
+            return false;
+        }
+        self.handle_remote_requests();
+        self.apply_replay_actions();
+        self.apply_speed_schedule();
+        if self.time_step_frame < self.time_step_frames {
+            if self.paused || self.fast_forward {
+                self.end_cross_fade();
+            } else {
+                self.on_cross_fade_frame();
+            }
+        } else if !self.paused {
+            if self.fast_forward {
+                self.on_fast_forward_frame();
+            } else {
+                self.on_time_step_frame();
+            }
+        }
+        true
+    }
+
+    // Returns false if the frontend quit.
+    fn handle_frontend_input(&mut self) -> bool {
+        for input in self.frontend.poll_input() {
+            if let InputEvent::Key {
+                action: KeyAction::Quit,
+                ..
+            } = input
+            {
+                return false;
+            }
+            self.handle_input(input);
+        }
+        true
+    }
+
+    fn apply_speed_schedule(&mut self) {
+        for change in self.speed_schedule.due(self.run_metrics.steps()) {
+            match change {
+                SpeedChange::FastForward => self.fast_forward = true,
+                SpeedChange::RealTime => self.fast_forward = false,
+                SpeedChange::Pause => self.paused = true,
+            }
+        }
+    }
+
+    fn end_cross_fade(&mut self) {
+        self.time_step_frame = self.time_step_frames;
+        self.frontend.fade_to(1.0);
+    }
+
+    fn on_cross_fade_frame(&mut self) {
+        self.frontend
+            .fade_to(self.time_step_frame as f32 / self.time_step_frames as f32);
+        self.time_step_frame += 1;
+    }
+
+    fn on_time_step_frame(&mut self) {
+        self.update_world();
+        self.load_world();
+        self.time_step_frame = 0;
+        self.frontend.request_redraw();
+    }
+
+    // Takes as many steps as fit in the frame budget, but at least one, so that fast forward
+    // runs flat out without dropping the UI below 60 FPS unless a single step is too slow.
+    fn on_fast_forward_frame(&mut self) {
+        let start = Instant::now();
+        loop {
+            self.update_world();
+            self.apply_replay_actions();
+            self.apply_speed_schedule();
+            if self.paused
+                || !self.fast_forward
+                || start.elapsed() + self.run_metrics.last_update() > FRAME_BUDGET
+            {
+                break;
+            }
+        }
+        self.draw_world();
+    }
+
+    fn on_single_step(&mut self) {
+        self.paused = true;
+        if self.phase_stepping {
+            self.step_phase();
+        } else {
+            self.update_and_draw();
+        }
+    }
+
+    fn step_phase(&mut self) {
+        let phase_names = self.world.update_phase_names();
+        let phase = self.next_phase;
+        println!(
+            "Step {} phase {} of {}: {}",
+            self.run_metrics.steps() + 1,
+            phase + 1,
+            phase_names.len(),
+            phase_names[phase]
+        );
+        if phase + 1 == phase_names.len() {
+            self.update_world();
+        } else {
+            self.world.update_phase(phase);
+            self.next_phase += 1;
+        }
+        self.draw_world();
+    }
+
+    fn restart(&mut self) {
+        self.world.reset();
+        warm_up(&mut self.world, self.fast_forward_steps);
+        self.initial_genome = modal_genome(self.world.grid().cells_iter().as_slice());
+        self.save_slots.forget_current();
+        if let Some(heredity) = self.pixel_buffer.heredity {
+            self.pixel_buffer.set_heredity(Some(HeredityView {
+                genome: self.initial_genome,
+                ..heredity
+            }));
+        }
+        self.edit_history.clear();
+        self.next_phase = 0;
+        self.update_and_draw();
+    }
+
+    fn update_and_draw(&mut self) {
+        self.update_world();
+        self.draw_world();
+    }
+
+    fn load_world(&mut self) {
+        if let Some(secs) = self.palette_cycle_secs {
+            self.pixel_buffer
+                .set_hue_turns(self.palette_cycle_start.elapsed().as_secs_f32() / secs);
+        }
+        let grid = self.world.grid();
+        // The changed cells only cover the last update.
+        match grid.changed_cells() {
+            Some(changed_cells) if self.steps_since_load <= 1 => self
+                .pixel_buffer
+                .load_changes(grid.cells_iter(), changed_cells),
+            _ => self.pixel_buffer.load(grid.cells_iter()),
+        }
+        self.steps_since_load = 0;
+        self.pixel_buffer.mark(
+            grid.cells_iter(),
+            &source_locs(&self.world),
+            self.highlighted,
+            self.lagging,
+        );
+        if self.camera.following() {
+            self.camera
+                .follow(&grid.find_objects(), self.pixel_buffer.covered_size());
+        }
+        self.frontend.present(&Frame {
+            step: self.run_metrics.steps(),
+            size: self.pixel_buffer.size,
+            pixels: Cow::Borrowed(&self.pixel_buffer.pixels),
+            changed_rows: self.pixel_buffer.changed_rows.as_deref(),
+            stats: self.world.step_stats(),
+        });
+    }
+
+    fn draw_world(&mut self) {
+        self.load_world();
+        self.frontend.fade_to(1.0);
+    }
+
+    fn update_world(&mut self) {
+        let start = Instant::now();
+        self.selection_probes.before_update(self.world.grid());
+        if self.next_phase > 0 {
+            // Finishes the update that was partway through.
+            let last_phase = self.world.update_phase_names().len() - 1;
+            self.world.update_phase(last_phase);
+            self.next_phase = 0;
+        } else {
+            self.world.update();
+        }
+        // Edits can't be undone once the world has moved on from them.
+        self.edit_history.clear();
+        let expired = after_selection_update(&mut self.selection_probes, &mut self.world);
+        self.run_metrics.record_update(start.elapsed());
+        report_expired_selection_pressures(&expired);
+        self.run_outputs
+            .after_update(self.run_metrics.steps(), &self.world);
+        self.watch
+            .after_update(self.run_metrics.steps(), self.world.grid());
+        self.highlighted = None;
+        if let Some(breakpoint) = self.breakpoints.check(&self.world) {
+            report_breakpoint(&breakpoint, self.run_metrics.steps());
+            self.paused = true;
+            self.highlighted = breakpoint.loc();
+        }
+        self.steps_since_load += 1;
+        self.set_lagging(self.run_metrics.last_update() > FRAME_BUDGET);
+    }
+
+    fn set_lagging(&mut self, lagging: bool) {
+        if lagging && !self.lagging {
+            println!(
+                "Simulation lagging: update took {:?}, over the {:?} frame budget",
+                self.run_metrics.last_update(),
+                FRAME_BUDGET
+            );
+        } else if !lagging && self.lagging {
+            println!("Simulation caught up");
+        }
+        self.lagging = lagging;
+    }
+
+    pub fn window_pos_to_loc(&self, pos: PhysicalPosition<f64>) -> Option<Loc> {
+        let (col, row) = self.frontend.window_pos_to_pixel(pos)?;
+        // The pixel as if the whole world were in view, so undo the zoom.
+        let size = self.pixel_buffer.size;
+        let [x, y] = self.camera.window_to_world([
+            (col as f32 + 0.5) / size.width as f32,
+            (row as f32 + 0.5) / size.height as f32,
+        ]);
+        let (col, row) = (
+            (x * size.width as f32) as u32,
+            (y * size.height as f32) as u32,
+        );
+        let downsample = self.pixel_buffer.downsample;
+        Some(Loc::new(row * downsample, col * downsample))
+    }
+
+    fn toggle_pinned(&mut self, loc: Loc) {
+        let grid = self.world.grid_mut();
+        let pinned = !grid.is_pinned(loc);
+        grid.set_pinned(loc, pinned);
+        if pinned {
+            println!("Pinned ({}, {})", loc.row, loc.col);
+        } else {
+            println!("Unpinned ({}, {})", loc.row, loc.col);
+        }
+    }
+
+    pub fn draw(&mut self) {
+        let start = Instant::now();
+        self.frontend.draw(self.camera.view());
+        self.run_metrics.record_render(start.elapsed());
+    }
+
+    fn report_performance(&self) {
+        println!("{}", self.run_metrics.summary());
+    }
+}
+
+fn source_locs<W: World>(world: &W) -> Vec<Loc> {
+    world
+        .sources()
+        .map_or(vec![], |sources| sources.source_locs())
+}
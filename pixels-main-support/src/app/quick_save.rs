@@ -0,0 +1,46 @@
+use super::App;
+use world_grid::World;
+
+impl<W: World> App<W> {
+    pub fn quick_save(&mut self) {
+        match self
+            .save_slots
+            .save(self.world.grid(), self.run_metrics.steps())
+        {
+            Ok(path) => println!(
+                "Saved slot {} to {}",
+                self.save_slots.slot_name(),
+                path.display()
+            ),
+            Err(err) => eprintln!("Can't save slot {}: {}", self.save_slots.slot_name(), err),
+        }
+    }
+
+    pub fn quick_load(&mut self) {
+        if !self.world.is_editable() {
+            eprintln!(
+                "Can't load slot {} into this world",
+                self.save_slots.slot_name()
+            );
+            return;
+        }
+        match self.save_slots.load(self.world.grid_mut()) {
+            Ok(path) => {
+                println!(
+                    "Loaded slot {} from {}",
+                    self.save_slots.slot_name(),
+                    path.display()
+                );
+                // The loaded cells aren't the ones the edits were made to.
+                self.edit_history.clear();
+                self.draw_world();
+            }
+            Err(err) => eprintln!("Can't load slot {}: {}", self.save_slots.slot_name(), err),
+        }
+    }
+
+    pub fn next_save_slot(&mut self) {
+        self.save_slots.next_slot();
+        println!("Save slot {}", self.save_slots.slot_name());
+    }
+}
@@ -0,0 +1,104 @@
+use super::App;
+use crate::UserAction;
+use arboard::Clipboard;
+use std::fs;
+use world_grid::{GridSize, Loc, Pattern, World};
+
+// Cells from the cursor to the edge of the area that K and Ctrl+C save as a pattern when
+// nothing is selected.
+const PATTERN_SAVE_RADIUS: u32 = 16;
+
+impl<W: World> App<W> {
+    pub fn report_selection(&self) {
+        if let Some((top_left, size)) = self.selection_rect() {
+            println!(
+                "Selected {}x{} cells from ({}, {})",
+                size.width, size.height, top_left.row, top_left.col
+            );
+        }
+    }
+
+    fn selection_rect(&self) -> Option<(Loc, GridSize)> {
+        let (start, end) = self.selection?;
+        let top_left = Loc::new(start.row.min(end.row), start.col.min(end.col));
+        let size = GridSize::new(
+            start.col.abs_diff(end.col) + 1,
+            start.row.abs_diff(end.row) + 1,
+        );
+        Some((top_left, size))
+    }
+
+    // The live cells of the selection, if any, or else of the area around the cursor.
+    fn pattern_at(&self, pointer: Option<Loc>) -> Option<Pattern> {
+        let (top_left, size) = self.selection_rect().or_else(|| {
+            let loc = pointer?;
+            let top_left = Loc::new(
+                loc.row.saturating_sub(PATTERN_SAVE_RADIUS),
+                loc.col.saturating_sub(PATTERN_SAVE_RADIUS),
+            );
+            let size = GridSize::new(2 * PATTERN_SAVE_RADIUS + 1, 2 * PATTERN_SAVE_RADIUS + 1);
+            Some((top_left, size))
+        })?;
+        let pattern = self.world.grid().extract_pattern(top_left, size).trimmed();
+        if pattern.num_live() == 0 {
+            println!("No live cells to save");
+            return None;
+        }
+        Some(pattern)
+    }
+
+    // Saves the live cells of the selection or near the cursor, in the seed bank directory.
+    pub fn on_save_pattern(&mut self, pointer: Option<Loc>) {
+        let Some(loc) = pointer else {
+            return;
+        };
+        let Some(pattern) = self.pattern_at(pointer) else {
+            return;
+        };
+        fs::create_dir_all(&self.seed_bank_dir).unwrap();
+        let path = self.seed_bank_dir.join(format!(
+            "pattern-{}-{}-{}.cells",
+            self.run_metrics.steps(),
+            loc.row,
+            loc.col
+        ));
+        pattern.save(&path).unwrap();
+        println!("Saved pattern {}", path.display());
+    }
+
+    // Copies the live cells of the selection or near the cursor to the clipboard as RLE, e.g.
+    // for pasting into Golly.
+    pub fn on_copy_pattern(&mut self, pointer: Option<Loc>) {
+        let Some(pattern) = self.pattern_at(pointer) else {
+            return;
+        };
+        match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(pattern.to_rle())) {
+            Ok(()) => println!("Copied {} live cells to the clipboard", pattern.num_live()),
+            Err(err) => eprintln!("Can't copy to the clipboard: {}", err),
+        }
+    }
+
+    // Pastes an RLE pattern from the clipboard, centered on the cursor. In worlds whose
+    // creatures have genes, its live cells get the saved seed's creature, if any.
+    pub fn on_paste_pattern(&mut self, pointer: Option<Loc>) {
+        let Some(loc) = pointer else {
+            return;
+        };
+        let text = match Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+            Ok(text) => text,
+            Err(err) => {
+                eprintln!("Can't paste from the clipboard: {}", err);
+                return;
+            }
+        };
+        match Pattern::parse_rle(&text) {
+            Ok(pattern) => self.perform(UserAction::PastePattern {
+                loc,
+                pattern,
+                genome: self.seed.as_ref().map_or(0, |seed| seed.genome),
+            }),
+            Err(err) => eprintln!("Clipboard doesn't hold an RLE pattern: {}", err),
+        }
+        self.end_stroke();
+    }
+}
@@ -0,0 +1,140 @@
+use super::{App, source_locs};
+use crate::UserAction;
+use crate::genome_panel::print_genome_panel;
+use crate::mouse_tool::MouseTool;
+use std::fs;
+use winit::window::Cursor;
+use world_grid::{Loc, Seed, World};
+
+const SOURCE_PICK_RADIUS: u32 = 2;
+
+impl<W: World> App<W> {
+    pub fn cycle_mouse_tool(&mut self) {
+        self.mouse_tool = self.mouse_tool.next();
+        self.frontend
+            .window()
+            .set_cursor(Cursor::Icon(self.mouse_tool.cursor_icon()));
+        println!("Mouse tool: {}", self.mouse_tool.name());
+    }
+
+    pub fn on_pointer_release(&mut self, pointer: Option<Loc>) {
+        self.last_drag_loc = None;
+        self.dragged_source = None;
+        if let Some(loc) = pointer {
+            match self.mouse_tool.release_action(loc) {
+                Some(user_action) => self.perform(user_action),
+                None => match self.mouse_tool {
+                    MouseTool::Stamp => self.on_inject_seed(pointer),
+                    MouseTool::Select => self.report_selection(),
+                    _ => (),
+                },
+            }
+        }
+        self.end_stroke();
+    }
+
+    // Called when the button goes down, and for each move while it's down.
+    pub fn on_pointer_drag(&mut self, loc: Loc) {
+        if !self.mouse_tool.drags() {
+            return;
+        }
+        if self.last_drag_loc == Some(loc) {
+            return;
+        }
+        let pressed = self.last_drag_loc.is_none();
+        self.last_drag_loc = Some(loc);
+        match self.mouse_tool {
+            MouseTool::Paint => match self.seed.clone() {
+                Some(seed) => self.perform(UserAction::InjectSeed {
+                    loc,
+                    seed,
+                    count: 1,
+                }),
+                None => println!("No seed to paint with; save one with G first"),
+            },
+            MouseTool::Erase => self.perform(UserAction::Erase(loc)),
+            MouseTool::Source => self.on_source_drag(loc, pressed),
+            MouseTool::Select => {
+                let start = if pressed {
+                    loc
+                } else {
+                    self.selection.map_or(loc, |(start, _)| start)
+                };
+                self.selection = Some((start, loc));
+            }
+            _ => (),
+        }
+    }
+
+    // Pressing on a source picks it up so that dragging moves it; pressing anywhere else
+    // places a new source.
+    fn on_source_drag(&mut self, loc: Loc, pressed: bool) {
+        if pressed {
+            self.dragged_source = self.source_near(loc);
+            if self.dragged_source.is_none() {
+                self.perform(UserAction::AddSource(loc));
+            }
+        } else if let Some(index) = self.dragged_source {
+            self.perform(UserAction::MoveSource { index, loc });
+        }
+    }
+
+    pub fn on_remove_source(&mut self, pointer: Option<Loc>) {
+        if let Some(index) = pointer.and_then(|loc| self.source_near(loc)) {
+            self.perform(UserAction::RemoveSource(index));
+        }
+    }
+
+    // The index of the source nearest loc, if any is close enough to pick. The radius is in
+    // pixels rather than cells, so that sources stay pickable in downsampled grids.
+    fn source_near(&self, loc: Loc) -> Option<usize> {
+        let radius = (SOURCE_PICK_RADIUS * self.pixel_buffer.downsample) as i64;
+        source_locs(&self.world)
+            .iter()
+            .map(|source_loc| {
+                let row_offset = source_loc.row as i64 - loc.row as i64;
+                let col_offset = source_loc.col as i64 - loc.col as i64;
+                row_offset * row_offset + col_offset * col_offset
+            })
+            .enumerate()
+            .filter(|(_, distance_squared)| *distance_squared <= radius * radius)
+            .min_by_key(|(_, distance_squared)| *distance_squared)
+            .map(|(index, _)| index)
+    }
+
+    pub fn inspect(&self, loc: Loc) {
+        let grid = self.world.grid();
+        grid.debug_print(loc.row, loc.col);
+        print_genome_panel(grid, loc);
+    }
+
+    pub fn on_save_seed(&mut self, pointer: Option<Loc>) {
+        let Some(seed) = pointer.and_then(|loc| self.world.grid().extract_seed(loc)) else {
+            return;
+        };
+        fs::create_dir_all(&self.seed_bank_dir).unwrap();
+        let path = self.seed_bank_dir.join(seed.file_name());
+        seed.save(&path).unwrap();
+        println!("Saved seed {}", path.display());
+        self.seed = Some(seed);
+    }
+
+    pub fn on_inject_seed(&mut self, pointer: Option<Loc>) {
+        if let Some(loc) = pointer
+            && let Some(seed) = self.seed.clone()
+        {
+            self.perform(UserAction::InjectSeed {
+                loc,
+                seed,
+                count: self.seed_count,
+            });
+            self.end_stroke();
+        }
+    }
+
+    pub fn inject_seed(&mut self, loc: Loc, seed: &Seed, count: usize) {
+        if let Err(err) = self.world.grid_mut().inject_seed(seed, loc, count) {
+            eprintln!("Can't inject seed: {}", err);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use super::App;
+use crate::coarse_grain::CoarseGrain;
+use crate::heredity::HeredityView;
+use world_grid::World;
+
+impl<W: World> App<W> {
+    pub fn cycle_zoom(&mut self) {
+        self.camera.cycle_zoom();
+        println!("Zoom: {}x", self.camera.zoom());
+        self.frontend.request_redraw();
+    }
+
+    pub fn toggle_following(&mut self) {
+        self.camera.toggle_following();
+        if self.camera.following() {
+            println!("Following spaceships at {}x zoom", self.camera.zoom());
+        } else {
+            println!("Not following spaceships");
+        }
+        self.load_world();
+        self.frontend.request_redraw();
+    }
+
+    pub fn cycle_coarse_grain(&mut self) {
+        let coarse_grain = CoarseGrain::next(self.pixel_buffer.coarse_grain);
+        self.set_coarse_grain(coarse_grain);
+    }
+
+    pub fn toggle_coarse_grain_mode(&mut self) {
+        if let Some(coarse_grain) = self.pixel_buffer.coarse_grain {
+            self.set_coarse_grain(Some(coarse_grain.toggle_mode()));
+        }
+    }
+
+    pub fn set_coarse_grain(&mut self, coarse_grain: Option<CoarseGrain>) {
+        match coarse_grain {
+            Some(coarse_grain) => println!(
+                "Coarse-grained view: {}x{} blocks, {:?}",
+                coarse_grain.block_size, coarse_grain.block_size, coarse_grain.mode
+            ),
+            None => println!("Coarse-grained view off"),
+        }
+        self.pixel_buffer.set_coarse_grain(coarse_grain);
+        self.draw_world();
+    }
+
+    pub fn cycle_heredity(&mut self) {
+        let heredity = HeredityView::next(self.pixel_buffer.heredity, self.initial_genome);
+        match heredity {
+            Some(heredity) => println!(
+                "Heredity view: gene distance from the {:?} genome",
+                heredity.reference
+            ),
+            None => println!("Heredity view off"),
+        }
+        self.pixel_buffer.set_heredity(heredity);
+        self.draw_world();
+    }
+}
@@ -0,0 +1,62 @@
+use crate::landscape::FitnessProbe;
+use crate::options::AppOptions;
+use crate::snapshot_diff::SnapshotDiffReport;
+use crate::spectrum::SpectrumAnalysis;
+use crate::tournament::Tournament;
+use crate::trials::Trials;
+use world_grid::{Random, World};
+
+// Runs the tournament, trials, or analysis the options ask for, if any, on worlds from
+// build_world, and returns whether there was one to run. These runs have no window and show
+// no frames; they only report their results.
+pub fn run_batch<W, F>(options: &AppOptions, tournament_rand: Random, build_world: F) -> bool
+where
+    W: World,
+    F: Fn() -> W,
+{
+    if !options.tournament_seed_paths.is_empty() {
+        Tournament::new(
+            &options.tournament_seed_paths,
+            options.entrant_count,
+            options.generations,
+            options.rounds,
+        )
+        .run(tournament_rand, build_world);
+        return true;
+    }
+    if let Some(count) = options.trial_count {
+        Trials::new(
+            count,
+            options.generations,
+            options.trials_csv_path.clone(),
+            options.baseline_path.clone(),
+        )
+        .run(build_world);
+        return true;
+    }
+    if let Some(gene_name) = &options.probe_gene {
+        FitnessProbe::new(
+            gene_name,
+            options.probe_snapshot_path.clone(),
+            options.probe_csv_path.clone(),
+            options.generations,
+        )
+        .run(build_world());
+        return true;
+    }
+    if let Some(paths) = &options.diff_snapshot_paths {
+        SnapshotDiffReport::new(paths.clone(), options.diff_image_path.clone()).run(build_world());
+        return true;
+    }
+    if let Some(field_name) = &options.spectrum_field {
+        SpectrumAnalysis::new(
+            field_name,
+            options.spectrum_npy_path.clone(),
+            options.spectrum_image_path.clone(),
+            options.generations,
+        )
+        .run(build_world());
+        return true;
+    }
+    false
+}
@@ -0,0 +1,141 @@
+use crate::metrics::world_stats;
+use std::fmt;
+use std::str::FromStr;
+use world_grid::{GridCell, Loc, World};
+
+// A condition that pauses the simulation when it becomes true, for catching rare events as
+// they happen.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Breakpoint {
+    // The cell gets a creature, e.g. "occupied:10,20".
+    Occupied(Loc),
+    // The cell loses its creature, e.g. "vacated:10,20".
+    Vacated(Loc),
+    // One of the world's stats (see metrics::world_stats) drops below the threshold, e.g.
+    // "population<100".
+    MetricBelow(String, f64),
+    // One of the world's stats rises above the threshold, e.g. "population>5000".
+    MetricAbove(String, f64),
+}
+
+impl Breakpoint {
+    // Whether the condition holds, or None if the world has no such metric.
+    fn holds<W: World>(&self, world: &W) -> Option<bool> {
+        match self {
+            Self::Occupied(loc) => Some(Self::occupied(world, *loc)),
+            Self::Vacated(loc) => Some(!Self::occupied(world, *loc)),
+            Self::MetricBelow(name, threshold) => {
+                Self::metric(world, name).map(|value| value < *threshold)
+            }
+            Self::MetricAbove(name, threshold) => {
+                Self::metric(world, name).map(|value| value > *threshold)
+            }
+        }
+    }
+
+    fn occupied<W: World>(world: &W, loc: Loc) -> bool {
+        world
+            .grid()
+            .try_cell(loc)
+            .is_ok_and(|cell| cell.genome().is_some())
+    }
+
+    fn metric<W: World>(world: &W, name: &str) -> Option<f64> {
+        world_stats(world)
+            .into_iter()
+            .find(|metric| metric.name == name)
+            .map(|metric| metric.value)
+    }
+
+    // The cell the breakpoint watches, if any.
+    pub fn loc(&self) -> Option<Loc> {
+        match self {
+            Self::Occupied(loc) | Self::Vacated(loc) => Some(*loc),
+            Self::MetricBelow(..) | Self::MetricAbove(..) => None,
+        }
+    }
+}
+
+impl FromStr for Breakpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid breakpoint: {}", s);
+        if let Some((kind, loc)) = s.split_once(':') {
+            let (row, col) = loc.split_once(',').ok_or_else(invalid)?;
+            let loc = Loc::new(
+                row.trim().parse().map_err(|_| invalid())?,
+                col.trim().parse().map_err(|_| invalid())?,
+            );
+            return match kind {
+                "occupied" => Ok(Self::Occupied(loc)),
+                "vacated" => Ok(Self::Vacated(loc)),
+                _ => Err(invalid()),
+            };
+        }
+        if let Some((name, threshold)) = s.split_once('<') {
+            let threshold = threshold.trim().parse().map_err(|_| invalid())?;
+            return Ok(Self::MetricBelow(name.trim().to_string(), threshold));
+        }
+        if let Some((name, threshold)) = s.split_once('>') {
+            let threshold = threshold.trim().parse().map_err(|_| invalid())?;
+            return Ok(Self::MetricAbove(name.trim().to_string(), threshold));
+        }
+        Err(invalid())
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Occupied(loc) => write!(f, "occupied:{},{}", loc.row, loc.col),
+            Self::Vacated(loc) => write!(f, "vacated:{},{}", loc.row, loc.col),
+            Self::MetricBelow(name, threshold) => write!(f, "{}<{}", name, threshold),
+            Self::MetricAbove(name, threshold) => write!(f, "{}>{}", name, threshold),
+        }
+    }
+}
+
+// The breakpoints in effect, checked after each step. Each fires only when its condition
+// becomes true, so a simulation resumed after a break runs on until the condition goes false
+// and then true again.
+#[derive(Default)]
+pub struct Breakpoints {
+    // Each with whether its condition held after the last check.
+    breakpoints: Vec<(Breakpoint, bool)>,
+}
+
+impl Breakpoints {
+    pub fn new<W: World>(breakpoints: &[Breakpoint], world: &W) -> Self {
+        let mut result = Self::default();
+        for breakpoint in breakpoints {
+            result.add(breakpoint.clone(), world);
+        }
+        result
+    }
+
+    pub fn add<W: World>(&mut self, breakpoint: Breakpoint, world: &W) {
+        let holds = breakpoint.holds(world);
+        if holds.is_none() {
+            println!("Breakpoint {} names no metric of this world", breakpoint);
+        }
+        self.breakpoints.push((breakpoint, holds.unwrap_or(false)));
+    }
+
+    // Returns the first breakpoint that fired, if any.
+    pub fn check<W: World>(&mut self, world: &W) -> Option<Breakpoint> {
+        let mut result = None;
+        for (breakpoint, held) in &mut self.breakpoints {
+            let holds = breakpoint.holds(world).unwrap_or(false);
+            if holds && !*held && result.is_none() {
+                result = Some(breakpoint.clone());
+            }
+            *held = holds;
+        }
+        result
+    }
+}
+
+pub fn report_breakpoint(breakpoint: &Breakpoint, step: u64) {
+    println!("Paused at breakpoint {} after step {}", breakpoint, step);
+}
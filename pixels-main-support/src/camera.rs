@@ -0,0 +1,101 @@
+use world_grid::{FoundObject, GridSize, ObjectKind};
+
+const ZOOM_LEVELS: [u32; 4] = [1, 2, 4, 8];
+// The zoom that following starts at when the view isn't zoomed in yet.
+const FOLLOW_ZOOM: u32 = 4;
+// The fraction of the way to its target that a following camera pans each time it's pointed
+// at it, so that it glides rather than jumps when it switches targets.
+const FOLLOW_EASING: f32 = 0.25;
+
+// Which part of the world the window shows: the whole world, or a zoomed in part of it, which
+// can follow the spaceships the world makes. Positions are texture coordinates, from 0.0 to
+// 1.0 across the world's pixels.
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    zoom: u32,
+    center: [f32; 2],
+    following: bool,
+}
+
+impl Camera {
+    pub fn new(zoom: u32, following: bool) -> Self {
+        let mut result = Self {
+            zoom: zoom.max(1),
+            center: [0.5, 0.5],
+            following: false,
+        };
+        if following {
+            result.toggle_following();
+        }
+        result
+    }
+
+    pub fn zoom(&self) -> u32 {
+        self.zoom
+    }
+
+    pub fn following(&self) -> bool {
+        self.following
+    }
+
+    pub fn cycle_zoom(&mut self) {
+        let index = ZOOM_LEVELS
+            .iter()
+            .position(|zoom| *zoom == self.zoom)
+            .map_or(0, |index| (index + 1) % ZOOM_LEVELS.len());
+        self.zoom = ZOOM_LEVELS[index];
+    }
+
+    pub fn toggle_following(&mut self) {
+        self.following ^= true;
+        if self.following && self.zoom == 1 {
+            self.zoom = FOLLOW_ZOOM;
+        }
+    }
+
+    // The part of the world in view, as its left, top, width, and height. The view stays
+    // within the world, even when its center is near an edge.
+    pub fn view(&self) -> [f32; 4] {
+        let size = 1.0 / self.zoom as f32;
+        let [x, y] = self
+            .center
+            .map(|center| (center - size / 2.0).clamp(0.0, 1.0 - size));
+        [x, y, size, size]
+    }
+
+    // Maps a position in the window, as a fraction of the way across the world's pixels, to
+    // the position in the world that it shows.
+    pub fn window_to_world(&self, position: [f32; 2]) -> [f32; 2] {
+        let [x, y, width, height] = self.view();
+        [x + position[0] * width, y + position[1] * height]
+    }
+
+    // Pans toward whichever spaceship is nearest the center of the view, if following any.
+    // The grid size is that of the cells the world's pixels cover.
+    pub fn follow(&mut self, objects: &[FoundObject], grid_size: GridSize) {
+        if !self.following {
+            return;
+        }
+        let target = objects
+            .iter()
+            .filter(|object| object.kind == ObjectKind::Spaceship)
+            .map(|object| {
+                let (row, col) = object.center();
+                [col / grid_size.width as f32, row / grid_size.height as f32]
+            })
+            .min_by(|target1, target2| {
+                self.distance_squared(*target1)
+                    .total_cmp(&self.distance_squared(*target2))
+            });
+        if let Some(target) = target {
+            for (center, target) in self.center.iter_mut().zip(target) {
+                *center += (target - *center) * FOLLOW_EASING;
+            }
+        }
+    }
+
+    fn distance_squared(&self, position: [f32; 2]) -> f32 {
+        let [dx, dy] = [position[0] - self.center[0], position[1] - self.center[1]];
+        dx * dx + dy * dy
+    }
+}
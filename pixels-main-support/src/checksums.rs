@@ -0,0 +1,54 @@
+use std::fs;
+use std::path::Path;
+
+// Compares two checksum logs, as written by --checksums, e.g. from the same replay run in
+// strict math mode on two platforms, and reports the first step at which the runs' grids
+// differ.
+pub fn compare_checksums(path: &Path, other_path: &Path) {
+    let checksums = read_checksums(path);
+    let other_checksums = read_checksums(other_path);
+    let mismatch = checksums
+        .iter()
+        .zip(&other_checksums)
+        .find(|(line, other_line)| line != other_line);
+    match mismatch {
+        Some(((step, checksum), (other_step, other_checksum))) if step == other_step => println!(
+            "Runs diverge at step {}: {:016x} vs. {:016x}",
+            step, checksum, other_checksum
+        ),
+        Some(((step, _), (other_step, _))) => println!(
+            "Runs log different steps: {} vs. {}; were they logged from the same start?",
+            step, other_step
+        ),
+        None => {
+            let num_steps = checksums.len().min(other_checksums.len());
+            println!("Runs match through {} steps", num_steps);
+            if checksums.len() != other_checksums.len() {
+                println!(
+                    "The runs have different lengths: {} vs. {} steps",
+                    checksums.len(),
+                    other_checksums.len()
+                );
+            }
+        }
+    }
+}
+
+fn read_checksums(path: &Path) -> Vec<(u64, u64)> {
+    fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("Can't read {}: {}", path.display(), err))
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.split_once(',')
+                .and_then(|(step, checksum)| {
+                    Some((
+                        step.trim().parse().ok()?,
+                        u64::from_str_radix(checksum.trim(), 16).ok()?,
+                    ))
+                })
+                .unwrap_or_else(|| panic!("Invalid checksum line: {}", line))
+        })
+        .collect()
+}
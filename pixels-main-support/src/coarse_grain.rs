@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use world_grid::{GridCell, GridSize};
+
+// Block sizes, in cells, that the coarse-grained view cycles through.
+const BLOCK_SIZES: [u32; 5] = [2, 4, 8, 16, 32];
+
+// How a block of cells is summarized as one color.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CoarseGrainMode {
+    // The mean of the cells' colors.
+    Mean,
+    // The most common cell color, as a stand-in for the most common cell state.
+    Majority,
+}
+
+// An analysis view that shows the grid as square blocks of cells, each summarized as one
+// color, for seeing large-scale structure in huge runs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CoarseGrain {
+    pub block_size: u32,
+    pub mode: CoarseGrainMode,
+}
+
+impl CoarseGrain {
+    // The next larger block size, or None after the largest.
+    pub fn next(coarse_grain: Option<Self>) -> Option<Self> {
+        match coarse_grain {
+            None => Some(Self {
+                block_size: BLOCK_SIZES[0],
+                mode: CoarseGrainMode::Mean,
+            }),
+            Some(coarse_grain) => BLOCK_SIZES
+                .iter()
+                .find(|block_size| **block_size > coarse_grain.block_size)
+                .map(|block_size| Self {
+                    block_size: *block_size,
+                    mode: coarse_grain.mode,
+                }),
+        }
+    }
+
+    pub fn toggle_mode(self) -> Self {
+        let mode = match self.mode {
+            CoarseGrainMode::Mean => CoarseGrainMode::Majority,
+            CoarseGrainMode::Majority => CoarseGrainMode::Mean,
+        };
+        Self { mode, ..self }
+    }
+
+    // Summarizes the block of cells with the given upper left corner, clipped to the grid,
+    // with each cell colored by cell_color.
+    pub fn block_color<C: GridCell>(
+        self,
+        cells: &[C],
+        grid_size: GridSize,
+        first_row: u32,
+        first_col: u32,
+        cell_color: impl Fn(&C) -> [u8; 4],
+    ) -> [u8; 4] {
+        let cell_color = &cell_color;
+        let colors =
+            (first_row..(first_row + self.block_size).min(grid_size.height)).flat_map(|row| {
+                (first_col..(first_col + self.block_size).min(grid_size.width))
+                    .map(move |col| cell_color(&cells[(row * grid_size.width + col) as usize]))
+            });
+        match self.mode {
+            CoarseGrainMode::Mean => mean_color(colors),
+            CoarseGrainMode::Majority => majority_color(colors),
+        }
+    }
+}
+
+fn mean_color(colors: impl Iterator<Item = [u8; 4]>) -> [u8; 4] {
+    let mut sums = [0u32; 4];
+    let mut count = 0;
+    for color in colors {
+        for (sum, value) in sums.iter_mut().zip(color) {
+            *sum += value as u32;
+        }
+        count += 1;
+    }
+    sums.map(|sum| (sum / count.max(1)) as u8)
+}
+
+fn majority_color(colors: impl Iterator<Item = [u8; 4]>) -> [u8; 4] {
+    let mut counts = HashMap::new();
+    for color in colors {
+        *counts.entry(color).or_insert(0) += 1;
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(color, count)| (*count, *color))
+        .map_or([0; 4], |(color, _)| color)
+}
@@ -0,0 +1,51 @@
+// Blends each cell's new color (input) over its previous color (background), fading dark
+// colors in later than bright ones.
+
+struct Fade {
+    dark_alpha: f32,
+    bright_alpha: f32,
+    max_dark_value: f32,
+    // 1.0 if the render target encodes sRGB, so that colors must be written as linear values.
+    srgb_target: f32,
+    // The part of the textures in view: left, top, width, and height.
+    view: vec4<f32>,
+}
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var background_texture: texture_2d<f32>;
+@group(0) @binding(2) var texture_sampler: sampler;
+@group(0) @binding(3) var<uniform> fade: Fade;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coord: vec2<f32>,
+}
+
+// A single triangle that covers the whole viewport.
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let tex_coord = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(tex_coord.x * 2.0 - 1.0, 1.0 - tex_coord.y * 2.0, 0.0, 1.0);
+    out.tex_coord = tex_coord;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let tex_coord = fade.view.xy + in.tex_coord * fade.view.zw;
+    let input = textureSample(input_texture, texture_sampler, tex_coord).rgb;
+    let background = textureSample(background_texture, texture_sampler, tex_coord).rgb;
+    let is_dark = all(input <= vec3<f32>(fade.max_dark_value));
+    var color = mix(background, input, select(fade.bright_alpha, fade.dark_alpha, is_dark));
+    if fade.srgb_target > 0.5 {
+        color = srgb_to_linear(color);
+    }
+    return vec4<f32>(color, 1.0);
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
@@ -0,0 +1,56 @@
+use crate::options::{AppOptions, exit_with_usage_error};
+use crate::replay::UserAction;
+use world_grid::World;
+
+// Exits if the options edit the cells of a world that can't be edited (see
+// World::is_editable).
+pub fn check_edit_options<W: World>(world: &W, options: &AppOptions) {
+    let edit_options = [
+        ("--pattern", options.pattern_path.is_some()),
+        ("--select", !options.selection_pressures.is_empty()),
+    ];
+    for (name, given) in edit_options {
+        if given && !world.is_editable() {
+            exit_with_usage_error(&format!("{} needs a world whose cells can be edited", name));
+        }
+    }
+}
+
+// Whether the world lets the action be applied: actions that edit the grid (see
+// UserAction::edits_grid) need a world whose cells can be edited.
+pub fn allows_action<W: World>(world: &W, action: &UserAction) -> bool {
+    !action.edits_grid() || world.is_editable()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_world::TestWorld;
+    use world_grid::Loc;
+
+    #[test]
+    fn only_editable_worlds_allow_actions_that_edit_the_grid() {
+        let loc = Loc::new(1, 2);
+        let editable = TestWorld::new(true);
+        let not_editable = TestWorld::new(false);
+        for action in [
+            UserAction::Erase(loc),
+            UserAction::TogglePinned(loc),
+            UserAction::Undo,
+        ] {
+            assert!(action.edits_grid());
+            assert!(allows_action(&editable, &action));
+            assert!(!allows_action(&not_editable, &action));
+        }
+        for action in [
+            UserAction::TogglePaused,
+            UserAction::SingleStep,
+            UserAction::Inspect(loc),
+            UserAction::AddSource(loc),
+        ] {
+            assert!(!action.edits_grid());
+            assert!(allows_action(&editable, &action));
+            assert!(allows_action(&not_editable, &action));
+        }
+    }
+}
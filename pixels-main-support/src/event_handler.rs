@@ -0,0 +1,243 @@
+use crate::app::App;
+use crate::input::{InputEvent, KeyAction};
+use crate::options::AppOptions;
+use crate::replay::UserAction;
+use crate::screensaver::Screensaver;
+use crate::world_app;
+use std::time::{Duration, Instant};
+use winit::application::ApplicationHandler;
+use winit::dpi::PhysicalPosition;
+use winit::event::{ElementState, KeyEvent, MouseButton, StartCause, WindowEvent};
+use winit::event_loop::ActiveEventLoop;
+use winit::keyboard::{KeyCode, ModifiersState, PhysicalKey};
+use winit::window::WindowId;
+use world_grid::{GridSize, Loc, Random, World};
+
+const CURSOR_TIMEOUT_MILLIS: u64 = 1000;
+
+// Turns the window system's events into the app's input, building the app once the window
+// can be.
+pub struct AppEventHandler<W, F>
+where
+    W: World,
+    F: Fn(GridSize) -> W,
+{
+    build_world: F,
+    options: AppOptions,
+    // Handed to the app once it's built.
+    selection_rand: Option<Random>,
+    pub app: Option<App<W>>,
+    cursor_position: PhysicalPosition<f64>,
+    screensaver: Option<Screensaver>,
+    cursor_timeout: Option<Instant>,
+    mouse_pressed: bool,
+    modifiers: ModifiersState,
+    time_step_frames: u32,
+    cell_pixel_width: u32,
+}
+
+impl<W, F> AppEventHandler<W, F>
+where
+    W: World,
+    F: Fn(GridSize) -> W,
+{
+    pub fn new(
+        options: AppOptions,
+        time_step_frames: u32,
+        cell_pixel_width: u32,
+        selection_rand: Random,
+        build_world: F,
+    ) -> Self {
+        let screensaver = options
+            .screensaver
+            .then(|| Screensaver::new(Duration::from_secs(options.reseed_interval_secs)));
+        Self {
+            build_world,
+            options,
+            selection_rand: Some(selection_rand),
+            app: None,
+            cursor_position: PhysicalPosition::new(0.0, 0.0),
+            screensaver,
+            cursor_timeout: None,
+            mouse_pressed: false,
+            modifiers: ModifiersState::empty(),
+            time_step_frames,
+            cell_pixel_width,
+        }
+    }
+
+    fn app(&mut self) -> &mut App<W> {
+        self.app.as_mut().unwrap()
+    }
+
+    fn show_cursor(&mut self) {
+        self.app().frontend.window().set_cursor_visible(true);
+        self.cursor_timeout = Some(Instant::now() + Duration::from_millis(CURSOR_TIMEOUT_MILLIS));
+    }
+
+    fn hide_cursor(&mut self) {
+        self.app().frontend.window().set_cursor_visible(false);
+        self.cursor_timeout = None;
+    }
+
+    // The cell under the cursor, if it's over the grid.
+    fn pointer_loc(&mut self) -> Option<Loc> {
+        let pos = self.cursor_position;
+        self.app().window_pos_to_loc(pos)
+    }
+
+    // The command a key gives, if any, unless a KeyBinding overrides it.
+    fn key_action(code: KeyCode, modifiers: ModifiersState) -> Option<KeyAction> {
+        let control = modifiers.control_key();
+        let action = match code {
+            KeyCode::Escape | KeyCode::KeyQ | KeyCode::KeyX => KeyAction::Quit,
+            KeyCode::KeyF => KeyAction::ToggleFastForward,
+            KeyCode::KeyP => KeyAction::TogglePaused,
+            KeyCode::KeyR => KeyAction::Restart,
+            KeyCode::KeyS => KeyAction::SingleStep,
+            KeyCode::KeyH => KeyAction::TogglePhaseStepping,
+            KeyCode::KeyG => KeyAction::SaveSeed,
+            KeyCode::KeyC if control => KeyAction::CopyPattern,
+            KeyCode::KeyV if control => KeyAction::PastePattern,
+            KeyCode::KeyK => KeyAction::SavePattern,
+            KeyCode::KeyV => KeyAction::InjectSeed,
+            KeyCode::KeyD => KeyAction::RemoveSource,
+            KeyCode::KeyC => KeyAction::CycleCoarseGrain,
+            KeyCode::KeyM => KeyAction::ToggleCoarseGrainMode,
+            KeyCode::KeyE => KeyAction::CycleHeredity,
+            KeyCode::KeyI => KeyAction::ReportPerformance,
+            KeyCode::KeyT => KeyAction::CycleMouseTool,
+            KeyCode::KeyZ if control => KeyAction::Undo,
+            KeyCode::KeyY if control => KeyAction::Redo,
+            KeyCode::KeyZ => KeyAction::CycleZoom,
+            KeyCode::KeyL => KeyAction::ToggleFollowing,
+            KeyCode::F5 => KeyAction::QuickSave,
+            KeyCode::F6 => KeyAction::NextSaveSlot,
+            KeyCode::F9 => KeyAction::QuickLoad,
+            _ => return None,
+        };
+        Some(action)
+    }
+
+    fn on_key_action(&mut self, action: KeyAction) {
+        let pointer = self.pointer_loc();
+        self.app()
+            .frontend
+            .queue_input(InputEvent::Key { action, pointer });
+        if action == KeyAction::CycleMouseTool {
+            self.show_cursor();
+        }
+    }
+}
+
+impl<W, F> ApplicationHandler for AppEventHandler<W, F>
+where
+    W: World,
+    F: Fn(GridSize) -> W,
+{
+    fn new_events(&mut self, event_loop: &ActiveEventLoop, _cause: StartCause) {
+        if self.app.is_some() {
+            if let Some(screensaver) = self.screensaver.as_mut()
+                && screensaver.reseed_due()
+            {
+                self.app().perform(UserAction::Restart);
+            }
+            if !self.app().on_frame() {
+                event_loop.exit();
+                return;
+            }
+        }
+
+        if let Some(cursor_timeout) = self.cursor_timeout
+            && Instant::now() >= cursor_timeout
+        {
+            self.hide_cursor();
+        }
+    }
+
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.app.is_none() {
+            self.app = Some(App::new(
+                event_loop,
+                &self.options,
+                self.time_step_frames,
+                self.cell_pixel_width,
+                self.selection_rand.take().unwrap(),
+                &self.build_world,
+            ));
+            self.app().on_create();
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        if let Some(screensaver) = self.screensaver.as_mut()
+            && screensaver.is_exit_input(&event)
+        {
+            event_loop.exit();
+            return;
+        }
+        match event {
+            WindowEvent::CloseRequested => {
+                event_loop.exit();
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_position = position;
+                self.show_cursor();
+                if self.mouse_pressed
+                    && let Some(loc) = self.pointer_loc()
+                {
+                    self.app()
+                        .frontend
+                        .queue_input(InputEvent::PointerDragged(loc));
+                }
+            }
+            WindowEvent::Resized(size) => {
+                self.app().frontend.resize(size);
+            }
+            WindowEvent::Focused(true) => {
+                self.app().frontend.request_redraw();
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(code),
+                        state: ElementState::Released,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                if let Some(action) =
+                    world_app::bound_action(code).or_else(|| Self::key_action(code, self.modifiers))
+                {
+                    self.on_key_action(action);
+                }
+            }
+            WindowEvent::MouseInput {
+                button: MouseButton::Left,
+                state,
+                ..
+            } => {
+                self.mouse_pressed = state == ElementState::Pressed;
+                let pointer = self.pointer_loc();
+                if !self.mouse_pressed {
+                    self.app()
+                        .frontend
+                        .queue_input(InputEvent::PointerReleased(pointer));
+                } else if let Some(loc) = pointer {
+                    self.app()
+                        .frontend
+                        .queue_input(InputEvent::PointerDragged(loc));
+                }
+                self.show_cursor();
+            }
+            WindowEvent::RedrawRequested => {
+                self.app().draw();
+            }
+            _ => (),
+        }
+    }
+}
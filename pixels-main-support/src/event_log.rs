@@ -0,0 +1,223 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use world_grid::{GridCell, LifeEvent, LifeEventDetector, WorldGrid};
+
+// Logs every birth and death to a file: CSV by default, or Parquet if the path ends in
+// ".parquet" and the "parquet" feature is enabled.
+pub struct EventLog {
+    detector: LifeEventDetector,
+    writer: EventLogWriter,
+}
+
+impl EventLog {
+    pub fn create<C: GridCell>(path: &Path, grid: &WorldGrid<C>) -> Self {
+        Self {
+            detector: LifeEventDetector::new(grid),
+            writer: EventLogWriter::create(path),
+        }
+    }
+
+    pub fn after_update<C: GridCell>(&mut self, generation: u64, grid: &WorldGrid<C>) {
+        let events = self.detector.detect(generation, grid);
+        self.writer.write(&events);
+    }
+}
+
+enum EventLogWriter {
+    Csv(BufWriter<File>),
+    #[cfg(feature = "parquet")]
+    Parquet(parquet_log::ParquetEventWriter),
+}
+
+impl EventLogWriter {
+    fn create(path: &Path) -> Self {
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "parquet")
+        {
+            Self::create_parquet(path)
+        } else {
+            let mut writer = BufWriter::new(File::create(path).unwrap());
+            writeln!(writer, "generation,row,col,kind,genome,parent_genomes").unwrap();
+            Self::Csv(writer)
+        }
+    }
+
+    #[cfg(feature = "parquet")]
+    fn create_parquet(path: &Path) -> Self {
+        Self::Parquet(parquet_log::ParquetEventWriter::create(path))
+    }
+
+    #[cfg(not(feature = "parquet"))]
+    fn create_parquet(path: &Path) -> Self {
+        panic!(
+            "Can't write {}: built without the \"parquet\" feature",
+            path.display()
+        );
+    }
+
+    fn write(&mut self, events: &[LifeEvent]) {
+        match self {
+            Self::Csv(writer) => {
+                for event in events {
+                    Self::write_csv_event(writer, event);
+                }
+                writer.flush().unwrap();
+            }
+            #[cfg(feature = "parquet")]
+            Self::Parquet(writer) => writer.write(events),
+        }
+    }
+
+    // Parent genomes are space-separated within their column.
+    fn write_csv_event(writer: &mut BufWriter<File>, event: &LifeEvent) {
+        let parent_genomes: Vec<String> = event
+            .parent_genomes
+            .iter()
+            .map(|genome| genome.to_string())
+            .collect();
+        writeln!(
+            writer,
+            "{},{},{},{},{},{}",
+            event.generation,
+            event.loc.row,
+            event.loc.col,
+            event.kind.name(),
+            event.genome,
+            parent_genomes.join(" ")
+        )
+        .unwrap();
+    }
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_log {
+    use parquet::data_type::{ByteArray, ByteArrayType, Int32Type, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+    use std::fs::File;
+    use std::path::Path;
+    use std::sync::Arc;
+    use world_grid::LifeEvent;
+
+    const SCHEMA: &str = "
+        message life_event {
+            REQUIRED INT64 generation;
+            REQUIRED INT32 row;
+            REQUIRED INT32 col;
+            REQUIRED BYTE_ARRAY kind (UTF8);
+            REQUIRED INT64 genome;
+            REPEATED INT64 parent_genomes;
+        }
+    ";
+    const ROW_GROUP_EVENTS: usize = 1 << 16;
+
+    // Buffers events into row groups. The file is only readable once closed, on drop.
+    pub struct ParquetEventWriter {
+        writer: Option<SerializedFileWriter<File>>,
+        events: Vec<LifeEvent>,
+    }
+
+    impl ParquetEventWriter {
+        pub fn create(path: &Path) -> Self {
+            let schema = Arc::new(parse_message_type(SCHEMA).unwrap());
+            let properties = Arc::new(WriterProperties::builder().build());
+            let file = File::create(path).unwrap();
+            Self {
+                writer: Some(SerializedFileWriter::new(file, schema, properties).unwrap()),
+                events: Vec::with_capacity(ROW_GROUP_EVENTS),
+            }
+        }
+
+        pub fn write(&mut self, events: &[LifeEvent]) {
+            self.events.extend_from_slice(events);
+            if self.events.len() >= ROW_GROUP_EVENTS {
+                self.write_row_group();
+            }
+        }
+
+        fn write_row_group(&mut self) {
+            if self.events.is_empty() {
+                return;
+            }
+            let events = &self.events;
+            let mut row_group = self.writer.as_mut().unwrap().next_row_group().unwrap();
+
+            let mut column = row_group.next_column().unwrap().unwrap();
+            let generations: Vec<i64> = events.iter().map(|e| e.generation as i64).collect();
+            column
+                .typed::<Int64Type>()
+                .write_batch(&generations, None, None)
+                .unwrap();
+            column.close().unwrap();
+
+            let mut column = row_group.next_column().unwrap().unwrap();
+            let rows: Vec<i32> = events.iter().map(|e| e.loc.row as i32).collect();
+            column
+                .typed::<Int32Type>()
+                .write_batch(&rows, None, None)
+                .unwrap();
+            column.close().unwrap();
+
+            let mut column = row_group.next_column().unwrap().unwrap();
+            let cols: Vec<i32> = events.iter().map(|e| e.loc.col as i32).collect();
+            column
+                .typed::<Int32Type>()
+                .write_batch(&cols, None, None)
+                .unwrap();
+            column.close().unwrap();
+
+            let mut column = row_group.next_column().unwrap().unwrap();
+            let kinds: Vec<ByteArray> = events.iter().map(|e| e.kind.name().into()).collect();
+            column
+                .typed::<ByteArrayType>()
+                .write_batch(&kinds, None, None)
+                .unwrap();
+            column.close().unwrap();
+
+            let mut column = row_group.next_column().unwrap().unwrap();
+            let genomes: Vec<i64> = events.iter().map(|e| e.genome as i64).collect();
+            column
+                .typed::<Int64Type>()
+                .write_batch(&genomes, None, None)
+                .unwrap();
+            column.close().unwrap();
+
+            // An empty list is a single level pair with definition level 0 and no value.
+            let mut column = row_group.next_column().unwrap().unwrap();
+            let mut parent_genomes = vec![];
+            let mut def_levels = vec![];
+            let mut rep_levels = vec![];
+            for event in events {
+                if event.parent_genomes.is_empty() {
+                    def_levels.push(0);
+                    rep_levels.push(0);
+                }
+                for (index, genome) in event.parent_genomes.iter().enumerate() {
+                    parent_genomes.push(*genome as i64);
+                    def_levels.push(1);
+                    rep_levels.push(if index == 0 { 0 } else { 1 });
+                }
+            }
+            column
+                .typed::<Int64Type>()
+                .write_batch(&parent_genomes, Some(&def_levels), Some(&rep_levels))
+                .unwrap();
+            column.close().unwrap();
+
+            row_group.close().unwrap();
+            self.events.clear();
+        }
+    }
+
+    impl Drop for ParquetEventWriter {
+        fn drop(&mut self) {
+            self.write_row_group();
+            if let Some(writer) = self.writer.take() {
+                writer.close().unwrap();
+            }
+        }
+    }
+}
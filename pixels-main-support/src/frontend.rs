@@ -0,0 +1,316 @@
+use crate::{InputEvent, KeyAction};
+use std::borrow::Cow;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+use world_grid::{GridCell, GridSize, StepStats, World, write_png};
+
+const DEFAULT_TERMINAL_COLUMNS: u32 = 80;
+const DEFAULT_TERMINAL_LINES: u32 = 24;
+// The terminal can't keep up with a frame per step, nor can anyone watching it.
+const MIN_TUI_FRAME_INTERVAL: Duration = Duration::from_millis(50);
+
+// Where a run shows its world, and where it takes input from: a window (see WindowFrontend),
+// or without one (see --headless), whichever --frontend chooses.
+pub trait Frontend {
+    fn init(&mut self, grid_size: GridSize);
+
+    // Whether to build a frame for present(), which is too slow to do every step for nothing.
+    fn frame_due(&self, step: u64) -> bool;
+
+    fn present(&mut self, frame: &Frame);
+
+    fn poll_input(&mut self) -> Vec<InputEvent>;
+
+    // Whether poll_input() can resume a paused run.
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FrontendKind {
+    #[default]
+    Headless,
+    Tui,
+    Png,
+}
+
+impl FromStr for FrontendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "headless" => Ok(Self::Headless),
+            "tui" => Ok(Self::Tui),
+            "png" => Ok(Self::Png),
+            _ => Err(format!("Invalid frontend: {}", s)),
+        }
+    }
+}
+
+impl FrontendKind {
+    pub fn build(self, frames_dir: &Path, frame_interval: u64) -> Box<dyn Frontend> {
+        match self {
+            Self::Headless => Box::new(HeadlessFrontend),
+            Self::Tui => Box::new(TuiFrontend::new(frame_interval)),
+            Self::Png => Box::new(PngFrontend::new(frames_dir, frame_interval)),
+        }
+    }
+}
+
+// The world's cell colors after a step, row by row, and its stats for showing progress.
+pub struct Frame<'a> {
+    pub step: u64,
+    pub size: GridSize,
+    // Borrowed from the app's pixel buffer, when it has one.
+    pub pixels: Cow<'a, [[u8; 4]]>,
+    // Which rows of pixels changed since the last frame presented, or None if any may have.
+    pub changed_rows: Option<&'a [bool]>,
+    pub stats: StepStats,
+}
+
+impl Frame<'_> {
+    pub fn new<W: World>(step: u64, world: &W) -> Self {
+        let grid = world.grid();
+        Self {
+            step,
+            size: grid.size(),
+            pixels: grid.cells_iter().map(|cell| cell.color_rgba()).collect(),
+            changed_rows: None,
+            stats: world.step_stats(),
+        }
+    }
+
+    fn pixel(&self, row: u32, col: u32) -> [u8; 4] {
+        self.pixels[(row * self.size.width + col) as usize]
+    }
+}
+
+// Shows nothing and takes no input, for runs observed only through their outputs (e.g.
+// --stats) and remote control.
+pub struct HeadlessFrontend;
+
+impl Frontend for HeadlessFrontend {
+    fn init(&mut self, _grid_size: GridSize) {}
+
+    fn frame_due(&self, _step: u64) -> bool {
+        false
+    }
+
+    fn present(&mut self, _frame: &Frame) {}
+
+    fn poll_input(&mut self) -> Vec<InputEvent> {
+        vec![]
+    }
+}
+
+// Draws the world in the terminal with 24-bit color, two cells to a character (the upper
+// half block's foreground is the top cell and its background the bottom one), sampling the
+// grid down to fit the terminal's $COLUMNS and $LINES. Commands are typed a line at a time:
+// p pauses or resumes, s single steps, r restarts, and q quits.
+struct TuiFrontend {
+    frame_interval: u64,
+    last_present: Option<Instant>,
+    columns: u32,
+    lines: u32,
+    commands: Receiver<String>,
+}
+
+impl TuiFrontend {
+    fn new(frame_interval: u64) -> Self {
+        let (sender, commands) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            frame_interval: frame_interval.max(1),
+            last_present: None,
+            columns: Self::terminal_dimension("COLUMNS", DEFAULT_TERMINAL_COLUMNS),
+            // Leaves a line for the status and one for typing commands.
+            lines: Self::terminal_dimension("LINES", DEFAULT_TERMINAL_LINES).saturating_sub(2),
+            commands,
+        }
+    }
+
+    fn terminal_dimension(name: &str, default: u32) -> u32 {
+        env::var(name)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+            .max(1)
+    }
+
+    fn parse_command(command: &str) -> Option<InputEvent> {
+        let action = match command.trim() {
+            "p" => KeyAction::TogglePaused,
+            "s" => KeyAction::SingleStep,
+            "r" => KeyAction::Restart,
+            "q" => KeyAction::Quit,
+            _ => return None,
+        };
+        Some(InputEvent::key(action))
+    }
+}
+
+impl Frontend for TuiFrontend {
+    fn init(&mut self, _grid_size: GridSize) {
+        // Clears the screen and hides the cursor.
+        print!("\x1b[2J\x1b[?25l");
+        io::stdout().flush().unwrap();
+    }
+
+    fn frame_due(&self, step: u64) -> bool {
+        step.is_multiple_of(self.frame_interval)
+            && self
+                .last_present
+                .is_none_or(|last_present| last_present.elapsed() >= MIN_TUI_FRAME_INTERVAL)
+    }
+
+    fn present(&mut self, frame: &Frame) {
+        self.last_present = Some(Instant::now());
+        let size = frame.size;
+        // Cells per character across, and per half character down.
+        let scale = size
+            .width
+            .div_ceil(self.columns)
+            .max(size.height.div_ceil(self.lines * 2))
+            .max(1);
+        let mut text = String::from("\x1b[H");
+        for line_row in (0..size.height).step_by(2 * scale as usize) {
+            for col in (0..size.width).step_by(scale as usize) {
+                let [top_red, top_green, top_blue, _] = frame.pixel(line_row, col);
+                let bottom_row = line_row + scale;
+                let [red, green, blue, _] = if bottom_row < size.height {
+                    frame.pixel(bottom_row, col)
+                } else {
+                    [0, 0, 0, 0xff]
+                };
+                text.push_str(&format!(
+                    "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m\u{2580}",
+                    top_red, top_green, top_blue, red, green, blue
+                ));
+            }
+            text.push_str("\x1b[0m\n");
+        }
+        let mut status = format!(
+            " step {}, generation {}, {} live",
+            frame.step, frame.stats.generation, frame.stats.live_cells
+        );
+        for total in &frame.stats.layer_totals {
+            status.push_str(&format!(", {} {:.1}", total.name, total.value));
+        }
+        text.push_str(&format!(
+            "\x1b[0K{} (p pause, s step, r restart, q quit)\n\x1b[0K",
+            status
+        ));
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(text.as_bytes()).unwrap();
+        stdout.flush().unwrap();
+    }
+
+    fn poll_input(&mut self) -> Vec<InputEvent> {
+        self.commands
+            .try_iter()
+            .filter_map(|command| Self::parse_command(&command))
+            .collect()
+    }
+
+    fn is_interactive(&self) -> bool {
+        true
+    }
+}
+
+impl Drop for TuiFrontend {
+    fn drop(&mut self) {
+        // Shows the cursor again.
+        print!("\x1b[?25h");
+        let _ = io::stdout().flush();
+    }
+}
+
+// Writes a PNG of the world to the frames directory every frame interval, one pixel per
+// cell, e.g. for rendering a video of a long run on a server.
+struct PngFrontend {
+    dir: PathBuf,
+    frame_interval: u64,
+}
+
+impl PngFrontend {
+    fn new(dir: &Path, frame_interval: u64) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            frame_interval: frame_interval.max(1),
+        }
+    }
+}
+
+impl Frontend for PngFrontend {
+    fn init(&mut self, _grid_size: GridSize) {
+        fs::create_dir_all(&self.dir).unwrap();
+    }
+
+    fn frame_due(&self, step: u64) -> bool {
+        step.is_multiple_of(self.frame_interval)
+    }
+
+    fn present(&mut self, frame: &Frame) {
+        let path = self.dir.join(format!("frame-{:08}.png", frame.step));
+        write_png(
+            BufWriter::new(File::create(path).unwrap()),
+            frame.size.width,
+            frame.size.height,
+            frame.pixels.as_flattened(),
+        )
+        .unwrap();
+    }
+
+    fn poll_input(&mut self) -> Vec<InputEvent> {
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UserAction;
+
+    fn tui_user_action(command: &str) -> Option<UserAction> {
+        match TuiFrontend::parse_command(command)? {
+            InputEvent::Key { action, .. } => action.user_action(),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn tui_commands_perform_their_user_actions() {
+        assert_eq!(tui_user_action("p"), Some(UserAction::TogglePaused));
+        assert_eq!(tui_user_action("s"), Some(UserAction::SingleStep));
+        assert_eq!(tui_user_action(" r\n"), Some(UserAction::Restart));
+    }
+
+    #[test]
+    fn tui_quit_command_is_the_quit_key() {
+        assert_eq!(
+            TuiFrontend::parse_command("q"),
+            Some(InputEvent::key(KeyAction::Quit))
+        );
+        assert_eq!(tui_user_action("q"), None);
+    }
+
+    #[test]
+    fn unknown_tui_commands_are_ignored() {
+        assert_eq!(TuiFrontend::parse_command(""), None);
+        assert_eq!(TuiFrontend::parse_command("pause"), None);
+    }
+}
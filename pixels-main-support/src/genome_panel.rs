@@ -0,0 +1,42 @@
+use world_grid::{GeneDescriptor, GridCell, Loc, WorldGrid};
+
+// Width of each gene's slider, in characters.
+const SLIDER_WIDTH: usize = 16;
+
+// Prints the genes (see GridCell::genes) of the creature at loc, one per line, each with its
+// bits, a slider showing its value, and the value as the creature reads it, e.g.
+//
+//     Genome of (12, 40): 0xc8051f
+//       red    11001000 [#############---] 200
+//       green  00000101 [----------------] 5
+//       blue   00011111 [##--------------] 31
+pub fn print_genome_panel<C: GridCell>(grid: &WorldGrid<C>, loc: Loc) {
+    let genes = C::genes();
+    let Some(genome) = grid.try_cell(loc).ok().and_then(|cell| cell.genome()) else {
+        return;
+    };
+    if genes.is_empty() {
+        return;
+    }
+    println!("Genome of ({}, {}): {:#x}", loc.row, loc.col, genome);
+    let name_width = genes.iter().map(|gene| gene.name.len()).max().unwrap();
+    for gene in genes {
+        println!(
+            "  {:width$} {}",
+            gene.name,
+            gene_line(gene, gene.value(genome)),
+            width = name_width
+        );
+    }
+}
+
+fn gene_line(gene: &GeneDescriptor, value: u8) -> String {
+    let filled = (value as usize * SLIDER_WIDTH + u8::MAX as usize / 2) / u8::MAX as usize;
+    format!(
+        "{:08b} [{}{}] {}",
+        value,
+        "#".repeat(filled),
+        "-".repeat(SLIDER_WIDTH - filled),
+        gene.format_value(value)
+    )
+}
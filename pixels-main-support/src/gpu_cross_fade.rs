@@ -0,0 +1,265 @@
+use pixels::Pixels;
+use pixels::wgpu;
+use std::ops::Range;
+use world_grid::GridSize;
+
+// Colors at or below this in every channel count as dark, and fade in later than bright ones.
+const MAX_DARK_VALUE: u8 = 0x10;
+const FADE_UNIFORM_SIZE: u64 = 8 * 4;
+
+// Draws the cross-fade from each cell's previous color to its new one on the GPU. Both colors
+// live in textures that are only rewritten when the world changes, and each frame a fragment
+// shader blends them by the current fade alphas, into the same part of the window that pixels
+// would scale its own buffer to. The textures trade roles at each new frame (see swap), so
+// the last input stays on the GPU as the background.
+pub struct GpuCrossFade {
+    textures: [wgpu::Texture; 2],
+    // Binds the first texture as the input and the second as the background, then the other
+    // way round.
+    bind_groups: [wgpu::BindGroup; 2],
+    // Which of the textures is the input.
+    input_index: usize,
+    texture_extent: wgpu::Extent3d,
+    fade_buffer: wgpu::Buffer,
+    pipeline: wgpu::RenderPipeline,
+    clear_color: wgpu::Color,
+    srgb_target: bool,
+}
+
+impl GpuCrossFade {
+    pub fn new(pixels: &Pixels, size: GridSize, clear_color: wgpu::Color) -> Self {
+        let device = pixels.device();
+        let texture_extent = wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        };
+        let textures = [
+            Self::create_texture(device, "cross_fade_texture_0", texture_extent),
+            Self::create_texture(device, "cross_fade_texture_1", texture_extent),
+        ];
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("cross_fade_sampler"),
+            ..Default::default()
+        });
+        let fade_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("cross_fade_uniform"),
+            size: FADE_UNIFORM_SIZE,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("cross_fade_bind_group_layout"),
+            entries: &[
+                Self::texture_layout_entry(0),
+                Self::texture_layout_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let views = textures
+            .each_ref()
+            .map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()));
+        let bind_groups = [(0, 1), (1, 0)].map(|(input, background)| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("cross_fade_bind_group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&views[input]),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&views[background]),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: fade_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        let render_format = pixels.render_texture_format();
+        let pipeline = Self::create_pipeline(device, &bind_group_layout, render_format);
+        Self {
+            textures,
+            bind_groups,
+            input_index: 0,
+            texture_extent,
+            fade_buffer,
+            pipeline,
+            clear_color,
+            srgb_target: render_format.is_srgb(),
+        }
+    }
+
+    fn create_texture(
+        device: &wgpu::Device,
+        label: &str,
+        texture_extent: wgpu::Extent3d,
+    ) -> wgpu::Texture {
+        // Not sRGB, so that the shader blends the raw color bytes, as a CPU blend would.
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: texture_extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        })
+    }
+
+    fn texture_layout_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        }
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        render_format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("cross_fade.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("cross_fade_pipeline_layout"),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("cross_fade_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: render_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        })
+    }
+
+    // Makes the input the background, and the old background the input, which still holds
+    // the frame before last until its changed rows are loaded (see load_rows).
+    pub fn swap(&mut self) {
+        self.input_index ^= 1;
+    }
+
+    // Loads the given rows of the input. The colors are RGBA bytes for the whole texture, one
+    // cell after another in row-major order.
+    pub fn load_rows(&self, pixels: &Pixels, rows: Range<u32>, colors: &[u8]) {
+        let bytes_per_row = 4 * self.texture_extent.width;
+        let bytes = (rows.start * bytes_per_row) as usize..(rows.end * bytes_per_row) as usize;
+        pixels.queue().write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.textures[self.input_index],
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: 0,
+                    y: rows.start,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &colors[bytes],
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(rows.len() as u32),
+            },
+            wgpu::Extent3d {
+                height: rows.len() as u32,
+                ..self.texture_extent
+            },
+        );
+    }
+
+    // The alphas are how far along the fade is, from 0.0 (all background) to 1.0 (all input).
+    // The view is the part of the textures to show (see Camera::view).
+    pub fn render(
+        &self,
+        pixels: &Pixels,
+        dark_alpha: f32,
+        bright_alpha: f32,
+        view: [f32; 4],
+    ) -> Result<(), pixels::Error> {
+        let fade = [
+            dark_alpha,
+            bright_alpha,
+            MAX_DARK_VALUE as f32 / 0xff as f32,
+            self.srgb_target as u8 as f32,
+            view[0],
+            view[1],
+            view[2],
+            view[3],
+        ];
+        let fade_bytes: Vec<u8> = fade.iter().flat_map(|value| value.to_ne_bytes()).collect();
+        pixels
+            .queue()
+            .write_buffer(&self.fade_buffer, 0, &fade_bytes);
+
+        pixels.render_with(|encoder, render_target, context| {
+            let (x, y, width, height) = context.scaling_renderer.clip_rect();
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("cross_fade_render_pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: render_target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(self.clear_color),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_groups[self.input_index], &[]);
+            pass.set_viewport(x as f32, y as f32, width as f32, height as f32, 0.0, 1.0);
+            pass.draw(0..3, 0..1);
+            Ok(())
+        })
+    }
+}
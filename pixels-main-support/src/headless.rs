@@ -0,0 +1,220 @@
+use crate::breakpoint::{Breakpoints, report_breakpoint};
+use crate::frontend::{Frame, Frontend, HeadlessFrontend};
+use crate::metrics::{RunMetrics, world_stats};
+use crate::probes::{
+    add_selection_pressure, after_selection_update, report_expired_selection_pressures,
+};
+use crate::remote::{RemoteCommand, RemoteControl, RemoteResponse};
+use crate::run_outputs::RunOutputs;
+use crate::speed_schedule::{SpeedChange, SpeedSchedule};
+use crate::watch::CellWatch;
+use crate::{InputEvent, KeyAction};
+use std::thread;
+use std::time::{Duration, Instant};
+use world_grid::{SelectionProbes, World};
+
+const PAUSED_POLL_MILLIS: u64 = 50;
+
+// Runs a world without a window, e.g. for multi-day experiments on a server. The world is
+// only observable and controllable via its frontend (see --frontend) and the remote control
+// HTTP server, if enabled.
+pub struct HeadlessApp<W: World> {
+    world: W,
+    run_metrics: RunMetrics,
+    paused: bool,
+    run_outputs: RunOutputs,
+    remote: Option<RemoteControl>,
+    selection_probes: SelectionProbes,
+    breakpoints: Breakpoints,
+    watch: CellWatch,
+    speed_schedule: SpeedSchedule,
+    frontend: Box<dyn Frontend>,
+    // The step of the world the frontend last presented, if it still shows it.
+    presented_step: Option<u64>,
+}
+
+impl<W: World> HeadlessApp<W> {
+    pub fn new(
+        world: W,
+        run_outputs: RunOutputs,
+        remote: Option<RemoteControl>,
+        selection_probes: SelectionProbes,
+        breakpoints: Breakpoints,
+        watch: CellWatch,
+        speed_schedule: SpeedSchedule,
+    ) -> Self {
+        Self {
+            world,
+            run_metrics: RunMetrics::new(),
+            paused: false,
+            run_outputs,
+            remote,
+            selection_probes,
+            breakpoints,
+            watch,
+            speed_schedule,
+            frontend: Box::new(HeadlessFrontend),
+            presented_step: None,
+        }
+    }
+
+    pub fn with_frontend(mut self, frontend: Box<dyn Frontend>) -> Self {
+        self.frontend = frontend;
+        self
+    }
+
+    pub fn world(&self) -> &W {
+        &self.world
+    }
+
+    pub fn run(&mut self, max_steps: Option<u64>) {
+        self.frontend.init(self.world.grid().size());
+        self.present_frame_if_due();
+        while max_steps.is_none_or(|max_steps| self.run_metrics.steps() < max_steps) {
+            self.handle_remote_requests();
+            if !self.handle_frontend_input() {
+                break;
+            }
+            // Only pauses matter without a window to show the steps in.
+            if self
+                .speed_schedule
+                .due(self.run_metrics.steps())
+                .contains(&SpeedChange::Pause)
+            {
+                self.paused = true;
+            }
+            // Without remote control or an interactive frontend, nothing could resume the run.
+            if self.paused && self.remote.is_none() && !self.frontend.is_interactive() {
+                break;
+            }
+            if self.paused {
+                // Catches up on any frame skipped before the pause.
+                self.present_frame_if_due();
+                thread::sleep(Duration::from_millis(PAUSED_POLL_MILLIS));
+            } else {
+                self.update_world();
+            }
+        }
+        println!("{}", self.run_metrics.summary());
+        self.watch.save_history();
+        self.run_outputs
+            .finish(self.run_metrics.steps(), &self.world);
+    }
+
+    fn update_world(&mut self) {
+        let start = Instant::now();
+        self.selection_probes.before_update(self.world.grid());
+        self.world.update();
+        let expired = after_selection_update(&mut self.selection_probes, &mut self.world);
+        self.run_metrics.record_update(start.elapsed());
+        report_expired_selection_pressures(&expired);
+        self.run_outputs
+            .after_update(self.run_metrics.steps(), &self.world);
+        self.watch
+            .after_update(self.run_metrics.steps(), self.world.grid());
+        if let Some(breakpoint) = self.breakpoints.check(&self.world) {
+            report_breakpoint(&breakpoint, self.run_metrics.steps());
+            self.paused = true;
+        }
+        self.present_frame_if_due();
+    }
+
+    fn present_frame_if_due(&mut self) {
+        let step = self.run_metrics.steps();
+        if self.presented_step != Some(step) && self.frontend.frame_due(step) {
+            self.frontend.present(&Frame::new(step, &self.world));
+            self.presented_step = Some(step);
+        }
+    }
+
+    // Returns false if the frontend quit.
+    fn handle_frontend_input(&mut self) -> bool {
+        for input in self.frontend.poll_input() {
+            // Only the commands for running the world apply without a window.
+            let InputEvent::Key { action, .. } = input else {
+                continue;
+            };
+            match action {
+                KeyAction::Quit => return false,
+                KeyAction::TogglePaused => self.paused = !self.paused,
+                KeyAction::SingleStep => {
+                    self.paused = true;
+                    self.update_world();
+                }
+                KeyAction::Restart => {
+                    self.world.reset();
+                    self.presented_step = None;
+                    self.present_frame_if_due();
+                }
+                _ => (),
+            }
+        }
+        true
+    }
+
+    fn handle_remote_requests(&mut self) {
+        while let Some(request) = self
+            .remote
+            .as_ref()
+            .and_then(|remote| remote.next_request())
+        {
+            let response = self.execute_remote_command(&request.command);
+            request.respond(response);
+        }
+    }
+
+    fn execute_remote_command(&mut self, command: &RemoteCommand) -> RemoteResponse {
+        match command {
+            RemoteCommand::Pause => {
+                self.paused = true;
+                RemoteResponse::ok()
+            }
+            RemoteCommand::Resume => {
+                self.paused = false;
+                RemoteResponse::ok()
+            }
+            RemoteCommand::Step => {
+                self.paused = true;
+                self.update_world();
+                RemoteResponse::ok()
+            }
+            RemoteCommand::Snapshot => {
+                let size = self.world.grid().size();
+                let stats = self.world.step_stats();
+                RemoteResponse::json(format!(
+                    "{{\"steps\":{},\"generation\":{},\"live_cells\":{},\"paused\":{},\"width\":{},\"height\":{}}}",
+                    self.run_metrics.steps(),
+                    stats.generation,
+                    stats.live_cells,
+                    self.paused,
+                    size.width,
+                    size.height
+                ))
+            }
+            RemoteCommand::Metrics => RemoteResponse::prometheus(
+                self.run_metrics.prometheus_text(&world_stats(&self.world)),
+            ),
+            RemoteCommand::SetParam { name, .. } => {
+                RemoteResponse::error(&format!("Unknown param {}", name))
+            }
+            RemoteCommand::Select(_) if !self.world.is_editable() => {
+                RemoteResponse::error("Can't edit this world's cells")
+            }
+            RemoteCommand::Select(pressure) => {
+                add_selection_pressure(&mut self.selection_probes, *pressure);
+                RemoteResponse::ok()
+            }
+            RemoteCommand::AddBreakpoint(breakpoint) => {
+                self.breakpoints.add(breakpoint.clone(), &self.world);
+                RemoteResponse::ok()
+            }
+            RemoteCommand::Watch(loc) => {
+                self.watch.toggle(*loc);
+                RemoteResponse::ok()
+            }
+            RemoteCommand::Unknown(target) => {
+                RemoteResponse::error(&format!("Unknown command {}", target))
+            }
+        }
+    }
+}
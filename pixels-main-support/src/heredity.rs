@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use world_grid::GridCell;
+
+const EMPTY_COLOR: [u8; 4] = [0, 0, 0, 0xff];
+
+// The genome that creatures' distances are measured from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HeredityReference {
+    // The most common genome when the world started.
+    Initial,
+    // The most common genome now, recomputed each time the grid is drawn.
+    Modal,
+}
+
+// An analysis view that colors each creature by how many of its gene bits (see
+// GridCell::genes) differ from a reference genome, from blue for none to red for half or more,
+// so that waves of adaptation show as they spread across the grid. Empty cells are black.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeredityView {
+    pub reference: HeredityReference,
+    pub genome: Option<u64>,
+}
+
+impl HeredityView {
+    // Off, then distance from the initial genome, then from the modal one, then off again.
+    pub fn next(view: Option<Self>, initial_genome: Option<u64>) -> Option<Self> {
+        match view.map(|view| view.reference) {
+            None => Some(Self {
+                reference: HeredityReference::Initial,
+                genome: initial_genome,
+            }),
+            Some(HeredityReference::Initial) => Some(Self {
+                reference: HeredityReference::Modal,
+                genome: None,
+            }),
+            Some(HeredityReference::Modal) => None,
+        }
+    }
+
+    pub fn update_reference<C: GridCell>(&mut self, cells: &[C]) {
+        if self.reference == HeredityReference::Modal {
+            self.genome = modal_genome(cells);
+        }
+    }
+
+    pub fn cell_color<C: GridCell>(&self, cell: &C) -> [u8; 4] {
+        let (Some(genome), Some(reference)) = (cell.genome(), self.genome) else {
+            return EMPTY_COLOR;
+        };
+        let mask = gene_mask::<C>();
+        let distance = ((genome ^ reference) & mask).count_ones() as f64;
+        // Half the bits differing is as far as heredity goes: random genomes differ by that.
+        let fraction = (2.0 * distance / mask.count_ones() as f64).min(1.0);
+        [
+            (fraction * 255.0).round() as u8,
+            ((1.0 - (2.0 * fraction - 1.0).abs()) * 160.0).round() as u8,
+            ((1.0 - fraction) * 255.0).round() as u8,
+            0xff,
+        ]
+    }
+}
+
+// The most common genome among the cells' creatures, if there are any.
+pub fn modal_genome<C: GridCell>(cells: &[C]) -> Option<u64> {
+    let mut counts = HashMap::new();
+    for genome in cells.iter().filter_map(|cell| cell.genome()) {
+        *counts.entry(genome).or_insert(0) += 1;
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(genome, count)| (*count, *genome))
+        .map(|(genome, _)| genome)
+}
+
+// The genome bits that are genes, or all of them for cells that don't list their genes.
+fn gene_mask<C: GridCell>() -> u64 {
+    let genes = C::genes();
+    if genes.is_empty() {
+        return u64::MAX;
+    }
+    genes
+        .iter()
+        .fold(0, |mask, gene| mask | (0xff << gene.shift))
+}
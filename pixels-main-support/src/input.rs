@@ -0,0 +1,72 @@
+use crate::UserAction;
+use world_grid::Loc;
+
+// What the user did, as frontends report it to the app: commands, and pointer actions at the
+// grid cell under the pointer, so that handling them doesn't depend on the window system.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+    // Commands that act on a cell (e.g. SaveSeed) act on the one under the pointer, if any.
+    Key {
+        action: KeyAction,
+        pointer: Option<Loc>,
+    },
+    // The button went down, or the pointer moved while it was down.
+    PointerDragged(Loc),
+    // The button came up, over the cell if any.
+    PointerReleased(Option<Loc>),
+}
+
+impl InputEvent {
+    pub fn key(action: KeyAction) -> Self {
+        Self::Key {
+            action,
+            pointer: None,
+        }
+    }
+}
+
+// The commands the keyboard gives, whatever keys give them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyAction {
+    Quit,
+    ToggleFastForward,
+    TogglePaused,
+    Restart,
+    SingleStep,
+    TogglePhaseStepping,
+    SaveSeed,
+    CopyPattern,
+    PastePattern,
+    SavePattern,
+    InjectSeed,
+    RemoveSource,
+    CycleCoarseGrain,
+    ToggleCoarseGrainMode,
+    CycleHeredity,
+    ReportPerformance,
+    CycleMouseTool,
+    Undo,
+    Redo,
+    CycleZoom,
+    ToggleFollowing,
+    QuickSave,
+    NextSaveSlot,
+    QuickLoad,
+}
+
+impl KeyAction {
+    // The action the command performs on its own, if any. The others depend on the app's
+    // state, e.g. the cell under the pointer or the mouse tool.
+    pub fn user_action(self) -> Option<UserAction> {
+        match self {
+            Self::ToggleFastForward => Some(UserAction::ToggleFastForward),
+            Self::TogglePaused => Some(UserAction::TogglePaused),
+            Self::Restart => Some(UserAction::Restart),
+            Self::SingleStep => Some(UserAction::SingleStep),
+            Self::TogglePhaseStepping => Some(UserAction::TogglePhaseStepping),
+            Self::Undo => Some(UserAction::Undo),
+            Self::Redo => Some(UserAction::Redo),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::options::exit_with_usage_error;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use world_grid::{GeneDescriptor, GridCell, Loc, World, WorldGrid};
+
+// Probed creatures are this many cells apart, so that no empty cell borders two of them.
+const PROBE_SPACING: u32 = 3;
+const HEATMAP_SHADES: &[u8] = b" .:-=+*#%@";
+
+// For each of the 256 values of one of the cells' genes (see GridCell::genes), gives a sample
+// of the creatures in an environment that gene value and steps the world once, measuring how
+// many of them survive and how many children carrying the value are born next to them. The
+// result shows what the environment's selection is rewarding.
+pub struct FitnessProbe {
+    gene_name: String,
+    snapshot_path: Option<PathBuf>,
+    csv_path: Option<PathBuf>,
+    warmup_steps: u64,
+}
+
+struct LandscapePoint {
+    survival: f64,
+    births: f64,
+}
+
+impl FitnessProbe {
+    pub fn new(
+        gene_name: &str,
+        snapshot_path: Option<PathBuf>,
+        csv_path: Option<PathBuf>,
+        warmup_steps: u64,
+    ) -> Self {
+        Self {
+            gene_name: gene_name.to_string(),
+            snapshot_path,
+            csv_path,
+            warmup_steps,
+        }
+    }
+
+    // The environment is the snapshot, if given, or else the world after its warm-up steps.
+    // Worlds that change their rules over time are probed under whatever rules are in effect
+    // after that.
+    pub fn run<W: World>(&self, mut world: W) {
+        if !world.is_editable() {
+            exit_with_usage_error("--probe needs a world whose cells can be edited");
+        }
+        match &self.snapshot_path {
+            Some(path) => Self::restore_grid(world.grid_mut(), &fs::read(path).unwrap()),
+            None => {
+                for _ in 0..self.warmup_steps {
+                    world.update();
+                }
+            }
+        }
+
+        let gene = Self::find_gene(world.grid(), &self.gene_name);
+        let environment = world.grid().encode_snapshot();
+        let probe_locs = Self::probe_locs(world.grid());
+        assert!(
+            !probe_locs.is_empty(),
+            "The environment has no creatures to probe"
+        );
+        let landscape: Vec<LandscapePoint> = (0..=u8::MAX)
+            .map(|value| {
+                Self::restore_grid(world.grid_mut(), &environment);
+                Self::probe(&mut world, &probe_locs, gene, value)
+            })
+            .collect();
+
+        println!(
+            "Survival odds of {} values over {} creatures (rows: high nybble, cols: low nybble)",
+            self.gene_name,
+            probe_locs.len()
+        );
+        Self::print_heatmap(&landscape);
+        match &self.csv_path {
+            Some(path) => Self::write_csv(
+                BufWriter::new(File::create(path).unwrap()),
+                gene,
+                &landscape,
+            ),
+            None => Self::write_csv(io::stdout().lock(), gene, &landscape),
+        }
+        .unwrap();
+    }
+
+    fn restore_grid<C: GridCell>(grid: &mut WorldGrid<C>, snapshot: &[u8]) {
+        *grid = WorldGrid::decode_snapshot(snapshot).unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    fn find_gene<C: GridCell>(_grid: &WorldGrid<C>, gene_name: &str) -> &'static GeneDescriptor {
+        GeneDescriptor::find::<C>(gene_name).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn probe_locs<C: GridCell>(grid: &WorldGrid<C>) -> Vec<Loc> {
+        let width = grid.size().width;
+        grid.cells_iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.genome().is_some())
+            .map(|(index, _)| Loc::new(index as u32 / width, index as u32 % width))
+            .filter(|loc| loc.row % PROBE_SPACING == 0 && loc.col % PROBE_SPACING == 0)
+            .collect()
+    }
+
+    fn probe<W: World>(
+        world: &mut W,
+        probe_locs: &[Loc],
+        gene: &GeneDescriptor,
+        value: u8,
+    ) -> LandscapePoint {
+        let before = Self::gene_values(world.grid(), gene);
+        Self::set_gene(world.grid_mut(), probe_locs, gene, value);
+        world.update();
+        let after = Self::gene_values(world.grid(), gene);
+
+        let size = world.grid().size();
+        let mut survivors = 0;
+        let mut births = 0;
+        for loc in probe_locs {
+            if after[loc.grid_index(size).unwrap()] == Some(value) {
+                survivors += 1;
+            }
+            for neighbor in Self::neighbor_indexes(*loc, size.width, size.height) {
+                if before[neighbor].is_none() && after[neighbor] == Some(value) {
+                    births += 1;
+                }
+            }
+        }
+        LandscapePoint {
+            survival: survivors as f64 / probe_locs.len() as f64,
+            births: births as f64 / probe_locs.len() as f64,
+        }
+    }
+
+    fn gene_values<C: GridCell>(grid: &WorldGrid<C>, gene: &GeneDescriptor) -> Vec<Option<u8>> {
+        grid.cells_iter()
+            .map(|cell| cell.genome().map(|genome| gene.value(genome)))
+            .collect()
+    }
+
+    fn set_gene<C: GridCell>(
+        grid: &mut WorldGrid<C>,
+        locs: &[Loc],
+        gene: &GeneDescriptor,
+        value: u8,
+    ) {
+        for loc in locs {
+            let cell = grid.cell_mut(*loc).unwrap();
+            if let Some(genome) = cell.genome() {
+                cell.set_genome(gene.with_value(genome, value));
+            }
+        }
+    }
+
+    fn neighbor_indexes(loc: Loc, width: u32, height: u32) -> impl Iterator<Item = usize> {
+        let rows = loc.row.saturating_sub(1)..=(loc.row + 1).min(height - 1);
+        rows.flat_map(move |row| {
+            let cols = loc.col.saturating_sub(1)..=(loc.col + 1).min(width - 1);
+            cols.map(move |col| (row, col))
+        })
+        .filter(move |&(row, col)| (row, col) != (loc.row, loc.col))
+        .map(move |(row, col)| (row * width + col) as usize)
+    }
+
+    fn print_heatmap(landscape: &[LandscapePoint]) {
+        for row in landscape.chunks(16) {
+            let shades: String = row
+                .iter()
+                .map(|point| {
+                    let shade = (point.survival * (HEATMAP_SHADES.len() - 1) as f64).round();
+                    HEATMAP_SHADES[shade as usize] as char
+                })
+                .collect();
+            println!("|{}|", shades);
+        }
+    }
+
+    // Each value is also given as the gene reads it (see GeneFormat), quoted.
+    fn write_csv<O: Write>(
+        mut output: O,
+        gene: &GeneDescriptor,
+        landscape: &[LandscapePoint],
+    ) -> io::Result<()> {
+        writeln!(output, "value,bits,survival,births,formatted")?;
+        for (value, point) in landscape.iter().enumerate() {
+            writeln!(
+                output,
+                "{},{:08b},{:.4},{:.4},\"{}\"",
+                value,
+                value,
+                point.survival,
+                point.births,
+                gene.format_value(value as u8)
+            )?;
+        }
+        output.flush()
+    }
+}
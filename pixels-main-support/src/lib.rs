@@ -1,402 +1,344 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
-use itertools::izip;
-use pixels::wgpu::Color;
-use pixels::{Pixels, PixelsBuilder, SurfaceTexture};
-use std::slice::Iter;
-use std::sync::Arc;
-use std::time::{Duration, Instant};
-use winit::application::ApplicationHandler;
-use winit::dpi::{PhysicalPosition, PhysicalSize};
-use winit::event::{ElementState, KeyEvent, MouseButton, StartCause, WindowEvent};
-use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
-use winit::keyboard::{KeyCode, PhysicalKey};
-use winit::window::{Cursor, CursorIcon, Fullscreen, Window, WindowId};
-use world_grid::{GridCell, GridSize, World, alpha_blend_with_background};
-
-const BACKGROUND_COLOR: Color = Color::BLACK;
-const CURSOR_TIMEOUT_MILLIS: u64 = 1000;
-
-pub fn animate<W, F>(time_step_frames: u32, build_world: F)
-where
-    W: World,
-    F: Fn(PhysicalSize<u32>) -> W,
-{
-    let event_loop = EventLoop::new().unwrap();
-    event_loop.set_control_flow(ControlFlow::Poll);
-    event_loop
-        .run_app(&mut AppEventHandler::new(time_step_frames, build_world))
-        .unwrap();
-}
-
-struct AppEventHandler<W, F>
-where
-    W: World,
-    F: Fn(PhysicalSize<u32>) -> W,
-{
-    build_world: F,
-    app: Option<App<W>>,
-    cursor_position: PhysicalPosition<f64>,
-    cursor_timeout: Option<Instant>,
+mod app;
+mod batch_runs;
+mod breakpoint;
+mod camera;
+mod checksums;
+mod coarse_grain;
+mod edit_checks;
+mod event_handler;
+mod event_log;
+mod frontend;
+mod genome_panel;
+mod gpu_cross_fade;
+mod headless;
+mod heredity;
+mod input;
+mod landscape;
+mod metrics;
+mod mouse_tool;
+mod options;
+mod pixel_buffer;
+mod probes;
+mod remote;
+mod replay;
+mod run_outputs;
+mod save_slots;
+mod screensaver;
+mod snapshot_diff;
+mod spectrum;
+mod speed_schedule;
+mod sweep_heatmap;
+#[cfg(test)]
+mod test_world;
+mod tournament;
+mod trials;
+mod watch;
+mod window_frontend;
+mod world_app;
+
+pub use frontend::{Frame, Frontend, FrontendKind};
+pub use input::{InputEvent, KeyAction};
+pub use options::AppOptions;
+pub use replay::UserAction;
+pub use winit::keyboard::KeyCode;
+pub use world_app::{KeyBindings, WorldApp};
+
+use batch_runs::run_batch;
+use breakpoint::Breakpoints;
+use checksums::compare_checksums;
+use edit_checks::check_edit_options;
+use event_handler::AppEventHandler;
+use headless::HeadlessApp;
+use options::load_option_file;
+use probes::initial_selection_probes;
+use remote::RemoteControl;
+use run_outputs::RunOutputs;
+use save_slots::print_save_trees;
+use speed_schedule::SpeedSchedule;
+use std::cell::RefCell;
+use std::fs;
+use std::time::Instant;
+use sweep_heatmap::write_sweep_heatmap;
+use watch::CellWatch;
+use winit::dpi::PhysicalSize;
+use winit::event_loop::{ControlFlow, EventLoop};
+use world_grid::{
+    GridSettings, GridSize, Loc, Pattern, Random, ReplicateWorld, Seed, World, WorldParams,
+    set_grid_memory_budget, set_update_threads, strict_math,
+};
+
+const DEFAULT_HEADLESS_WINDOW_SIZE: PhysicalSize<u32> = PhysicalSize::new(1920, 1080);
+// Grids built for small windows, or given by --grid-size, get at least this many cells on a
+// side, which every world can run on.
+const MIN_GRID_SIDE: u32 = 8;
+
+pub fn animate<W, F>(
     time_step_frames: u32,
-}
-
-impl<W, F> AppEventHandler<W, F>
-where
-    W: World,
-    F: Fn(PhysicalSize<u32>) -> W,
+    cell_pixel_width: u32,
+    randoms: RunRandoms,
+    build_world: F,
+) where
+    W: World + Clone,
+    F: Fn(GridSize, Random) -> W,
 {
-    fn new(time_step_frames: u32, build_world: F) -> Self {
-        Self {
-            build_world,
-            app: None,
-            cursor_position: PhysicalPosition::new(0.0, 0.0),
-            cursor_timeout: None,
+    let options = AppOptions::from_args();
+    if options.print_save_trees {
+        print_save_trees(&options.save_slots_dir);
+        return;
+    }
+    if let Some((path, other_path)) = &options.compare_checksum_paths {
+        compare_checksums(path, other_path);
+        return;
+    }
+    if let Some(path) = &options.sweep_heatmap_csv_path {
+        write_sweep_heatmap(path, options.sweep_heatmap_columns.as_ref());
+        return;
+    }
+    strict_math::set_strict_math(options.strict_math);
+    if let Some(num_threads) = options.update_threads {
+        set_update_threads(num_threads);
+    }
+    if let Some(max_memory_mib) = options.max_memory_mib {
+        set_grid_memory_budget(max_memory_mib.saturating_mul(1 << 20));
+    }
+    // Overrides the world's own settings, e.g. its schedule.
+    let grid_settings = GridSettings {
+        edge_mode: Some(options.edge_mode),
+        pinned_locs: options.pinned_locs.clone(),
+        population_cap: options.population_cap,
+        update_schedule: options.update_schedule,
+        verify_updates: options.verify_updates,
+    };
+    let fast_forward_steps = options.fast_forward_steps;
+    // Each world built, e.g. for each round of a tournament, gets its own stream.
+    let world_rand = RefCell::new(randoms.world);
+    let build_world = |grid_size| {
+        let mut world = build_world(grid_size, world_rand.borrow_mut().fork());
+        world.apply_grid_settings(&grid_settings);
+        warm_up(&mut world, fast_forward_steps);
+        world
+    };
+    if let Some(variant_schedule) = options.replicate_schedule {
+        // The replicates and their diff take a third of the grid each.
+        run(
+            options,
             time_step_frames,
-        }
-    }
-
-    fn app(&mut self) -> &mut App<W> {
-        self.app.as_mut().unwrap()
-    }
-
-    fn show_cursor(&mut self) {
-        self.app().window.set_cursor_visible(true);
-        self.cursor_timeout = Some(Instant::now() + Duration::from_millis(CURSOR_TIMEOUT_MILLIS));
-    }
-
-    fn hide_cursor(&mut self) {
-        self.app().window.set_cursor_visible(false);
-        self.cursor_timeout = None;
+            cell_pixel_width,
+            randoms.selection,
+            randoms.tournament,
+            |grid_size: GridSize| {
+                let panel_size = GridSize::new((grid_size.width / 3).max(1), grid_size.height);
+                ReplicateWorld::new(build_world(panel_size), variant_schedule)
+            },
+        );
+    } else {
+        run(
+            options,
+            time_step_frames,
+            cell_pixel_width,
+            randoms.selection,
+            randoms.tournament,
+            build_world,
+        );
     }
 }
 
-impl<W, F> ApplicationHandler for AppEventHandler<W, F>
-where
+fn run<W, F>(
+    options: AppOptions,
+    time_step_frames: u32,
+    cell_pixel_width: u32,
+    selection_rand: Random,
+    tournament_rand: Random,
+    build_world: F,
+) where
     W: World,
-    F: Fn(PhysicalSize<u32>) -> W,
+    F: Fn(GridSize) -> W,
 {
-    fn new_events(&mut self, _event_loop: &ActiveEventLoop, _cause: StartCause) {
-        if self.app.is_some() {
-            self.app().on_frame();
+    let headless_grid_size = grid_size(
+        &options,
+        options
+            .headless_window_size
+            .unwrap_or(DEFAULT_HEADLESS_WINDOW_SIZE),
+        cell_pixel_width,
+    );
+    if run_batch(&options, tournament_rand, || {
+        build_world(headless_grid_size)
+    }) {
+        return;
+    }
+    if options.headless_window_size.is_some() {
+        let mut world = build_world(headless_grid_size);
+        check_edit_options(&world, &options);
+        let seed = options
+            .seed_path
+            .as_deref()
+            .map(|path| load_option_file(path, Seed::load));
+        place_initial_pattern(&mut world, &options, seed.as_ref());
+        let run_outputs = RunOutputs::new(&options, world.grid());
+        let remote = options.http_port.map(RemoteControl::start);
+        let selection_probes = initial_selection_probes(&options, selection_rand);
+        let breakpoints = Breakpoints::new(&options.breakpoints, &world);
+        let watch = CellWatch::new(&options, world.grid());
+        let mut app = HeadlessApp::new(
+            world,
+            run_outputs,
+            remote,
+            selection_probes,
+            breakpoints,
+            watch,
+            SpeedSchedule::new(&options.speed_changes),
+        )
+        .with_frontend(
+            options
+                .frontend
+                .build(&options.frames_dir, options.frame_interval),
+        );
+        app.run(options.max_steps);
+        if let Some(path) = &options.save_snapshot_path {
+            fs::write(path, app.world().grid().encode_snapshot()).unwrap();
         }
-
-        if let Some(cursor_timeout) = self.cursor_timeout
-            && Instant::now() >= cursor_timeout
-        {
-            self.hide_cursor();
+        if let Some(path) = &options.save_pattern_path {
+            let grid = app.world().grid();
+            let pattern = grid.extract_pattern(Loc::new(0, 0), grid.size()).trimmed();
+            pattern.save(path).unwrap();
+            println!("Saved pattern {}", path.display());
         }
+        return;
     }
 
-    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        if self.app.is_none() {
-            self.app = Some(App::new(
-                event_loop,
-                self.time_step_frames,
-                &self.build_world,
-            ));
-            self.app().on_create();
-        }
+    let event_loop = EventLoop::new().unwrap();
+    event_loop.set_control_flow(ControlFlow::Poll);
+    let mut event_handler = AppEventHandler::new(
+        options,
+        time_step_frames,
+        cell_pixel_width,
+        selection_rand,
+        build_world,
+    );
+    event_loop.run_app(&mut event_handler).unwrap();
+    if let Some(app) = &event_handler.app {
+        app.watch.save_history();
+        app.run_outputs.finish(app.run_metrics.steps(), &app.world);
     }
+}
 
-    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
-        match event {
-            WindowEvent::CloseRequested => {
-                event_loop.exit();
-            }
-            WindowEvent::CursorMoved { position, .. } => {
-                self.cursor_position = position;
-                self.show_cursor();
-            }
-            WindowEvent::Focused(true) => {
-                self.app().request_redraw();
-            }
-            WindowEvent::KeyboardInput {
-                event:
-                    KeyEvent {
-                        physical_key: PhysicalKey::Code(code),
-                        state: ElementState::Released,
-                        repeat: false,
-                        ..
-                    },
-                ..
-            } => match code {
-                KeyCode::Escape | KeyCode::KeyQ | KeyCode::KeyX => {
-                    event_loop.exit();
-                }
-                KeyCode::KeyF => {
-                    self.app().toggle_fast_forward();
-                }
-                KeyCode::KeyP => {
-                    self.app().toggle_paused();
-                }
-                KeyCode::KeyR => {
-                    self.app().restart();
-                }
-                KeyCode::KeyS => {
-                    self.app().on_single_step();
-                }
-                _ => (),
-            },
-            WindowEvent::MouseInput {
-                button: MouseButton::Left,
-                state: ElementState::Released,
-                ..
-            } => {
-                let pos = self.cursor_position;
-                self.app().on_mouse_click(pos);
-                self.show_cursor();
-            }
-            WindowEvent::RedrawRequested => {
-                self.app().draw();
-            }
-            _ => (),
-        }
+// Takes the --fast-forward steps before the world is shown, without rendering them.
+fn warm_up<W: World>(world: &mut W, steps: u64) {
+    if steps == 0 {
+        return;
     }
+    let start = Instant::now();
+    world.fast_forward(steps);
+    println!("Fast-forwarded {} steps in {:?}", steps, start.elapsed());
 }
 
-struct App<W: World> {
-    world: W,
-    window: Arc<Window>,
-    pixels: Pixels<'static>,
-    cross_fade_buffer: PixelCrossFadeBuffer,
-    time_step_frame: u32,
-    time_step_frames: u32,
-    paused: bool,
-    fast_forward: bool,
+// Places the --pattern file's cells at the center of the grid. In worlds whose creatures have
+// genes, the live cells get the seed's creature, if any.
+fn place_initial_pattern<W: World>(world: &mut W, options: &AppOptions, seed: Option<&Seed>) {
+    let Some(path) = &options.pattern_path else {
+        return;
+    };
+    let pattern = load_option_file(path, Pattern::load);
+    let size = world.grid().size();
+    let center = Loc::new(size.height / 2, size.width / 2);
+    let genome = seed.map_or(0, |seed| seed.genome);
+    let placed = world.grid_mut().place_pattern(&pattern, center, genome);
+    println!(
+        "Placed pattern {} with {} live cells",
+        pattern
+            .name
+            .as_deref()
+            .unwrap_or(&path.display().to_string()),
+        placed
+    );
 }
 
-impl<W: World> App<W> {
-    fn new<F>(event_loop: &ActiveEventLoop, time_step_frames: u32, build_world: &F) -> Self
-    where
-        F: Fn(PhysicalSize<u32>) -> W,
-    {
-        let window = Arc::new(Self::build_window(event_loop));
-        let world = build_world(window.inner_size());
-        let pixels = Self::build_pixels(&window, world.grid().size());
-        let cross_fade_buffer = PixelCrossFadeBuffer::new(world.grid().size());
-        Self {
-            world,
-            window,
-            pixels,
-            cross_fade_buffer,
-            time_step_frame: 0,
-            time_step_frames,
-            paused: false,
-            fast_forward: false,
-        }
-    }
-
-    fn build_window(event_loop: &ActiveEventLoop) -> Window {
-        let window_attributes = Window::default_attributes()
-            .with_cursor(Cursor::Icon(CursorIcon::Crosshair))
-            .with_fullscreen(Some(Fullscreen::Borderless(None)))
-            .with_visible(false);
-        event_loop.create_window(window_attributes).unwrap()
-    }
-
-    fn build_pixels(window: &Arc<Window>, size: GridSize) -> Pixels<'static> {
-        let window_size = window.inner_size();
-        let surface_texture =
-            SurfaceTexture::new(window_size.width, window_size.height, window.clone());
-        PixelsBuilder::new(size.width, size.height, surface_texture)
-            .clear_color(BACKGROUND_COLOR)
-            .build()
-            .unwrap()
-    }
-
-    fn on_create(&mut self) {
-        self.world.update();
-        self.cross_fade_buffer.load(self.world.grid().cells_iter());
-        self.cross_fade_buffer.straight_to_output();
-
-        self.window.set_cursor_visible(false);
-        self.window.set_visible(true);
-        self.window.request_redraw();
-    }
-
-    fn toggle_paused(&mut self) {
-        self.paused ^= true;
-    }
-
-    fn toggle_fast_forward(&mut self) {
-        self.fast_forward ^= true;
-    }
-
-    fn on_frame(&mut self) {
-        if self.time_step_frame < self.time_step_frames {
-            if self.paused || self.fast_forward {
-                self.end_cross_fade();
-            } else {
-                self.on_cross_fade_frame();
-            }
-        } else if !self.paused {
-            if self.fast_forward {
-                self.update_and_draw();
-            } else {
-                self.on_time_step_frame();
-            }
-        }
-    }
-
-    fn end_cross_fade(&mut self) {
-        self.time_step_frame = self.time_step_frames;
-        self.cross_fade_buffer.straight_to_output();
-        self.window.request_redraw();
-    }
-
-    fn on_cross_fade_frame(&mut self) {
-        self.cross_fade_buffer
-            .blend_to_output(self.time_step_frame as f32 / self.time_step_frames as f32);
-        self.time_step_frame += 1;
-        self.window.request_redraw();
-    }
-
-    fn on_time_step_frame(&mut self) {
-        self.world.update();
-        self.cross_fade_buffer.load(self.world.grid().cells_iter());
-        self.time_step_frame = 0;
-        self.window.request_redraw();
-    }
-
-    fn on_single_step(&mut self) {
-        self.paused = true;
-        self.update_and_draw();
-    }
-
-    fn restart(&mut self) {
-        self.world.reset();
-        self.update_and_draw();
-    }
-
-    fn update_and_draw(&mut self) {
-        self.world.update();
-        self.cross_fade_buffer.load(self.world.grid().cells_iter());
-        self.cross_fade_buffer.straight_to_output();
-        self.window.request_redraw();
-    }
-
-    fn on_mouse_click(&self, pos: PhysicalPosition<f64>) {
-        let (col, row) = self
-            .pixels
-            .window_pos_to_pixel((pos.x as f32, pos.y as f32))
-            .unwrap();
-        self.world.grid().debug_print(row as u32, col as u32);
-    }
-
-    fn request_redraw(&self) {
-        self.window.request_redraw();
-    }
-
-    fn draw(&mut self) {
-        for (screen_pixel, buffer_pixel) in izip!(
-            self.pixels.frame_mut().chunks_exact_mut(4),
-            self.cross_fade_buffer.output_pixels.iter()
-        ) {
-            screen_pixel.copy_from_slice(&buffer_pixel.bytes);
-        }
-        self.pixels.render().unwrap();
+// The world's default params, or in surprise mode (--surprise) randomly sampled ones, which
+// get printed.
+pub fn world_params<P: WorldParams>(rand: &mut Random) -> P {
+    if !AppOptions::from_args().surprise {
+        return P::default();
     }
+    let params = P::random(rand);
+    println!("Surprise params: {}", params);
+    params
 }
 
-struct PixelCrossFadeBuffer {
-    input_pixels: Vec<Pixel>,
-    background_pixels: Vec<Pixel>,
-    output_pixels: Vec<Pixel>,
+// The random number streams of the parts of a run that draw their own, each forked in turn
+// from one root seeded by --random-seed, if given. Forking them all up front, in a fixed
+// order, keeps each one's stream the same whichever of the others the run uses.
+pub struct RunRandoms {
+    // Forked again for each world built.
+    pub world: Random,
+    // For sampling params in surprise mode (see world_params).
+    pub surprise: Random,
+    pub selection: Random,
+    pub tournament: Random,
 }
 
-impl PixelCrossFadeBuffer {
-    fn new(size: GridSize) -> Self {
-        let num_pixels = size.area();
+impl RunRandoms {
+    pub fn new(seed: Option<u64>) -> Self {
+        let mut root = match seed {
+            Some(seed) => Random::from_seed(seed),
+            None => Random::new(),
+        };
         Self {
-            input_pixels: vec![Pixel::zeros(); num_pixels],
-            background_pixels: vec![Pixel::zeros(); num_pixels],
-            output_pixels: vec![Pixel::zeros(); num_pixels],
-        }
-    }
-
-    fn load<C: GridCell>(&mut self, cells: Iter<'_, C>) {
-        for (input_pixel, background_pixel, cell) in izip!(
-            self.input_pixels.iter_mut(),
-            self.background_pixels.iter_mut(),
-            cells
-        ) {
-            *background_pixel = *input_pixel;
-            background_pixel.make_opaque();
-
-            input_pixel.bytes = cell.color_rgba();
-            input_pixel.make_transparent();
-        }
-    }
-
-    fn straight_to_output(&mut self) {
-        self.blend_to_output(1.0);
-    }
-
-    fn blend_to_output(&mut self, fraction: f32) {
-        const BLEND_SMOOTHNESS_FACTOR: f32 = 1.5;
-        let dark_pixel_alpha = fraction_to_alpha(
-            (fraction * BLEND_SMOOTHNESS_FACTOR - (BLEND_SMOOTHNESS_FACTOR - 1.0)).max(0.0),
-        );
-        let bright_pixel_alpha = fraction_to_alpha((fraction * BLEND_SMOOTHNESS_FACTOR).min(1.0));
-
-        for (input_pixel, background_pixel, output_pixel) in izip!(
-            self.input_pixels.iter(),
-            self.background_pixels.iter(),
-            self.output_pixels.iter_mut()
-        ) {
-            let mut input_pixel = *input_pixel;
-            input_pixel.set_alpha(if input_pixel.is_dark() {
-                dark_pixel_alpha
-            } else {
-                bright_pixel_alpha
-            });
-
-            output_pixel.bytes =
-                alpha_blend_with_background(input_pixel.bytes, background_pixel.bytes);
-            output_pixel.make_opaque();
+            world: root.fork(),
+            surprise: root.fork(),
+            selection: root.fork(),
+            tournament: root.fork(),
         }
     }
 }
 
-fn fraction_to_alpha(fraction: f32) -> u8 {
-    (fraction * 0xff as f32) as u8
-}
-
-#[derive(Clone, Copy, Debug)]
-struct Pixel {
-    bytes: [u8; 4],
+// The size of grid to build for the window: --grid-size, if given, so that experiments don't
+// depend on the monitor, with the rendering keeping the grid's aspect ratio, letterboxed in
+// the window. Otherwise as many cells of the given width as fit in the window.
+fn grid_size(
+    options: &AppOptions,
+    window_size: PhysicalSize<u32>,
+    cell_pixel_width: u32,
+) -> GridSize {
+    let cell_pixel_width = cell_pixel_width.max(1);
+    let size = options.grid_size.unwrap_or(GridSize::new(
+        window_size.width / cell_pixel_width,
+        window_size.height / cell_pixel_width,
+    ));
+    GridSize::new(
+        size.width.max(MIN_GRID_SIDE),
+        size.height.max(MIN_GRID_SIDE),
+    )
 }
 
-impl Pixel {
-    fn zeros() -> Self {
-        Self { bytes: [0; 4] }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    fn set_alpha(&mut self, alpha: u8) {
-        self.bytes[3] = alpha;
+    fn options(args: &[&str]) -> AppOptions {
+        AppOptions::parse(args.iter().map(|arg| arg.to_string()))
     }
 
-    fn make_transparent(&mut self) {
-        self.set_alpha(0x00);
+    #[test]
+    fn grid_size_fills_the_window_with_cells() {
+        let size = grid_size(&options(&[]), PhysicalSize::new(800, 600), 4);
+        assert_eq!(size, GridSize::new(200, 150));
     }
 
-    fn make_opaque(&mut self) {
-        self.set_alpha(0xff);
-    }
-
-    fn is_dark(&self) -> bool {
-        const MAX_DARK_VALUE: u8 = 0x10;
-        self.bytes[0] <= MAX_DARK_VALUE
-            && self.bytes[1] <= MAX_DARK_VALUE
-            && self.bytes[2] <= MAX_DARK_VALUE
+    #[test]
+    fn grid_size_is_at_least_the_minimum_on_each_side() {
+        let min = MIN_GRID_SIDE;
+        let size = grid_size(&options(&[]), PhysicalSize::new(20, 600), 4);
+        assert_eq!(size, GridSize::new(min, 150));
+        let size = grid_size(
+            &options(&["--grid-size", "3x1000"]),
+            PhysicalSize::new(800, 600),
+            4,
+        );
+        assert_eq!(size, GridSize::new(min, 1000));
+        let size = grid_size(&options(&[]), PhysicalSize::new(0, 0), 0);
+        assert_eq!(size, GridSize::new(min, min));
     }
 }
-
-pub fn window_size_to_grid_size(window_size: PhysicalSize<u32>, cell_pixel_width: u32) -> GridSize {
-    GridSize::new(
-        window_size.width / cell_pixel_width,
-        window_size.height / cell_pixel_width,
-    )
-}
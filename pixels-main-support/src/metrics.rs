@@ -0,0 +1,259 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+use world_grid::{World, WorldMetric};
+
+const METRIC_PREFIX: &str = "evo_grid";
+const ENTROPY_BLOCK_SIZE: u32 = 2;
+// The recent update rate counts the updates within this long before now.
+const RECENT_WINDOW: Duration = Duration::from_secs(1);
+
+// The world's own metrics plus those that every world gets, such as its block entropy.
+pub fn world_stats<W: World>(world: &W) -> Vec<WorldMetric> {
+    let mut result = world.metrics();
+    result.push(WorldMetric::new(
+        "block_entropy",
+        world.grid().block_entropy(ENTROPY_BLOCK_SIZE),
+    ));
+    result
+}
+
+// Simulation time (steps, i.e. generations) against wall-clock time, and how long updates and
+// renders take, so that slow worlds and performance regressions show.
+#[derive(Clone, Debug)]
+pub struct RunMetrics {
+    started: Instant,
+    steps: u64,
+    last_update: Duration,
+    max_update: Duration,
+    total_update: Duration,
+    // When each update within the recent window finished.
+    recent_updates: VecDeque<Instant>,
+    renders: u64,
+    last_render: Duration,
+    max_render: Duration,
+    total_render: Duration,
+}
+
+impl RunMetrics {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            steps: 0,
+            last_update: Duration::ZERO,
+            max_update: Duration::ZERO,
+            total_update: Duration::ZERO,
+            recent_updates: VecDeque::new(),
+            renders: 0,
+            last_render: Duration::ZERO,
+            max_render: Duration::ZERO,
+            total_render: Duration::ZERO,
+        }
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn last_update(&self) -> Duration {
+        self.last_update
+    }
+
+    pub fn record_update(&mut self, duration: Duration) {
+        self.steps += 1;
+        self.last_update = duration;
+        self.max_update = self.max_update.max(duration);
+        self.total_update += duration;
+        let now = Instant::now();
+        self.recent_updates.push_back(now);
+        self.forget_old_updates(now);
+    }
+
+    pub fn record_render(&mut self, duration: Duration) {
+        self.renders += 1;
+        self.last_render = duration;
+        self.max_render = self.max_render.max(duration);
+        self.total_render += duration;
+    }
+
+    fn forget_old_updates(&mut self, now: Instant) {
+        while let Some(oldest) = self.recent_updates.front()
+            && now.duration_since(*oldest) > RECENT_WINDOW
+        {
+            self.recent_updates.pop_front();
+        }
+    }
+
+    // Updates per second over the last second, which unlike steps_per_second isn't diluted
+    // by time spent paused long ago.
+    pub fn recent_steps_per_second(&self) -> f64 {
+        let now = Instant::now();
+        let recent = self
+            .recent_updates
+            .iter()
+            .rev()
+            .take_while(|finished| now.duration_since(**finished) <= RECENT_WINDOW)
+            .count();
+        let window = RECENT_WINDOW.min(self.started.elapsed()).as_secs_f64();
+        if window > 0.0 {
+            recent as f64 / window
+        } else {
+            0.0
+        }
+    }
+
+    pub fn mean_update(&self) -> Duration {
+        Self::mean(self.total_update, self.steps)
+    }
+
+    pub fn mean_render(&self) -> Duration {
+        Self::mean(self.total_render, self.renders)
+    }
+
+    fn mean(total: Duration, count: u64) -> Duration {
+        if count > 0 {
+            total / count as u32
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    // A one-line summary for the console, e.g. "Step 1200 after 20.0s: 60.0 steps/s (58.1
+    // mean), update 4.2ms mean 9.8ms max, render 0.3ms mean 1.1ms max". Runs without a window
+    // leave out the renders.
+    pub fn summary(&self) -> String {
+        let mut result = format!(
+            "Step {} after {:.1?}: {:.1} steps/s ({:.1} mean), update {:.1?} mean {:.1?} max",
+            self.steps,
+            self.started.elapsed(),
+            self.recent_steps_per_second(),
+            self.steps_per_second(),
+            self.mean_update(),
+            self.max_update
+        );
+        if self.renders > 0 {
+            write!(
+                result,
+                ", render {:.1?} mean {:.1?} max",
+                self.mean_render(),
+                self.max_render
+            )
+            .unwrap();
+        }
+        result
+    }
+
+    pub fn steps_per_second(&self) -> f64 {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.steps as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    // Formats these metrics and the world's own metrics in the Prometheus text exposition format.
+    pub fn prometheus_text(&self, world_metrics: &[WorldMetric]) -> String {
+        let mut result = String::new();
+        Self::write_metric(
+            &mut result,
+            "steps_total",
+            "counter",
+            "World update steps since start.",
+            self.steps as f64,
+        );
+        Self::write_metric(
+            &mut result,
+            "steps_per_second",
+            "gauge",
+            "Mean world update steps per second since start.",
+            self.steps_per_second(),
+        );
+        Self::write_metric(
+            &mut result,
+            "recent_steps_per_second",
+            "gauge",
+            "World update steps in the last second.",
+            self.recent_steps_per_second(),
+        );
+        Self::write_metric(
+            &mut result,
+            "last_update_seconds",
+            "gauge",
+            "Duration of the most recent world update.",
+            self.last_update.as_secs_f64(),
+        );
+        Self::write_metric(
+            &mut result,
+            "max_update_seconds",
+            "gauge",
+            "Duration of the slowest world update since start.",
+            self.max_update.as_secs_f64(),
+        );
+        Self::write_metric(
+            &mut result,
+            "update_seconds_total",
+            "counter",
+            "Total time spent in world updates.",
+            self.total_update.as_secs_f64(),
+        );
+        Self::write_metric(
+            &mut result,
+            "renders_total",
+            "counter",
+            "Frames rendered since start.",
+            self.renders as f64,
+        );
+        Self::write_metric(
+            &mut result,
+            "last_render_seconds",
+            "gauge",
+            "Duration of the most recent frame render.",
+            self.last_render.as_secs_f64(),
+        );
+        Self::write_metric(
+            &mut result,
+            "max_render_seconds",
+            "gauge",
+            "Duration of the slowest frame render since start.",
+            self.max_render.as_secs_f64(),
+        );
+        Self::write_metric(
+            &mut result,
+            "render_seconds_total",
+            "counter",
+            "Total time spent rendering frames.",
+            self.total_render.as_secs_f64(),
+        );
+        Self::write_metric(
+            &mut result,
+            "uptime_seconds",
+            "gauge",
+            "Seconds since the simulation started.",
+            self.started.elapsed().as_secs_f64(),
+        );
+        for metric in world_metrics {
+            Self::write_metric(
+                &mut result,
+                &format!("world_{}", metric.name),
+                "gauge",
+                "World-specific metric.",
+                metric.value,
+            );
+        }
+        result
+    }
+
+    fn write_metric(result: &mut String, name: &str, kind: &str, help: &str, value: f64) {
+        let name = format!("{}_{}", METRIC_PREFIX, name);
+        writeln!(result, "# HELP {} {}", name, help).unwrap();
+        writeln!(result, "# TYPE {} {}", name, kind).unwrap();
+        writeln!(result, "{} {}", name, value).unwrap();
+    }
+}
+
+impl Default for RunMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
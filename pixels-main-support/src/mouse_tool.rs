@@ -0,0 +1,150 @@
+use crate::UserAction;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use winit::window::CursorIcon;
+use world_grid::{GridCell, Loc, WorldGrid};
+
+const PERTURB_RADIUS: u32 = 2;
+
+// What a left click does. The current tool is shown by the shape of the mouse cursor.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MouseTool {
+    #[default]
+    Inspect,
+    Watch,
+    Pin,
+    Paint,
+    Erase,
+    Stamp,
+    Perturb,
+    Source,
+    Select,
+}
+
+impl MouseTool {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Inspect => Self::Watch,
+            Self::Watch => Self::Pin,
+            Self::Pin => Self::Paint,
+            Self::Paint => Self::Erase,
+            Self::Erase => Self::Stamp,
+            Self::Stamp => Self::Perturb,
+            Self::Perturb => Self::Source,
+            Self::Source => Self::Select,
+            Self::Select => Self::Inspect,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Inspect => "inspect",
+            Self::Watch => "watch",
+            Self::Pin => "pin",
+            Self::Paint => "paint",
+            Self::Erase => "erase",
+            Self::Stamp => "stamp",
+            Self::Perturb => "perturb",
+            Self::Source => "source",
+            Self::Select => "select",
+        }
+    }
+
+    pub fn cursor_icon(self) -> CursorIcon {
+        match self {
+            Self::Inspect => CursorIcon::Crosshair,
+            Self::Watch => CursorIcon::ZoomIn,
+            Self::Pin => CursorIcon::Pointer,
+            Self::Paint => CursorIcon::Cell,
+            Self::Erase => CursorIcon::NotAllowed,
+            Self::Stamp => CursorIcon::Copy,
+            Self::Perturb => CursorIcon::Move,
+            Self::Source => CursorIcon::Grab,
+            Self::Select => CursorIcon::Cell,
+        }
+    }
+
+    // Whether the tool keeps acting on each cell the cursor is dragged over, rather than
+    // once per click.
+    pub fn drags(self) -> bool {
+        matches!(
+            self,
+            Self::Paint | Self::Erase | Self::Source | Self::Select
+        )
+    }
+
+    // The action a click on the cell performs on its own when the button comes up, if any.
+    // The other tools act as they're dragged, or depend on the app's state, e.g. the seed.
+    pub fn release_action(self, loc: Loc) -> Option<UserAction> {
+        match self {
+            Self::Inspect => Some(UserAction::Inspect(loc)),
+            Self::Watch => Some(UserAction::ToggleWatch(loc)),
+            Self::Pin => Some(UserAction::TogglePinned(loc)),
+            Self::Perturb => Some(UserAction::Perturb(loc)),
+            _ => None,
+        }
+    }
+}
+
+pub fn erase<C: GridCell>(grid: &mut WorldGrid<C>, loc: Loc) {
+    if let Some(cell) = grid.cell_mut(loc) {
+        cell.clear();
+    }
+}
+
+// Flips one bit of one gene (see GridCell::genes) of each creature near center.
+// The bit is picked by hashing the creature's location and genome, so that replays of a
+// perturbation match the original.
+pub fn perturb<C: GridCell>(grid: &mut WorldGrid<C>, center: Loc) {
+    let genes = C::genes();
+    if genes.is_empty() {
+        return;
+    }
+    for row in center.row.saturating_sub(PERTURB_RADIUS)..=center.row + PERTURB_RADIUS {
+        for col in center.col.saturating_sub(PERTURB_RADIUS)..=center.col + PERTURB_RADIUS {
+            if let Some(cell) = grid.cell_mut(Loc::new(row, col))
+                && let Some(genome) = cell.genome()
+            {
+                let mut hasher = DefaultHasher::new();
+                (row, col, genome).hash(&mut hasher);
+                let choice = hasher.finish() as usize % (genes.len() * 8);
+                let shift = genes[choice / 8].shift;
+                cell.set_genome(genome ^ (1 << (shift as usize + choice % 8)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clicks_perform_their_tools_user_actions() {
+        let loc = Loc::new(3, 5);
+        assert_eq!(
+            MouseTool::Inspect.release_action(loc),
+            Some(UserAction::Inspect(loc))
+        );
+        assert_eq!(
+            MouseTool::Watch.release_action(loc),
+            Some(UserAction::ToggleWatch(loc))
+        );
+        assert_eq!(
+            MouseTool::Pin.release_action(loc),
+            Some(UserAction::TogglePinned(loc))
+        );
+        assert_eq!(
+            MouseTool::Perturb.release_action(loc),
+            Some(UserAction::Perturb(loc))
+        );
+    }
+
+    #[test]
+    fn dragging_tools_do_nothing_on_release() {
+        let loc = Loc::new(3, 5);
+        for tool in [MouseTool::Paint, MouseTool::Erase, MouseTool::Source] {
+            assert_eq!(tool.release_action(loc), None, "{}", tool.name());
+        }
+    }
+}
@@ -0,0 +1,483 @@
+use crate::breakpoint::Breakpoint;
+use crate::frontend::FrontendKind;
+use crate::speed_schedule::ScheduledChange;
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use winit::dpi::PhysicalSize;
+use world_grid::{EdgeMode, GridSize, Loc, SelectionPressure, UpdateSchedule};
+
+// The options from a WorldApp's config file, which come before the command line's.
+static CONFIG_ARGS: OnceLock<Vec<String>> = OnceLock::new();
+
+pub(crate) fn set_config_args(args: Vec<String>) {
+    CONFIG_ARGS.set(args).unwrap();
+}
+
+// Opens a file named by an option, exiting with the path and the error if it can't be.
+pub(crate) fn load_option_file<T, F>(path: &Path, load: F) -> T
+where
+    F: FnOnce(&Path) -> io::Result<T>,
+{
+    load(path).unwrap_or_else(|err| {
+        exit_with_usage_error(&format!("Can't open {}: {}", path.display(), err))
+    })
+}
+
+// Reports a mistake on the command line, e.g. an invalid option value, and exits, as it's the
+// user's to fix rather than a bug to panic over.
+pub(crate) fn exit_with_usage_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(2);
+}
+
+#[derive(Clone, Debug)]
+pub struct AppOptions {
+    pub record_path: Option<PathBuf>,
+    pub replay_path: Option<PathBuf>,
+    pub delta_stream_path: Option<PathBuf>,
+    pub npy_dir: Option<PathBuf>,
+    pub npy_interval: u64,
+    pub event_log_path: Option<PathBuf>,
+    // Where to save the lineage tree of the creatures alive when the run ends, as DOT if the
+    // path ends in ".dot" or ".gv", else as Newick. With lineage_root, only that genome's
+    // descendants.
+    pub lineage_path: Option<PathBuf>,
+    pub lineage_root: Option<u64>,
+    // Where to save the generations at which the lineages of the creatures alive when the
+    // run ends coalesce, as CSV.
+    pub coalescence_path: Option<PathBuf>,
+    pub stats_path: Option<PathBuf>,
+    pub checksums_path: Option<PathBuf>,
+    pub compare_checksum_paths: Option<(PathBuf, PathBuf)>,
+    pub diff_snapshot_paths: Option<(PathBuf, PathBuf)>,
+    pub diff_image_path: Option<PathBuf>,
+    pub sweep_heatmap_csv_path: Option<PathBuf>,
+    // The x, y, and metric columns of the sweep CSV, if not its first three.
+    pub sweep_heatmap_columns: Option<[String; 3]>,
+    pub census_path: Option<PathBuf>,
+    pub census_interval: u64,
+    pub http_port: Option<u16>,
+    pub headless_window_size: Option<PhysicalSize<u32>>,
+    // Where a --headless run shows its frames, every frame_interval steps.
+    pub frontend: FrontendKind,
+    pub frames_dir: PathBuf,
+    pub frame_interval: u64,
+    pub max_steps: Option<u64>,
+    pub seed_bank_dir: PathBuf,
+    pub save_slots_dir: PathBuf,
+    pub print_save_trees: bool,
+    pub seed_path: Option<PathBuf>,
+    pub seed_count: usize,
+    pub pattern_path: Option<PathBuf>,
+    pub save_pattern_path: Option<PathBuf>,
+    pub tournament_seed_paths: Vec<PathBuf>,
+    pub entrant_count: usize,
+    pub generations: u64,
+    pub rounds: usize,
+    pub trial_count: Option<usize>,
+    pub trials_csv_path: Option<PathBuf>,
+    pub baseline_path: Option<PathBuf>,
+    pub probe_gene: Option<String>,
+    pub probe_snapshot_path: Option<PathBuf>,
+    pub probe_csv_path: Option<PathBuf>,
+    pub save_snapshot_path: Option<PathBuf>,
+    // Checks each update against a single-threaded one (see WorldGrid::set_verify_updates).
+    pub verify_updates: bool,
+    // E.g. for comparing --checksums across thread counts, which should match (see Random).
+    pub update_threads: Option<usize>,
+    // The most memory, in MiB, that a grid's cells may take (see set_grid_memory_budget).
+    pub max_memory_mib: Option<u64>,
+    pub population_cap: Option<usize>,
+    // Whether evo-conway runs as a Moran process (see EvoConwayWorld::with_moran_process).
+    pub moran_process: bool,
+    pub fast_forward_steps: u64,
+    pub strict_math: bool,
+    pub update_schedule: Option<UpdateSchedule>,
+    pub edge_mode: EdgeMode,
+    pub selection_pressures: Vec<SelectionPressure>,
+    pub breakpoints: Vec<Breakpoint>,
+    pub speed_changes: Vec<ScheduledChange>,
+    pub watched_locs: Vec<Loc>,
+    // Where to save the watched cells' last watch_history_length states when the run ends.
+    pub watch_history_path: Option<PathBuf>,
+    pub watch_history_length: usize,
+    pub pinned_locs: Vec<Loc>,
+    pub replicate_schedule: Option<UpdateSchedule>,
+    pub world_size: Option<PhysicalSize<u32>>,
+    pub grid_size: Option<GridSize>,
+    pub monitor_index: Option<usize>,
+    pub exclusive_fullscreen: bool,
+    pub refresh_rate_hz: Option<u32>,
+    pub surprise: bool,
+    // Seeds the runs' randomness, so that a run can be reproduced exactly.
+    pub random_seed: Option<u64>,
+    pub rule_table_path: Option<PathBuf>,
+    pub source_layout_path: Option<PathBuf>,
+    // The wind for evo-grid, parsed there (see evo_grid::Wind).
+    pub wind: Option<String>,
+    // Rock, paper, and scissors' odds of invading the species each beats.
+    pub invasion_odds: Option<[f64; 3]>,
+    // A named rule for conway, e.g. "seeds" (see conway_life_pixels::PRESET_NAMES).
+    pub preset: Option<String>,
+    pub world_name: Option<String>,
+    pub zoom: u32,
+    pub follow: bool,
+    // Seconds per full rotation of substance hues, if they cycle.
+    pub palette_cycle_secs: Option<f32>,
+    pub spectrum_field: Option<String>,
+    pub spectrum_npy_path: Option<PathBuf>,
+    pub spectrum_image_path: Option<PathBuf>,
+    pub screensaver: bool,
+    pub reseed_interval_secs: u64,
+}
+
+impl Default for AppOptions {
+    fn default() -> Self {
+        Self {
+            record_path: None,
+            replay_path: None,
+            delta_stream_path: None,
+            npy_dir: None,
+            npy_interval: 1,
+            event_log_path: None,
+            lineage_path: None,
+            lineage_root: None,
+            coalescence_path: None,
+            stats_path: None,
+            checksums_path: None,
+            compare_checksum_paths: None,
+            diff_snapshot_paths: None,
+            diff_image_path: None,
+            sweep_heatmap_csv_path: None,
+            sweep_heatmap_columns: None,
+            census_path: None,
+            census_interval: 100,
+            http_port: None,
+            headless_window_size: None,
+            frontend: FrontendKind::default(),
+            frames_dir: PathBuf::from("frames"),
+            frame_interval: 1,
+            max_steps: None,
+            seed_bank_dir: PathBuf::from("seed-bank"),
+            save_slots_dir: PathBuf::from("saves"),
+            print_save_trees: false,
+            seed_path: None,
+            seed_count: 1,
+            pattern_path: None,
+            save_pattern_path: None,
+            tournament_seed_paths: vec![],
+            entrant_count: 50,
+            generations: 1000,
+            rounds: 5,
+            trial_count: None,
+            trials_csv_path: None,
+            baseline_path: None,
+            probe_gene: None,
+            probe_snapshot_path: None,
+            probe_csv_path: None,
+            save_snapshot_path: None,
+            verify_updates: false,
+            update_threads: None,
+            max_memory_mib: None,
+            population_cap: None,
+            moran_process: false,
+            fast_forward_steps: 0,
+            strict_math: false,
+            update_schedule: None,
+            edge_mode: EdgeMode::default(),
+            selection_pressures: vec![],
+            breakpoints: vec![],
+            speed_changes: vec![],
+            watched_locs: vec![],
+            watch_history_path: None,
+            watch_history_length: 1000,
+            pinned_locs: vec![],
+            replicate_schedule: None,
+            world_size: None,
+            grid_size: None,
+            monitor_index: None,
+            exclusive_fullscreen: false,
+            refresh_rate_hz: None,
+            surprise: false,
+            random_seed: None,
+            rule_table_path: None,
+            source_layout_path: None,
+            wind: None,
+            invasion_odds: None,
+            preset: None,
+            world_name: None,
+            zoom: 1,
+            follow: false,
+            palette_cycle_secs: None,
+            spectrum_field: None,
+            spectrum_npy_path: None,
+            spectrum_image_path: None,
+            screensaver: false,
+            reseed_interval_secs: 300,
+        }
+    }
+}
+
+impl AppOptions {
+    pub fn from_args() -> Self {
+        let config_args = CONFIG_ARGS.get().into_iter().flatten().cloned();
+        Self::parse(config_args.chain(env::args().skip(1)))
+    }
+
+    pub fn parse<I>(args: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut result = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--record" => result.record_path = Some(Self::path_value(&arg, args.next())),
+                "--replay" => result.replay_path = Some(Self::path_value(&arg, args.next())),
+                "--record-deltas" => {
+                    result.delta_stream_path = Some(Self::path_value(&arg, args.next()))
+                }
+                "--export-npy" => result.npy_dir = Some(Self::path_value(&arg, args.next())),
+                "--export-interval" => result.npy_interval = Self::parsed_value(&arg, args.next()),
+                "--event-log" => result.event_log_path = Some(Self::path_value(&arg, args.next())),
+                "--lineage" => result.lineage_path = Some(Self::path_value(&arg, args.next())),
+                "--lineage-root" => {
+                    result.lineage_root = Some(Self::parsed_value(&arg, args.next()))
+                }
+                "--coalescence" => {
+                    result.coalescence_path = Some(Self::path_value(&arg, args.next()))
+                }
+                "--stats" => result.stats_path = Some(Self::path_value(&arg, args.next())),
+                "--checksums" => result.checksums_path = Some(Self::path_value(&arg, args.next())),
+                "--compare-checksums" => {
+                    result.compare_checksum_paths = Some((
+                        Self::path_value(&arg, args.next()),
+                        Self::path_value(&arg, args.next()),
+                    ))
+                }
+                "--diff-snapshots" => {
+                    result.diff_snapshot_paths = Some((
+                        Self::path_value(&arg, args.next()),
+                        Self::path_value(&arg, args.next()),
+                    ))
+                }
+                "--diff-image" => {
+                    result.diff_image_path = Some(Self::path_value(&arg, args.next()))
+                }
+                "--sweep-heatmap" => {
+                    result.sweep_heatmap_csv_path = Some(Self::path_value(&arg, args.next()))
+                }
+                "--heatmap-columns" => {
+                    result.sweep_heatmap_columns = Some(Self::names_triple_value(&arg, args.next()))
+                }
+                "--census" => result.census_path = Some(Self::path_value(&arg, args.next())),
+                "--census-interval" => {
+                    result.census_interval = Self::parsed_value(&arg, args.next())
+                }
+                "--http" => result.http_port = Some(Self::parsed_value(&arg, args.next())),
+                "--headless" => {
+                    result.headless_window_size = Some(Self::size_value(&arg, args.next()))
+                }
+                "--frontend" => result.frontend = Self::parsed_value(&arg, args.next()),
+                "--frames-dir" => result.frames_dir = Self::path_value(&arg, args.next()),
+                "--frame-interval" => result.frame_interval = Self::parsed_value(&arg, args.next()),
+                "--max-steps" => result.max_steps = Some(Self::parsed_value(&arg, args.next())),
+                "--seed-bank" => result.seed_bank_dir = Self::path_value(&arg, args.next()),
+                "--save-dir" => result.save_slots_dir = Self::path_value(&arg, args.next()),
+                "--save-tree" => result.print_save_trees = true,
+                "--seed" => result.seed_path = Some(Self::path_value(&arg, args.next())),
+                "--seed-count" => result.seed_count = Self::parsed_value(&arg, args.next()),
+                "--pattern" => result.pattern_path = Some(Self::path_value(&arg, args.next())),
+                "--save-pattern" => {
+                    result.save_pattern_path = Some(Self::path_value(&arg, args.next()))
+                }
+                "--tournament" => {
+                    result.tournament_seed_paths = Self::string_value(&arg, args.next())
+                        .split(',')
+                        .map(PathBuf::from)
+                        .collect()
+                }
+                "--entrant-count" => result.entrant_count = Self::parsed_value(&arg, args.next()),
+                "--generations" => result.generations = Self::parsed_value(&arg, args.next()),
+                "--rounds" => result.rounds = Self::parsed_value(&arg, args.next()),
+                "--trials" => result.trial_count = Some(Self::parsed_value(&arg, args.next())),
+                "--trials-csv" => {
+                    result.trials_csv_path = Some(Self::path_value(&arg, args.next()))
+                }
+                "--baseline" => result.baseline_path = Some(Self::path_value(&arg, args.next())),
+                "--probe" => result.probe_gene = Some(Self::string_value(&arg, args.next())),
+                "--probe-snapshot" => {
+                    result.probe_snapshot_path = Some(Self::path_value(&arg, args.next()))
+                }
+                "--probe-csv" => result.probe_csv_path = Some(Self::path_value(&arg, args.next())),
+                "--save-snapshot" => {
+                    result.save_snapshot_path = Some(Self::path_value(&arg, args.next()))
+                }
+                "--verify-updates" => result.verify_updates = true,
+                "--fast-forward" => {
+                    result.fast_forward_steps = Self::parsed_value(&arg, args.next())
+                }
+                "--threads" => result.update_threads = Some(Self::parsed_value(&arg, args.next())),
+                "--max-memory" => {
+                    result.max_memory_mib = Some(Self::parsed_value(&arg, args.next()))
+                }
+                "--strict-math" => result.strict_math = true,
+                "--moran" => result.moran_process = true,
+                "--population-cap" => {
+                    result.population_cap = Some(Self::parsed_value(&arg, args.next()))
+                }
+                "--select" => result
+                    .selection_pressures
+                    .push(Self::parsed_value(&arg, args.next())),
+                "--break" => result
+                    .breakpoints
+                    .push(Self::parsed_value(&arg, args.next())),
+                "--at" => result
+                    .speed_changes
+                    .push(Self::parsed_value(&arg, args.next())),
+                "--watch" => result.watched_locs.push(Self::loc_value(&arg, args.next())),
+                "--watch-history" => {
+                    result.watch_history_path = Some(Self::path_value(&arg, args.next()))
+                }
+                "--watch-history-length" => {
+                    result.watch_history_length = Self::parsed_value(&arg, args.next())
+                }
+                "--pin" => result.pinned_locs.push(Self::loc_value(&arg, args.next())),
+                "--replicate" => {
+                    result.replicate_schedule = Some(Self::parsed_value(&arg, args.next()))
+                }
+                "--schedule" => {
+                    result.update_schedule = Some(Self::parsed_value(&arg, args.next()))
+                }
+                "--edges" => result.edge_mode = Self::parsed_value(&arg, args.next()),
+                "--world-size" => result.world_size = Some(Self::size_value(&arg, args.next())),
+                "--grid-size" => {
+                    let size = Self::size_value(&arg, args.next());
+                    if size.width == 0 || size.height == 0 {
+                        exit_with_usage_error(&format!("Invalid value for {}: empty grid", arg));
+                    }
+                    result.grid_size = Some(GridSize::new(size.width, size.height));
+                }
+                "--monitor" => result.monitor_index = Some(Self::parsed_value(&arg, args.next())),
+                "--exclusive" => result.exclusive_fullscreen = true,
+                "--refresh-rate" => {
+                    result.refresh_rate_hz = Some(Self::parsed_value(&arg, args.next()))
+                }
+                "--surprise" => result.surprise = true,
+                "--random-seed" => result.random_seed = Some(Self::parsed_value(&arg, args.next())),
+                "--rule-table" => {
+                    result.rule_table_path = Some(Self::path_value(&arg, args.next()))
+                }
+                "--sources" => {
+                    result.source_layout_path = Some(Self::path_value(&arg, args.next()))
+                }
+                "--invasion-odds" => {
+                    result.invasion_odds = Some(Self::odds_triple_value(&arg, args.next()))
+                }
+                "--wind" => result.wind = Some(Self::string_value(&arg, args.next())),
+                "--preset" => result.preset = Some(Self::string_value(&arg, args.next())),
+                "--world" => result.world_name = Some(Self::string_value(&arg, args.next())),
+                "--zoom" => result.zoom = Self::parsed_value(&arg, args.next()),
+                "--follow" => result.follow = true,
+                "--palette-cycle" => {
+                    let secs: f32 = Self::parsed_value(&arg, args.next());
+                    if secs <= 0.0 {
+                        exit_with_usage_error(&format!("Invalid value for {}: {}", arg, secs));
+                    }
+                    result.palette_cycle_secs = Some(secs);
+                }
+                "--spectrum" => result.spectrum_field = Some(Self::string_value(&arg, args.next())),
+                "--spectrum-npy" => {
+                    result.spectrum_npy_path = Some(Self::path_value(&arg, args.next()))
+                }
+                "--spectrum-image" => {
+                    result.spectrum_image_path = Some(Self::path_value(&arg, args.next()))
+                }
+                "--screensaver" => result.screensaver = true,
+                "--reseed-interval" => {
+                    result.reseed_interval_secs = Self::parsed_value(&arg, args.next())
+                }
+                _ => exit_with_usage_error(&format!("Unknown option {}", arg)),
+            }
+        }
+        if result.refresh_rate_hz.is_some() && !result.exclusive_fullscreen {
+            exit_with_usage_error("--refresh-rate requires --exclusive");
+        }
+        result
+    }
+
+    fn path_value(option: &str, value: Option<String>) -> PathBuf {
+        PathBuf::from(Self::string_value(option, value))
+    }
+
+    // Parses e.g. "1920x1080".
+    fn size_value(option: &str, value: Option<String>) -> PhysicalSize<u32> {
+        let value = Self::string_value(option, value);
+        value
+            .split_once('x')
+            .and_then(|(width, height)| {
+                Some(PhysicalSize::new(width.parse().ok()?, height.parse().ok()?))
+            })
+            .unwrap_or_else(|| {
+                exit_with_usage_error(&format!("Invalid value for {}: {}", option, value))
+            })
+    }
+
+    // E.g. "10,20" for row 10, column 20.
+    fn loc_value(option: &str, value: Option<String>) -> Loc {
+        let value = Self::string_value(option, value);
+        value
+            .split_once(',')
+            .and_then(|(row, col)| {
+                Some(Loc::new(row.trim().parse().ok()?, col.trim().parse().ok()?))
+            })
+            .unwrap_or_else(|| {
+                exit_with_usage_error(&format!("Invalid value for {}: {}", option, value))
+            })
+    }
+
+    // E.g. "0.5,0.8,1". A single value, e.g. "0.5", goes for all three.
+    fn odds_triple_value(option: &str, value: Option<String>) -> [f64; 3] {
+        let value = Self::string_value(option, value);
+        let odds: Vec<f64> = value
+            .split(',')
+            .map(|odds| odds.trim().parse().ok())
+            .collect::<Option<_>>()
+            .filter(|odds: &Vec<f64>| odds.iter().all(|odds| (0.0..=1.0).contains(odds)))
+            .unwrap_or_else(|| {
+                exit_with_usage_error(&format!("Invalid value for {}: {}", option, value))
+            });
+        match odds[..] {
+            [odds] => [odds; 3],
+            [first, second, third] => [first, second, third],
+            _ => exit_with_usage_error(&format!("Invalid value for {}: {}", option, value)),
+        }
+    }
+
+    // E.g. "source_rate,wind,mean_population".
+    fn names_triple_value(option: &str, value: Option<String>) -> [String; 3] {
+        let value = Self::string_value(option, value);
+        let names: Vec<String> = value
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .collect();
+        names.try_into().unwrap_or_else(|_| {
+            exit_with_usage_error(&format!("Invalid value for {}: {}", option, value))
+        })
+    }
+
+    fn parsed_value<T: FromStr>(option: &str, value: Option<String>) -> T {
+        let value = Self::string_value(option, value);
+        value.parse().unwrap_or_else(|_| {
+            exit_with_usage_error(&format!("Invalid value for {}: {}", option, value))
+        })
+    }
+
+    fn string_value(option: &str, value: Option<String>) -> String {
+        value.unwrap_or_else(|| exit_with_usage_error(&format!("Missing value for {}", option)))
+    }
+}
@@ -0,0 +1,336 @@
+use crate::coarse_grain::{CoarseGrain, CoarseGrainMode};
+use crate::heredity::HeredityView;
+use std::borrow::Cow;
+use std::mem;
+use std::slice::Iter;
+use winit::dpi::PhysicalSize;
+use world_grid::{GridCell, GridSize, Loc};
+
+const SOURCE_MARKER_COLOR: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+const LAGGING_INDICATOR_COLOR: [u8; 4] = [0xff, 0x00, 0x00, 0xff];
+const LAGGING_INDICATOR_SIZE: u32 = 4;
+const BREAKPOINT_HIGHLIGHT_COLOR: [u8; 4] = [0xff, 0xff, 0x00, 0xff];
+// Cells from the highlighted cell to the ring around it.
+const BREAKPOINT_HIGHLIGHT_RADIUS: i64 = 3;
+
+// The pixels the window shows: the cells' colors in the current view (e.g. coarse-grained),
+// sampled down to fit the window, with markers drawn over them.
+pub struct PixelBuffer {
+    grid_size: GridSize,
+    // Each pixel shows the average color of a square block of this many cells on a side, so
+    // that grids bigger than the window still fit in it.
+    pub downsample: u32,
+    pub size: GridSize,
+    pub pixels: Vec<[u8; 4]>,
+    // Indexes of pixels currently overwritten by source markers.
+    marked_pixels: Vec<usize>,
+    pub coarse_grain: Option<CoarseGrain>,
+    pub heredity: Option<HeredityView>,
+    // How far cells' backdrop hues are rotated (see GridCell::hue_shifted_color_rgba), in
+    // turns of the color wheel.
+    hue_turns: f32,
+    // Whether all pixels need reloading, even if their cells haven't changed.
+    stale: bool,
+    // Which rows of pixels have changed since the last load began, or None if all may have,
+    // so that frontends can redraw just those.
+    pub changed_rows: Option<Vec<bool>>,
+}
+
+impl PixelBuffer {
+    pub fn new(grid_size: GridSize, window_size: PhysicalSize<u32>) -> Self {
+        let downsample = grid_size
+            .width
+            .div_ceil(window_size.width.max(1))
+            .max(grid_size.height.div_ceil(window_size.height.max(1)))
+            .max(1);
+        let size = GridSize::new(
+            grid_size.width.div_ceil(downsample),
+            grid_size.height.div_ceil(downsample),
+        );
+        Self {
+            grid_size,
+            downsample,
+            size,
+            pixels: vec![[0; 4]; size.area()],
+            marked_pixels: vec![],
+            coarse_grain: None,
+            heredity: None,
+            hue_turns: 0.0,
+            stale: false,
+            changed_rows: None,
+        }
+    }
+
+    pub fn set_coarse_grain(&mut self, coarse_grain: Option<CoarseGrain>) {
+        self.coarse_grain = coarse_grain;
+        self.stale = true;
+    }
+
+    pub fn set_heredity(&mut self, heredity: Option<HeredityView>) {
+        self.heredity = heredity;
+        self.stale = true;
+    }
+
+    // The hues change between loads, not during the cross-fades, which smooth the steps.
+    pub fn set_hue_turns(&mut self, hue_turns: f32) {
+        let hue_turns = hue_turns.rem_euclid(1.0);
+        if hue_turns != self.hue_turns {
+            self.hue_turns = hue_turns;
+            self.stale = true;
+        }
+    }
+
+    pub fn load<C: GridCell>(&mut self, cells: Iter<'_, C>) {
+        let cells = cells.as_slice();
+        if let Some(heredity) = self.heredity.as_mut() {
+            heredity.update_reference(cells);
+        }
+        self.marked_pixels.clear();
+        self.stale = false;
+        self.changed_rows = None;
+        match self.coarse_grain {
+            Some(coarse_grain) => self.load_coarse_grained(cells, coarse_grain),
+            None => {
+                for index in 0..self.pixels.len() {
+                    self.load_pixel(index, cells);
+                }
+            }
+        }
+    }
+
+    // Fills each block of pixels with its block of cells' summary color. The blocks are
+    // rounded up to whole pixels.
+    pub fn load_coarse_grained<C: GridCell>(&mut self, cells: &[C], coarse_grain: CoarseGrain) {
+        let block_pixels = coarse_grain.block_size.div_ceil(self.downsample);
+        let coarse_grain = CoarseGrain {
+            block_size: block_pixels * self.downsample,
+            ..coarse_grain
+        };
+        let (width, height) = (self.size.width, self.size.height);
+        for block_row in (0..height).step_by(block_pixels as usize) {
+            for block_col in (0..width).step_by(block_pixels as usize) {
+                let color = coarse_grain.block_color(
+                    cells,
+                    self.grid_size,
+                    block_row * self.downsample,
+                    block_col * self.downsample,
+                    |cell| self.cell_color(cell),
+                );
+                for row in block_row..(block_row + block_pixels).min(height) {
+                    for col in block_col..(block_col + block_pixels).min(width) {
+                        self.set_pixel((row * width + col) as usize, color);
+                    }
+                }
+            }
+        }
+    }
+
+    // Like load, but only for the cells flagged as changed.
+    pub fn load_changes<C: GridCell>(&mut self, cells: Iter<'_, C>, changed_cells: &[bool]) {
+        // A heredity view's modal reference can change with any update.
+        if self.stale || self.coarse_grain.is_some() || self.heredity.is_some() {
+            self.load(cells);
+            return;
+        }
+        let cells = cells.as_slice();
+        self.changed_rows = Some(vec![false; self.size.height as usize]);
+        let changed_pixels = self.changed_pixels(changed_cells);
+        for (index, _) in changed_pixels
+            .iter()
+            .enumerate()
+            .filter(|(_, changed)| **changed)
+        {
+            self.load_pixel(index, cells);
+        }
+    }
+
+    pub fn changed_pixels<'a>(&self, changed_cells: &'a [bool]) -> Cow<'a, [bool]> {
+        if self.downsample == 1 {
+            return Cow::Borrowed(changed_cells);
+        }
+        let mut result = vec![false; self.pixels.len()];
+        let grid_width = self.grid_size.width as usize;
+        let downsample = self.downsample as usize;
+        for (index, _) in changed_cells
+            .iter()
+            .enumerate()
+            .filter(|(_, changed)| **changed)
+        {
+            let (row, col) = (
+                index / grid_width / downsample,
+                index % grid_width / downsample,
+            );
+            result[row * self.size.width as usize + col] = true;
+        }
+        Cow::Owned(result)
+    }
+
+    pub fn load_pixel<C: GridCell>(&mut self, index: usize, cells: &[C]) {
+        self.set_pixel(index, self.pixel_color(index, cells));
+    }
+
+    pub fn set_pixel(&mut self, index: usize, color: [u8; 4]) {
+        self.pixels[index] = color;
+        if let Some(changed_rows) = self.changed_rows.as_mut() {
+            changed_rows[index / self.size.width as usize] = true;
+        }
+    }
+
+    pub fn pixel_color<C: GridCell>(&self, index: usize, cells: &[C]) -> [u8; 4] {
+        if self.downsample == 1 {
+            return self.cell_color(&cells[index]);
+        }
+        let width = self.size.width as usize;
+        let (row, col) = ((index / width) as u32, (index % width) as u32);
+        CoarseGrain {
+            block_size: self.downsample,
+            mode: CoarseGrainMode::Mean,
+        }
+        .block_color(
+            cells,
+            self.grid_size,
+            row * self.downsample,
+            col * self.downsample,
+            |cell| self.cell_color(cell),
+        )
+    }
+
+    pub fn cell_color<C: GridCell>(&self, cell: &C) -> [u8; 4] {
+        match &self.heredity {
+            Some(heredity) => heredity.cell_color(cell),
+            None => cell.hue_shifted_color_rgba(self.hue_turns),
+        }
+    }
+
+    // Draws a small plus-shaped marker at each source loc, and a square in the top left corner
+    // if the simulation is lagging, after restoring the pixels under the previous markers.
+    pub fn mark<C: GridCell>(
+        &mut self,
+        cells: Iter<'_, C>,
+        source_locs: &[Loc],
+        highlighted: Option<Loc>,
+        lagging: bool,
+    ) {
+        let cells = cells.as_slice();
+        for index in mem::take(&mut self.marked_pixels) {
+            let color = self.pixel_color(index, cells);
+            self.set_pixel(index, color);
+        }
+        let (width, height) = (self.size.width as i64, self.size.height as i64);
+        for loc in source_locs {
+            let (row, col) = (
+                (loc.row / self.downsample) as i64,
+                (loc.col / self.downsample) as i64,
+            );
+            for (row_offset, col_offset) in [(0, 0), (-1, 0), (1, 0), (0, -1), (0, 1)] {
+                let (row, col) = (row + row_offset, col + col_offset);
+                if (0..height).contains(&row) && (0..width).contains(&col) {
+                    self.mark_pixel((row * width + col) as usize, SOURCE_MARKER_COLOR);
+                }
+            }
+        }
+        if let Some(loc) = highlighted {
+            // A square ring around the cell.
+            let (row, col) = (
+                (loc.row / self.downsample) as i64,
+                (loc.col / self.downsample) as i64,
+            );
+            let radius = BREAKPOINT_HIGHLIGHT_RADIUS;
+            for row_offset in -radius..=radius {
+                for col_offset in -radius..=radius {
+                    let (row, col) = (row + row_offset, col + col_offset);
+                    if row_offset.abs().max(col_offset.abs()) == radius
+                        && (0..height).contains(&row)
+                        && (0..width).contains(&col)
+                    {
+                        self.mark_pixel((row * width + col) as usize, BREAKPOINT_HIGHLIGHT_COLOR);
+                    }
+                }
+            }
+        }
+        if lagging {
+            for row in 0..LAGGING_INDICATOR_SIZE.min(self.size.height) {
+                for col in 0..LAGGING_INDICATOR_SIZE.min(self.size.width) {
+                    let index = (row * self.size.width + col) as usize;
+                    self.mark_pixel(index, LAGGING_INDICATOR_COLOR);
+                }
+            }
+        }
+    }
+
+    pub fn mark_pixel(&mut self, index: usize, color: [u8; 4]) {
+        self.set_pixel(index, color);
+        self.marked_pixels.push(index);
+    }
+
+    // The grid size the pixels cover, which is rounded up to whole pixels.
+    pub fn covered_size(&self) -> GridSize {
+        GridSize::new(
+            self.size.width * self.downsample,
+            self.size.height * self.downsample,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_world::TestCell;
+
+    fn cells(size: GridSize) -> Vec<TestCell> {
+        (0..size.area())
+            .map(|index| TestCell { value: index as u8 })
+            .collect()
+    }
+
+    fn gray(value: u8) -> [u8; 4] {
+        [value, value, value, 0xff]
+    }
+
+    #[test]
+    fn load_changes_reloads_just_the_changed_cells_and_flags_their_rows() {
+        let size = GridSize::new(4, 3);
+        let mut buffer = PixelBuffer::new(size, PhysicalSize::new(4, 3));
+        buffer.load(cells(size).iter());
+        let mut changed_cells = vec![false; size.area()];
+        changed_cells[5] = true;
+        let mut cells = cells(size);
+        cells[5].value = 100;
+        cells[6].value = 200;
+        buffer.load_changes(cells.iter(), &changed_cells);
+        assert_eq!(buffer.pixels[5], gray(100));
+        // Unflagged cells keep their old pixels.
+        assert_eq!(buffer.pixels[6], gray(6));
+        assert_eq!(buffer.changed_rows, Some(vec![false, true, false]));
+    }
+
+    #[test]
+    fn load_changes_maps_changed_cells_to_their_downsampled_pixels() {
+        let size = GridSize::new(4, 4);
+        let mut buffer = PixelBuffer::new(size, PhysicalSize::new(2, 2));
+        assert_eq!(buffer.downsample, 2);
+        let mut cells = vec![TestCell::default(); size.area()];
+        buffer.load(cells.iter());
+        let mut changed_cells = vec![false; size.area()];
+        // Row 3, column 2, in the bottom right block.
+        changed_cells[14] = true;
+        cells[14].value = 100;
+        buffer.load_changes(cells.iter(), &changed_cells);
+        assert_eq!(buffer.pixels[3], gray(25));
+        assert_eq!(&buffer.pixels[..3], &[gray(0); 3]);
+        assert_eq!(buffer.changed_rows, Some(vec![false, true]));
+    }
+
+    #[test]
+    fn load_changes_reloads_everything_once_the_view_changes() {
+        let size = GridSize::new(4, 3);
+        let mut buffer = PixelBuffer::new(size, PhysicalSize::new(4, 3));
+        buffer.load(cells(size).iter());
+        buffer.set_hue_turns(0.5);
+        let mut cells = cells(size);
+        cells[6].value = 200;
+        buffer.load_changes(cells.iter(), &vec![false; size.area()]);
+        assert_eq!(buffer.pixels[6], gray(200));
+        assert_eq!(buffer.changed_rows, None);
+    }
+}
@@ -0,0 +1,34 @@
+use crate::options::AppOptions;
+use world_grid::{Random, SelectionPressure, SelectionProbes, World};
+
+// The selection pressures given by --select, applied from the first step.
+pub fn initial_selection_probes(options: &AppOptions, rand: Random) -> SelectionProbes {
+    let mut result = SelectionProbes::new(rand);
+    for pressure in &options.selection_pressures {
+        add_selection_pressure(&mut result, *pressure);
+    }
+    result
+}
+
+// Applies the probes' pressures to the world's creatures, if there are any, as they can only
+// be added to editable worlds (see World::is_editable).
+pub fn after_selection_update<W: World>(
+    probes: &mut SelectionProbes,
+    world: &mut W,
+) -> Vec<SelectionPressure> {
+    if probes.is_empty() {
+        return vec![];
+    }
+    probes.after_update(world.grid_mut())
+}
+
+pub fn add_selection_pressure(probes: &mut SelectionProbes, pressure: SelectionPressure) {
+    println!("Applying selection pressure {}", pressure);
+    probes.add(pressure);
+}
+
+pub fn report_expired_selection_pressures(expired: &[SelectionPressure]) {
+    for pressure in expired {
+        println!("Selection pressure {} expired", pressure);
+    }
+}
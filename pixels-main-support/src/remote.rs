@@ -0,0 +1,190 @@
+use crate::breakpoint::Breakpoint;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use world_grid::{Loc, SelectionPressure};
+
+// A minimal HTTP/1.1 server on localhost that forwards each request to the app
+// as a RemoteRequest, and writes back whatever response body the app replies with.
+pub struct RemoteControl {
+    requests: Receiver<RemoteRequest>,
+}
+
+impl RemoteControl {
+    pub fn start(port: u16) -> Self {
+        let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+        let (sender, requests) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                Self::handle_connection(stream, &sender);
+            }
+        });
+        Self { requests }
+    }
+
+    pub fn next_request(&self) -> Option<RemoteRequest> {
+        self.requests.try_recv().ok()
+    }
+
+    fn handle_connection(mut stream: TcpStream, sender: &Sender<RemoteRequest>) {
+        let Some(target) = Self::read_request_target(&stream) else {
+            return;
+        };
+        let (reply_sender, reply_receiver) = mpsc::channel();
+        let request = RemoteRequest {
+            command: RemoteCommand::parse(&target),
+            reply: reply_sender,
+        };
+        if sender.send(request).is_err() {
+            return;
+        }
+        let response = reply_receiver
+            .recv()
+            .unwrap_or_else(|_| RemoteResponse::error("App closed"));
+        let _ = write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            response.status,
+            response.content_type,
+            response.body.len(),
+            response.body
+        );
+    }
+
+    fn read_request_target(stream: &TcpStream) -> Option<String> {
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).ok()?;
+        // Drain the headers; no endpoint takes a request body.
+        let mut header = String::new();
+        while reader.read_line(&mut header).ok()? > 2 {
+            header.clear();
+        }
+        request_line.split_whitespace().nth(1).map(str::to_string)
+    }
+}
+
+pub struct RemoteRequest {
+    pub command: RemoteCommand,
+    reply: Sender<RemoteResponse>,
+}
+
+impl RemoteRequest {
+    pub fn respond(self, response: RemoteResponse) {
+        let _ = self.reply.send(response);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RemoteCommand {
+    Pause,
+    Resume,
+    Step,
+    SetParam { name: String, value: String },
+    Snapshot,
+    Metrics,
+    Select(SelectionPressure),
+    AddBreakpoint(Breakpoint),
+    Watch(Loc),
+    Unknown(String),
+}
+
+impl RemoteCommand {
+    fn parse(target: &str) -> Self {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        match path {
+            "/pause" => Self::Pause,
+            "/resume" => Self::Resume,
+            "/step" => Self::Step,
+            "/snapshot" => Self::Snapshot,
+            "/metrics" => Self::Metrics,
+            // E.g. "/select?pressure=10,20,50,50,8,0.2,100" (see SelectionPressure).
+            "/select" => query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| *key == "pressure")
+                .and_then(|(_, val)| val.parse().ok())
+                .map_or_else(|| Self::Unknown(target.to_string()), Self::Select),
+            // E.g. "/break?when=population<100" (see Breakpoint).
+            "/break" => query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| *key == "when")
+                .and_then(|(_, val)| val.parse().ok())
+                .map_or_else(|| Self::Unknown(target.to_string()), Self::AddBreakpoint),
+            // E.g. "/watch?row=10&col=20"; watching a watched cell stops watching it.
+            "/watch" => {
+                let mut row = None;
+                let mut col = None;
+                for (key, val) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+                    match key {
+                        "row" => row = val.parse().ok(),
+                        "col" => col = val.parse().ok(),
+                        _ => (),
+                    }
+                }
+                match (row, col) {
+                    (Some(row), Some(col)) => Self::Watch(Loc::new(row, col)),
+                    _ => Self::Unknown(target.to_string()),
+                }
+            }
+            "/set-param" => {
+                let mut name = None;
+                let mut value = None;
+                for (key, val) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+                    match key {
+                        "name" => name = Some(val.to_string()),
+                        "value" => value = Some(val.to_string()),
+                        _ => (),
+                    }
+                }
+                match (name, value) {
+                    (Some(name), Some(value)) => Self::SetParam { name, value },
+                    _ => Self::Unknown(target.to_string()),
+                }
+            }
+            _ => Self::Unknown(target.to_string()),
+        }
+    }
+}
+
+pub struct RemoteResponse {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl RemoteResponse {
+    pub fn ok() -> Self {
+        Self::text("200 OK", "OK")
+    }
+
+    pub fn json(body: String) -> Self {
+        Self {
+            status: "200 OK",
+            content_type: "application/json",
+            body,
+        }
+    }
+
+    pub fn prometheus(body: String) -> Self {
+        Self {
+            status: "200 OK",
+            content_type: "text/plain; version=0.0.4",
+            body,
+        }
+    }
+
+    pub fn error(message: &str) -> Self {
+        Self::text("400 Bad Request", message)
+    }
+
+    fn text(status: &'static str, body: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain",
+            body: body.to_string(),
+        }
+    }
+}
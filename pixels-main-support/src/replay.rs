@@ -0,0 +1,207 @@
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use world_grid::{Loc, Pattern, Seed, SelectionPressure};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum UserAction {
+    TogglePaused,
+    ToggleFastForward,
+    Restart,
+    SingleStep,
+    TogglePhaseStepping,
+    Inspect(Loc),
+    ToggleWatch(Loc),
+    TogglePinned(Loc),
+    InjectSeed {
+        loc: Loc,
+        seed: Seed,
+        count: usize,
+    },
+    Erase(Loc),
+    Perturb(Loc),
+    EndStroke,
+    Undo,
+    Redo,
+    AddSource(Loc),
+    MoveSource {
+        index: usize,
+        loc: Loc,
+    },
+    RemoveSource(usize),
+    ApplySelection(SelectionPressure),
+    PastePattern {
+        loc: Loc,
+        pattern: Pattern,
+        genome: u64,
+    },
+}
+
+impl UserAction {
+    // Whether the action changes the world's grid, which only editable worlds allow (see
+    // World::is_editable).
+    pub fn edits_grid(&self) -> bool {
+        matches!(
+            self,
+            Self::TogglePinned(_)
+                | Self::InjectSeed { .. }
+                | Self::Erase(_)
+                | Self::Perturb(_)
+                | Self::Undo
+                | Self::Redo
+                | Self::ApplySelection(_)
+                | Self::PastePattern { .. }
+        )
+    }
+
+    fn format(&self) -> String {
+        match self {
+            Self::TogglePaused => "pause".to_string(),
+            Self::ToggleFastForward => "fast-forward".to_string(),
+            Self::Restart => "restart".to_string(),
+            Self::SingleStep => "step".to_string(),
+            Self::TogglePhaseStepping => "phase-stepping".to_string(),
+            Self::Inspect(loc) => format!("inspect {} {}", loc.row, loc.col),
+            Self::ToggleWatch(loc) => format!("watch {} {}", loc.row, loc.col),
+            Self::TogglePinned(loc) => format!("pin {} {}", loc.row, loc.col),
+            Self::InjectSeed { loc, seed, count } => format!(
+                "inject-seed {} {} {} {} {:x}",
+                loc.row, loc.col, count, seed.format_name, seed.genome
+            ),
+            Self::Erase(loc) => format!("erase {} {}", loc.row, loc.col),
+            Self::Perturb(loc) => format!("perturb {} {}", loc.row, loc.col),
+            Self::EndStroke => "end-stroke".to_string(),
+            Self::Undo => "undo".to_string(),
+            Self::Redo => "redo".to_string(),
+            Self::AddSource(loc) => format!("add-source {} {}", loc.row, loc.col),
+            Self::MoveSource { index, loc } => {
+                format!("move-source {} {} {}", index, loc.row, loc.col)
+            }
+            Self::RemoveSource(index) => format!("remove-source {}", index),
+            Self::ApplySelection(pressure) => format!("select {}", pressure),
+            Self::PastePattern {
+                loc,
+                pattern,
+                genome,
+            } => format!(
+                "paste-pattern {} {} {:x} {}",
+                loc.row,
+                loc.col,
+                genome,
+                pattern.rle_body()
+            ),
+        }
+    }
+
+    fn parse(fields: &[&str]) -> Option<Self> {
+        match fields {
+            ["pause"] => Some(Self::TogglePaused),
+            ["fast-forward"] => Some(Self::ToggleFastForward),
+            ["restart"] => Some(Self::Restart),
+            ["step"] => Some(Self::SingleStep),
+            ["phase-stepping"] => Some(Self::TogglePhaseStepping),
+            ["inspect", row, col] => Some(Self::Inspect(Loc::new(
+                row.parse().ok()?,
+                col.parse().ok()?,
+            ))),
+            ["watch", row, col] => Some(Self::ToggleWatch(Loc::new(
+                row.parse().ok()?,
+                col.parse().ok()?,
+            ))),
+            ["pin", row, col] => Some(Self::TogglePinned(Loc::new(
+                row.parse().ok()?,
+                col.parse().ok()?,
+            ))),
+            ["inject-seed", row, col, count, format_name, genome] => Some(Self::InjectSeed {
+                loc: Loc::new(row.parse().ok()?, col.parse().ok()?),
+                seed: Seed::new(format_name, u64::from_str_radix(genome, 16).ok()?),
+                count: count.parse().ok()?,
+            }),
+            ["erase", row, col] => {
+                Some(Self::Erase(Loc::new(row.parse().ok()?, col.parse().ok()?)))
+            }
+            ["perturb", row, col] => Some(Self::Perturb(Loc::new(
+                row.parse().ok()?,
+                col.parse().ok()?,
+            ))),
+            ["end-stroke"] => Some(Self::EndStroke),
+            ["undo"] => Some(Self::Undo),
+            ["redo"] => Some(Self::Redo),
+            ["add-source", row, col] => Some(Self::AddSource(Loc::new(
+                row.parse().ok()?,
+                col.parse().ok()?,
+            ))),
+            ["move-source", index, row, col] => Some(Self::MoveSource {
+                index: index.parse().ok()?,
+                loc: Loc::new(row.parse().ok()?, col.parse().ok()?),
+            }),
+            ["remove-source", index] => Some(Self::RemoveSource(index.parse().ok()?)),
+            ["select", pressure] => Some(Self::ApplySelection(pressure.parse().ok()?)),
+            ["paste-pattern", row, col, genome, body] => Some(Self::PastePattern {
+                loc: Loc::new(row.parse().ok()?, col.parse().ok()?),
+                pattern: Pattern::parse_rle(body).ok()?,
+                genome: u64::from_str_radix(genome, 16).ok()?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+// Writes each user action along with the number of world steps taken before it,
+// one per line, e.g. "120 inspect 10 20".
+pub struct ReplayRecorder {
+    writer: BufWriter<File>,
+}
+
+impl ReplayRecorder {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    pub fn record(&mut self, step: u64, action: &UserAction) {
+        writeln!(self.writer, "{} {}", step, action.format()).unwrap();
+        self.writer.flush().unwrap();
+    }
+}
+
+pub struct ReplayPlayer {
+    events: VecDeque<(u64, UserAction)>,
+}
+
+impl ReplayPlayer {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let events = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                Self::parse_event(line).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Invalid replay event at line {}: {}", index + 1, line),
+                    )
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self { events })
+    }
+
+    fn parse_event(line: &str) -> Option<(u64, UserAction)> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (step, action) = fields.split_first()?;
+        Some((step.parse().ok()?, UserAction::parse(action)?))
+    }
+
+    pub fn next_due(&mut self, step: u64) -> Option<UserAction> {
+        match self.events.front() {
+            Some((event_step, _)) if *event_step <= step => {
+                self.events.pop_front().map(|(_, action)| action)
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use crate::AppOptions;
+use crate::event_log::EventLog;
+use crate::metrics::world_stats;
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use world_grid::{
+    DeltaStreamRecorder, GridCell, LifeEventDetector, LineageTree, ObjectCensus, TransitionCounter,
+    TransitionCounts, World, WorldGrid,
+};
+
+// Files written as the world steps, as selected by the command-line options.
+pub struct RunOutputs {
+    delta_recorder: Option<DeltaStreamRecorder<BufWriter<File>>>,
+    npy_exporter: Option<NpyExporter>,
+    event_log: Option<EventLog>,
+    stats_log: Option<StatsLog>,
+    checksum_log: Option<ChecksumLog>,
+    census_log: Option<CensusLog>,
+    lineage_log: Option<LineageLog>,
+}
+
+impl RunOutputs {
+    pub fn new<C: GridCell>(options: &AppOptions, grid: &WorldGrid<C>) -> Self {
+        Self {
+            delta_recorder: options
+                .delta_stream_path
+                .as_deref()
+                .map(|path| Self::create_delta_recorder(path, grid)),
+            npy_exporter: options
+                .npy_dir
+                .as_ref()
+                .map(|dir| NpyExporter::new(dir.clone(), options.npy_interval)),
+            event_log: options
+                .event_log_path
+                .as_deref()
+                .map(|path| EventLog::create(path, grid)),
+            stats_log: options
+                .stats_path
+                .as_deref()
+                .map(|path| StatsLog::create(path, grid)),
+            checksum_log: options.checksums_path.as_deref().map(ChecksumLog::create),
+            census_log: options
+                .census_path
+                .as_deref()
+                .map(|path| CensusLog::create(path, options.census_interval)),
+            lineage_log: (options.lineage_path.is_some() || options.coalescence_path.is_some())
+                .then(|| LineageLog::new(options, grid)),
+        }
+    }
+
+    fn create_delta_recorder<C: GridCell>(
+        path: &Path,
+        grid: &WorldGrid<C>,
+    ) -> DeltaStreamRecorder<BufWriter<File>> {
+        DeltaStreamRecorder::new(BufWriter::new(File::create(path).unwrap()), grid).unwrap()
+    }
+
+    pub fn after_update<W: World>(&mut self, step: u64, world: &W) {
+        let grid = world.grid();
+        if let Some(delta_recorder) = self.delta_recorder.as_mut() {
+            delta_recorder.record_step(grid).unwrap();
+        }
+        if let Some(npy_exporter) = self.npy_exporter.as_ref() {
+            npy_exporter.after_update(step, grid);
+        }
+        if let Some(event_log) = self.event_log.as_mut() {
+            event_log.after_update(step, grid);
+        }
+        if let Some(stats_log) = self.stats_log.as_mut() {
+            stats_log.after_update(step, world);
+        }
+        if let Some(census_log) = self.census_log.as_mut() {
+            census_log.after_update(step, grid);
+        }
+        if let Some(checksum_log) = self.checksum_log.as_mut() {
+            checksum_log.after_update(step, grid);
+        }
+        if let Some(lineage_log) = self.lineage_log.as_mut() {
+            lineage_log.after_update(step, grid);
+        }
+    }
+
+    // Writes the outputs that can only be written once the run ends, after the given step.
+    pub fn finish<W: World>(&self, step: u64, world: &W) {
+        if let Some(lineage_log) = self.lineage_log.as_ref() {
+            lineage_log.save(step, world.grid());
+        }
+    }
+}
+
+// Writes each of the cell fields as a separate .npy file, e.g. "step_000100_occupied.npy".
+struct NpyExporter {
+    dir: PathBuf,
+    interval: u64,
+}
+
+impl NpyExporter {
+    fn new(dir: PathBuf, interval: u64) -> Self {
+        fs::create_dir_all(&dir).unwrap();
+        Self {
+            dir,
+            interval: interval.max(1),
+        }
+    }
+
+    fn after_update<C: GridCell>(&self, step: u64, grid: &WorldGrid<C>) {
+        if !step.is_multiple_of(self.interval) {
+            return;
+        }
+        for (field, name) in C::field_names().iter().enumerate() {
+            let path = self.dir.join(format!("step_{:06}_{}.npy", step, name));
+            let writer = BufWriter::new(File::create(path).unwrap());
+            grid.write_field_npy(field, writer).unwrap();
+        }
+    }
+}
+
+// Writes the world's stats (see metrics::world_stats) and the step's cell state transition
+// counts after each step as CSV, with one column per value, for plotting how they change over
+// a run, e.g. birth, death, and turnover rates.
+struct StatsLog {
+    writer: BufWriter<File>,
+    wrote_header: bool,
+    transition_counter: TransitionCounter,
+}
+
+impl StatsLog {
+    fn create<C: GridCell>(path: &Path, grid: &WorldGrid<C>) -> Self {
+        Self {
+            writer: BufWriter::new(File::create(path).unwrap()),
+            wrote_header: false,
+            transition_counter: TransitionCounter::new(grid),
+        }
+    }
+
+    fn after_update<W: World>(&mut self, step: u64, world: &W) {
+        let stats = world_stats(world);
+        let transitions = self.transition_counter.count(world.grid());
+        if !self.wrote_header {
+            let names: Vec<_> = stats
+                .iter()
+                .map(|stat| stat.name)
+                .chain(TransitionCounts::names())
+                .collect();
+            writeln!(self.writer, "step,{}", names.join(",")).unwrap();
+            self.wrote_header = true;
+        }
+        let values: Vec<_> = stats
+            .iter()
+            .map(|stat| stat.value.to_string())
+            .chain(transitions.values().map(|value| value.to_string()))
+            .collect();
+        writeln!(self.writer, "{},{}", step, values.join(",")).unwrap();
+        self.writer.flush().unwrap();
+    }
+}
+
+// Writes the grid's checksum (see WorldGrid::checksum) after each step, for comparing runs
+// with --compare-checksums.
+struct ChecksumLog {
+    writer: BufWriter<File>,
+}
+
+impl ChecksumLog {
+    fn create(path: &Path) -> Self {
+        let mut writer = BufWriter::new(File::create(path).unwrap());
+        writeln!(writer, "step,checksum").unwrap();
+        Self { writer }
+    }
+
+    fn after_update<C: GridCell>(&mut self, step: u64, grid: &WorldGrid<C>) {
+        writeln!(self.writer, "{},{:016x}", step, grid.checksum()).unwrap();
+        self.writer.flush().unwrap();
+    }
+}
+
+// Every interval steps, takes a census of the small objects in the grid (see
+// WorldGrid::object_census), printing it and writing it as a CSV row.
+struct CensusLog {
+    writer: BufWriter<File>,
+    interval: u64,
+}
+
+impl CensusLog {
+    fn create(path: &Path, interval: u64) -> Self {
+        let mut writer = BufWriter::new(File::create(path).unwrap());
+        let names: Vec<_> = ObjectCensus::names().chain(["other"]).collect();
+        writeln!(writer, "step,{}", names.join(",")).unwrap();
+        Self {
+            writer,
+            interval: interval.max(1),
+        }
+    }
+
+    fn after_update<C: GridCell>(&mut self, step: u64, grid: &WorldGrid<C>) {
+        if !step.is_multiple_of(self.interval) {
+            return;
+        }
+        let census = grid.object_census();
+        let counts: Vec<_> = census
+            .counts
+            .iter()
+            .map(|(name, count)| format!("{}={}", name, count))
+            .collect();
+        println!(
+            "Census at step {}: {} other={}",
+            step,
+            counts.join(" "),
+            census.other
+        );
+        let values: Vec<_> = census
+            .counts
+            .iter()
+            .map(|(_, count)| count.to_string())
+            .chain([census.other.to_string()])
+            .collect();
+        writeln!(self.writer, "{},{}", step, values.join(",")).unwrap();
+        self.writer.flush().unwrap();
+    }
+}
+
+// Builds the lineage tree of the run's genomes from its births, and at the end saves the
+// ancestry of the creatures alive then, and when their lineages coalesced.
+struct LineageLog {
+    path: Option<PathBuf>,
+    root: Option<u64>,
+    coalescence_path: Option<PathBuf>,
+    detector: LifeEventDetector,
+    tree: LineageTree,
+}
+
+impl LineageLog {
+    fn new<C: GridCell>(options: &AppOptions, grid: &WorldGrid<C>) -> Self {
+        Self {
+            path: options.lineage_path.clone(),
+            root: options.lineage_root,
+            coalescence_path: options.coalescence_path.clone(),
+            detector: LifeEventDetector::new(grid),
+            tree: LineageTree::new(0, grid),
+        }
+    }
+
+    fn after_update<C: GridCell>(&mut self, step: u64, grid: &WorldGrid<C>) {
+        let events = self.detector.detect(step, grid);
+        self.tree.record(&events);
+    }
+
+    fn save<C: GridCell>(&self, step: u64, grid: &WorldGrid<C>) {
+        let living: Vec<u64> = grid.cells_iter().filter_map(|cell| cell.genome()).collect();
+        if let Some(path) = &self.path {
+            self.save_tree(path, &living);
+        }
+        if let Some(path) = &self.coalescence_path {
+            self.save_coalescence(path, step, &living);
+        }
+    }
+
+    fn save_tree(&self, path: &Path, living: &[u64]) {
+        let tree = self.tree.pruned(living, self.root);
+        let writer = BufWriter::new(File::create(path).unwrap());
+        let is_dot = path
+            .extension()
+            .is_some_and(|extension| extension == "dot" || extension == "gv");
+        if is_dot {
+            tree.write_dot(writer).unwrap();
+        } else {
+            tree.write_newick(writer).unwrap();
+        }
+        println!(
+            "Saved lineage of {} genomes to {}",
+            tree.len(),
+            path.display()
+        );
+    }
+
+    // One row per coalescence, most recent first, with the number of lineages left going
+    // further back.
+    fn save_coalescence(&self, path: &Path, step: u64, living: &[u64]) {
+        match self.tree.mrca(living).and_then(|mrca| self.tree.node(mrca)) {
+            Some(mrca) => println!(
+                "Living genomes' most recent common ancestor arose at step {}, {} steps ago",
+                mrca.origin,
+                step.saturating_sub(mrca.origin)
+            ),
+            None => println!("Living genomes have no common ancestor"),
+        }
+        let generations = self.tree.coalescence_generations(living);
+        let mut writer = BufWriter::new(File::create(path).unwrap());
+        writeln!(writer, "step,steps_ago,lineages").unwrap();
+        let num_lineages = living.iter().collect::<BTreeSet<_>>().len();
+        for (index, generation) in generations.iter().enumerate() {
+            writeln!(
+                writer,
+                "{},{},{}",
+                generation,
+                step.saturating_sub(*generation),
+                num_lineages - index - 1
+            )
+            .unwrap();
+        }
+        println!(
+            "Saved {} coalescences to {}",
+            generations.len(),
+            path.display()
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use world_grid::{GridCell, WorldGrid};
+
+const SLOT_NAMES: [&str; 4] = ["1", "2", "3", "4"];
+const TREE_FILE_NAME: &str = "tree.txt";
+
+// Quick-save slots, for branching from an interesting state again and again: F5 saves the
+// grid's snapshot (see WorldGrid::encode_snapshot) to the current slot, F9 loads it back, and
+// F6 moves on to the next slot. Each world's slots are kept in their own directory, named for
+// its cell format, so that worlds don't overwrite each other's saves.
+//
+// Each save is also recorded in the world's exploration tree (see print_save_trees) as a
+// child of the save the run resumed from, i.e. the one it last loaded or saved.
+pub struct SaveSlots {
+    dir: PathBuf,
+    slot: usize,
+    // The id of the save the run descends from, if any.
+    current: Option<u32>,
+}
+
+impl SaveSlots {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            slot: 0,
+            current: None,
+        }
+    }
+
+    pub fn slot_name(&self) -> &'static str {
+        SLOT_NAMES[self.slot]
+    }
+
+    pub fn next_slot(&mut self) {
+        self.slot = (self.slot + 1) % SLOT_NAMES.len();
+    }
+
+    // Restarted runs descend from no save.
+    pub fn forget_current(&mut self) {
+        self.current = None;
+    }
+
+    // Returns the path saved to.
+    pub fn save<C: GridCell>(&mut self, grid: &WorldGrid<C>, step: u64) -> Result<PathBuf, String> {
+        let path = self.path::<C>();
+        let tree_path = self.world_dir::<C>().join(TREE_FILE_NAME);
+        let save = || -> io::Result<SaveRecord> {
+            fs::create_dir_all(self.world_dir::<C>())?;
+            fs::write(&path, grid.encode_snapshot())?;
+            let records = SaveRecord::read_all(&tree_path)?;
+            let record = SaveRecord {
+                id: records
+                    .iter()
+                    .map(|record| record.id + 1)
+                    .max()
+                    .unwrap_or(1),
+                parent: self.current,
+                slot: self.slot_name().to_string(),
+                step,
+            };
+            record.append(&tree_path)?;
+            Ok(record)
+        };
+        let record = save().map_err(|err| err.to_string())?;
+        self.current = Some(record.id);
+        Ok(path)
+    }
+
+    // Returns the path loaded from.
+    pub fn load<C: GridCell>(&mut self, grid: &mut WorldGrid<C>) -> Result<PathBuf, String> {
+        let path = self.path::<C>();
+        let bytes = fs::read(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        grid.load_snapshot(&bytes)
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        // The slot holds its latest save.
+        let tree_path = self.world_dir::<C>().join(TREE_FILE_NAME);
+        self.current = SaveRecord::read_all(&tree_path)
+            .map_err(|err| format!("{}: {}", tree_path.display(), err))?
+            .iter()
+            .rev()
+            .find(|record| record.slot == self.slot_name())
+            .map(|record| record.id);
+        Ok(path)
+    }
+
+    fn path<C: GridCell>(&self) -> PathBuf {
+        self.world_dir::<C>()
+            .join(format!("slot-{}.snapshot", self.slot_name()))
+    }
+
+    fn world_dir<C: GridCell>(&self) -> PathBuf {
+        self.dir.join(C::FORMAT_NAME)
+    }
+}
+
+// One save in an exploration tree, stored as a line of "id parent slot step", with "-" for a
+// save made before any was loaded or saved, which starts a tree.
+#[derive(Clone, Debug)]
+struct SaveRecord {
+    id: u32,
+    parent: Option<u32>,
+    slot: String,
+    // The run's step count when saved.
+    step: u64,
+}
+
+impl SaveRecord {
+    // A missing file holds no records.
+    fn read_all(path: &Path) -> io::Result<Vec<Self>> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err),
+        };
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                Self::parse(line).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Invalid save record: {}", line),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [id, parent, slot, step] = fields[..] else {
+            return None;
+        };
+        Some(Self {
+            id: id.parse().ok()?,
+            parent: match parent {
+                "-" => None,
+                _ => Some(parent.parse().ok()?),
+            },
+            slot: slot.to_string(),
+            step: step.parse().ok()?,
+        })
+    }
+
+    fn append(&self, path: &Path) -> io::Result<()> {
+        let parent = self
+            .parent
+            .map_or("-".to_string(), |parent| parent.to_string());
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{} {} {} {}", self.id, parent, self.slot, self.step)
+    }
+}
+
+// Prints the exploration tree of each world's saves under dir, e.g.
+//
+//     evo-grid
+//       #1 slot 1 at step 120
+//         #2 slot 2 at step 310 *
+//         #3 slot 1 at step 205 *
+//
+// where * marks the saves still in their slots, which can be loaded.
+pub fn print_save_trees(dir: &Path) {
+    let mut world_dirs: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap_or_else(|err| panic!("{}: {}", dir.display(), err))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.join(TREE_FILE_NAME).is_file())
+        .collect();
+    world_dirs.sort();
+    if world_dirs.is_empty() {
+        println!("No saves in {}", dir.display());
+    }
+    for world_dir in world_dirs {
+        let tree_path = world_dir.join(TREE_FILE_NAME);
+        let records = SaveRecord::read_all(&tree_path)
+            .unwrap_or_else(|err| panic!("{}: {}", tree_path.display(), err));
+        println!("{}", world_dir.file_name().unwrap().to_string_lossy());
+        // Parents whose records are gone, e.g. deleted by hand, leave their children as roots.
+        let roots = records.iter().filter(|record| {
+            record
+                .parent
+                .is_none_or(|parent| records.iter().all(|other| other.id != parent))
+        });
+        for root in roots {
+            print_save_subtree(&records, root, 1);
+        }
+    }
+}
+
+fn print_save_subtree(records: &[SaveRecord], record: &SaveRecord, depth: usize) {
+    let in_slot = records
+        .iter()
+        .rev()
+        .find(|other| other.slot == record.slot)
+        .is_some_and(|latest| latest.id == record.id);
+    println!(
+        "{:indent$}#{} slot {} at step {}{}",
+        "",
+        record.id,
+        record.slot,
+        record.step,
+        if in_slot { " *" } else { "" },
+        indent = 2 * depth
+    );
+    for child in records
+        .iter()
+        .filter(|child| child.parent == Some(record.id))
+    {
+        print_save_subtree(records, child, depth + 1);
+    }
+}
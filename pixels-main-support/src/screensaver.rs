@@ -0,0 +1,59 @@
+use std::time::{Duration, Instant};
+use winit::dpi::PhysicalPosition;
+use winit::event::{ElementState, KeyEvent, WindowEvent};
+
+// How far the mouse can drift, in pixels, before it counts as input.
+const CURSOR_MOVE_THRESHOLD: f64 = 10.0;
+
+// Screensaver mode: any input quits, and the world is periodically reseeded so that the
+// screen doesn't settle into a static or empty pattern.
+pub struct Screensaver {
+    reseed_interval: Duration,
+    last_reseed: Instant,
+    first_cursor_position: Option<PhysicalPosition<f64>>,
+}
+
+impl Screensaver {
+    pub fn new(reseed_interval: Duration) -> Self {
+        Self {
+            reseed_interval,
+            last_reseed: Instant::now(),
+            first_cursor_position: None,
+        }
+    }
+
+    // Only presses count, so that releasing the key that started the screensaver doesn't
+    // immediately quit it.
+    pub fn is_exit_input(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            }
+            | WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                ..
+            }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::Touch(_) => true,
+            WindowEvent::CursorMoved { position, .. } => {
+                let first_position = *self.first_cursor_position.get_or_insert(*position);
+                (position.x - first_position.x).hypot(position.y - first_position.y)
+                    > CURSOR_MOVE_THRESHOLD
+            }
+            _ => false,
+        }
+    }
+
+    pub fn reseed_due(&mut self) -> bool {
+        if self.last_reseed.elapsed() < self.reseed_interval {
+            return false;
+        }
+        self.last_reseed = Instant::now();
+        true
+    }
+}
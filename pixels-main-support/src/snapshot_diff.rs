@@ -0,0 +1,97 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use world_grid::{GridCell, World, WorldGrid};
+
+// Cells per side of each pixel of the changed-cell density image.
+const DENSITY_BLOCK_SIZE: u32 = 4;
+
+// Compares two snapshots of the world's grid and prints their aggregate differences: the
+// population change, the shifts in gene bit frequencies, the substance mass change, and how
+// many cells changed, optionally saving a density map of the changed cells as an image.
+pub struct SnapshotDiffReport {
+    paths: (PathBuf, PathBuf),
+    image_path: Option<PathBuf>,
+}
+
+impl SnapshotDiffReport {
+    pub fn new(paths: (PathBuf, PathBuf), image_path: Option<PathBuf>) -> Self {
+        Self { paths, image_path }
+    }
+
+    // The world only picks the cell type to decode the snapshots as.
+    pub fn run<W: World>(&self, world: W) {
+        self.report(world.grid());
+    }
+
+    fn report<C: GridCell>(&self, _grid: &WorldGrid<C>) {
+        let grid = Self::read_snapshot::<C>(&self.paths.0);
+        let other_grid = Self::read_snapshot::<C>(&self.paths.1);
+        let diff = grid
+            .diff(&other_grid)
+            .unwrap_or_else(|err| panic!("Can't compare the snapshots: {}", err));
+        println!("{} -> {}", self.paths.0.display(), self.paths.1.display());
+        let (population, other_population) = diff.populations;
+        println!(
+            "Population: {} -> {} ({:+})",
+            population,
+            other_population,
+            other_population as i64 - population as i64
+        );
+        let (mass, other_mass) = diff.substance_masses;
+        println!(
+            "Substance mass: {:.2} -> {:.2} ({:+.2})",
+            mass,
+            other_mass,
+            other_mass - mass
+        );
+        println!(
+            "Changed cells: {} of {} ({:.1}%)",
+            diff.num_changed(),
+            diff.changed_cells.len(),
+            100.0 * diff.num_changed() as f64 / diff.changed_cells.len() as f64
+        );
+        Self::print_bit_frequency_shifts::<C>(&diff.bit_frequencies);
+        if let Some(path) = &self.image_path {
+            diff.write_density_pgm(
+                BufWriter::new(File::create(path).unwrap()),
+                DENSITY_BLOCK_SIZE,
+            )
+            .unwrap();
+            println!("Saved changed-cell density map {}", path.display());
+        }
+    }
+
+    fn read_snapshot<C: GridCell>(path: &Path) -> WorldGrid<C> {
+        WorldGrid::decode_snapshot(&fs::read(path).unwrap())
+            .unwrap_or_else(|err| panic!("{}: {}", path.display(), err))
+    }
+
+    // One line per gene, with the change in percentage points of creatures having each bit
+    // set, from the highest bit, e.g. "red  +0.0 -2.5 ...".
+    fn print_bit_frequency_shifts<C: GridCell>(bit_frequencies: &[([f64; 8], [f64; 8])]) {
+        let genes = C::genes();
+        if genes.is_empty() {
+            return;
+        }
+        println!("Gene bit frequency shifts, in percentage points, highest bit first:");
+        let name_width = genes.iter().map(|gene| gene.name.len()).max().unwrap();
+        for (gene, (frequencies, other_frequencies)) in genes.iter().zip(bit_frequencies) {
+            let shifts: Vec<String> = (0..8)
+                .rev()
+                .map(|bit| {
+                    format!(
+                        "{:+5.1}",
+                        100.0 * (other_frequencies[bit] - frequencies[bit])
+                    )
+                })
+                .collect();
+            println!(
+                "  {:width$} {}",
+                gene.name,
+                shifts.join(" "),
+                width = name_width
+            );
+        }
+    }
+}
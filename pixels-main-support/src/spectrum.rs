@@ -0,0 +1,89 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use world_grid::{GridCell, World, WorldGrid};
+
+// Bar widths of the printed radial profile.
+const PROFILE_BAR_WIDTH: usize = 60;
+
+// Runs the world for its warm-up steps, then computes the power spectrum of one of its cell
+// fields, prints its radial profile and peak wavelength, and optionally saves it.
+pub struct SpectrumAnalysis {
+    field_name: String,
+    npy_path: Option<PathBuf>,
+    image_path: Option<PathBuf>,
+    warmup_steps: u64,
+}
+
+impl SpectrumAnalysis {
+    pub fn new(
+        field_name: &str,
+        npy_path: Option<PathBuf>,
+        image_path: Option<PathBuf>,
+        warmup_steps: u64,
+    ) -> Self {
+        Self {
+            field_name: field_name.to_string(),
+            npy_path,
+            image_path,
+            warmup_steps,
+        }
+    }
+
+    pub fn run<W: World>(&self, mut world: W) {
+        for _ in 0..self.warmup_steps {
+            world.update();
+        }
+        self.analyze(world.grid());
+    }
+
+    fn analyze<C: GridCell>(&self, grid: &WorldGrid<C>) {
+        let field = C::field_names()
+            .iter()
+            .position(|name| *name == self.field_name)
+            .unwrap_or_else(|| {
+                panic!(
+                    "Unknown field {}; the fields are {}",
+                    self.field_name,
+                    C::field_names().join(", ")
+                )
+            });
+        let spectrum = grid.power_spectrum(field);
+
+        let profile = spectrum.radial_profile();
+        let max_power = profile.iter().skip(1).copied().fold(0.0, f32::max);
+        let max_len = spectrum.size.width.max(spectrum.size.height);
+        println!(
+            "Power spectrum of {} over the central {}x{} cells",
+            self.field_name, spectrum.size.width, spectrum.size.height
+        );
+        println!("wavelength  power");
+        for (ring, power) in profile.iter().enumerate().skip(1) {
+            let bar_len = if max_power > 0.0 {
+                (power / max_power * PROFILE_BAR_WIDTH as f32).round() as usize
+            } else {
+                0
+            };
+            println!(
+                "{:>10.1}  {}",
+                max_len as f64 / ring as f64,
+                "#".repeat(bar_len)
+            );
+        }
+        match spectrum.peak_wavelength() {
+            Some(wavelength) => println!("Peak wavelength: {:.1} cells", wavelength),
+            None => println!("No peak: the field is uniform"),
+        }
+
+        if let Some(path) = &self.npy_path {
+            spectrum
+                .write_npy(BufWriter::new(File::create(path).unwrap()))
+                .unwrap();
+        }
+        if let Some(path) = &self.image_path {
+            spectrum
+                .write_pgm(BufWriter::new(File::create(path).unwrap()))
+                .unwrap();
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpeedChange {
+    FastForward,
+    RealTime,
+    Pause,
+}
+
+// A change of speed at a given step, e.g. "10000:real-time" to slow down once the run reaches
+// step 10000, after e.g. "0:fast" fast-forwarded it to there.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScheduledChange {
+    pub step: u64,
+    pub change: SpeedChange,
+}
+
+impl FromStr for ScheduledChange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid scheduled change: {}", s);
+        let (step, change) = s.split_once(':').ok_or_else(invalid)?;
+        let change = match change.trim() {
+            "fast" => SpeedChange::FastForward,
+            "real-time" => SpeedChange::RealTime,
+            "pause" => SpeedChange::Pause,
+            _ => return Err(invalid()),
+        };
+        Ok(Self {
+            step: step.trim().parse().map_err(|_| invalid())?,
+            change,
+        })
+    }
+}
+
+impl fmt::Display for ScheduledChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let change = match self.change {
+            SpeedChange::FastForward => "fast",
+            SpeedChange::RealTime => "real-time",
+            SpeedChange::Pause => "pause",
+        };
+        write!(f, "{}:{}", self.step, change)
+    }
+}
+
+// Scheduled changes of speed, e.g. for demos that fast-forward to an interesting epoch and
+// then slow down or pause there for viewing.
+#[derive(Default)]
+pub struct SpeedSchedule {
+    // In reverse order of step, so that the next change due is last.
+    changes: Vec<ScheduledChange>,
+}
+
+impl SpeedSchedule {
+    pub fn new(changes: &[ScheduledChange]) -> Self {
+        let mut changes = changes.to_vec();
+        changes.sort_by_key(|change| change.step);
+        changes.reverse();
+        Self { changes }
+    }
+
+    // The changes due by the given step that haven't been returned before, in order.
+    pub fn due(&mut self, step: u64) -> Vec<SpeedChange> {
+        let mut result = vec![];
+        while let Some(change) = self.changes.pop_if(|change| change.step <= step) {
+            println!("Step {}: scheduled change {}", step, change);
+            result.push(change.change);
+        }
+        result
+    }
+}
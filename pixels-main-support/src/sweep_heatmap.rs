@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+use world_grid::write_png;
+
+// Pixels per side of each (x, y) pair's square.
+const SQUARE_SIZE: u32 = 16;
+// From the lowest metric value to the highest, interpolated between.
+const COLOR_STOPS: [[u8; 3]; 3] = [[0x30, 0x12, 0x6e], [0x21, 0x91, 0x8c], [0xfd, 0xe7, 0x25]];
+const NO_RUNS_COLOR: [u8; 3] = [0x00, 0x00, 0x00];
+
+// Summarizes a parameter sweep's results as a heatmap image saved alongside their CSV, with
+// the same name but a ".png" extension. The CSV has a header row and a row per run, e.g. one
+// appended by each run of a shell loop over the options being swept. Each distinct value of
+// the x parameter gets a column of squares, and each of the y parameter a row, with y
+// increasing upward, and each square is colored by the mean of the outcome metric over that
+// pair's runs, from dark blue for the lowest to yellow for the highest. Columns are named as
+// "x,y,metric", or are the CSV's first three if not given.
+pub fn write_sweep_heatmap(csv_path: &Path, column_names: Option<&[String; 3]>) {
+    let csv = fs::read_to_string(csv_path).unwrap();
+    let mut lines = csv.lines().filter(|line| !line.trim().is_empty());
+    let header: Vec<&str> = lines
+        .next()
+        .unwrap_or_else(|| panic!("{} is empty", csv_path.display()))
+        .split(',')
+        .map(str::trim)
+        .collect();
+    let columns = match column_names {
+        Some(names) => names.each_ref().map(|name| {
+            header
+                .iter()
+                .position(|column| column == name)
+                .unwrap_or_else(|| panic!("{} has no column {}", csv_path.display(), name))
+        }),
+        None if header.len() >= 3 => [0, 1, 2],
+        None => panic!("{} has fewer than three columns", csv_path.display()),
+    };
+
+    // The sum and count of the metric values for each (x, y) pair, keyed by the values' bits,
+    // as floats can't be keys.
+    let mut sums: BTreeMap<(u64, u64), (f64, usize)> = BTreeMap::new();
+    for line in lines {
+        let fields: Vec<&str> = line.split(',').collect();
+        let [x, y, metric] = columns.map(|column| {
+            fields
+                .get(column)
+                .and_then(|field| field.trim().parse::<f64>().ok())
+                .unwrap_or_else(|| panic!("Invalid row in {}: {}", csv_path.display(), line))
+        });
+        let sum = sums.entry((x.to_bits(), y.to_bits())).or_default();
+        sum.0 += metric;
+        sum.1 += 1;
+    }
+    let means: BTreeMap<(u64, u64), f64> = sums
+        .into_iter()
+        .map(|(pair, (sum, count))| (pair, sum / count as f64))
+        .collect();
+    let xs: Vec<u64> = sorted_distinct(means.keys().map(|(x, _)| *x));
+    let ys: Vec<u64> = sorted_distinct(means.keys().map(|(_, y)| *y));
+    let (min, max) = means
+        .values()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), mean| {
+            (min.min(*mean), max.max(*mean))
+        });
+
+    let (width, height) = (xs.len() as u32, ys.len() as u32);
+    let mut pixels = vec![0; (4 * width * height * SQUARE_SIZE * SQUARE_SIZE) as usize];
+    for (row, y) in ys.iter().rev().enumerate() {
+        for (col, x) in xs.iter().enumerate() {
+            let color = means
+                .get(&(*x, *y))
+                .map_or(NO_RUNS_COLOR, |mean| color_for((mean - min) / (max - min)));
+            for pixel_row in 0..SQUARE_SIZE {
+                let start = ((row as u32 * SQUARE_SIZE + pixel_row) * width * SQUARE_SIZE
+                    + col as u32 * SQUARE_SIZE) as usize;
+                for pixel in pixels[4 * start..4 * (start + SQUARE_SIZE as usize)].chunks_mut(4) {
+                    pixel.copy_from_slice(&[color[0], color[1], color[2], 0xff]);
+                }
+            }
+        }
+    }
+    let image_path = csv_path.with_extension("png");
+    write_png(
+        BufWriter::new(File::create(&image_path).unwrap()),
+        width * SQUARE_SIZE,
+        height * SQUARE_SIZE,
+        &pixels,
+    )
+    .unwrap();
+    println!(
+        "Saved a {}x{} heatmap of {} to {}, from {} (dark) to {} (bright)",
+        width,
+        height,
+        header[columns[2]],
+        image_path.display(),
+        min,
+        max
+    );
+}
+
+fn sorted_distinct(values: impl Iterator<Item = u64>) -> Vec<u64> {
+    let mut result: Vec<f64> = values.map(f64::from_bits).collect();
+    result.sort_by(f64::total_cmp);
+    result.dedup();
+    result.into_iter().map(f64::to_bits).collect()
+}
+
+// A fraction from 0 to 1 of the way through the color stops. All the same values, which
+// give NaN, get the middle color.
+fn color_for(fraction: f64) -> [u8; 3] {
+    let fraction = if fraction.is_nan() {
+        0.5
+    } else {
+        fraction.clamp(0.0, 1.0)
+    };
+    let position = fraction * (COLOR_STOPS.len() - 1) as f64;
+    let index = (position.floor() as usize).min(COLOR_STOPS.len() - 2);
+    let within = position - index as f64;
+    let (from, to) = (COLOR_STOPS[index], COLOR_STOPS[index + 1]);
+    [0, 1, 2].map(|channel| {
+        (from[channel] as f64 + within * (to[channel] as f64 - from[channel] as f64)).round() as u8
+    })
+}
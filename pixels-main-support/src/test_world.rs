@@ -0,0 +1,83 @@
+use world_grid::{
+    CellCodec, GridCell, GridSize, Neighborhood, Random, SnapshotError, SnapshotReader,
+    SnapshotWriter, World, WorldGrid,
+};
+
+// A cell whose color is its value in gray, for checking which pixels get loaded.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TestCell {
+    pub value: u8,
+}
+
+impl CellCodec for TestCell {
+    const FORMAT_NAME: &'static str = "test";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_u8(self.value);
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        Ok(Self {
+            value: reader.read_u8()?,
+        })
+    }
+}
+
+impl GridCell for TestCell {
+    fn color_rgba(&self) -> [u8; 4] {
+        [self.value, self.value, self.value, 0xff]
+    }
+
+    fn clear(&mut self) {
+        self.value = 0;
+    }
+
+    fn update(
+        &self,
+        _neighborhood: &Neighborhood<Self>,
+        _next_cell: &mut Self,
+        _rand: &mut Option<Random>,
+    ) {
+    }
+
+    fn debug_print(&self, row: u32, col: u32) {
+        println!("({}, {}): {}", row, col, self.value);
+    }
+}
+
+// A world of TestCells whose cells can be edited or not.
+pub struct TestWorld {
+    grid: WorldGrid<TestCell>,
+    editable: bool,
+}
+
+impl TestWorld {
+    pub fn new(editable: bool) -> Self {
+        Self {
+            grid: WorldGrid::new(GridSize::new(4, 4)),
+            editable,
+        }
+    }
+}
+
+impl World for TestWorld {
+    fn grid(&self) -> &WorldGrid<impl GridCell> {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut WorldGrid<impl GridCell> {
+        &mut self.grid
+    }
+
+    fn update(&mut self) {
+        self.grid.update(&mut None, |_grid| {});
+    }
+
+    fn reset(&mut self) {
+        self.grid.clear();
+    }
+
+    fn is_editable(&self) -> bool {
+        self.editable
+    }
+}
@@ -0,0 +1,128 @@
+use crate::options::{exit_with_usage_error, load_option_file};
+use std::path::PathBuf;
+use world_grid::{GridCell, Loc, Random, Seed, World, WorldGrid};
+
+const MAX_PLACEMENT_ATTEMPTS: usize = 100;
+
+// Pits saved genomes against each other: each round starts from a freshly built, cleared
+// world seeded with equal numbers of each entrant at random locations, and after a fixed
+// number of generations the entrant with the most exact-genome descendants wins the round.
+pub struct Tournament {
+    entrants: Vec<Seed>,
+    entrant_count: usize,
+    generations: u64,
+    rounds: usize,
+}
+
+impl Tournament {
+    pub fn new(
+        seed_paths: &[PathBuf],
+        entrant_count: usize,
+        generations: u64,
+        rounds: usize,
+    ) -> Self {
+        if seed_paths.len() < 2 {
+            exit_with_usage_error("A tournament needs at least two seeds");
+        }
+        Self {
+            entrants: seed_paths
+                .iter()
+                .map(|path| load_option_file(path, Seed::load))
+                .collect(),
+            entrant_count,
+            generations,
+            rounds,
+        }
+    }
+
+    pub fn run<W, F>(&self, mut rand: Random, build_world: F)
+    where
+        W: World,
+        F: Fn() -> W,
+    {
+        let mut wins = vec![0; self.entrants.len()];
+        let mut extinctions = 0;
+        for round in 1..=self.rounds {
+            let mut world = build_world();
+            if !world.is_editable() {
+                exit_with_usage_error("--tournament needs a world whose cells can be edited");
+            }
+            self.seed_world(world.grid_mut(), &mut rand);
+            for _ in 0..self.generations {
+                world.update();
+            }
+
+            let (counts, others) = self.count_entrants(world.grid());
+            println!(
+                "Round {}: {}, other: {}",
+                round,
+                self.format_counts(&counts),
+                others
+            );
+            match Self::winner(&counts) {
+                Some(winner) => wins[winner] += 1,
+                None => extinctions += 1,
+            }
+        }
+        println!(
+            "Wins: {}, extinct: {}",
+            self.format_counts(&wins),
+            extinctions
+        );
+    }
+
+    fn seed_world<C: GridCell>(&self, grid: &mut WorldGrid<C>, rand: &mut Random) {
+        grid.clear();
+        let size = grid.size();
+        for _ in 0..self.entrant_count {
+            for entrant in &self.entrants {
+                // Retry a few times to avoid stacking entrants on the same cell.
+                for _ in 0..MAX_PLACEMENT_ATTEMPTS {
+                    let loc = Loc::new(
+                        rand.next_in_range(0..size.height),
+                        rand.next_in_range(0..size.width),
+                    );
+                    if grid.extract_seed(loc).is_none() {
+                        grid.inject_seed(entrant, loc, 1).unwrap();
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    // Returns each entrant's number of creatures and the number of creatures of other genomes.
+    fn count_entrants<C: GridCell>(&self, grid: &WorldGrid<C>) -> (Vec<usize>, usize) {
+        let mut counts = vec![0; self.entrants.len()];
+        let mut others = 0;
+        for genome in grid.cells_iter().filter_map(|cell| cell.genome()) {
+            match self
+                .entrants
+                .iter()
+                .position(|entrant| entrant.genome == genome)
+            {
+                Some(index) => counts[index] += 1,
+                None => others += 1,
+            }
+        }
+        (counts, others)
+    }
+
+    fn winner(counts: &[usize]) -> Option<usize> {
+        counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .max_by_key(|(_, count)| **count)
+            .map(|(index, _)| index)
+    }
+
+    fn format_counts(&self, counts: &[usize]) -> String {
+        self.entrants
+            .iter()
+            .zip(counts)
+            .map(|(entrant, count)| format!("{:x}: {}", entrant.genome, count))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
@@ -0,0 +1,221 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use world_grid::{GridCell, World, WorldGrid};
+
+// For 95% confidence intervals, using the normal approximation.
+const Z_95: f64 = 1.96;
+
+// Runs a world many times from independent random starts and summarizes the outcomes, e.g. to
+// show a rule change's effect: run the old build with --trials-csv to save its trials, then
+// the new build with --baseline pointing at that file, and compare the two summaries and the
+// confidence intervals for their differences. The population is the number of cells with
+// creatures (see GridCell::genome), so works for any world with creatures.
+pub struct Trials {
+    count: usize,
+    generations: u64,
+    csv_path: Option<PathBuf>,
+    baseline_path: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TrialResult {
+    mean_population: f64,
+    final_population: f64,
+}
+
+impl TrialResult {
+    fn extinct(&self) -> bool {
+        self.final_population == 0.0
+    }
+}
+
+impl Trials {
+    pub fn new(
+        count: usize,
+        generations: u64,
+        csv_path: Option<PathBuf>,
+        baseline_path: Option<PathBuf>,
+    ) -> Self {
+        assert!(count >= 2, "Need at least two trials");
+        Self {
+            count,
+            generations,
+            csv_path,
+            baseline_path,
+        }
+    }
+
+    pub fn run<W, F>(&self, build_world: F)
+    where
+        W: World,
+        F: Fn() -> W,
+    {
+        let results: Vec<TrialResult> = (1..=self.count)
+            .map(|trial| {
+                let result = self.run_trial(build_world());
+                println!(
+                    "Trial {}: mean population {:.1}, final population {}",
+                    trial, result.mean_population, result.final_population
+                );
+                result
+            })
+            .collect();
+        if let Some(path) = &self.csv_path {
+            Self::write_csv(&results, BufWriter::new(File::create(path).unwrap()));
+        }
+
+        println!(
+            "Over {} trials of {} generations:",
+            self.count, self.generations
+        );
+        Self::print_summary(&results);
+        if let Some(path) = &self.baseline_path {
+            let baseline = Self::read_csv(&fs::read_to_string(path).unwrap());
+            println!(
+                "Baseline ({} trials from {}):",
+                baseline.len(),
+                path.display()
+            );
+            Self::print_summary(&baseline);
+            println!("Difference from baseline:");
+            Self::print_differences(&results, &baseline);
+        }
+    }
+
+    fn run_trial<W: World>(&self, mut world: W) -> TrialResult {
+        let mut total_population = 0.0;
+        for _ in 0..self.generations {
+            world.update();
+            total_population += Self::population(world.grid());
+        }
+        TrialResult {
+            mean_population: total_population / self.generations.max(1) as f64,
+            final_population: Self::population(world.grid()),
+        }
+    }
+
+    fn population<C: GridCell>(grid: &WorldGrid<C>) -> f64 {
+        grid.cells_iter()
+            .filter(|cell| cell.genome().is_some())
+            .count() as f64
+    }
+
+    fn print_summary(results: &[TrialResult]) {
+        for (name, values) in Self::columns(results) {
+            let (mean, half_width) = Self::mean_interval(&values);
+            println!(
+                "  {}: {:.2} (95% CI {:.2} to {:.2})",
+                name,
+                mean,
+                mean - half_width,
+                mean + half_width
+            );
+        }
+    }
+
+    fn print_differences(results: &[TrialResult], baseline: &[TrialResult]) {
+        for ((name, values), (_, baseline_values)) in Self::columns(results)
+            .into_iter()
+            .zip(Self::columns(baseline))
+        {
+            let (mean, half_width) = Self::mean_interval(&values);
+            let (baseline_mean, baseline_half_width) = Self::mean_interval(&baseline_values);
+            // The standard errors add in quadrature, as in Welch's t-test.
+            let difference = mean - baseline_mean;
+            let half_width = half_width.hypot(baseline_half_width);
+            let verdict = if difference.abs() > half_width {
+                "significant"
+            } else {
+                "not significant"
+            };
+            println!(
+                "  {}: {:+.2} (95% CI {:+.2} to {:+.2}, {})",
+                name,
+                difference,
+                difference - half_width,
+                difference + half_width,
+                verdict
+            );
+        }
+    }
+
+    // The extinction rate is the mean of a column of zeros and ones.
+    fn columns(results: &[TrialResult]) -> [(&'static str, Vec<f64>); 3] {
+        [
+            (
+                "mean population",
+                results
+                    .iter()
+                    .map(|result| result.mean_population)
+                    .collect(),
+            ),
+            (
+                "final population",
+                results
+                    .iter()
+                    .map(|result| result.final_population)
+                    .collect(),
+            ),
+            (
+                "extinction rate",
+                results
+                    .iter()
+                    .map(|result| result.extinct() as u8 as f64)
+                    .collect(),
+            ),
+        ]
+    }
+
+    // Returns the mean and the half-width of its 95% confidence interval.
+    fn mean_interval(values: &[f64]) -> (f64, f64) {
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = values
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>()
+            / (n - 1.0).max(1.0);
+        (mean, Z_95 * (variance / n).sqrt())
+    }
+
+    fn write_csv<O: Write>(results: &[TrialResult], mut output: O) {
+        writeln!(output, "trial,mean_population,final_population").unwrap();
+        for (trial, result) in results.iter().enumerate() {
+            writeln!(
+                output,
+                "{},{},{}",
+                trial + 1,
+                result.mean_population,
+                result.final_population
+            )
+            .unwrap();
+        }
+        output.flush().unwrap();
+    }
+
+    fn read_csv(text: &str) -> Vec<TrialResult> {
+        text.lines()
+            .skip(1)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let fields: Vec<f64> = line
+                    .split(',')
+                    .map(|field| {
+                        field
+                            .trim()
+                            .parse()
+                            .unwrap_or_else(|_| panic!("Invalid baseline line: {}", line))
+                    })
+                    .collect();
+                let [_, mean_population, final_population] = fields[..] else {
+                    panic!("Invalid baseline line: {}", line);
+                };
+                TrialResult {
+                    mean_population,
+                    final_population,
+                }
+            })
+            .collect()
+    }
+}
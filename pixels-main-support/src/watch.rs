@@ -0,0 +1,81 @@
+use crate::AppOptions;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use world_grid::{CellHistory, GridCell, Loc, WorldGrid};
+
+// More would scroll each step's output off the console before it could be read.
+const MAX_WATCHED_CELLS: usize = 8;
+
+// Cells whose full state is printed after each step, for following what happens to
+// particular creatures and substances over time.
+#[derive(Default)]
+pub struct CellWatch {
+    locs: Vec<Loc>,
+    // The watched cells' recent states, and where to save them at the end of the run, if
+    // --watch-history is set.
+    history: Option<(CellHistory, PathBuf)>,
+}
+
+impl CellWatch {
+    pub fn new<C: GridCell>(options: &AppOptions, grid: &WorldGrid<C>) -> Self {
+        let mut result = Self {
+            locs: vec![],
+            history: options
+                .watch_history_path
+                .clone()
+                .map(|path| (CellHistory::new(grid, options.watch_history_length), path)),
+        };
+        for loc in &options.watched_locs {
+            result.toggle(*loc);
+        }
+        result
+    }
+
+    // Starts watching the cell, or stops if it's already watched.
+    pub fn toggle(&mut self, loc: Loc) {
+        if let Some(index) = self.locs.iter().position(|watched| *watched == loc) {
+            self.locs.remove(index);
+            println!("Stopped watching ({}, {})", loc.row, loc.col);
+        } else if self.locs.len() >= MAX_WATCHED_CELLS {
+            println!(
+                "Can't watch ({}, {}): already watching {} cells",
+                loc.row, loc.col, MAX_WATCHED_CELLS
+            );
+            return;
+        } else {
+            self.locs.push(loc);
+            println!("Watching ({}, {})", loc.row, loc.col);
+        }
+        if let Some((history, _path)) = self.history.as_mut() {
+            history.set_recorded(loc, self.locs.contains(&loc));
+        }
+    }
+
+    pub fn after_update<C: GridCell>(&mut self, step: u64, grid: &WorldGrid<C>) {
+        for loc in &self.locs {
+            if let Some(index) = loc.grid_index(grid.size()) {
+                println!(
+                    "Step {} ({}, {}): {:?}",
+                    step,
+                    loc.row,
+                    loc.col,
+                    grid.cells_iter().as_slice()[index]
+                );
+            }
+        }
+        if let Some((history, _path)) = self.history.as_mut() {
+            history.record(grid);
+        }
+    }
+
+    // Saves the recent states of the watched cells, if keeping them.
+    pub fn save_history(&self) {
+        if let Some((history, path)) = &self.history {
+            history
+                .write_csv(BufWriter::new(File::create(path).unwrap()))
+                .unwrap();
+            println!("Saved watched cell history to {}", path.display());
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use crate::frontend::{Frame, Frontend};
+use crate::gpu_cross_fade::GpuCrossFade;
+use crate::input::InputEvent;
+use pixels::wgpu::Color;
+use pixels::{Pixels, PixelsBuilder, SurfaceTexture};
+use std::mem;
+use std::ops::Range;
+use std::sync::Arc;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::window::Window;
+use world_grid::GridSize;
+
+// Shows the world in a window, cross-fading from each presented frame to the next on the GPU.
+// Winit runs the event loop and calls the app back, rather than being polled, so the event
+// handler queues the window's input here (see queue_input) for poll_input to hand over.
+pub struct WindowFrontend {
+    window: Arc<Window>,
+    pixels: Pixels<'static>,
+    gpu_cross_fade: GpuCrossFade,
+    // Which rows the last frame presented changed, or None if any may have.
+    last_changed_rows: Option<Vec<bool>>,
+    dark_pixel_alpha: f32,
+    bright_pixel_alpha: f32,
+    // Whether the window has no area to draw in, e.g. while minimized.
+    minimized: bool,
+    input: Vec<InputEvent>,
+}
+
+impl WindowFrontend {
+    // Frames are the given size, which the window scales up to fill it.
+    pub fn new(window: Arc<Window>, size: GridSize, background_color: Color) -> Self {
+        let pixels = Self::build_pixels(&window, size, background_color);
+        let gpu_cross_fade = GpuCrossFade::new(&pixels, size, background_color);
+        Self {
+            window,
+            pixels,
+            gpu_cross_fade,
+            last_changed_rows: None,
+            dark_pixel_alpha: 1.0,
+            bright_pixel_alpha: 1.0,
+            minimized: false,
+            input: vec![],
+        }
+    }
+
+    fn build_pixels(
+        window: &Arc<Window>,
+        size: GridSize,
+        background_color: Color,
+    ) -> Pixels<'static> {
+        let window_size = window.inner_size();
+        let surface_texture = SurfaceTexture::new(
+            window_size.width.max(1),
+            window_size.height.max(1),
+            window.clone(),
+        );
+        PixelsBuilder::new(size.width, size.height, surface_texture)
+            .clear_color(background_color)
+            .build()
+            .unwrap()
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    pub fn queue_input(&mut self, event: InputEvent) {
+        self.input.push(event);
+    }
+
+    // The frame pixel under the window position, as (column, row), if any.
+    pub fn window_pos_to_pixel(&self, pos: PhysicalPosition<f64>) -> Option<(usize, usize)> {
+        self.pixels
+            .window_pos_to_pixel((pos.x as f32, pos.y as f32))
+            .ok()
+    }
+
+    // Shows the given fraction of the way from the previous frame to the last one presented.
+    pub fn fade_to(&mut self, fraction: f32) {
+        const BLEND_SMOOTHNESS_FACTOR: f32 = 1.5;
+        self.dark_pixel_alpha =
+            (fraction * BLEND_SMOOTHNESS_FACTOR - (BLEND_SMOOTHNESS_FACTOR - 1.0)).max(0.0);
+        self.bright_pixel_alpha = (fraction * BLEND_SMOOTHNESS_FACTOR).min(1.0);
+        self.window.request_redraw();
+    }
+
+    pub fn request_redraw(&self) {
+        self.window.request_redraw();
+    }
+
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        self.minimized = size.width == 0 || size.height == 0;
+        if !self.minimized {
+            self.pixels.resize_surface(size.width, size.height).unwrap();
+            self.window.request_redraw();
+        }
+    }
+
+    // Draws the cross-fade, with the view's part of the world filling the window.
+    pub fn draw(&mut self, view: [f32; 4]) {
+        if self.minimized {
+            return;
+        }
+        self.gpu_cross_fade
+            .render(
+                &self.pixels,
+                self.dark_pixel_alpha,
+                self.bright_pixel_alpha,
+                view,
+            )
+            .unwrap();
+    }
+}
+
+impl Frontend for WindowFrontend {
+    fn init(&mut self, _grid_size: GridSize) {
+        self.window.set_cursor_visible(false);
+        self.window.set_visible(true);
+        self.window.request_redraw();
+    }
+
+    // The app presents a frame whenever the world changes, which the window then fades to
+    // over the following frames (see fade_to).
+    fn frame_due(&self, _step: u64) -> bool {
+        true
+    }
+
+    // The last frame's texture becomes the background, and the one before it the input,
+    // which only needs the rows changed by either frame since.
+    fn present(&mut self, frame: &Frame) {
+        self.gpu_cross_fade.swap();
+        let changed_rows = frame.changed_rows.map(<[bool]>::to_vec);
+        let stale_rows = match (&changed_rows, &self.last_changed_rows) {
+            (Some(rows), Some(last_rows)) => rows
+                .iter()
+                .zip(last_rows)
+                .map(|(changed, last_changed)| *changed || *last_changed)
+                .collect(),
+            _ => vec![true; frame.size.height as usize],
+        };
+        for rows in row_ranges(&stale_rows) {
+            self.gpu_cross_fade
+                .load_rows(&self.pixels, rows, frame.pixels.as_flattened());
+        }
+        self.last_changed_rows = changed_rows;
+        self.window.request_redraw();
+    }
+
+    fn poll_input(&mut self) -> Vec<InputEvent> {
+        mem::take(&mut self.input)
+    }
+
+    fn is_interactive(&self) -> bool {
+        true
+    }
+}
+
+// The runs of flagged rows.
+fn row_ranges(rows: &[bool]) -> Vec<Range<u32>> {
+    let mut ranges: Vec<Range<u32>> = vec![];
+    for (row, _) in rows.iter().enumerate().filter(|(_, flagged)| **flagged) {
+        let row = row as u32;
+        match ranges.last_mut() {
+            Some(range) if range.end == row => range.end += 1,
+            _ => ranges.push(row..row + 1),
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn row_ranges_are_the_runs_of_flagged_rows() {
+        assert_eq!(row_ranges(&[]), vec![]);
+        assert_eq!(row_ranges(&[false, false]), vec![]);
+        assert_eq!(row_ranges(&[true, true, true]), vec![0..3]);
+        assert_eq!(
+            row_ranges(&[true, false, true, true, false, false, true]),
+            vec![0..1, 2..4, 6..7]
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use crate::KeyAction;
+use crate::options;
+use crate::{AppOptions, RunRandoms, animate};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use winit::keyboard::KeyCode;
+use world_grid::{GridSize, Random, World};
+
+const DEFAULT_TIME_STEP_FRAMES: u32 = 4;
+const DEFAULT_CELL_PIXEL_WIDTH: u32 = 4;
+
+// The bindings set by WorldApp::with_keybindings, which the window's key handling consults.
+static KEY_BINDINGS: OnceLock<KeyBindings> = OnceLock::new();
+
+// Everything a world's binary sets up before animating it, so that its main can be e.g.
+//
+//     WorldApp::new(ConwayWorld::new)
+//         .with_params(|rand| conway_life_pixels::PARAMS.init(|| world_params(rand)))
+//         .with_config("conway.conf")
+//         .run();
+//
+// The world factory is given the grid size that fits the window (or --grid-size) and its own
+// Random (see RunRandoms), as each world's new() takes them.
+pub struct WorldApp<W, F>
+where
+    W: World + Clone,
+    F: Fn(GridSize, Random) -> W,
+{
+    world_factory: F,
+    time_step_frames: u32,
+    cell_pixel_width: u32,
+    init_params: Option<fn(&mut Random)>,
+    key_bindings: KeyBindings,
+}
+
+impl<W, F> WorldApp<W, F>
+where
+    W: World + Clone,
+    F: Fn(GridSize, Random) -> W,
+{
+    pub fn new(world_factory: F) -> Self {
+        Self {
+            world_factory,
+            time_step_frames: DEFAULT_TIME_STEP_FRAMES,
+            cell_pixel_width: DEFAULT_CELL_PIXEL_WIDTH,
+            init_params: None,
+            key_bindings: KeyBindings::default(),
+        }
+    }
+
+    pub fn with_time_step_frames(mut self, time_step_frames: u32) -> Self {
+        self.time_step_frames = time_step_frames;
+        self
+    }
+
+    pub fn with_cell_pixel_width(mut self, cell_pixel_width: u32) -> Self {
+        self.cell_pixel_width = cell_pixel_width;
+        self
+    }
+
+    // Sets the world's params, once the config file's options are in, so that they can
+    // depend on the options (e.g. --surprise, via world_params, which draws from the given
+    // Random).
+    pub fn with_params(mut self, init_params: fn(&mut Random)) -> Self {
+        self.init_params = Some(init_params);
+        self
+    }
+
+    // Reads default options from the file, if there is one: command-line options, laid out
+    // however is convenient, with everything from a '#' to the end of its line ignored. The
+    // actual command line's options come after the file's, so they override them. Options
+    // read before this, e.g. to capture in the world factory, don't include the file's.
+    pub fn with_config<P: AsRef<Path>>(self, path: P) -> Self {
+        let path = path.as_ref();
+        if let Ok(text) = fs::read_to_string(path) {
+            let args = text
+                .lines()
+                .flat_map(|line| line.split('#').next().unwrap().split_whitespace())
+                .map(str::to_string)
+                .collect();
+            options::set_config_args(args);
+        }
+        self
+    }
+
+    pub fn with_keybindings(mut self, key_bindings: KeyBindings) -> Self {
+        self.key_bindings = key_bindings;
+        self
+    }
+
+    pub fn run(self) {
+        let mut randoms = RunRandoms::new(AppOptions::from_args().random_seed);
+        if let Some(init_params) = self.init_params {
+            init_params(&mut randoms.surprise);
+        }
+        KEY_BINDINGS.set(self.key_bindings).unwrap();
+        animate(
+            self.time_step_frames,
+            self.cell_pixel_width,
+            randoms,
+            self.world_factory,
+        );
+    }
+}
+
+// Keys for a world's binary to give commands with, e.g. Space to single step, in place of
+// whatever they give by default. The commands' default keys still give them too.
+#[derive(Clone, Debug, Default)]
+pub struct KeyBindings {
+    bindings: Vec<(KeyCode, KeyAction)>,
+}
+
+impl KeyBindings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bind(mut self, key: KeyCode, action: KeyAction) -> Self {
+        self.bindings.push((key, action));
+        self
+    }
+
+    fn action(&self, key: KeyCode) -> Option<KeyAction> {
+        self.bindings
+            .iter()
+            .find(|(bound_key, _)| *bound_key == key)
+            .map(|(_, action)| *action)
+    }
+}
+
+// The command bound to the key, if any.
+pub(crate) fn bound_action(key: KeyCode) -> Option<KeyAction> {
+    KEY_BINDINGS.get()?.action(key)
+}
@@ -0,0 +1,341 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use arrayvec::ArrayVec;
+use std::fmt;
+use world_grid::{
+    BitSet8, BitSet8Gene, CellCodec, CellUpdate, GeneDescriptor, GeneFormat, GridCell, GridSize,
+    Neighborhood, Random, SnapshotError, SnapshotReader, SnapshotWriter, World, WorldGrid,
+    WorldMetric, WorldParams, WorldParamsCell,
+};
+
+const UPDATE_PHASE_NAMES: [&str; 2] = ["payoffs", "imitation"];
+// Rounds of the iterated game each pair of neighbors plays per step.
+const ROUNDS: u32 = 4;
+// Strategies that always cooperate and always defect (see Strategy).
+const ALWAYS_COOPERATE: u8 = 0b11111;
+const ALWAYS_DEFECT: u8 = 0;
+const PAYOFF_TOLERANCE: f32 = 1e-4;
+
+pub static PARAMS: WorldParamsCell<PdParams> = WorldParamsCell::new();
+
+// Nowak and May's spatial prisoner's dilemma: each step, every creature plays an iterated
+// prisoner's dilemma with each of its neighbors and itself, adding up its payoffs, and then
+// takes on the strategy of whichever creature around it, itself included, scored best. With
+// only always-cooperate and always-defect strategies, as it starts, and a temptation payoff
+// between about 1.8 and 2, the two keep invading each other in ever-changing kaleidoscopic
+// patterns. Mutations, if any, bring in the other strategies a strategy gene can encode.
+#[derive(Clone, Debug)]
+pub struct PdWorld {
+    grid: WorldGrid<PdGridCell>,
+    rand: Option<Random>,
+}
+
+impl PdWorld {
+    pub fn new(grid_size: GridSize, rand: Random) -> Self {
+        let mut result = Self::new_empty(grid_size, rand);
+        result.add_random_strategies();
+        result
+    }
+
+    fn new_empty(grid_size: GridSize, rand: Random) -> Self {
+        assert!(!grid_size.is_empty());
+        Self {
+            grid: WorldGrid::new(grid_size),
+            rand: Some(rand),
+        }
+    }
+
+    fn add_random_strategies(&mut self) {
+        let rand = self.rand.as_mut().unwrap();
+        for cell in self.grid.cells.cells_iter_mut() {
+            let bits = if rand.next_bool(PARAMS.get().initial_cooperators) {
+                ALWAYS_COOPERATE
+            } else {
+                ALWAYS_DEFECT
+            };
+            *cell = PdGridCell::new(BitSet8Gene::new(BitSet8::new(bits)));
+        }
+        self.grid.mark_all_changed();
+    }
+
+    fn update_phase_with(&mut self, cell_update: CellUpdate<PdGridCell>) {
+        self.grid
+            .update_with(&mut self.rand, |_grid| {}, cell_update);
+    }
+}
+
+impl World for PdWorld {
+    fn grid(&self) -> &WorldGrid<impl GridCell> {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut WorldGrid<impl GridCell> {
+        &mut self.grid
+    }
+
+    fn update(&mut self) {
+        self.update_phase(0);
+        self.update_phase(1);
+    }
+
+    fn reset(&mut self) {
+        self.grid.clear();
+        self.add_random_strategies();
+    }
+
+    fn metrics(&self) -> Vec<WorldMetric> {
+        let cooperators = self
+            .grid
+            .cells_iter()
+            .filter(|cell| cell.strategy().opens_cooperating())
+            .count();
+        let payoff: f64 = self.grid.cells_iter().map(|cell| cell.payoff as f64).sum();
+        vec![
+            WorldMetric::new("cooperators", cooperators as f64),
+            WorldMetric::new("defectors", (self.grid.num_cells() - cooperators) as f64),
+            WorldMetric::new("mean_payoff", payoff / self.grid.num_cells() as f64),
+        ]
+    }
+
+    fn update_phase_names(&self) -> &'static [&'static str] {
+        &UPDATE_PHASE_NAMES
+    }
+
+    // The imitation phase needs the payoffs of the neighbors' neighbors, which are out of a
+    // cell's sight, so the payoffs are worked out in a grid update of their own first.
+    fn update_phase(&mut self, phase: usize) {
+        match phase {
+            0 => self.update_phase_with(|cell, neighborhood, next_cell, _rand| {
+                cell.update_next_payoff(neighborhood, next_cell)
+            }),
+            _ => self.update_phase_with(PdGridCell::update),
+        }
+    }
+}
+
+// A memory-one strategy for the iterated game, encoded in a gene's bits: bit 0 is whether to
+// cooperate in the first round, and bits 1 through 4 are whether to cooperate after a round
+// in which the player and its opponent, in that order, cooperated and cooperated, cooperated
+// and defected, defected and cooperated, or defected and defected. The rest are unused. E.g.
+// tit for tat, which opens by cooperating and then does whatever its opponent did last, is
+// 0b01011.
+#[derive(Clone, Copy, Debug)]
+struct Strategy(BitSet8);
+
+impl Strategy {
+    fn opens_cooperating(&self) -> bool {
+        self.0.is_bit_set(0)
+    }
+
+    fn cooperates_after(&self, cooperated: bool, opponent_cooperated: bool) -> bool {
+        self.0
+            .is_bit_set(1 + 2 * !cooperated as usize + !opponent_cooperated as usize)
+    }
+
+    // The mean payoff per round to this strategy from an iterated game against the other.
+    fn payoff_against(&self, other: Self) -> f32 {
+        let (mut cooperates, mut other_cooperates) =
+            (self.opens_cooperating(), other.opens_cooperating());
+        let mut total = 0.0;
+        for _ in 0..ROUNDS {
+            total += Self::round_payoff(cooperates, other_cooperates);
+            (cooperates, other_cooperates) = (
+                self.cooperates_after(cooperates, other_cooperates),
+                other.cooperates_after(other_cooperates, cooperates),
+            );
+        }
+        total / ROUNDS as f32
+    }
+
+    // Nowak and May's simplified payoffs: 1 each for mutual cooperation, the temptation
+    // payoff for defecting against a cooperator, and nothing otherwise.
+    fn round_payoff(cooperates: bool, other_cooperates: bool) -> f32 {
+        match (cooperates, other_cooperates) {
+            (true, true) => 1.0,
+            (false, true) => PARAMS.get().temptation,
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PdGridCell {
+    strategy_gene: BitSet8Gene,
+    // Whether the strategy before the last imitation opened by cooperating, for coloring
+    // switches.
+    previously_cooperated: bool,
+    // The total from the last payoff phase.
+    payoff: f32,
+}
+
+impl PdGridCell {
+    fn new(strategy_gene: BitSet8Gene) -> Self {
+        Self {
+            strategy_gene,
+            previously_cooperated: Strategy(strategy_gene.value).opens_cooperating(),
+            payoff: 0.0,
+        }
+    }
+
+    fn strategy(&self) -> Strategy {
+        Strategy(self.strategy_gene.value)
+    }
+
+    fn update_next_payoff(
+        &self,
+        neighborhood: &Neighborhood<PdGridCell>,
+        next_cell: &mut PdGridCell,
+    ) {
+        let strategy = self.strategy();
+        let mut payoff = strategy.payoff_against(strategy);
+        neighborhood.for_neighbor_cells(|neighbor| {
+            payoff += strategy.payoff_against(neighbor.strategy());
+        });
+        next_cell.payoff = payoff;
+    }
+
+    // The best scoring cell's strategy gene, keeping this cell's own on ties.
+    fn best_strategy_gene(&self, neighborhood: &Neighborhood<PdGridCell>) -> BitSet8Gene {
+        let mut best = *self;
+        neighborhood.for_neighbor_cells(|neighbor| {
+            if neighbor.payoff > best.payoff {
+                best = *neighbor;
+            }
+        });
+        best.strategy_gene
+    }
+}
+
+impl CellCodec for PdGridCell {
+    const FORMAT_NAME: &'static str = "prisoners-dilemma";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_u8(self.strategy_gene.value.bits);
+        writer.write_bool(self.previously_cooperated);
+        writer.write_f32(self.payoff);
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        Ok(Self {
+            strategy_gene: BitSet8Gene::new(BitSet8::new(reader.read_u8()?)),
+            previously_cooperated: reader.read_bool()?,
+            payoff: reader.read_f32()?,
+        })
+    }
+}
+
+impl GridCell for PdGridCell {
+    // Nowak and May's colors: blue for cooperators and red for defectors that stayed that way,
+    // green for new cooperators, and yellow for new defectors.
+    fn color_rgba(&self) -> [u8; 4] {
+        match (
+            self.previously_cooperated,
+            self.strategy().opens_cooperating(),
+        ) {
+            (true, true) => [0x20, 0x40, 0xe0, 0xff],
+            (false, false) => [0xe0, 0x20, 0x20, 0xff],
+            (false, true) => [0x20, 0xc0, 0x40, 0xff],
+            (true, false) => [0xf0, 0xe0, 0x20, 0xff],
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = Self::new(BitSet8Gene::new(BitSet8::new(ALWAYS_COOPERATE)));
+    }
+
+    // The imitation phase (see PdWorld::update_phase), with the payoffs already worked out.
+    fn update(
+        &self,
+        neighborhood: &Neighborhood<PdGridCell>,
+        next_cell: &mut PdGridCell,
+        rand: &mut Option<Random>,
+    ) {
+        let mut best = ArrayVec::<BitSet8Gene, 8>::new();
+        best.push(self.best_strategy_gene(neighborhood));
+        next_cell.strategy_gene = BitSet8Gene::merge(&best, rand, PARAMS.get().mutation_odds);
+        next_cell.previously_cooperated = self.strategy().opens_cooperating();
+    }
+
+    fn debug_print(&self, _row: u32, _col: u32) {}
+
+    fn is_inert(&self) -> bool {
+        false
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["strategy_gene", "payoff"]
+    }
+
+    fn field_value(&self, field: usize) -> f32 {
+        match field {
+            0 => self.strategy_gene.value.bits as f32,
+            _ => self.payoff,
+        }
+    }
+
+    fn field_tolerance(field: usize) -> f32 {
+        if field == 1 { PAYOFF_TOLERANCE } else { 0.0 }
+    }
+
+    fn genome(&self) -> Option<u64> {
+        Some(self.strategy_gene.value.bits as u64)
+    }
+
+    fn set_genome(&mut self, genome: u64) {
+        *self = Self::new(BitSet8Gene::new(BitSet8::new(genome as u8)));
+    }
+
+    // Every cell holds a creature, so there is nothing to remove.
+    fn remove_creature(&mut self) {}
+
+    fn genes() -> &'static [GeneDescriptor] {
+        const GENES: [GeneDescriptor; 1] =
+            [GeneDescriptor::new("strategy_gene", 0, GeneFormat::Bits)];
+        &GENES
+    }
+}
+
+#[derive(Debug)]
+pub struct PdParams {
+    // The payoff for defecting against a cooperator, from 1 to 2.
+    temptation: f32,
+    // The fraction of creatures that start out always cooperating; the rest always defect.
+    initial_cooperators: f64,
+    // Odds of each strategy gene bit flipping when a strategy is imitated.
+    mutation_odds: f64,
+}
+
+impl Default for PdParams {
+    fn default() -> Self {
+        Self {
+            temptation: 1.85,
+            initial_cooperators: 0.9,
+            mutation_odds: 0.0,
+        }
+    }
+}
+
+impl WorldParams for PdParams {
+    fn random(rand: &mut Random) -> Self {
+        Self {
+            temptation: rand.next_in_range(1.6..2.0),
+            initial_cooperators: rand.next_in_range(0.5..0.95),
+            mutation_odds: if rand.next_bool(0.5) {
+                0.0
+            } else {
+                10f64.powf(rand.next_in_range(-5.0..-3.0))
+            },
+        }
+    }
+}
+
+impl fmt::Display for PdParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "temptation={:.3} initial_cooperators={:.2} mutation_odds={:.6}",
+            self.temptation, self.initial_cooperators, self.mutation_odds
+        )
+    }
+}
@@ -0,0 +1,12 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use pixels_main_support::{WorldApp, world_params};
+use prisoners_dilemma_pixels::PdWorld;
+
+fn main() {
+    WorldApp::new(PdWorld::new)
+        .with_time_step_frames(10)
+        .with_params(|rand| prisoners_dilemma_pixels::PARAMS.init(|| world_params(rand)))
+        .run();
+}
@@ -0,0 +1,314 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use ant_colony_pixels::AntGridCell;
+use conway_life_pixels::ConwayGridCell;
+use evo_conway_life_pixels::EvoConwayGridCell;
+use evo_grid::EvoGridCell;
+use evo_substance_pixels::EvoSubstanceCell;
+use prisoners_dilemma_pixels::PdGridCell;
+use rock_paper_scissors_pixels::RpsGridCell;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use wireworld_pixels::WireworldGridCell;
+use world_grid::{
+    CellCodec, DeltaStream, GridCell, GridSize, WorldGrid, snapshot_format_name, write_png,
+};
+
+const DEFAULT_FRAME_WIDTH: u32 = 1920;
+const DEFAULT_FRAME_HEIGHT: u32 = 1080;
+const DEFAULT_FPS: u32 = 30;
+// Outputs with these extensions are encoded as videos by ffmpeg.
+const VIDEO_EXTENSIONS: [&str; 4] = ["mp4", "mkv", "mov", "webm"];
+
+// Renders a run saved as snapshots (see --save-snapshot) or as a delta stream (see
+// --record-deltas) into frames offline, at any resolution and without the real-time
+// window's frame budget, e.g.
+//
+//     render-frames run.deltas --size 3840x2160 --out frames
+//     render-frames snapshots --fps 60 --every 10 --out run.mp4
+//
+// A directory is read as one snapshot per file, in file name order. Frames are written to
+// the output directory as numbered PNGs or, if the output is a video file, piped to ffmpeg.
+// Worlds render with their default params.
+fn main() {
+    let options = Options::from_args();
+    let input = Input::read(&options.input);
+    let format_name = input.format_name();
+    let num_frames = match format_name.as_str() {
+        AntGridCell::FORMAT_NAME => {
+            ant_colony_pixels::PARAMS.init(Default::default);
+            render::<AntGridCell>(&input, &options)
+        }
+        ConwayGridCell::FORMAT_NAME => {
+            conway_life_pixels::PARAMS.init(Default::default);
+            render::<ConwayGridCell>(&input, &options)
+        }
+        EvoConwayGridCell::FORMAT_NAME => {
+            evo_conway_life_pixels::PARAMS.init(Default::default);
+            render::<EvoConwayGridCell>(&input, &options)
+        }
+        EvoGridCell::FORMAT_NAME => {
+            evo_grid::PARAMS.init(Default::default);
+            render::<EvoGridCell>(&input, &options)
+        }
+        EvoSubstanceCell::FORMAT_NAME => {
+            evo_substance_pixels::PARAMS.init(Default::default);
+            render::<EvoSubstanceCell>(&input, &options)
+        }
+        PdGridCell::FORMAT_NAME => {
+            prisoners_dilemma_pixels::PARAMS.init(Default::default);
+            render::<PdGridCell>(&input, &options)
+        }
+        RpsGridCell::FORMAT_NAME => {
+            rock_paper_scissors_pixels::PARAMS.init(Default::default);
+            render::<RpsGridCell>(&input, &options)
+        }
+        WireworldGridCell::FORMAT_NAME => {
+            wireworld_pixels::PARAMS.init(Default::default);
+            render::<WireworldGridCell>(&input, &options)
+        }
+        _ => panic!("Can't render {} cells", format_name),
+    };
+    println!(
+        "Rendered {} frames to {}",
+        num_frames,
+        options.output.display()
+    );
+}
+
+struct Options {
+    input: PathBuf,
+    output: PathBuf,
+    frame_size: GridSize,
+    fps: u32,
+    // Renders only every this many steps.
+    every: usize,
+}
+
+impl Options {
+    fn from_args() -> Self {
+        let mut input = None;
+        let mut output = None;
+        let mut frame_size = GridSize::new(DEFAULT_FRAME_WIDTH, DEFAULT_FRAME_HEIGHT);
+        let mut fps = DEFAULT_FPS;
+        let mut every = 1;
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--out" => output = Some(PathBuf::from(Self::string_value(&arg, args.next()))),
+                "--size" => frame_size = Self::size_value(&arg, args.next()),
+                "--fps" => fps = Self::parsed_value(&arg, args.next()),
+                "--every" => every = Self::parsed_value::<usize>(&arg, args.next()).max(1),
+                _ if arg.starts_with("--") => panic!("Unknown option {}", arg),
+                _ => input = Some(PathBuf::from(arg)),
+            }
+        }
+        Self {
+            input: input.expect("No snapshot directory or delta stream given"),
+            output: output.expect("No --out given"),
+            frame_size,
+            fps,
+            every,
+        }
+    }
+
+    // E.g. "1920x1080".
+    fn size_value(option: &str, value: Option<String>) -> GridSize {
+        let value = Self::string_value(option, value);
+        value
+            .split_once('x')
+            .and_then(|(width, height)| {
+                Some(GridSize::new(width.parse().ok()?, height.parse().ok()?))
+            })
+            .filter(|size| !size.is_empty())
+            .unwrap_or_else(|| panic!("Invalid value for {}: {}", option, value))
+    }
+
+    fn parsed_value<T: FromStr>(option: &str, value: Option<String>) -> T {
+        let value = Self::string_value(option, value);
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("Invalid value for {}: {}", option, value))
+    }
+
+    fn string_value(option: &str, value: Option<String>) -> String {
+        value.unwrap_or_else(|| panic!("Missing value for {}", option))
+    }
+}
+
+enum Input {
+    // Read one at a time, since a long run's snapshots may not all fit in memory.
+    Snapshots(Vec<PathBuf>),
+    DeltaStream(Vec<u8>),
+}
+
+impl Input {
+    fn read(path: &Path) -> Self {
+        if !path.is_dir() {
+            let bytes = fs::read(path).unwrap();
+            return if DeltaStream::is_delta_stream(&bytes) {
+                Self::DeltaStream(bytes)
+            } else {
+                Self::Snapshots(vec![path.to_path_buf()])
+            };
+        }
+        let mut paths: Vec<PathBuf> = fs::read_dir(path)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.is_file())
+            .collect();
+        paths.sort();
+        assert!(!paths.is_empty(), "No snapshots in {}", path.display());
+        Self::Snapshots(paths)
+    }
+
+    fn format_name(&self) -> String {
+        match self {
+            Self::Snapshots(paths) => snapshot_format_name(&fs::read(&paths[0]).unwrap())
+                .unwrap_or_else(|err| panic!("{}: {}", paths[0].display(), err)),
+            Self::DeltaStream(bytes) => {
+                DeltaStream::format_name(bytes).unwrap_or_else(|err| panic!("{}", err))
+            }
+        }
+    }
+}
+
+// Returns the number of frames rendered.
+fn render<C: GridCell>(input: &Input, options: &Options) -> usize {
+    let mut output = FrameOutput::create(options);
+    let mut step = 0;
+    let mut render_grid = |grid: &WorldGrid<C>| {
+        if step % options.every == 0 {
+            output.write_frame(&draw_frame(grid, options.frame_size));
+        }
+        step += 1;
+    };
+    match input {
+        Input::Snapshots(paths) => {
+            for path in paths {
+                let grid = WorldGrid::<C>::decode_snapshot(&fs::read(path).unwrap())
+                    .unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+                render_grid(&grid);
+            }
+        }
+        Input::DeltaStream(bytes) => DeltaStream::parse::<C>(bytes)
+            .and_then(|stream| stream.for_each_grid(render_grid))
+            .unwrap_or_else(|err| panic!("{}", err)),
+    }
+    output.finish()
+}
+
+// The grid scaled to fit the frame, keeping its aspect ratio, and centered on black, as RGB
+// bytes. Each pixel gets the color of the cell under its center.
+fn draw_frame<C: GridCell>(grid: &WorldGrid<C>, frame_size: GridSize) -> Vec<u8> {
+    let grid_size = grid.size();
+    let scale = (frame_size.width as f64 / grid_size.width as f64)
+        .min(frame_size.height as f64 / grid_size.height as f64);
+    let left = (frame_size.width as f64 - grid_size.width as f64 * scale) / 2.0;
+    let top = (frame_size.height as f64 - grid_size.height as f64 * scale) / 2.0;
+    let cells = grid.cells_iter().as_slice();
+    let mut result = vec![0; frame_size.area() * 3];
+    for (index, pixel) in result.chunks_exact_mut(3).enumerate() {
+        let x = (index % frame_size.width as usize) as f64 + 0.5;
+        let y = (index / frame_size.width as usize) as f64 + 0.5;
+        let (col, row) = (((x - left) / scale).floor(), ((y - top) / scale).floor());
+        if (0.0..grid_size.width as f64).contains(&col)
+            && (0.0..grid_size.height as f64).contains(&row)
+        {
+            let cell = &cells[row as usize * grid_size.width as usize + col as usize];
+            pixel.copy_from_slice(&cell.color_rgba()[..3]);
+        }
+    }
+    result
+}
+
+enum FrameOutput {
+    Pngs {
+        dir: PathBuf,
+        frame_size: GridSize,
+        num_frames: usize,
+    },
+    Video {
+        ffmpeg: Child,
+        num_frames: usize,
+    },
+}
+
+impl FrameOutput {
+    fn create(options: &Options) -> Self {
+        let is_video = options
+            .output
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| VIDEO_EXTENSIONS.contains(&extension));
+        if !is_video {
+            fs::create_dir_all(&options.output).unwrap();
+            return Self::Pngs {
+                dir: options.output.clone(),
+                frame_size: options.frame_size,
+                num_frames: 0,
+            };
+        }
+        let size = format!("{}x{}", options.frame_size.width, options.frame_size.height);
+        let ffmpeg = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24", "-s", &size])
+            .args(["-r", &options.fps.to_string(), "-i", "-"])
+            // The pixel format most players support, which needs an even width and height.
+            .args(["-pix_fmt", "yuv420p"])
+            .arg(&options.output)
+            .stdin(Stdio::piped())
+            .spawn()
+            .unwrap_or_else(|err| panic!("Can't run ffmpeg: {}", err));
+        Self::Video {
+            ffmpeg,
+            num_frames: 0,
+        }
+    }
+
+    fn write_frame(&mut self, frame: &[u8]) {
+        match self {
+            Self::Pngs {
+                dir,
+                frame_size,
+                num_frames,
+            } => {
+                let path = dir.join(format!("frame-{:06}.png", num_frames));
+                write_png(
+                    BufWriter::new(File::create(path).unwrap()),
+                    frame_size.width,
+                    frame_size.height,
+                    frame,
+                )
+                .unwrap();
+                *num_frames += 1;
+            }
+            Self::Video { ffmpeg, num_frames } => {
+                ffmpeg.stdin.as_mut().unwrap().write_all(frame).unwrap();
+                *num_frames += 1;
+            }
+        }
+    }
+
+    // Returns the number of frames written.
+    fn finish(self) -> usize {
+        match self {
+            Self::Pngs { num_frames, .. } => num_frames,
+            Self::Video {
+                mut ffmpeg,
+                num_frames,
+            } => {
+                // Closing its input tells ffmpeg the video is done.
+                drop(ffmpeg.stdin.take());
+                let status = ffmpeg.wait().unwrap();
+                assert!(status.success(), "ffmpeg failed: {}", status);
+                num_frames
+            }
+        }
+    }
+}
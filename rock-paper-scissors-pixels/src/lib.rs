@@ -0,0 +1,223 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::fmt;
+use world_grid::{
+    CellCodec, GridCell, GridSize, Neighborhood, Random, SnapshotError, SnapshotReader,
+    SnapshotWriter, World, WorldGrid, WorldMetric, WorldParams, WorldParamsCell,
+};
+
+pub const NUM_SPECIES: usize = 3;
+const SPECIES_NAMES: [&str; NUM_SPECIES] = ["rock", "paper", "scissors"];
+// Fewer beating neighbors than this can't invade a cell. Lower thresholds make noisier waves.
+const INVASION_THRESHOLD: u32 = 3;
+
+pub static PARAMS: WorldParamsCell<RpsParams> = WorldParamsCell::new();
+
+// The cyclic-dominance game of rock, paper, and scissors played out in space: every cell holds
+// one of three species, each of which beats the next one around the cycle, and each step a
+// cell with at least INVASION_THRESHOLD neighbors of the species that beats it takes on that
+// species, with that species' invasion odds. From a random start, the species soon sort
+// themselves into rotating spirals, each chasing the one it beats.
+#[derive(Clone, Debug)]
+pub struct RpsWorld {
+    grid: WorldGrid<RpsGridCell>,
+    rand: Option<Random>,
+}
+
+impl RpsWorld {
+    pub fn new(grid_size: GridSize, rand: Random) -> Self {
+        let mut result = Self::new_empty(grid_size, rand);
+        result.add_random_species();
+        result
+    }
+
+    fn new_empty(grid_size: GridSize, rand: Random) -> Self {
+        assert!(!grid_size.is_empty());
+        Self {
+            grid: WorldGrid::new(grid_size),
+            rand: Some(rand),
+        }
+    }
+
+    fn add_random_species(&mut self) {
+        let rand = self.rand.as_mut().unwrap();
+        for cell in self.grid.cells.cells_iter_mut() {
+            cell.species = rand.next_in_range(0..NUM_SPECIES as u8);
+        }
+        self.grid.mark_all_changed();
+    }
+}
+
+impl World for RpsWorld {
+    fn grid(&self) -> &WorldGrid<impl GridCell> {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut WorldGrid<impl GridCell> {
+        &mut self.grid
+    }
+
+    fn update(&mut self) {
+        self.grid.update(&mut self.rand, |_grid| {});
+    }
+
+    fn reset(&mut self) {
+        self.grid.clear();
+        self.add_random_species();
+    }
+
+    fn metrics(&self) -> Vec<WorldMetric> {
+        let mut counts = [0; NUM_SPECIES];
+        for cell in self.grid.cells_iter() {
+            counts[cell.species as usize] += 1;
+        }
+        SPECIES_NAMES
+            .iter()
+            .zip(counts)
+            .map(|(name, count)| WorldMetric::new(name, count as f64))
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RpsGridCell {
+    // From 0 to NUM_SPECIES - 1. Each species beats the next one, wrapping around.
+    pub species: u8,
+}
+
+impl RpsGridCell {
+    // The species that beats this cell's.
+    fn predator(&self) -> u8 {
+        (self.species + NUM_SPECIES as u8 - 1) % NUM_SPECIES as u8
+    }
+
+    fn num_predator_neighbors(&self, neighborhood: &Neighborhood<RpsGridCell>) -> u32 {
+        let predator = self.predator();
+        neighborhood
+            .neighbors()
+            .filter(|neighbor| neighbor.species == predator)
+            .count() as u32
+    }
+}
+
+impl CellCodec for RpsGridCell {
+    const FORMAT_NAME: &'static str = "rock-paper-scissors";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_u8(self.species);
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        let species = reader.read_u8()?;
+        if species as usize >= NUM_SPECIES {
+            return Err(SnapshotError::InvalidValue);
+        }
+        Ok(Self { species })
+    }
+}
+
+impl GridCell for RpsGridCell {
+    fn color_rgba(&self) -> [u8; 4] {
+        PARAMS.get().colors[self.species as usize]
+    }
+
+    fn clear(&mut self) {
+        self.species = 0;
+    }
+
+    fn update(
+        &self,
+        neighborhood: &Neighborhood<RpsGridCell>,
+        next_cell: &mut RpsGridCell,
+        rand: &mut Option<Random>,
+    ) {
+        if self.num_predator_neighbors(neighborhood) < INVASION_THRESHOLD {
+            return;
+        }
+        let predator = self.predator();
+        let odds = PARAMS.get().invasion_odds[predator as usize];
+        // Without randomness (e.g. during a world's warm-up), likely invasions always happen.
+        if rand
+            .as_mut()
+            .map_or(odds >= 0.5, |rand| rand.next_bool(odds))
+        {
+            next_cell.species = predator;
+        }
+    }
+
+    fn debug_print(&self, _row: u32, _col: u32) {}
+
+    fn is_inert(&self) -> bool {
+        false
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["species"]
+    }
+
+    fn field_value(&self, _field: usize) -> f32 {
+        self.species as f32
+    }
+
+    fn genome(&self) -> Option<u64> {
+        Some(self.species as u64)
+    }
+
+    fn set_genome(&mut self, genome: u64) {
+        self.species = (genome % NUM_SPECIES as u64) as u8;
+    }
+
+    // Every cell holds a species, so there is nothing to remove.
+    fn remove_creature(&mut self) {}
+}
+
+#[derive(Debug)]
+pub struct RpsParams {
+    // Indexed by species: the odds that it takes over a cell of the species it beats when
+    // they face each other.
+    invasion_odds: [f64; NUM_SPECIES],
+    colors: [[u8; 4]; NUM_SPECIES],
+}
+
+impl RpsParams {
+    pub fn with_invasion_odds(mut self, invasion_odds: [f64; NUM_SPECIES]) -> Self {
+        self.invasion_odds = invasion_odds.map(|odds| odds.clamp(0.0, 1.0));
+        self
+    }
+}
+
+impl Default for RpsParams {
+    fn default() -> Self {
+        Self {
+            invasion_odds: [0.5; NUM_SPECIES],
+            colors: [
+                [0xe0, 0x30, 0x30, 0xff],
+                [0xf0, 0xe0, 0xc0, 0xff],
+                [0x30, 0x60, 0xe0, 0xff],
+            ],
+        }
+    }
+}
+
+impl WorldParams for RpsParams {
+    fn random(rand: &mut Random) -> Self {
+        let mut colors = Self::default().colors;
+        rand.shuffle(&mut colors);
+        Self {
+            invasion_odds: [(); NUM_SPECIES].map(|_| rand.next_in_range(0.2..=1.0)),
+            colors,
+        }
+    }
+}
+
+impl fmt::Display for RpsParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let odds: Vec<String> = self
+            .invasion_odds
+            .iter()
+            .map(|odds| format!("{:.2}", odds))
+            .collect();
+        write!(f, "invasion_odds={}", odds.join(","))
+    }
+}
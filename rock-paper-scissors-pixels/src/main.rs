@@ -0,0 +1,22 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use pixels_main_support::{AppOptions, WorldApp, world_params};
+use rock_paper_scissors_pixels::{RpsParams, RpsWorld};
+
+// Runs with the invasion odds given by --invasion-odds, else the params' odds.
+fn main() {
+    WorldApp::new(RpsWorld::new)
+        .with_time_step_frames(2)
+        .with_cell_pixel_width(2)
+        .with_params(|rand| {
+            rock_paper_scissors_pixels::PARAMS.init(|| {
+                let params: RpsParams = world_params(rand);
+                match AppOptions::from_args().invasion_odds {
+                    Some(invasion_odds) => params.with_invasion_odds(invasion_odds),
+                    None => params,
+                }
+            })
+        })
+        .run();
+}
@@ -0,0 +1,262 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use pixels_main_support::{AppOptions, WorldApp, world_params};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use world_grid::{
+    CellCodec, GridCell, GridSize, Neighborhood, Random, SnapshotError, SnapshotReader,
+    SnapshotWriter, World, WorldGrid, WorldMetric, WorldParams, WorldParamsCell,
+};
+
+// One entry per state of a 3x3 neighborhood (see Neighborhood::state_code).
+const TABLE_SIZE: usize = 1 << 9;
+const CENTER_BIT: usize = 4;
+
+static PARAMS: WorldParamsCell<RuleTableParams> = WorldParamsCell::new();
+
+// Runs the rule table given by --rule-table, else Conway's Life, or a random table with
+// --surprise.
+fn main() {
+    WorldApp::new(RuleTableWorld::new)
+        .with_params(|rand| {
+            PARAMS.init(|| match AppOptions::from_args().rule_table_path {
+                Some(path) => RuleTableParams::load(&path),
+                None => world_params(rand),
+            });
+        })
+        .run();
+}
+
+#[derive(Clone, Debug)]
+pub struct RuleTableWorld {
+    grid: WorldGrid<RuleTableGridCell>,
+    rand: Option<Random>,
+}
+
+impl RuleTableWorld {
+    pub fn new(grid_size: GridSize, rand: Random) -> Self {
+        let mut result = Self::new_empty(grid_size, rand);
+        result.add_random_cells();
+        result
+    }
+
+    fn new_empty(grid_size: GridSize, rand: Random) -> Self {
+        assert!(!grid_size.is_empty());
+        Self {
+            grid: WorldGrid::new(grid_size),
+            rand: Some(rand),
+        }
+    }
+
+    fn add_random_cells(&mut self) {
+        for cell in self.grid.cells.cells_iter_mut() {
+            if let Some(rand) = self.rand.as_mut()
+                && rand.next_bool(PARAMS.get().density)
+            {
+                cell.on = true;
+            }
+        }
+    }
+}
+
+impl World for RuleTableWorld {
+    fn grid(&self) -> &WorldGrid<impl GridCell> {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut WorldGrid<impl GridCell> {
+        &mut self.grid
+    }
+
+    fn update(&mut self) {
+        self.grid.update(&mut self.rand, |_grid| {});
+    }
+
+    fn reset(&mut self) {
+        self.grid.clear();
+        self.add_random_cells();
+    }
+
+    fn metrics(&self) -> Vec<WorldMetric> {
+        let population = self.grid.cells_iter().filter(|cell| cell.on).count();
+        vec![WorldMetric::new("population", population as f64)]
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RuleTableGridCell {
+    pub on: bool,
+}
+
+impl CellCodec for RuleTableGridCell {
+    const FORMAT_NAME: &'static str = "rule-table";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_bool(self.on);
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        Ok(Self {
+            on: reader.read_bool()?,
+        })
+    }
+}
+
+impl GridCell for RuleTableGridCell {
+    fn color_rgba(&self) -> [u8; 4] {
+        if self.on {
+            PARAMS.get().on_color
+        } else {
+            PARAMS.get().off_color
+        }
+    }
+
+    fn clear(&mut self) {
+        self.on = false;
+    }
+
+    fn update(
+        &self,
+        neighborhood: &Neighborhood<RuleTableGridCell>,
+        next_cell: &mut RuleTableGridCell,
+        _rand: &mut Option<Random>,
+    ) {
+        next_cell.on = PARAMS.get().table[neighborhood.state_code(|cell| cell.on) as usize];
+    }
+
+    fn debug_print(&self, _row: u32, _col: u32) {}
+
+    fn is_inert(&self) -> bool {
+        !self.on
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["on"]
+    }
+
+    fn field_value(&self, _field: usize) -> f32 {
+        self.on as u8 as f32
+    }
+
+    fn genome(&self) -> Option<u64> {
+        self.on.then_some(0)
+    }
+
+    fn set_genome(&mut self, _genome: u64) {
+        self.on = true;
+    }
+
+    fn remove_creature(&mut self) {
+        self.on = false;
+    }
+}
+
+#[derive(Debug)]
+pub struct RuleTableParams {
+    // Where the table came from, for display.
+    name: String,
+    // Each cell's next state, indexed by its neighborhood's state code.
+    table: Box<[bool; TABLE_SIZE]>,
+    density: f64,
+    on_color: [u8; 4],
+    off_color: [u8; 4],
+}
+
+impl RuleTableParams {
+    fn new(name: String, table: Box<[bool; TABLE_SIZE]>) -> Self {
+        Self {
+            name,
+            table,
+            density: 0.3,
+            on_color: [0x80, 0x80, 0x80, 0xff],
+            off_color: [0x00, 0x00, 0x40, 0xff],
+        }
+    }
+
+    // Reads a table file: TABLE_SIZE '0' and '1' characters giving the next state for each
+    // state code in order, laid out however is convenient. Whitespace is ignored, as is
+    // everything from a '#' to the end of its line.
+    fn load(path: &Path) -> Self {
+        let text = fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("Can't read {}: {}", path.display(), err));
+        let entries: Vec<bool> = text
+            .lines()
+            .flat_map(|line| line.split('#').next().unwrap().chars())
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c {
+                '0' => false,
+                '1' => true,
+                _ => panic!("Invalid character in {}: {:?}", path.display(), c),
+            })
+            .collect();
+        let table = entries.try_into().unwrap_or_else(|entries: Vec<bool>| {
+            panic!(
+                "{} has {} entries, not {}",
+                path.display(),
+                entries.len(),
+                TABLE_SIZE
+            )
+        });
+        Self::new(path.display().to_string(), Box::new(table))
+    }
+
+    // The table for a rule that depends only on whether the cell is on and how many of its
+    // neighbors are.
+    fn totalistic_table(birth_counts: &[u32], survival_counts: &[u32]) -> Box<[bool; TABLE_SIZE]> {
+        let mut result = Box::new([false; TABLE_SIZE]);
+        for (code, next_on) in result.iter_mut().enumerate() {
+            let on = code & (1 << CENTER_BIT) != 0;
+            let neighbors = (code & !(1 << CENTER_BIT)).count_ones();
+            let counts = if on { survival_counts } else { birth_counts };
+            *next_on = counts.contains(&neighbors);
+        }
+        result
+    }
+}
+
+impl Default for RuleTableParams {
+    fn default() -> Self {
+        Self::new("conway".to_string(), Self::totalistic_table(&[3], &[2, 3]))
+    }
+}
+
+impl WorldParams for RuleTableParams {
+    // A random table, sparse enough that it doesn't just fill the grid with noise.
+    fn random(rand: &mut Random) -> Self {
+        let on_odds = rand.next_in_range(0.05..0.3);
+        let mut table = Box::new([false; TABLE_SIZE]);
+        // The all-off neighborhood stays off, or the whole grid would flash.
+        for next_on in table.iter_mut().skip(1) {
+            *next_on = rand.next_bool(on_odds);
+        }
+        let on_color = [
+            0xff,
+            rand.next_in_range(0x40..=0xff),
+            rand.next_in_range(0..0x80),
+        ];
+        let [red, green, blue] = rand.shuffle_color_rgb(on_color);
+        Self {
+            on_color: [red, green, blue, 0xff],
+            density: rand.next_in_range(0.1..0.5),
+            ..Self::new(format!("random({:.2})", on_odds), table)
+        }
+    }
+}
+
+impl fmt::Display for RuleTableParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [red, green, blue, _] = self.on_color;
+        let table: String = self
+            .table
+            .iter()
+            .map(|next_on| if *next_on { '1' } else { '0' })
+            .collect();
+        write!(
+            f,
+            "table={} density={:.2} on_color=#{:02x}{:02x}{:02x} entries={}",
+            self.name, self.density, red, green, blue, table
+        )
+    }
+}
@@ -0,0 +1,51 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::env;
+use std::process::{self, Command};
+use world_grid::Random;
+
+// The bundled world binaries, which are expected to sit next to this one.
+const WORLD_BINARIES: [&str; 9] = [
+    "conway-life-pixels",
+    "evo-conway-life-pixels",
+    "evo-substance-pixels",
+    "evo-grid-pixels",
+    "rule-table-pixels",
+    "rock-paper-scissors-pixels",
+    "prisoners-dilemma-pixels",
+    "ant-colony-pixels",
+    "wireworld-pixels",
+];
+
+// Runs a randomly chosen world in screensaver mode with surprise params. Other options are
+// passed through to the world, except for the Windows screensaver ones: "/s" (run) is
+// dropped, and "/c" (configure) and "/p" (preview) aren't supported, so they just exit.
+fn main() {
+    let mut args = vec!["--screensaver".to_string(), "--surprise".to_string()];
+    for arg in env::args().skip(1) {
+        match arg.to_lowercase().as_str() {
+            "/s" => (),
+            "/c" | "/p" => return,
+            _ => args.push(arg),
+        }
+    }
+
+    let exe_dir = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let world_paths: Vec<_> = WORLD_BINARIES
+        .iter()
+        .map(|binary| {
+            exe_dir
+                .join(binary)
+                .with_extension(env::consts::EXE_EXTENSION)
+        })
+        .filter(|path| path.exists())
+        .collect();
+    if world_paths.is_empty() {
+        panic!("No world binaries found in {}", exe_dir.display());
+    }
+    let world_path = &world_paths[Random::new().next_in_range(0..world_paths.len())];
+
+    let status = Command::new(world_path).args(&args).status().unwrap();
+    process::exit(status.code().unwrap_or(1));
+}
@@ -0,0 +1,286 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::fmt;
+use world_grid::{
+    CellCodec, GridCell, GridSize, Loc, Neighborhood, Random, SnapshotError, SnapshotReader,
+    SnapshotWriter, World, WorldGrid, WorldMetric, WorldParams, WorldParamsCell,
+};
+
+const NUM_STATES: usize = 4;
+const MIN_LOOP_SIDE: u32 = 6;
+const MAX_LOOP_SIDE: u32 = 40;
+
+pub static PARAMS: WorldParamsCell<WireworldParams> = WorldParamsCell::new();
+
+// Brian Silverman's Wireworld, in which electrons run along wires, for building digital
+// circuits such as diodes, clocks, and logic gates. Circuits are best loaded as multistate
+// RLE patterns (see --pattern), as saved by Golly for its WireWorld rule. Without one, the
+// world starts with random rectangular loops of wire, each with an electron running around
+// it, which spark off each other where they cross.
+#[derive(Clone, Debug)]
+pub struct WireworldWorld {
+    grid: WorldGrid<WireworldGridCell>,
+    rand: Option<Random>,
+    random_loops: bool,
+}
+
+impl WireworldWorld {
+    pub fn new(grid_size: GridSize, rand: Random) -> Self {
+        let mut result = Self::new_empty(grid_size, rand);
+        result.random_loops = true;
+        result.add_random_loops();
+        result
+    }
+
+    // Starts with no wire, e.g. for loading a circuit.
+    pub fn new_empty(grid_size: GridSize, rand: Random) -> Self {
+        assert!(!grid_size.is_empty());
+        Self {
+            grid: WorldGrid::new(grid_size),
+            rand: Some(rand),
+            random_loops: false,
+        }
+    }
+
+    fn add_random_loops(&mut self) {
+        let size = self.grid.size();
+        let num_loops = (size.area() as f64 * PARAMS.get().loops_per_1000_cells / 1000.0) as usize;
+        for _ in 0..num_loops.max(1) {
+            self.add_random_loop();
+        }
+        self.grid.mark_all_changed();
+    }
+
+    fn add_random_loop(&mut self) {
+        let size = self.grid.size();
+        let rand = self.rand.as_mut().unwrap();
+        let width = rand
+            .next_in_range(MIN_LOOP_SIDE..=MAX_LOOP_SIDE)
+            .min(size.width);
+        let height = rand
+            .next_in_range(MIN_LOOP_SIDE..=MAX_LOOP_SIDE)
+            .min(size.height);
+        let top = rand.next_in_range(0..=size.height - height);
+        let left = rand.next_in_range(0..=size.width - width);
+        let perimeter = Self::loop_locs(Loc::new(top, left), width, height);
+        for loc in &perimeter {
+            self.grid.cells[*loc].state = WireState::Conductor;
+        }
+        // An electron is a head with its tail just behind it.
+        let head = rand.next_in_range(0..perimeter.len());
+        let tail = (head + perimeter.len() - 1) % perimeter.len();
+        self.grid.cells[perimeter[head]].state = WireState::Head;
+        self.grid.cells[perimeter[tail]].state = WireState::Tail;
+    }
+
+    // The cells of the rectangle's edge, clockwise from its top left.
+    fn loop_locs(top_left: Loc, width: u32, height: u32) -> Vec<Loc> {
+        let (top, left) = (top_left.row, top_left.col);
+        let (bottom, right) = (top + height - 1, left + width - 1);
+        let top_edge = (left..right).map(|col| Loc::new(top, col));
+        let right_edge = (top..bottom).map(|row| Loc::new(row, right));
+        let bottom_edge = (left + 1..=right).rev().map(|col| Loc::new(bottom, col));
+        let left_edge = (top + 1..=bottom).rev().map(|row| Loc::new(row, left));
+        top_edge
+            .chain(right_edge)
+            .chain(bottom_edge)
+            .chain(left_edge)
+            .collect()
+    }
+}
+
+impl World for WireworldWorld {
+    fn grid(&self) -> &WorldGrid<impl GridCell> {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut WorldGrid<impl GridCell> {
+        &mut self.grid
+    }
+
+    fn update(&mut self) {
+        self.grid.update(&mut self.rand, |_grid| {});
+    }
+
+    fn reset(&mut self) {
+        self.grid.clear();
+        if self.random_loops {
+            self.add_random_loops();
+        }
+    }
+
+    fn metrics(&self) -> Vec<WorldMetric> {
+        let mut counts = [0; NUM_STATES];
+        for cell in self.grid.cells_iter() {
+            counts[cell.state as usize] += 1;
+        }
+        vec![
+            WorldMetric::new("electrons", counts[WireState::Head as usize] as f64),
+            WorldMetric::new(
+                "wire",
+                (self.grid.num_cells() - counts[WireState::Empty as usize]) as f64,
+            ),
+        ]
+    }
+}
+
+// Numbered as in Golly's WireWorld rule, so that its patterns load as is.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WireState {
+    #[default]
+    Empty = 0,
+    Head = 1,
+    Tail = 2,
+    Conductor = 3,
+}
+
+impl WireState {
+    fn from_index(index: u8) -> Option<Self> {
+        match index {
+            0 => Some(Self::Empty),
+            1 => Some(Self::Head),
+            2 => Some(Self::Tail),
+            3 => Some(Self::Conductor),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WireworldGridCell {
+    pub state: WireState,
+}
+
+impl WireworldGridCell {
+    fn num_head_neighbors(neighborhood: &Neighborhood<WireworldGridCell>) -> u32 {
+        neighborhood
+            .neighbors()
+            .filter(|neighbor| neighbor.state == WireState::Head)
+            .count() as u32
+    }
+}
+
+impl CellCodec for WireworldGridCell {
+    const FORMAT_NAME: &'static str = "wireworld";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_u8(self.state as u8);
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        let state = WireState::from_index(reader.read_u8()?).ok_or(SnapshotError::InvalidValue)?;
+        Ok(Self { state })
+    }
+}
+
+impl GridCell for WireworldGridCell {
+    fn color_rgba(&self) -> [u8; 4] {
+        PARAMS.get().colors[self.state as usize]
+    }
+
+    fn clear(&mut self) {
+        self.state = WireState::Empty;
+    }
+
+    // Heads become tails, tails become wire again, and wire next to one or two heads becomes
+    // a head.
+    fn update(
+        &self,
+        neighborhood: &Neighborhood<WireworldGridCell>,
+        next_cell: &mut WireworldGridCell,
+        _rand: &mut Option<Random>,
+    ) {
+        next_cell.state = match self.state {
+            WireState::Empty => WireState::Empty,
+            WireState::Head => WireState::Tail,
+            WireState::Tail => WireState::Conductor,
+            WireState::Conductor => match Self::num_head_neighbors(neighborhood) {
+                1 | 2 => WireState::Head,
+                _ => WireState::Conductor,
+            },
+        };
+    }
+
+    fn debug_print(&self, _row: u32, _col: u32) {}
+
+    // Wire changes only next to electrons, which aren't inert.
+    fn is_inert(&self) -> bool {
+        matches!(self.state, WireState::Empty | WireState::Conductor)
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["state"]
+    }
+
+    fn field_value(&self, _field: usize) -> f32 {
+        self.state as u8 as f32
+    }
+
+    fn genome(&self) -> Option<u64> {
+        (self.state != WireState::Empty).then_some(self.state as u64)
+    }
+
+    // Genomes that aren't states, and 0 (empty), e.g. from drawing with no seed, lay wire.
+    fn set_genome(&mut self, genome: u64) {
+        self.state = match WireState::from_index(genome.min(u8::MAX as u64) as u8) {
+            Some(WireState::Empty) | None => WireState::Conductor,
+            Some(state) => state,
+        };
+    }
+
+    fn remove_creature(&mut self) {
+        self.state = WireState::Empty;
+    }
+
+    fn num_pattern_states() -> u8 {
+        NUM_STATES as u8
+    }
+
+    fn pattern_state(&self) -> u8 {
+        self.state as u8
+    }
+
+    // States beyond Wireworld's are read as wire.
+    fn set_pattern_state(&mut self, state: u8, _genome: u64) {
+        self.state = WireState::from_index(state).unwrap_or(WireState::Conductor);
+    }
+}
+
+#[derive(Debug)]
+pub struct WireworldParams {
+    // How many random loops of wire to start with, if not loading a circuit.
+    loops_per_1000_cells: f64,
+    // Indexed by state.
+    colors: [[u8; 4]; NUM_STATES],
+}
+
+impl Default for WireworldParams {
+    fn default() -> Self {
+        Self {
+            loops_per_1000_cells: 0.6,
+            // Golly's.
+            colors: [
+                [0x30, 0x30, 0x30, 0xff],
+                [0x00, 0x80, 0xff, 0xff],
+                [0xff, 0xff, 0xff, 0xff],
+                [0xff, 0x80, 0x00, 0xff],
+            ],
+        }
+    }
+}
+
+impl WorldParams for WireworldParams {
+    fn random(rand: &mut Random) -> Self {
+        Self {
+            loops_per_1000_cells: rand.next_in_range(0.05..=1.0),
+            ..Self::default()
+        }
+    }
+}
+
+impl fmt::Display for WireworldParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "loops_per_1000_cells={:.2}", self.loops_per_1000_cells)
+    }
+}
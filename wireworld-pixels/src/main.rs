@@ -0,0 +1,21 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use pixels_main_support::{AppOptions, KeyAction, KeyBindings, KeyCode, WorldApp, world_params};
+use wireworld_pixels::WireworldWorld;
+
+// Runs the --pattern circuit, if any, on its own, else random loops of wire. Space single
+// steps, for following electrons through a circuit.
+fn main() {
+    let has_pattern = AppOptions::from_args().pattern_path.is_some();
+    WorldApp::new(move |grid_size, rand| {
+        if has_pattern {
+            WireworldWorld::new_empty(grid_size, rand)
+        } else {
+            WireworldWorld::new(grid_size, rand)
+        }
+    })
+    .with_params(|rand| wireworld_pixels::PARAMS.init(|| world_params(rand)))
+    .with_keybindings(KeyBindings::new().bind(KeyCode::Space, KeyAction::SingleStep))
+    .run();
+}
@@ -0,0 +1,269 @@
+use crate::snapshot::{CellCodec, SnapshotError, SnapshotReader, SnapshotWriter};
+use crate::{
+    GridCell, GridSettings, GridSize, Neighborhood, Random, StepStats, World, WorldGrid,
+    WorldMetric, WorldSources,
+};
+
+// An object-safe view of a World, with its cells erased to buffers of their colors and
+// genomes, so that worlds of different types can be held as Box<dyn AnyWorld>, e.g. to switch
+// between them at runtime. Every World that is Clone is an AnyWorld.
+pub trait AnyWorld {
+    fn grid_size(&self) -> GridSize;
+    fn update(&mut self);
+    fn reset(&mut self);
+    fn metrics(&self) -> Vec<WorldMetric>;
+    fn generation(&self) -> u64;
+    fn step_stats(&self) -> StepStats;
+
+    // Writes each cell's color to rgba, four bytes per cell, in row-major order.
+    fn write_rgba(&self, rgba: &mut [u8]);
+
+    // Writes each cell's genome (see GridCell::genome) to genomes, in row-major order.
+    fn write_genomes(&self, genomes: &mut [Option<u64>]);
+
+    fn sources(&self) -> Option<&dyn WorldSources>;
+    fn sources_mut(&mut self) -> Option<&mut dyn WorldSources>;
+    fn update_phase_names(&self) -> &'static [&'static str];
+    fn update_phase(&mut self, phase: usize);
+    fn apply_grid_settings(&mut self, settings: &GridSettings);
+    fn clone_box(&self) -> Box<dyn AnyWorld>;
+}
+
+impl<W: World + Clone + 'static> AnyWorld for W {
+    fn grid_size(&self) -> GridSize {
+        self.grid().size()
+    }
+
+    fn update(&mut self) {
+        World::update(self);
+    }
+
+    fn reset(&mut self) {
+        World::reset(self);
+    }
+
+    fn metrics(&self) -> Vec<WorldMetric> {
+        World::metrics(self)
+    }
+
+    fn generation(&self) -> u64 {
+        World::generation(self)
+    }
+
+    fn step_stats(&self) -> StepStats {
+        World::step_stats(self)
+    }
+
+    fn write_rgba(&self, rgba: &mut [u8]) {
+        for (pixel, cell) in rgba.chunks_exact_mut(4).zip(self.grid().cells_iter()) {
+            pixel.copy_from_slice(&cell.color_rgba());
+        }
+    }
+
+    fn write_genomes(&self, genomes: &mut [Option<u64>]) {
+        for (genome, cell) in genomes.iter_mut().zip(self.grid().cells_iter()) {
+            *genome = cell.genome();
+        }
+    }
+
+    fn sources(&self) -> Option<&dyn WorldSources> {
+        World::sources(self)
+    }
+
+    fn sources_mut(&mut self) -> Option<&mut dyn WorldSources> {
+        World::sources_mut(self)
+    }
+
+    fn update_phase_names(&self) -> &'static [&'static str] {
+        World::update_phase_names(self)
+    }
+
+    fn update_phase(&mut self, phase: usize) {
+        World::update_phase(self, phase);
+    }
+
+    fn apply_grid_settings(&mut self, settings: &GridSettings) {
+        World::apply_grid_settings(self, settings);
+    }
+
+    fn clone_box(&self) -> Box<dyn AnyWorld> {
+        Box::new(self.clone())
+    }
+}
+
+// Runs a Box<dyn AnyWorld> as a World, so that code generic over World, such as the viewer,
+// can run worlds chosen at runtime. As with LayeredWorld, the grid only holds a copy of each
+// cell's color and genome, so the world can't be edited, though its grid settings can be.
+pub struct DynWorld {
+    world: Box<dyn AnyWorld>,
+    grid: WorldGrid<ErasedCell>,
+    rgba: Vec<u8>,
+    genomes: Vec<Option<u64>>,
+}
+
+impl DynWorld {
+    pub fn new(world: Box<dyn AnyWorld>) -> Self {
+        let size = world.grid_size();
+        let num_cells = (size.width * size.height) as usize;
+        let mut result = Self {
+            world,
+            grid: WorldGrid::new(size),
+            rgba: vec![0; 4 * num_cells],
+            genomes: vec![None; num_cells],
+        };
+        result.copy_cells();
+        result
+    }
+
+    pub fn world(&self) -> &dyn AnyWorld {
+        self.world.as_ref()
+    }
+
+    // Switches to another world, whose grid may be a different size.
+    pub fn replace(&mut self, world: Box<dyn AnyWorld>) {
+        *self = Self::new(world);
+    }
+
+    fn copy_cells(&mut self) {
+        self.world.write_rgba(&mut self.rgba);
+        self.world.write_genomes(&mut self.genomes);
+        for ((cell, color), genome) in self
+            .grid
+            .cells
+            .cells_iter_mut()
+            .zip(self.rgba.chunks_exact(4))
+            .zip(&self.genomes)
+        {
+            cell.color.copy_from_slice(color);
+            cell.genome = *genome;
+        }
+        self.grid.mark_all_changed();
+    }
+}
+
+impl Clone for DynWorld {
+    fn clone(&self) -> Self {
+        Self {
+            world: self.world.clone_box(),
+            grid: self.grid.clone(),
+            rgba: self.rgba.clone(),
+            genomes: self.genomes.clone(),
+        }
+    }
+}
+
+impl World for DynWorld {
+    fn grid(&self) -> &WorldGrid<impl GridCell> {
+        &self.grid
+    }
+
+    // The copy, whose changes are discarded at the next update (see is_editable).
+    fn grid_mut(&mut self) -> &mut WorldGrid<impl GridCell> {
+        &mut self.grid
+    }
+
+    fn update(&mut self) {
+        self.world.update();
+        self.copy_cells();
+    }
+
+    fn reset(&mut self) {
+        self.world.reset();
+        self.copy_cells();
+    }
+
+    fn metrics(&self) -> Vec<WorldMetric> {
+        self.world.metrics()
+    }
+
+    fn generation(&self) -> u64 {
+        self.world.generation()
+    }
+
+    fn step_stats(&self) -> StepStats {
+        self.world.step_stats()
+    }
+
+    fn sources(&self) -> Option<&dyn WorldSources> {
+        self.world.sources()
+    }
+
+    fn sources_mut(&mut self) -> Option<&mut dyn WorldSources> {
+        self.world.sources_mut()
+    }
+
+    fn update_phase_names(&self) -> &'static [&'static str] {
+        self.world.update_phase_names()
+    }
+
+    fn update_phase(&mut self, phase: usize) {
+        self.world.update_phase(phase);
+        self.copy_cells();
+    }
+
+    // The copy gets the settings too, e.g. so that pinned cells show as pinned.
+    fn apply_grid_settings(&mut self, settings: &GridSettings) {
+        self.world.apply_grid_settings(settings);
+        settings.apply(&mut self.grid);
+    }
+
+    fn is_editable(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ErasedCell {
+    color: [u8; 4],
+    genome: Option<u64>,
+}
+
+impl CellCodec for ErasedCell {
+    const FORMAT_NAME: &'static str = "erased";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_bytes(&self.color);
+        writer.write_option(&self.genome, |writer, genome| writer.write_u64(*genome));
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        Ok(Self {
+            color: reader.read_array()?,
+            genome: reader.read_option(|reader| reader.read_u64())?,
+        })
+    }
+}
+
+impl GridCell for ErasedCell {
+    fn color_rgba(&self) -> [u8; 4] {
+        self.color
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    // The world updates itself; see DynWorld::update.
+    fn update(
+        &self,
+        _neighborhood: &Neighborhood<ErasedCell>,
+        _next_cell: &mut ErasedCell,
+        _rand: &mut Option<Random>,
+    ) {
+    }
+
+    fn debug_print(&self, row: u32, col: u32) {
+        println!(
+            "({}, {}): Color: {:?}, Genome: {:?}",
+            row, col, self.color, self.genome
+        );
+    }
+
+    fn is_inert(&self) -> bool {
+        true
+    }
+
+    fn genome(&self) -> Option<u64> {
+        self.genome
+    }
+}
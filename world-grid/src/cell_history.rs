@@ -0,0 +1,105 @@
+use crate::{GridCell, Loc, WorldGrid};
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::io::{self, Write};
+
+// The last so many states of chosen cells, as their export fields (see
+// GridCell::field_names), recorded after each step, for plotting a single site's state over
+// time, e.g. to study oscillations and other local dynamics.
+#[derive(Clone, Debug)]
+pub struct CellHistory {
+    length: usize,
+    field_names: &'static [&'static str],
+    sites: Vec<SiteHistory>,
+}
+
+#[derive(Clone, Debug)]
+struct SiteHistory {
+    loc: Loc,
+    // Oldest first, with the step each was recorded after.
+    states: VecDeque<(u64, Vec<f32>)>,
+}
+
+impl CellHistory {
+    // Keeps the last length states of each recorded cell of the grid's cell type.
+    pub fn new<C>(_grid: &WorldGrid<C>, length: usize) -> Self
+    where
+        C: Clone + Debug + GridCell,
+    {
+        Self {
+            length: length.max(1),
+            field_names: C::field_names(),
+            sites: vec![],
+        }
+    }
+
+    // Starts recording the cell's states, or stops and drops its history.
+    pub fn set_recorded(&mut self, loc: Loc, recorded: bool) {
+        let index = self.sites.iter().position(|site| site.loc == loc);
+        match (index, recorded) {
+            (None, true) => self.sites.push(SiteHistory {
+                loc,
+                states: VecDeque::with_capacity(self.length),
+            }),
+            (Some(index), false) => {
+                self.sites.remove(index);
+            }
+            _ => (),
+        }
+    }
+
+    pub fn is_recorded(&self, loc: Loc) -> bool {
+        self.sites.iter().any(|site| site.loc == loc)
+    }
+
+    // Records the current states of the cells, dropping their oldest beyond the length. Cells
+    // outside the grid are skipped.
+    pub fn record<C>(&mut self, grid: &WorldGrid<C>)
+    where
+        C: Clone + Debug + GridCell,
+    {
+        for site in &mut self.sites {
+            let Ok(cell) = grid.try_cell(site.loc) else {
+                continue;
+            };
+            if site.states.len() == self.length {
+                site.states.pop_front();
+            }
+            let values = (0..self.field_names.len())
+                .map(|field| cell.field_value(field))
+                .collect();
+            site.states.push_back((grid.step(), values));
+        }
+    }
+
+    // The cell's recorded steps and field values, oldest first, or none if it isn't recorded.
+    pub fn states(&self, loc: Loc) -> impl Iterator<Item = (u64, &[f32])> {
+        self.sites
+            .iter()
+            .filter(move |site| site.loc == loc)
+            .flat_map(|site| site.states.iter())
+            .map(|(step, values)| (*step, values.as_slice()))
+    }
+
+    // Writes every recorded state as CSV, one row per cell per step, with the cell's row and
+    // column, the step, and the field values.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let header: Vec<_> = ["row", "col", "step"]
+            .iter()
+            .chain(self.field_names)
+            .copied()
+            .collect();
+        writeln!(writer, "{}", header.join(","))?;
+        for site in &self.sites {
+            for (step, values) in &site.states {
+                let row: Vec<_> = [site.loc.row as u64, site.loc.col as u64, *step]
+                    .iter()
+                    .map(|value| value.to_string())
+                    .chain(values.iter().map(|value| value.to_string()))
+                    .collect();
+                writeln!(writer, "{}", row.join(","))?;
+            }
+        }
+        Ok(())
+    }
+}
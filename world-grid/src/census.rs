@@ -0,0 +1,226 @@
+use crate::{GridCell, Loc, WorldGrid};
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+// Objects left behind in the "ash" of Conway-style worlds, drawn with 'O' for occupied cells.
+// Objects with several phases or orientations need only list the phases that no rotation or
+// reflection of another listed phase covers.
+const CENSUS_OBJECTS: [(&str, ObjectKind, &[&[&str]]); 7] = [
+    ("block", ObjectKind::StillLife, &[&["OO", "OO"]]),
+    (
+        "beehive",
+        ObjectKind::StillLife,
+        &[&[".OO.", "O..O", ".OO."]],
+    ),
+    (
+        "loaf",
+        ObjectKind::StillLife,
+        &[&[".OO.", "O..O", ".O.O", "..O."]],
+    ),
+    ("boat", ObjectKind::StillLife, &[&["OO.", "O.O", ".O."]]),
+    ("tub", ObjectKind::StillLife, &[&[".O.", "O.O", ".O."]]),
+    ("blinker", ObjectKind::Oscillator, &[&["OOO"]]),
+    (
+        "glider",
+        ObjectKind::Spaceship,
+        &[&[".O.", "..O", "OOO"], &["O.O", ".OO", ".O."]],
+    ),
+];
+
+// No census object has more cells than this, so bigger clumps can be skipped early.
+const MAX_OBJECT_CELLS: usize = 8;
+
+// Cell offsets from the top left of an object's bounding box, sorted.
+type Shape = Vec<(u32, u32)>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObjectKind {
+    StillLife,
+    Oscillator,
+    Spaceship,
+}
+
+// A census object found in a grid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FoundObject {
+    pub name: &'static str,
+    pub kind: ObjectKind,
+    // The top left of the object's bounding box.
+    pub loc: Loc,
+    pub height: u32,
+    pub width: u32,
+}
+
+impl FoundObject {
+    // The center of the object's bounding box, as (row, col).
+    pub fn center(&self) -> (f32, f32) {
+        (
+            self.loc.row as f32 + self.height as f32 / 2.0,
+            self.loc.col as f32 + self.width as f32 / 2.0,
+        )
+    }
+}
+
+// How many of each census object a grid holds, plus how many other clumps of occupied cells.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ObjectCensus {
+    pub counts: Vec<(&'static str, u32)>,
+    pub other: u32,
+}
+
+impl ObjectCensus {
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        CENSUS_OBJECTS.iter().map(|(name, _, _)| *name)
+    }
+}
+
+impl<C> WorldGrid<C>
+where
+    C: Clone + Debug + GridCell,
+{
+    // Counts the census objects in the grid (see find_objects), and the clumps of occupied cells
+    // that aren't census objects.
+    pub fn object_census(&self) -> ObjectCensus {
+        let mut counts: HashMap<&str, u32> = HashMap::new();
+        let mut other = 0;
+        self.for_each_clump(|object| match object {
+            Some(object) => *counts.entry(object.name).or_insert(0) += 1,
+            None => other += 1,
+        });
+        ObjectCensus {
+            counts: ObjectCensus::names()
+                .map(|name| (name, counts.get(name).copied().unwrap_or(0)))
+                .collect(),
+            other,
+        }
+    }
+
+    // Finds the census objects in the grid, where an object is a clump of occupied cells (see
+    // GridCell::genome) that touch, including diagonally, and match one of the objects in any
+    // phase, rotation, or reflection. Objects close enough to interact still count separately.
+    pub fn find_objects(&self) -> Vec<FoundObject> {
+        let mut result = vec![];
+        self.for_each_clump(|object| result.extend(object));
+        result
+    }
+
+    // Calls f for each clump of occupied cells, with the census object it matches, if any.
+    fn for_each_clump<F>(&self, mut f: F)
+    where
+        F: FnMut(Option<FoundObject>),
+    {
+        let size = self.size();
+        let (width, height) = (size.width as i64, size.height as i64);
+        let mut unvisited: Vec<bool> = self
+            .cells_iter()
+            .map(|cell| cell.genome().is_some())
+            .collect();
+        let shape_objects = Self::census_shapes();
+        let mut stack = vec![];
+        for start in 0..unvisited.len() {
+            if !unvisited[start] {
+                continue;
+            }
+            unvisited[start] = false;
+            stack.push(start);
+            let mut clump = vec![];
+            while let Some(index) = stack.pop() {
+                let (row, col) = (index as i64 / width, index as i64 % width);
+                if clump.len() <= MAX_OBJECT_CELLS {
+                    clump.push((row as u32, col as u32));
+                }
+                for (row_offset, col_offset) in NEIGHBOR_OFFSETS {
+                    let (row, col) = (row + row_offset, col + col_offset);
+                    if (0..height).contains(&row) && (0..width).contains(&col) {
+                        let neighbor = (row * width + col) as usize;
+                        if unvisited[neighbor] {
+                            unvisited[neighbor] = false;
+                            stack.push(neighbor);
+                        }
+                    }
+                }
+            }
+            let loc = Loc::new(
+                clump.iter().map(|(row, _)| *row).min().unwrap(),
+                clump.iter().map(|(_, col)| *col).min().unwrap(),
+            );
+            let shape = Self::normalized(clump);
+            f(shape_objects.get(&shape).map(|&(name, kind)| FoundObject {
+                name,
+                kind,
+                loc,
+                height: shape.iter().map(|(row, _)| row + 1).max().unwrap(),
+                width: shape.iter().map(|(_, col)| col + 1).max().unwrap(),
+            }));
+        }
+    }
+
+    fn census_shapes() -> HashMap<Shape, (&'static str, ObjectKind)> {
+        let mut result = HashMap::new();
+        for (name, kind, phases) in CENSUS_OBJECTS {
+            for phase in phases {
+                let cells: Vec<(i64, i64)> = phase
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(row, line)| {
+                        line.chars()
+                            .enumerate()
+                            .filter(|(_, c)| *c == 'O')
+                            .map(move |(col, _)| (row as i64, col as i64))
+                    })
+                    .collect();
+                for transform in 0..8 {
+                    let transformed = cells
+                        .iter()
+                        .map(|&cell| Self::transformed(cell, transform))
+                        .collect();
+                    result.insert(Self::normalized_signed(transformed), (name, kind));
+                }
+            }
+        }
+        result
+    }
+
+    // One of the 8 rotations and reflections of a cell offset.
+    fn transformed((row, col): (i64, i64), transform: u8) -> (i64, i64) {
+        let (row, col) = if transform & 1 == 0 {
+            (row, col)
+        } else {
+            (col, row)
+        };
+        let row = if transform & 2 == 0 { row } else { -row };
+        let col = if transform & 4 == 0 { col } else { -col };
+        (row, col)
+    }
+
+    fn normalized(cells: Vec<(u32, u32)>) -> Shape {
+        Self::normalized_signed(
+            cells
+                .into_iter()
+                .map(|(row, col)| (row as i64, col as i64))
+                .collect(),
+        )
+    }
+
+    fn normalized_signed(cells: Vec<(i64, i64)>) -> Shape {
+        let min_row = cells.iter().map(|(row, _)| *row).min().unwrap_or(0);
+        let min_col = cells.iter().map(|(_, col)| *col).min().unwrap_or(0);
+        let mut result: Shape = cells
+            .into_iter()
+            .map(|(row, col)| ((row - min_row) as u32, (col - min_col) as u32))
+            .collect();
+        result.sort_unstable();
+        result
+    }
+}
+
+const NEIGHBOR_OFFSETS: [(i64, i64); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
@@ -0,0 +1,185 @@
+use crate::snapshot::{SnapshotError, SnapshotReader, SnapshotWriter, snapshot_format_name};
+use crate::{GridCell, Loc, WorldGrid};
+use std::fmt::Debug;
+use std::io::{self, Write};
+use std::mem;
+
+const DELTA_STREAM_MAGIC: &[u8; 4] = b"EVGD";
+const DELTA_STREAM_VERSION: u16 = 1;
+
+// Writes a keyframe snapshot of the grid followed by one frame per step holding only the
+// cells that changed in that step. Layout: magic, version, keyframe length and bytes, then
+// for each frame the number of changed cells followed by each cell's row, column, and encoding.
+pub struct DeltaStreamRecorder<W: Write> {
+    writer: W,
+    // Encoding of every cell as of the last recorded step, and where each cell's encoding starts.
+    encoded_cells: Vec<u8>,
+    cell_offsets: Vec<usize>,
+}
+
+impl<W: Write> DeltaStreamRecorder<W> {
+    pub fn new<C>(mut writer: W, grid: &WorldGrid<C>) -> io::Result<Self>
+    where
+        C: Clone + Debug + GridCell,
+    {
+        let keyframe = grid.encode_snapshot();
+        writer.write_all(DELTA_STREAM_MAGIC)?;
+        writer.write_all(&DELTA_STREAM_VERSION.to_le_bytes())?;
+        writer.write_all(&(keyframe.len() as u64).to_le_bytes())?;
+        writer.write_all(&keyframe)?;
+
+        let (encoded_cells, cell_offsets) = Self::encode_cells(grid, keyframe.len());
+        Ok(Self {
+            writer,
+            encoded_cells,
+            cell_offsets,
+        })
+    }
+
+    pub fn record_step<C>(&mut self, grid: &WorldGrid<C>) -> io::Result<()>
+    where
+        C: Clone + Debug + GridCell,
+    {
+        let (encoded_cells, cell_offsets) = Self::encode_cells(grid, self.encoded_cells.len());
+        let previous_cells = mem::replace(&mut self.encoded_cells, encoded_cells);
+        let previous_offsets = mem::replace(&mut self.cell_offsets, cell_offsets);
+
+        let mut frame = SnapshotWriter::with_capacity(0);
+        let mut num_changes: u32 = 0;
+        let width = grid.size().width as usize;
+        for index in 0..self.cell_offsets.len() {
+            let cell = Self::cell_bytes(&self.encoded_cells, &self.cell_offsets, index);
+            if cell != Self::cell_bytes(&previous_cells, &previous_offsets, index) {
+                frame.write_u32((index / width) as u32);
+                frame.write_u32((index % width) as u32);
+                frame.write_bytes(cell);
+                num_changes += 1;
+            }
+        }
+
+        self.writer.write_all(&num_changes.to_le_bytes())?;
+        self.writer.write_all(&frame.into_bytes())?;
+        self.writer.flush()
+    }
+
+    fn encode_cells<C>(grid: &WorldGrid<C>, capacity: usize) -> (Vec<u8>, Vec<usize>)
+    where
+        C: Clone + Debug + GridCell,
+    {
+        let mut writer = SnapshotWriter::with_capacity(capacity);
+        let mut cell_offsets = Vec::with_capacity(grid.num_cells());
+        for cell in grid.cells_iter() {
+            cell_offsets.push(writer.len());
+            cell.encode(&mut writer);
+        }
+        (writer.into_bytes(), cell_offsets)
+    }
+
+    fn cell_bytes<'a>(encoded_cells: &'a [u8], cell_offsets: &[usize], index: usize) -> &'a [u8] {
+        let end = cell_offsets
+            .get(index + 1)
+            .copied()
+            .unwrap_or(encoded_cells.len());
+        &encoded_cells[cell_offsets[index]..end]
+    }
+}
+
+// Reconstructs the grid as of any recorded step from the bytes of a delta stream.
+pub struct DeltaStream<'a> {
+    keyframe: &'a [u8],
+    frames: Vec<&'a [u8]>,
+}
+
+impl<'a> DeltaStream<'a> {
+    pub fn parse<C>(bytes: &'a [u8]) -> Result<Self, SnapshotError>
+    where
+        C: Clone + Debug + GridCell,
+    {
+        let mut reader = SnapshotReader::new(bytes);
+        let keyframe = Self::read_keyframe(&mut reader)?;
+        let size = WorldGrid::<C>::decode_snapshot(keyframe)?.size();
+
+        let mut frames = vec![];
+        while reader.remaining() > 0 {
+            let frame_start = reader.unread_bytes();
+            let num_changes = reader.read_u32()?;
+            for _ in 0..num_changes {
+                let loc = Loc::new(reader.read_u32()?, reader.read_u32()?);
+                if loc.grid_index(size).is_none() {
+                    return Err(SnapshotError::InvalidValue);
+                }
+                C::decode(&mut reader)?;
+            }
+            let frame_len = frame_start.len() - reader.remaining();
+            frames.push(&frame_start[..frame_len]);
+        }
+        Ok(Self { keyframe, frames })
+    }
+
+    pub fn is_delta_stream(bytes: &[u8]) -> bool {
+        bytes.starts_with(DELTA_STREAM_MAGIC)
+    }
+
+    // The cell format name of the stream's keyframe (see snapshot_format_name).
+    pub fn format_name(bytes: &[u8]) -> Result<String, SnapshotError> {
+        snapshot_format_name(Self::read_keyframe(&mut SnapshotReader::new(bytes))?)
+    }
+
+    fn read_keyframe<'b>(reader: &mut SnapshotReader<'b>) -> Result<&'b [u8], SnapshotError> {
+        if reader.read_bytes(DELTA_STREAM_MAGIC.len())? != DELTA_STREAM_MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let version = reader.read_u16()?;
+        if version != DELTA_STREAM_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let keyframe_len = reader.read_u64()?;
+        reader.read_bytes(usize::try_from(keyframe_len).map_err(|_| SnapshotError::Truncated)?)
+    }
+
+    pub fn num_steps(&self) -> usize {
+        self.frames.len()
+    }
+
+    // Step 0 is the keyframe.
+    pub fn grid_at<C>(&self, step: usize) -> Result<WorldGrid<C>, SnapshotError>
+    where
+        C: Clone + Debug + GridCell,
+    {
+        let mut grid = WorldGrid::<C>::decode_snapshot(self.keyframe)?;
+        for frame in self.frames.iter().take(step) {
+            Self::apply_frame(&mut grid, frame)?;
+        }
+        Ok(grid)
+    }
+
+    // Calls f with the grid as of each step in turn, starting with the keyframe, applying each
+    // frame once rather than replaying them all for each step as grid_at would.
+    pub fn for_each_grid<C, F>(&self, mut f: F) -> Result<(), SnapshotError>
+    where
+        C: Clone + Debug + GridCell,
+        F: FnMut(&WorldGrid<C>),
+    {
+        let mut grid = WorldGrid::<C>::decode_snapshot(self.keyframe)?;
+        f(&grid);
+        for frame in &self.frames {
+            Self::apply_frame(&mut grid, frame)?;
+            f(&grid);
+        }
+        Ok(())
+    }
+
+    fn apply_frame<C>(grid: &mut WorldGrid<C>, frame: &[u8]) -> Result<(), SnapshotError>
+    where
+        C: Clone + Debug + GridCell,
+    {
+        let mut reader = SnapshotReader::new(frame);
+        let num_changes = reader.read_u32()?;
+        for _ in 0..num_changes {
+            let loc = Loc::new(reader.read_u32()?, reader.read_u32()?);
+            let cell = C::decode(&mut reader)?;
+            *grid.cell_mut(loc).ok_or(SnapshotError::InvalidValue)? = cell;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+use crate::snapshot::SnapshotWriter;
+use crate::{EdgeMode, GridCell, GridSize, Loc, WorldGridCells};
+use alloc::vec;
+use alloc::vec::Vec;
+
+// The side of the square tiles that changes are tracked by. Smaller tiles skip more cells
+// but cost more to track.
+const TILE_SIZE: u32 = 8;
+
+// Bits of a tile's dirty neighbors (see DirtyTiles::dirty_neighbors), laid out as in
+// Neighborhood::state_code: a row of three tiles per three bits, from the row above.
+const CENTER_BIT: u16 = 1 << 4;
+const LEFT_COLUMN_BITS: u16 = 0b001_001_001;
+const MIDDLE_COLUMN_BITS: u16 = 0b010_010_010;
+const RIGHT_COLUMN_BITS: u16 = 0b100_100_100;
+const TOP_ROW_BITS: u16 = 0b000_000_111;
+const MIDDLE_ROW_BITS: u16 = 0b000_111_000;
+const BOTTOM_ROW_BITS: u16 = 0b111_000_000;
+
+// Which square tiles of a grid changed in its last update, so that the next update can skip
+// the cells with no changes in their neighborhoods (see WorldGrid::set_skip_quiescent_tiles).
+#[derive(Clone, Debug)]
+pub(crate) struct DirtyTiles {
+    grid_size: GridSize,
+    // The grid's size in tiles, with partial tiles along the bottom and right edges.
+    tiles_size: GridSize,
+    dirty: Vec<bool>,
+}
+
+impl DirtyTiles {
+    pub(crate) fn new(grid_size: GridSize) -> Self {
+        let tiles_size = GridSize::new(
+            grid_size.width.div_ceil(TILE_SIZE),
+            grid_size.height.div_ceil(TILE_SIZE),
+        );
+        Self {
+            grid_size,
+            tiles_size,
+            dirty: vec![true; tiles_size.area()],
+        }
+    }
+
+    // Marks the tiles holding any updated cell that differs between the two grids, via the
+    // cells' snapshot encodings. Cells that weren't updated (see WorldGrid::update_cells) are
+    // taken to be unchanged.
+    pub(crate) fn record_changes<C>(
+        &mut self,
+        cells: &WorldGridCells<C>,
+        next_cells: &WorldGridCells<C>,
+        active: &[bool],
+    ) where
+        C: Clone + Copy + Default + GridCell,
+    {
+        self.dirty.fill(false);
+        let mut writer = SnapshotWriter::with_capacity(32);
+        let mut next_writer = SnapshotWriter::with_capacity(32);
+        let mut cells_match = |cell: &C, next_cell: &C| {
+            writer.clear();
+            next_writer.clear();
+            cell.encode(&mut writer);
+            next_cell.encode(&mut next_writer);
+            writer.as_bytes() == next_writer.as_bytes()
+        };
+        let dirty = &mut self.dirty;
+        Self::for_each_tile_row_span(
+            self.grid_size,
+            self.tiles_size,
+            |_row, index, span_len, tile_index| {
+                if dirty[tile_index] {
+                    return;
+                }
+                let span = index..index + span_len;
+                dirty[tile_index] = active[span.clone()]
+                    .iter()
+                    .zip(&cells.cells[span.clone()])
+                    .zip(&next_cells.cells[span])
+                    .any(|((active, cell), next_cell)| *active && !cells_match(cell, next_cell));
+            },
+        );
+    }
+
+    // Clears the flags of the cells whose neighborhoods can't have changed: those in unchanged
+    // tiles, other than the cells along their edges with changed tiles.
+    pub(crate) fn deactivate_quiescent(&self, active: &mut [bool], edge_mode: EdgeMode) {
+        let dirty_neighbors: Vec<u16> = (0..self.dirty.len())
+            .map(|tile_index| self.dirty_neighbors(tile_index, edge_mode))
+            .collect();
+        let (height, tile_size) = (self.grid_size.height as usize, TILE_SIZE as usize);
+        Self::for_each_tile_row_span(
+            self.grid_size,
+            self.tiles_size,
+            |row, index, span_len, tile_index| {
+                let dirty_neighbors = dirty_neighbors[tile_index];
+                if dirty_neighbors & CENTER_BIT != 0 {
+                    return;
+                }
+                let mut row_bits = MIDDLE_ROW_BITS;
+                if row % tile_size == 0 {
+                    row_bits |= TOP_ROW_BITS;
+                }
+                if row % tile_size == tile_size - 1 || row == height - 1 {
+                    row_bits |= BOTTOM_ROW_BITS;
+                }
+                for (col, active) in active[index..index + span_len].iter_mut().enumerate() {
+                    let mut col_bits = MIDDLE_COLUMN_BITS;
+                    if col == 0 {
+                        col_bits |= LEFT_COLUMN_BITS;
+                    }
+                    if col == span_len - 1 {
+                        col_bits |= RIGHT_COLUMN_BITS;
+                    }
+                    *active &= dirty_neighbors & row_bits & col_bits != 0;
+                }
+            },
+        );
+    }
+
+    // Which of the 3x3 block of tiles centered on a tile changed, as bits in the layout of
+    // Neighborhood::state_code.
+    fn dirty_neighbors(&self, tile_index: usize, edge_mode: EdgeMode) -> u16 {
+        let tiles_width = self.tiles_size.width;
+        let tile_loc = Loc::new(
+            tile_index as u32 / tiles_width,
+            tile_index as u32 % tiles_width,
+        );
+        let mut result = 0;
+        for row_offset in -1..=1 {
+            for col_offset in -1..=1 {
+                if edge_mode
+                    .offset_loc(tile_loc, row_offset, col_offset, self.tiles_size)
+                    .and_then(|loc| loc.grid_index(self.tiles_size))
+                    .is_some_and(|index| self.dirty[index])
+                {
+                    result |= 1 << ((row_offset + 1) * 3 + (col_offset + 1));
+                }
+            }
+        }
+        result
+    }
+
+    // Passes the row, grid index, and length of each tile's span of each grid row, with the
+    // tile's index, in row-major order.
+    fn for_each_tile_row_span<F>(grid_size: GridSize, tiles_size: GridSize, mut f: F)
+    where
+        F: FnMut(usize, usize, usize, usize),
+    {
+        let (width, tile_size) = (grid_size.width as usize, TILE_SIZE as usize);
+        for row in 0..grid_size.height as usize {
+            let first_tile_index = row / tile_size * tiles_size.width as usize;
+            for (tile_col, left) in (0..width).step_by(tile_size).enumerate() {
+                let span_len = tile_size.min(width - left);
+                f(
+                    row,
+                    row * width + left,
+                    span_len,
+                    first_tile_index + tile_col,
+                );
+            }
+        }
+    }
+}
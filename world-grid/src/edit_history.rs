@@ -0,0 +1,147 @@
+use crate::snapshot::{SnapshotReader, SnapshotWriter};
+use crate::{GridCell, Loc, WorldGrid};
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+// Edits kept for undoing. Older ones are dropped.
+const MAX_UNDO_EDITS: usize = 100;
+
+// The cells that one interactive edit changed, with their encoded states (see CellCodec)
+// before and after it, so that the edit can be undone and redone without keeping copies of
+// the whole grid.
+#[derive(Clone, Debug, Default)]
+pub struct GridEdit {
+    cells: Vec<CellEdit>,
+}
+
+#[derive(Clone, Debug)]
+struct CellEdit {
+    loc: Loc,
+    before: Vec<u8>,
+    after: Vec<u8>,
+}
+
+impl GridEdit {
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    // Folds a later edit into this one, so that they undo together.
+    fn merge(&mut self, later: GridEdit) {
+        for cell in later.cells {
+            match self
+                .cells
+                .iter_mut()
+                .find(|earlier| earlier.loc == cell.loc)
+            {
+                Some(earlier) => earlier.after = cell.after,
+                None => self.cells.push(cell),
+            }
+        }
+    }
+}
+
+// Undo and redo stacks of edits. An edit can be left open, e.g. while a paint stroke is still
+// being dragged, so that the edits after it merge into it until it's closed.
+#[derive(Default)]
+pub struct EditHistory {
+    undo: VecDeque<GridEdit>,
+    redo: Vec<GridEdit>,
+    open: bool,
+}
+
+impl EditHistory {
+    pub fn push(&mut self, edit: GridEdit, open: bool) {
+        if edit.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        match self.undo.back_mut() {
+            Some(last) if self.open => last.merge(edit),
+            _ => {
+                self.undo.push_back(edit);
+                if self.undo.len() > MAX_UNDO_EDITS {
+                    self.undo.pop_front();
+                }
+            }
+        }
+        self.open = open;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.open = false;
+    }
+
+    pub fn undo<C: GridCell>(&mut self, grid: &mut WorldGrid<C>) -> bool {
+        self.open = false;
+        let Some(edit) = self.undo.pop_back() else {
+            return false;
+        };
+        grid.restore_cells(&edit, true);
+        self.redo.push(edit);
+        true
+    }
+
+    pub fn redo<C: GridCell>(&mut self, grid: &mut WorldGrid<C>) -> bool {
+        self.open = false;
+        let Some(edit) = self.redo.pop() else {
+            return false;
+        };
+        grid.restore_cells(&edit, false);
+        self.undo.push_back(edit);
+        true
+    }
+}
+
+impl<C> WorldGrid<C>
+where
+    C: Clone + Debug + GridCell,
+{
+    // Starts recording the states of the cells changed via cell_mut, until end_edit.
+    pub fn begin_edit(&mut self) {
+        self.edit_before = Some(vec![]);
+    }
+
+    pub fn end_edit(&mut self) -> GridEdit {
+        let mut before = self.edit_before.take().unwrap_or_default();
+        // Keeps each cell's state from before its first change.
+        before.sort_by_key(|(loc, _)| (loc.row, loc.col));
+        before.dedup_by_key(|(loc, _)| *loc);
+        let cells = before
+            .into_iter()
+            .map(|(loc, cell)| CellEdit {
+                loc,
+                before: Self::encode_cell(&cell),
+                after: Self::encode_cell(&self.cells[loc]),
+            })
+            .filter(|cell| cell.before != cell.after)
+            .collect();
+        GridEdit { cells }
+    }
+
+    fn encode_cell(cell: &C) -> Vec<u8> {
+        let mut writer = SnapshotWriter::with_capacity(1);
+        cell.encode(&mut writer);
+        writer.into_bytes()
+    }
+
+    fn restore_cells(&mut self, edit: &GridEdit, before: bool) {
+        for cell in &edit.cells {
+            let bytes = if before { &cell.before } else { &cell.after };
+            self.cells[cell.loc] = C::decode(&mut SnapshotReader::new(bytes)).unwrap();
+        }
+        self.mark_all_changed();
+    }
+}
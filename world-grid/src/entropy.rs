@@ -0,0 +1,51 @@
+use crate::snapshot::SnapshotWriter;
+use crate::{GridCell, WorldGrid};
+use std::collections::BTreeMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+
+impl<C> WorldGrid<C>
+where
+    C: Clone + Debug + GridCell,
+{
+    // Spatial block entropy: the Shannon entropy of the distribution of patterns of cell states
+    // in the grid's whole, non-overlapping block_size x block_size blocks, in bits per cell.
+    // A cell's state is its snapshot encoding. Random soup scores high and ordered structure
+    // low, so this tracks the transition from one to the other.
+    pub fn block_entropy(&self, block_size: u32) -> f64 {
+        let size = self.size();
+        let (blocks_down, blocks_across) = (size.height / block_size, size.width / block_size);
+        let num_blocks = blocks_down as usize * blocks_across as usize;
+        if num_blocks == 0 {
+            return 0.0;
+        }
+
+        let cells = self.cells.cells_iter().as_slice();
+        // Ordered, so that the sum below is the same from run to run.
+        let mut pattern_counts: BTreeMap<u64, u32> = BTreeMap::new();
+        let mut writer = SnapshotWriter::with_capacity(32);
+        for block_row in 0..blocks_down {
+            for block_col in 0..blocks_across {
+                for row in block_row * block_size..(block_row + 1) * block_size {
+                    for col in block_col * block_size..(block_col + 1) * block_size {
+                        cells[(row * size.width + col) as usize].encode(&mut writer);
+                    }
+                }
+                let mut hasher = DefaultHasher::new();
+                writer.as_bytes().hash(&mut hasher);
+                *pattern_counts.entry(hasher.finish()).or_insert(0) += 1;
+                writer.clear();
+            }
+        }
+
+        let entropy: f64 = pattern_counts
+            .values()
+            .map(|count| {
+                let probability = *count as f64 / num_blocks as f64;
+                -probability * probability.log2()
+            })
+            .sum();
+        entropy / (block_size * block_size) as f64
+    }
+}
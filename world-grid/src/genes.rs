@@ -0,0 +1,86 @@
+use crate::{BitSet8, GridCell};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// How a gene's value reads, e.g. in the inspector and exports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GeneFormat {
+    // A number from 0 to 255, e.g. a color channel.
+    Number,
+    // The eight bits, highest first, e.g. "00101100".
+    Bits,
+    // A set of neighbor counts from 1 to 8, one per bit, e.g. "[2,3]".
+    NeighborCounts,
+}
+
+impl GeneFormat {
+    pub fn format(&self, value: u8) -> String {
+        match self {
+            Self::Number => value.to_string(),
+            Self::Bits => format!("{:08b}", value),
+            Self::NeighborCounts => {
+                let counts: Vec<String> = BitSet8::new(value)
+                    .iter()
+                    .map(|index| (index + 1).to_string())
+                    .collect();
+                format!("[{}]", counts.join(","))
+            }
+        }
+    }
+}
+
+// One of a creature's 8-bit genes: its name, its bit offset within the packed genome (see
+// GridCell::genome), and how its value reads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GeneDescriptor {
+    pub name: &'static str,
+    pub shift: u32,
+    pub format: GeneFormat,
+}
+
+impl GeneDescriptor {
+    pub const fn new(name: &'static str, shift: u32, format: GeneFormat) -> Self {
+        Self {
+            name,
+            shift,
+            format,
+        }
+    }
+
+    pub fn value(&self, genome: u64) -> u8 {
+        (genome >> self.shift) as u8
+    }
+
+    pub fn with_value(&self, genome: u64, value: u8) -> u64 {
+        (genome & !(0xff << self.shift)) | ((value as u64) << self.shift)
+    }
+
+    pub fn format_value(&self, value: u8) -> String {
+        self.format.format(value)
+    }
+
+    // The descriptor of the gene with the given name, or an error listing the genes.
+    pub fn find<C: GridCell>(name: &str) -> Result<&'static Self, String> {
+        C::genes()
+            .iter()
+            .find(|gene| gene.name == name)
+            .ok_or_else(|| {
+                let names: Vec<_> = C::genes().iter().map(|gene| gene.name).collect();
+                format!(
+                    "Unknown gene {}; expected one of [{}]",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+
+    // Each gene of the genome as "name=value", e.g. "red=255 green=0 blue=12".
+    pub fn describe_genome<C: GridCell>(genome: u64) -> String {
+        C::genes()
+            .iter()
+            .map(|gene| format!("{}={}", gene.name, gene.format_value(gene.value(genome))))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
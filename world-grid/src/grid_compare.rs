@@ -0,0 +1,107 @@
+use crate::snapshot::SnapshotWriter;
+use crate::{GridCell, GridSize, Loc, WorldGrid, WorldGridCells};
+use core::error::Error;
+use core::fmt::{self, Debug, Display, Formatter};
+
+// The first difference found between two grids, in row-major order.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GridDifference {
+    Size(GridSize, GridSize),
+    Cell(Loc),
+    Field {
+        loc: Loc,
+        field: &'static str,
+        values: (f32, f32),
+    },
+}
+
+impl Display for GridDifference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Size(size, other_size) => write!(
+                f,
+                "grid sizes differ: {}x{} vs. {}x{}",
+                size.width, size.height, other_size.width, other_size.height
+            ),
+            Self::Cell(loc) => write!(f, "cells at ({}, {}) differ", loc.row, loc.col),
+            Self::Field { loc, field, values } => write!(
+                f,
+                "{} at ({}, {}) differs: {} vs. {}",
+                field, loc.row, loc.col, values.0, values.1
+            ),
+        }
+    }
+}
+
+impl Error for GridDifference {}
+
+impl<C> WorldGridCells<C>
+where
+    C: Clone + Copy + Default + GridCell,
+{
+    // Compares every cell's full state, via its snapshot encoding.
+    pub fn compare_exact(&self, other: &Self) -> Result<(), GridDifference> {
+        self.check_same_size(other)?;
+        for (index, (cell, other_cell)) in self.cells.iter().zip(&other.cells).enumerate() {
+            if !Self::cells_match(cell, other_cell) {
+                return Err(GridDifference::Cell(self.index_loc(index)));
+            }
+        }
+        Ok(())
+    }
+
+    fn cells_match(cell: &C, other_cell: &C) -> bool {
+        let mut writer = SnapshotWriter::with_capacity(32);
+        cell.encode(&mut writer);
+        let cell_len = writer.len();
+        other_cell.encode(&mut writer);
+        let bytes = writer.into_bytes();
+        bytes[..cell_len] == bytes[cell_len..]
+    }
+
+    // Compares the cells' exported fields (see GridCell::field_names), allowing each field to
+    // differ by up to its GridCell::field_tolerance, e.g. to absorb f32 rounding differences
+    // between backends.
+    pub fn compare_approx(&self, other: &Self) -> Result<(), GridDifference> {
+        self.check_same_size(other)?;
+        for (index, (cell, other_cell)) in self.cells.iter().zip(&other.cells).enumerate() {
+            for (field, name) in C::field_names().iter().enumerate() {
+                let values = (cell.field_value(field), other_cell.field_value(field));
+                if (values.0 - values.1).abs() > C::field_tolerance(field) {
+                    return Err(GridDifference::Field {
+                        loc: self.index_loc(index),
+                        field: name,
+                        values,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_same_size(&self, other: &Self) -> Result<(), GridDifference> {
+        if self.size == other.size {
+            Ok(())
+        } else {
+            Err(GridDifference::Size(self.size, other.size))
+        }
+    }
+
+    fn index_loc(&self, index: usize) -> Loc {
+        let width = self.size.width as usize;
+        Loc::new((index / width) as u32, (index % width) as u32)
+    }
+}
+
+impl<C> WorldGrid<C>
+where
+    C: Clone + Debug + GridCell,
+{
+    pub fn compare_exact(&self, other: &Self) -> Result<(), GridDifference> {
+        self.cells.compare_exact(&other.cells)
+    }
+
+    pub fn compare_approx(&self, other: &Self) -> Result<(), GridDifference> {
+        self.cells.compare_approx(&other.cells)
+    }
+}
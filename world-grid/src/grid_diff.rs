@@ -0,0 +1,117 @@
+use crate::grid_compare::GridDifference;
+use crate::snapshot::SnapshotWriter;
+use crate::{GridCell, GridSize, WorldGrid};
+use std::fmt::Debug;
+use std::io::{self, Write};
+
+// Aggregate differences between two grids of the same size, e.g. snapshots of one run at two
+// steps or of two runs from the same start.
+#[derive(Clone, Debug)]
+pub struct GridDiff {
+    pub size: GridSize,
+    // Creatures (see GridCell::genome) in each grid.
+    pub populations: (usize, usize),
+    // For each gene (see GridCell::genes), the fraction of creatures with each of its bits
+    // set, from the lowest bit, in each grid.
+    pub bit_frequencies: Vec<([f64; 8], [f64; 8])>,
+    // Total substance (see GridCell::substance_amount) in each grid.
+    pub substance_masses: (f64, f64),
+    // Row-major flags for whether each cell's full state differs.
+    pub changed_cells: Vec<bool>,
+}
+
+impl GridDiff {
+    pub fn num_changed(&self) -> usize {
+        self.changed_cells
+            .iter()
+            .filter(|changed| **changed)
+            .count()
+    }
+
+    // Writes a grayscale image in binary PGM format with one pixel per block of
+    // block_size x block_size cells, from black where none of its cells changed to white
+    // where all did.
+    pub fn write_density_pgm<W: Write>(&self, mut writer: W, block_size: u32) -> io::Result<()> {
+        let block_size = block_size.max(1);
+        let (width, height) = (
+            self.size.width.div_ceil(block_size),
+            self.size.height.div_ceil(block_size),
+        );
+        let mut changed = vec![0u32; (width * height) as usize];
+        let mut cells = vec![0u32; (width * height) as usize];
+        for (index, cell_changed) in self.changed_cells.iter().enumerate() {
+            let (row, col) = (
+                index as u32 / self.size.width,
+                index as u32 % self.size.width,
+            );
+            let block = ((row / block_size) * width + col / block_size) as usize;
+            cells[block] += 1;
+            changed[block] += *cell_changed as u32;
+        }
+        write!(writer, "P5\n{} {}\n255\n", width, height)?;
+        let pixels: Vec<u8> = changed
+            .iter()
+            .zip(&cells)
+            .map(|(changed, cells)| (*changed as f64 / *cells as f64 * 255.0).round() as u8)
+            .collect();
+        writer.write_all(&pixels)
+    }
+}
+
+impl<C> WorldGrid<C>
+where
+    C: Clone + Debug + GridCell,
+{
+    pub fn diff(&self, other: &Self) -> Result<GridDiff, GridDifference> {
+        if self.size() != other.size() {
+            return Err(GridDifference::Size(self.size(), other.size()));
+        }
+        let changed_cells = self
+            .cells_iter()
+            .zip(other.cells_iter())
+            .map(|(cell, other_cell)| Self::encode(cell) != Self::encode(other_cell))
+            .collect();
+        Ok(GridDiff {
+            size: self.size(),
+            populations: (self.population(), other.population()),
+            bit_frequencies: self
+                .bit_frequencies()
+                .into_iter()
+                .zip(other.bit_frequencies())
+                .collect(),
+            substance_masses: (self.substance_mass(), other.substance_mass()),
+            changed_cells,
+        })
+    }
+
+    fn encode(cell: &C) -> Vec<u8> {
+        let mut writer = SnapshotWriter::with_capacity(1);
+        cell.encode(&mut writer);
+        writer.into_bytes()
+    }
+
+    fn population(&self) -> usize {
+        self.cells_iter()
+            .filter(|cell| cell.genome().is_some())
+            .count()
+    }
+
+    fn bit_frequencies(&self) -> Vec<[f64; 8]> {
+        let genes = C::genes();
+        let mut counts = vec![[0usize; 8]; genes.len()];
+        let mut population = 0;
+        for genome in self.cells_iter().filter_map(|cell| cell.genome()) {
+            population += 1;
+            for (gene, gene_counts) in genes.iter().zip(counts.iter_mut()) {
+                let value = gene.value(genome);
+                for (bit, count) in gene_counts.iter_mut().enumerate() {
+                    *count += (value >> bit) as usize & 1;
+                }
+            }
+        }
+        counts
+            .iter()
+            .map(|gene_counts| gene_counts.map(|count| count as f64 / population.max(1) as f64))
+            .collect()
+    }
+}
@@ -0,0 +1,52 @@
+use crate::{GridSize, Loc};
+use core::error::Error;
+use core::fmt::{self, Display, Formatter};
+
+// Bytes per mebibyte, for reporting memory sizes.
+const MIB: u64 = 1 << 20;
+
+// Why a grid couldn't be made or a cell reached, e.g. from coordinates that came from mouse
+// input, a config file, or a network command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GridError {
+    EmptySize(GridSize),
+    OutOfBounds {
+        loc: Loc,
+        size: GridSize,
+    },
+    // The grid's cells would take more bytes than the budget (see set_grid_memory_budget).
+    OverMemoryBudget {
+        size: GridSize,
+        bytes: u64,
+        budget: u64,
+    },
+}
+
+impl Display for GridError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptySize(size) => {
+                write!(f, "grid size {}x{} is empty", size.width, size.height)
+            }
+            Self::OutOfBounds { loc, size } => write!(
+                f,
+                "({}, {}) is outside the {}x{} grid",
+                loc.row, loc.col, size.width, size.height
+            ),
+            Self::OverMemoryBudget {
+                size,
+                bytes,
+                budget,
+            } => write!(
+                f,
+                "a {}x{} grid needs {} MiB, over the {} MiB memory budget",
+                size.width,
+                size.height,
+                bytes.div_ceil(MIB),
+                budget / MIB
+            ),
+        }
+    }
+}
+
+impl Error for GridError {}
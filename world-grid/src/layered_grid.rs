@@ -0,0 +1,285 @@
+use crate::{GridCell, GridSettings, GridSize, Loc, Neighborhood, Random, WorldGrid, alpha_blend};
+use std::any::Any;
+use std::fmt::Debug;
+
+// A cell type that can make up a layer of a LayeredWorldGrid. Its updates can also look at
+// the layers below, e.g. creatures at the substances they feed on. Cells that only look at
+// their own layer can keep the default, GridCell::update.
+pub trait LayerCell: GridCell + 'static {
+    // Whether the cells never change on their own, e.g. terrain, so that updates can skip
+    // their layers.
+    const IS_STATIC: bool = false;
+
+    // Whether updates must also reach the cells over, or next to, the cells of the layers
+    // below that aren't inert, e.g. empty substance cells over a source. Cells that change
+    // only near their own layer's cells that aren't inert, e.g. creatures, can leave them out.
+    const UPDATES_OVER_BELOW: bool = true;
+
+    fn update_over(
+        &self,
+        neighborhood: &Neighborhood<Self>,
+        _below: &LayersBelow,
+        next_cell: &mut Self,
+        rand: &mut Option<Random>,
+    ) {
+        self.update(neighborhood, next_cell, rand);
+    }
+}
+
+// A layer of a LayeredWorldGrid: a grid of some cell type, with the type erased so that
+// layers of different types can be held together, as AnyWorld does for worlds. Every
+// WorldGrid of LayerCells is a GridLayer.
+pub trait GridLayer: Debug + Send + Sync {
+    fn size(&self) -> GridSize;
+
+    // Also updates the cells over, or next to, the cells of the layers below that aren't
+    // inert, unless the cells opt out (see LayerCell::UPDATES_OVER_BELOW).
+    fn update(&mut self, below: &LayersBelow, rand: &mut Option<Random>);
+
+    fn clear(&mut self);
+    fn color_rgba(&self, loc: Loc) -> [u8; 4];
+    fn set_step(&mut self, step: u64);
+    fn set_pinned_locs(&mut self, locs: &[Loc]);
+    fn apply_settings(&mut self, settings: &GridSettings);
+
+    // Flags the cells that aren't inert, and their neighbors, as WorldGrid::update does.
+    fn mark_active(&self, active: &mut [bool]);
+
+    fn clone_box(&self) -> Box<dyn GridLayer>;
+
+    // For getting the layer's grid back as its own type (see LayeredWorldGrid::layer).
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<C: LayerCell> GridLayer for WorldGrid<C> {
+    fn size(&self) -> GridSize {
+        WorldGrid::size(self)
+    }
+
+    fn update(&mut self, below: &LayersBelow, rand: &mut Option<Random>) {
+        if C::IS_STATIC {
+            self.step += 1;
+            return;
+        }
+        let mut active = vec![false; self.num_cells()];
+        if C::UPDATES_OVER_BELOW {
+            for (_name, layer) in below.layers {
+                layer.mark_active(&mut active);
+            }
+        }
+        self.update_with_active(
+            rand,
+            |_grid| {},
+            |cell, neighborhood, next_cell, rand| {
+                cell.update_over(neighborhood, below, next_cell, rand)
+            },
+            active,
+        );
+    }
+
+    fn clear(&mut self) {
+        WorldGrid::clear(self);
+    }
+
+    fn color_rgba(&self, loc: Loc) -> [u8; 4] {
+        self.cells[loc].color_rgba()
+    }
+
+    fn set_step(&mut self, step: u64) {
+        self.step = step;
+    }
+
+    fn set_pinned_locs(&mut self, locs: &[Loc]) {
+        self.pinned_locs.clear();
+        for loc in locs {
+            self.set_pinned(*loc, true);
+        }
+    }
+
+    fn apply_settings(&mut self, settings: &GridSettings) {
+        settings.apply(self);
+    }
+
+    fn mark_active(&self, active: &mut [bool]) {
+        self.cells.mark_active(active, self.edge_mode);
+    }
+
+    fn clone_box(&self) -> Box<dyn GridLayer> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// The layers below the one being updated, as of their own updates this step.
+pub struct LayersBelow<'a> {
+    layers: &'a [(&'static str, Box<dyn GridLayer>)],
+}
+
+impl<'a> LayersBelow<'a> {
+    // For updating a layer on its own, e.g. via GridCell::update.
+    pub fn none() -> Self {
+        Self { layers: &[] }
+    }
+
+    // The grid of the nearest layer below with cells of type C, if any.
+    pub fn grid<C: LayerCell>(&self) -> Option<&'a WorldGrid<C>> {
+        self.layers
+            .iter()
+            .rev()
+            .find_map(|(_name, layer)| layer.as_any().downcast_ref())
+    }
+
+    // The neighborhood in that grid centered on the same cell as the given one, e.g. for
+    // looking at the cells under a cell's neighbors.
+    pub fn neighborhood<C: LayerCell, D: GridCell>(
+        &self,
+        over: &Neighborhood<D>,
+    ) -> Option<Neighborhood<'a, C>> {
+        let grid = self.grid::<C>()?;
+        Some(Neighborhood::new(&grid.cells, over.center, over.step).with_edge_mode(grid.edge_mode))
+    }
+
+    // The cell in that grid under the given neighborhood's center.
+    pub fn cell<C: LayerCell, D: GridCell>(&self, over: &Neighborhood<D>) -> Option<&'a C> {
+        Some(&self.grid::<C>()?.cells[over.center])
+    }
+}
+
+// Several grids of the same size, each with its own cell type, e.g. terrain, substances, and
+// creatures, so that each layer's cells and rules can change without touching the others.
+// The layers are kept bottom first, in the order they were added, and are updated in that
+// order, each from its own cells and those of the layers below (see LayerCell). Each cell's
+// color is the layers' colors alpha-blended from the bottom up. Unlike LayeredWorld, which
+// runs two whole worlds, this is one world's grid, and the world reaches into the layers by
+// name to seed or edit them.
+#[derive(Debug)]
+pub struct LayeredWorldGrid {
+    size: GridSize,
+    layers: Vec<(&'static str, Box<dyn GridLayer>)>,
+}
+
+impl LayeredWorldGrid {
+    pub fn new(size: GridSize) -> Self {
+        Self {
+            size,
+            layers: vec![],
+        }
+    }
+
+    // Adds a layer on top of the others.
+    pub fn with_layer<C: LayerCell>(mut self, name: &'static str, grid: WorldGrid<C>) -> Self {
+        assert_eq!(grid.size(), self.size, "Layer {} is the wrong size", name);
+        assert!(
+            self.layer_index(name).is_none(),
+            "Duplicate layer name {}",
+            name
+        );
+        self.layers.push((name, Box::new(grid)));
+        self
+    }
+
+    pub fn size(&self) -> GridSize {
+        self.size
+    }
+
+    // Bottom first.
+    pub fn layer_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.layers.iter().map(|(name, _)| *name)
+    }
+
+    // The named layer's grid, if it has that cell type.
+    pub fn layer<C: LayerCell>(&self, name: &str) -> Option<&WorldGrid<C>> {
+        let index = self.layer_index(name)?;
+        self.layers[index].1.as_any().downcast_ref()
+    }
+
+    pub fn layer_mut<C: LayerCell>(&mut self, name: &str) -> Option<&mut WorldGrid<C>> {
+        let index = self.layer_index(name)?;
+        self.layers[index].1.as_any_mut().downcast_mut()
+    }
+
+    // Updates each layer in turn, bottom first.
+    pub fn update(&mut self, rand: &mut Option<Random>) {
+        for index in 0..self.layers.len() {
+            self.update_layer(index, rand);
+        }
+    }
+
+    // Updates just the layer at the given index, bottom first, e.g. to run an update a layer
+    // at a time (see World::update_phase).
+    pub fn update_layer(&mut self, index: usize, rand: &mut Option<Random>) {
+        let (below, layers) = self.layers.split_at_mut(index);
+        layers[0].1.update(&LayersBelow { layers: below }, rand);
+    }
+
+    pub fn clear(&mut self) {
+        for (_name, layer) in &mut self.layers {
+            layer.clear();
+        }
+    }
+
+    // Sets every layer's step count, e.g. to match a grid restored from a snapshot.
+    pub fn set_step(&mut self, step: u64) {
+        for (_name, layer) in &mut self.layers {
+            layer.set_step(step);
+        }
+    }
+
+    // Pins the given cells in every layer, and unpins the rest (see WorldGrid::set_pinned).
+    pub fn set_pinned_locs(&mut self, locs: &[Loc]) {
+        for (_name, layer) in &mut self.layers {
+            layer.set_pinned_locs(locs);
+        }
+    }
+
+    pub fn apply_settings(&mut self, settings: &GridSettings) {
+        for (_name, layer) in &mut self.layers {
+            layer.apply_settings(settings);
+        }
+    }
+
+    // The layers' colors at the cell, alpha-blended from the bottom up.
+    pub fn color_rgba(&self, loc: Loc) -> [u8; 4] {
+        self.layers
+            .iter()
+            .fold([0, 0, 0, 0], |below, (_name, layer)| {
+                alpha_blend(layer.color_rgba(loc), below)
+            })
+    }
+
+    // Writes each cell's composited color to rgba, four bytes per cell, in row-major order.
+    pub fn write_rgba(&self, rgba: &mut [u8]) {
+        let width = self.size.width;
+        for (index, pixel) in rgba.chunks_exact_mut(4).enumerate() {
+            let loc = Loc::new(index as u32 / width, index as u32 % width);
+            pixel.copy_from_slice(&self.color_rgba(loc));
+        }
+    }
+
+    fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layers
+            .iter()
+            .position(|(layer_name, _)| *layer_name == name)
+    }
+}
+
+impl Clone for LayeredWorldGrid {
+    fn clone(&self) -> Self {
+        Self {
+            size: self.size,
+            layers: self
+                .layers
+                .iter()
+                .map(|(name, layer)| (*name, layer.clone_box()))
+                .collect(),
+        }
+    }
+}
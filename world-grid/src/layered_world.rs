@@ -0,0 +1,183 @@
+use crate::snapshot::{CellCodec, SnapshotError, SnapshotReader, SnapshotWriter};
+use crate::{
+    GridCell, GridSettings, Neighborhood, Random, StepStats, World, WorldGrid, WorldMetric,
+    alpha_blend,
+};
+
+// Runs two independent worlds on grids of the same size, showing the top world's colors
+// alpha-blended over the bottom world's, e.g. a Conway layer over a substance layer. The
+// layered grid only holds the two colors of each cell, so the layers can't be edited, though
+// their grid settings can be.
+pub struct LayeredWorld<A: World, B: World> {
+    top: A,
+    bottom: B,
+    grid: WorldGrid<LayeredCell>,
+    // Each layer updates only every so many steps (see with_update_periods).
+    update_periods: [u64; 2],
+    step: u64,
+}
+
+impl<A: World, B: World> LayeredWorld<A, B> {
+    pub fn new(top: A, bottom: B) -> Self {
+        let size = top.grid().size();
+        assert_eq!(size, bottom.grid().size(), "Layer grid sizes differ");
+        let mut result = Self {
+            top,
+            bottom,
+            grid: WorldGrid::new(size),
+            update_periods: [1, 1],
+            step: 0,
+        };
+        result.composite();
+        result
+    }
+
+    // Makes the top layer update only every top_period steps, and the bottom layer every
+    // bottom_period steps, e.g. so a slow layer doesn't take the time of a fast one.
+    pub fn with_update_periods(mut self, top_period: u64, bottom_period: u64) -> Self {
+        self.update_periods = [top_period.max(1), bottom_period.max(1)];
+        self
+    }
+
+    pub fn top(&self) -> &A {
+        &self.top
+    }
+
+    pub fn bottom(&self) -> &B {
+        &self.bottom
+    }
+
+    fn composite(&mut self) {
+        for ((cell, top_cell), bottom_cell) in self
+            .grid
+            .cells
+            .cells_iter_mut()
+            .zip(self.top.grid().cells_iter())
+            .zip(self.bottom.grid().cells_iter())
+        {
+            cell.top_color = top_cell.color_rgba();
+            cell.bottom_color = bottom_cell.color_rgba();
+        }
+        self.grid.mark_all_changed();
+    }
+}
+
+impl<A: World, B: World> World for LayeredWorld<A, B> {
+    fn grid(&self) -> &WorldGrid<impl GridCell> {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut WorldGrid<impl GridCell> {
+        &mut self.grid
+    }
+
+    fn update(&mut self) {
+        let [top_period, bottom_period] = self.update_periods;
+        let update_top = self.step.is_multiple_of(top_period);
+        let update_bottom = self.step.is_multiple_of(bottom_period);
+        if update_top {
+            self.top.update();
+        }
+        if update_bottom {
+            self.bottom.update();
+        }
+        if update_top || update_bottom {
+            self.composite();
+        }
+        self.step += 1;
+    }
+
+    fn reset(&mut self) {
+        self.top.reset();
+        self.bottom.reset();
+        self.step = 0;
+        self.composite();
+    }
+
+    fn metrics(&self) -> Vec<WorldMetric> {
+        let mut result = self.top.metrics();
+        result.extend(self.bottom.metrics());
+        result
+    }
+
+    fn generation(&self) -> u64 {
+        self.step
+    }
+
+    // Each layer's live cells count as one of the layered world's totals.
+    fn step_stats(&self) -> StepStats {
+        let top = self.top.step_stats();
+        let bottom = self.bottom.step_stats();
+        let mut result = StepStats {
+            generation: self.step,
+            live_cells: top.live_cells + bottom.live_cells,
+            layer_totals: vec![],
+        }
+        .with_layer_total("top_live_cells", top.live_cells as f64)
+        .with_layer_total("bottom_live_cells", bottom.live_cells as f64);
+        result.layer_totals.extend(top.layer_totals);
+        result.layer_totals.extend(bottom.layer_totals);
+        result
+    }
+
+    fn apply_grid_settings(&mut self, settings: &GridSettings) {
+        self.top.apply_grid_settings(settings);
+        self.bottom.apply_grid_settings(settings);
+    }
+
+    fn is_editable(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LayeredCell {
+    top_color: [u8; 4],
+    bottom_color: [u8; 4],
+}
+
+impl CellCodec for LayeredCell {
+    const FORMAT_NAME: &'static str = "layered";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_bytes(&self.top_color);
+        writer.write_bytes(&self.bottom_color);
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        Ok(Self {
+            top_color: reader.read_array()?,
+            bottom_color: reader.read_array()?,
+        })
+    }
+}
+
+impl GridCell for LayeredCell {
+    fn color_rgba(&self) -> [u8; 4] {
+        alpha_blend(self.top_color, self.bottom_color)
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    // The layers update themselves; see LayeredWorld::update.
+    fn update(
+        &self,
+        _neighborhood: &Neighborhood<LayeredCell>,
+        _next_cell: &mut LayeredCell,
+        _rand: &mut Option<Random>,
+    ) {
+    }
+
+    fn debug_print(&self, row: u32, col: u32) {
+        println!(
+            "({}, {}): Top color: {:?}, Bottom color: {:?}",
+            row, col, self.top_color, self.bottom_color
+        );
+    }
+
+    fn is_inert(&self) -> bool {
+        true
+    }
+}
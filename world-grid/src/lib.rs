@@ -1,22 +1,417 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
-
+// Without std, only the core types build (see the features in Cargo.toml).
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod any_world;
+#[cfg(feature = "std")]
+mod cell_history;
+#[cfg(feature = "std")]
+mod census;
+#[cfg(feature = "std")]
+mod delta_stream;
+mod dirty_tiles;
+mod edit_history;
+#[cfg(feature = "std")]
+mod entropy;
+mod genes;
+mod grid_compare;
+#[cfg(feature = "std")]
+mod grid_diff;
+mod grid_error;
+#[cfg(feature = "std")]
+mod layered_grid;
+#[cfg(feature = "std")]
+mod layered_world;
+#[cfg(feature = "std")]
+mod life_events;
+#[cfg(feature = "std")]
+mod lineage;
+#[cfg(feature = "std")]
+mod macrocell;
+#[cfg(feature = "std")]
+mod npy;
+#[cfg(feature = "std")]
+mod pattern;
+#[cfg(feature = "std")]
+mod png_image;
+#[cfg(feature = "std")]
+mod replicate_world;
+#[cfg(feature = "std")]
+mod rle;
+#[cfg(feature = "std")]
+mod seed_bank;
+#[cfg(feature = "std")]
+mod selection;
+mod snapshot;
+#[cfg(feature = "std")]
+mod spectrum;
+#[cfg(feature = "std")]
+pub mod strict_math;
+#[cfg(feature = "std")]
+mod transitions;
+
+#[cfg(feature = "std")]
+pub use any_world::{AnyWorld, DynWorld, ErasedCell};
+#[cfg(feature = "std")]
+pub use cell_history::CellHistory;
+#[cfg(feature = "std")]
+pub use census::{FoundObject, ObjectCensus, ObjectKind};
+#[cfg(feature = "std")]
+pub use delta_stream::{DeltaStream, DeltaStreamRecorder};
+pub use edit_history::{EditHistory, GridEdit};
+pub use genes::{GeneDescriptor, GeneFormat};
+pub use grid_compare::GridDifference;
+#[cfg(feature = "std")]
+pub use grid_diff::GridDiff;
+pub use grid_error::GridError;
+#[cfg(feature = "std")]
+pub use layered_grid::{GridLayer, LayerCell, LayeredWorldGrid, LayersBelow};
+#[cfg(feature = "std")]
+pub use layered_world::{LayeredCell, LayeredWorld};
+#[cfg(feature = "std")]
+pub use life_events::{LifeEvent, LifeEventDetector, LifeEventKind};
+#[cfg(feature = "std")]
+pub use lineage::{LineageNode, LineageTree};
+#[cfg(feature = "std")]
+pub use npy::write_npy_f32;
+#[cfg(feature = "std")]
+pub use pattern::Pattern;
+#[cfg(feature = "std")]
+pub use png_image::write_png;
+#[cfg(feature = "std")]
+pub use replicate_world::{ReplicateCell, ReplicateWorld};
+#[cfg(feature = "std")]
+pub use seed_bank::Seed;
+#[cfg(feature = "std")]
+pub use selection::{SelectionPressure, SelectionProbes};
+pub use snapshot::{
+    CellCodec, GridSnapshot, SnapshotError, SnapshotReader, SnapshotWriter, snapshot_format_name,
+};
+#[cfg(feature = "std")]
+pub use spectrum::PowerSpectrum;
+#[cfg(feature = "std")]
+pub use transitions::{TransitionCounter, TransitionCounts};
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 use arrayvec::ArrayVec;
+use core::fmt::{Debug, Display};
+use core::mem;
+use core::ops::{Index, IndexMut};
+use core::slice::{ChunksExactMut, Iter, IterMut};
+use core::str::FromStr;
+use core::sync::atomic::{AtomicU64, Ordering};
+use dirty_tiles::DirtyTiles;
 use rand::SeedableRng;
 use rand::distr::uniform::{SampleRange, SampleUniform};
 use rand::prelude::*;
 use rand::rngs::SmallRng;
+#[cfg(feature = "std")]
 use rand_distr::{Distribution, Normal};
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
-use std::fmt::Debug;
-use std::mem;
-use std::ops::{Index, IndexMut, Range, RangeInclusive};
-use std::slice::{ChunksExactMut, Iter, IterMut};
+#[cfg(feature = "std")]
+use std::ops::RangeInclusive;
+
+// The default for set_grid_memory_budget: 4 GiB.
+const DEFAULT_GRID_MEMORY_BUDGET: u64 = 1 << 32;
+
+static GRID_MEMORY_BUDGET: AtomicU64 = AtomicU64::new(DEFAULT_GRID_MEMORY_BUDGET);
+
+// Sets the most memory, in bytes, that a new grid's cells may take, so that absurd grid
+// sizes, e.g. from the command line or a network command, fail with a GridError rather than
+// exhausting memory.
+pub fn set_grid_memory_budget(bytes: u64) {
+    GRID_MEMORY_BUDGET.store(bytes, Ordering::Relaxed);
+}
+
+pub fn grid_memory_budget() -> u64 {
+    GRID_MEMORY_BUDGET.load(Ordering::Relaxed)
+}
+
+// Sets how many threads grid updates run on, rather than one per CPU. Must be called before
+// the first update.
+#[cfg(feature = "parallel")]
+pub fn set_update_threads(num_threads: usize) {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build_global()
+        .unwrap();
+}
 
 pub trait World {
     fn grid(&self) -> &WorldGrid<impl GridCell>;
+    fn grid_mut(&mut self) -> &mut WorldGrid<impl GridCell>;
     fn update(&mut self);
     fn reset(&mut self);
+
+    // Takes the given number of steps at once, e.g. to get past a random start's transients
+    // before showing the world.
+    fn fast_forward(&mut self, steps: u64) {
+        for _ in 0..steps {
+            self.update();
+        }
+    }
+
+    fn metrics(&self) -> Vec<WorldMetric> {
+        vec![]
+    }
+
+    // Updates so far, including any taken to settle a new world's random start, e.g. for the
+    // front end to show progress. Worlds that update their grids once per step keep this
+    // default, the grid's step count.
+    fn generation(&self) -> u64 {
+        self.grid().step()
+    }
+
+    // Counts cheap enough to take after every step, unlike some metrics. Worlds with layers,
+    // e.g. substances, add their totals.
+    fn step_stats(&self) -> StepStats {
+        StepStats::new(self.generation(), self.grid())
+    }
+
+    // The world's point sources, for worlds that have them (see WorldSources). Others keep
+    // these defaults.
+    fn sources(&self) -> Option<&dyn WorldSources> {
+        None
+    }
+
+    fn sources_mut(&mut self) -> Option<&mut dyn WorldSources> {
+        None
+    }
+
+    // The names of the phases of the world's updates, in order, for worlds that can be updated
+    // a phase at a time, e.g. to watch each phase in turn. Worlds that can't keep this default.
+    fn update_phase_names(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    // Leaves the world as of the end of the given phase of its next update. Calling this for
+    // each phase in order does the same as one call to update.
+    fn update_phase(&mut self, _phase: usize) {
+        self.update();
+    }
+
+    // Overrides the world's grid settings, e.g. from the command line. Worlds whose grid is
+    // only a copy of their cells pass the settings on to the grids they run.
+    fn apply_grid_settings(&mut self, settings: &GridSettings) {
+        settings.apply(self.grid_mut());
+    }
+
+    // Whether changes made through grid_mut, e.g. edits, reach the world's cells. Worlds whose
+    // grid is only a copy of their cells (see DynWorld and LayeredWorld) can't be edited:
+    // grid_mut gives the copy, and changes made to it are discarded when it's next copied.
+    fn is_editable(&self) -> bool {
+        true
+    }
+}
+
+// Point sources that feed a world's grid, e.g. evo-grid's substance sources, which can be
+// edited at runtime. Sources are numbered in the order of source_locs.
+pub trait WorldSources {
+    fn source_locs(&self) -> Vec<Loc>;
+    fn add_source(&mut self, loc: Loc);
+    fn move_source(&mut self, index: usize, loc: Loc);
+    fn remove_source(&mut self, index: usize);
+}
+
+// A world's tunable parameters, such as its rules, densities, mutation rates, and palette.
+// Surprise mode samples them at random instead of using the defaults, and displays them.
+pub trait WorldParams: Debug + Default + Display {
+    // Samples params within bounds that keep the world interesting.
+    fn random(rand: &mut Random) -> Self;
+}
+
+// Holds a world's params, which all its cells share, so they're set once at startup, e.g.
+//     static PARAMS: WorldParamsCell<ConwayParams> = WorldParamsCell::new();
+#[cfg(feature = "std")]
+pub struct WorldParamsCell<P>(std::sync::OnceLock<P>);
+
+#[cfg(feature = "std")]
+impl<P: WorldParams> WorldParamsCell<P> {
+    pub const fn new() -> Self {
+        Self(std::sync::OnceLock::new())
+    }
+
+    // The params, which are the defaults from here on if they haven't been set.
+    pub fn get(&self) -> &P {
+        self.0.get_or_init(P::default)
+    }
+
+    // Sets the params, unless they've already been set or read.
+    pub fn init<F: FnOnce() -> P>(&self, init: F) {
+        self.0.get_or_init(init);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<P: WorldParams> Default for WorldParamsCell<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldMetric {
+    pub name: &'static str,
+    pub value: f64,
+}
+
+impl WorldMetric {
+    pub fn new(name: &'static str, value: f64) -> Self {
+        Self { name, value }
+    }
+}
+
+// A world's generation and counts of its state after a step (see World::step_stats).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StepStats {
+    pub generation: u64,
+    // Cells with creatures (see GridCell::genome).
+    pub live_cells: usize,
+    // The total of each of the world's layers, e.g. the amount of its substance.
+    pub layer_totals: Vec<WorldMetric>,
+}
+
+impl StepStats {
+    pub fn new<C>(generation: u64, grid: &WorldGrid<C>) -> Self
+    where
+        C: Clone + Debug + GridCell,
+    {
+        Self {
+            generation,
+            live_cells: grid
+                .cells_iter()
+                .filter(|cell| cell.genome().is_some())
+                .count(),
+            layer_totals: vec![],
+        }
+    }
+
+    pub fn with_layer_total(mut self, name: &'static str, total: f64) -> Self {
+        self.layer_totals.push(WorldMetric::new(name, total));
+        self
+    }
+}
+
+// What lies beyond a grid's edges, for finding cells' neighbors.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum EdgeMode {
+    // Nothing: edge cells have fewer neighbors.
+    #[default]
+    Clamped,
+    // The opposite edge, as on a torus, so that every cell has a full neighborhood. Grids
+    // narrower than three cells see some neighbors more than once.
+    Wrapped,
+}
+
+impl EdgeMode {
+    // The location offset from another by -1, 0, or 1 rows and columns, if any, e.g. for
+    // moving things between neighboring cells.
+    pub fn offset_loc(
+        self,
+        loc: Loc,
+        row_offset: i32,
+        col_offset: i32,
+        size: GridSize,
+    ) -> Option<Loc> {
+        Some(Loc::new(
+            self.offset_index(loc.row, row_offset, size.height)?,
+            self.offset_index(loc.col, col_offset, size.width)?,
+        ))
+    }
+
+    // The index offset from another by -1, 0, or 1 along an axis of length max, if any.
+    fn offset_index(self, index: u32, offset: i32, max: u32) -> Option<u32> {
+        match self {
+            Self::Clamped => index
+                .checked_add_signed(offset)
+                .filter(|index| *index < max),
+            Self::Wrapped => Some((index as i64 + offset as i64).rem_euclid(max as i64) as u32),
+        }
+    }
+}
+
+impl FromStr for EdgeMode {
+    type Err = String;
+
+    // Parses "clamp" or "wrap".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clamp" => Ok(Self::Clamped),
+            "wrap" => Ok(Self::Wrapped),
+            _ => Err(format!("Invalid edge mode: {}", s)),
+        }
+    }
+}
+
+// How the cells of a grid take turns updating within a step.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum UpdateSchedule {
+    // Every cell updates at once, from the same previous state.
+    #[default]
+    Synchronous,
+    // Each cell updates with the given odds, from the same previous state, and otherwise keeps
+    // its state for the step.
+    Probabilistic(f64),
+    // The cells update one at a time, in a new random order each step, each seeing the
+    // updates made before it. This can't be parallelized, so is much slower.
+    RandomSequential,
+}
+
+impl FromStr for UpdateSchedule {
+    type Err = String;
+
+    // Parses "sync", "async", or an update probability, e.g. "0.5".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sync" => Ok(Self::Synchronous),
+            "async" => Ok(Self::RandomSequential),
+            _ => match s.parse() {
+                Ok(odds) if (0.0..=1.0).contains(&odds) => Ok(Self::Probabilistic(odds)),
+                _ => Err(format!("Invalid update schedule: {}", s)),
+            },
+        }
+    }
+}
+
+// Grid settings that override a world's own (see World::apply_grid_settings), e.g. from the
+// command line. Each one left unset keeps the world's own.
+#[derive(Clone, Debug, Default)]
+pub struct GridSettings {
+    pub edge_mode: Option<EdgeMode>,
+    // Added to the world's own pinned cells.
+    pub pinned_locs: Vec<Loc>,
+    pub population_cap: Option<usize>,
+    pub update_schedule: Option<UpdateSchedule>,
+    // Turns on checking updates, if set (see WorldGrid::set_verify_updates).
+    pub verify_updates: bool,
+}
+
+impl GridSettings {
+    pub fn apply<C: Clone + GridCell>(&self, grid: &mut WorldGrid<C>) {
+        if let Some(edge_mode) = self.edge_mode {
+            grid.set_edge_mode(edge_mode);
+        }
+        for loc in &self.pinned_locs {
+            grid.set_pinned(*loc, true);
+        }
+        if self.population_cap.is_some() {
+            grid.set_population_cap(self.population_cap);
+        }
+        if let Some(update_schedule) = self.update_schedule {
+            grid.set_update_schedule(update_schedule);
+        }
+        if self.verify_updates {
+            grid.set_verify_updates(true);
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -27,6 +422,28 @@ where
     size: GridSize,
     pub cells: WorldGridCells<C>,
     pub next_cells: WorldGridCells<C>,
+    update_schedule: UpdateSchedule,
+    edge_mode: EdgeMode,
+    // Updates so far, so that cells can update slow components only every so many steps (see
+    // Neighborhood::is_update_step).
+    step: u64,
+    verify_updates: bool,
+    // The most creatures (see GridCell::genome) the grid can hold, if limited.
+    population_cap: Option<usize>,
+    // Cells that keep their states through updates (see set_pinned).
+    pinned_locs: Vec<Loc>,
+    // Which cells may have changed in the last update (see changed_cells), and whether cells
+    // have been changed some other way since the update before it.
+    changed_cells: Option<Vec<bool>>,
+    changed_outside_update: bool,
+    // Whether updates skip tiles without nearby changes (see set_skip_quiescent_tiles), and
+    // which tiles changed in the last update, if known, i.e. no cells have been changed some
+    // other way since.
+    skip_quiescent_tiles: bool,
+    dirty_tiles: Option<DirtyTiles>,
+    // While an edit is being recorded (see begin_edit), the cells changed via cell_mut, with
+    // their states before the change.
+    edit_before: Option<Vec<(Loc, C)>>,
 }
 
 impl<C> WorldGrid<C>
@@ -34,17 +451,111 @@ where
     C: Clone + Debug + GridCell,
 {
     pub fn new(size: GridSize) -> Self {
-        assert!(!size.is_empty());
-        Self {
+        Self::try_new(size).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_new(size: GridSize) -> Result<Self, GridError> {
+        // Checked for both copies of the cells before allocating either.
+        size.check_memory_budget(2 * mem::size_of::<C>() as u64)?;
+        Ok(Self {
             size,
-            cells: WorldGridCells::new(size),
-            next_cells: WorldGridCells::new(size),
+            cells: WorldGridCells::try_new(size)?,
+            next_cells: WorldGridCells::try_new(size)?,
+            update_schedule: UpdateSchedule::default(),
+            edge_mode: EdgeMode::default(),
+            step: 0,
+            verify_updates: false,
+            population_cap: None,
+            pinned_locs: vec![],
+            changed_cells: None,
+            changed_outside_update: true,
+            skip_quiescent_tiles: false,
+            dirty_tiles: None,
+            edit_before: None,
+        })
+    }
+
+    // When set, each update is also run single-threaded from the same state and random
+    // number streams, and any difference from the parallel update panics. This catches
+    // cell updates that depend on the order in which rows happen to be updated.
+    pub fn set_verify_updates(&mut self, verify_updates: bool) {
+        self.verify_updates = verify_updates;
+    }
+
+    // When set, births that would take an update's population over the cap are undone, chosen
+    // at random across the grid, so that runaway worlds stay bounded and worlds that start at
+    // the cap keep a constant population, as in a Moran process. Only births are undone, so a
+    // population that starts over the cap shrinks to it as creatures die.
+    pub fn set_population_cap(&mut self, population_cap: Option<usize>) {
+        self.population_cap = population_cap;
+    }
+
+    // A pinned cell holds its state forever, e.g. as an eternal substance well, a permanent
+    // wall, or an always-on Conway cell: updates leave it as is, though it still affects its
+    // neighbors. Edits via cell_mut still change it, and it then holds its new state.
+    pub fn set_pinned(&mut self, loc: Loc, pinned: bool) {
+        let index = self
+            .pinned_locs
+            .iter()
+            .position(|pinned_loc| *pinned_loc == loc);
+        match (index, pinned) {
+            (None, true) if loc.grid_index(self.size).is_some() => self.pinned_locs.push(loc),
+            (Some(index), false) => {
+                self.pinned_locs.swap_remove(index);
+            }
+            _ => (),
         }
     }
 
+    // When set, synchronous updates skip the cells far from any change in the last update,
+    // tracked by square tiles, so that mostly settled worlds update quickly. Only for cells
+    // whose updates depend on nothing but their neighborhoods' states, e.g. Conway's, and that
+    // change only via GridCell::update.
+    pub fn set_skip_quiescent_tiles(&mut self, skip_quiescent_tiles: bool) {
+        self.skip_quiescent_tiles = skip_quiescent_tiles;
+        self.dirty_tiles = None;
+    }
+
+    pub fn is_pinned(&self, loc: Loc) -> bool {
+        self.pinned_locs.contains(&loc)
+    }
+
+    pub fn pinned_locs(&self) -> &[Loc] {
+        &self.pinned_locs
+    }
+
+    pub fn update_schedule(&self) -> UpdateSchedule {
+        self.update_schedule
+    }
+
+    pub fn set_update_schedule(&mut self, update_schedule: UpdateSchedule) {
+        self.update_schedule = update_schedule;
+    }
+
+    pub fn edge_mode(&self) -> EdgeMode {
+        self.edge_mode
+    }
+
+    pub fn set_edge_mode(&mut self, edge_mode: EdgeMode) {
+        self.edge_mode = edge_mode;
+        // Edge cells' neighborhoods may have changed.
+        self.dirty_tiles = None;
+    }
+
+    pub fn step(&self) -> u64 {
+        self.step
+    }
+
+    // The neighborhood of a cell as of the last update, e.g. for world-level updates that
+    // look at a cell's neighbors.
+    pub fn neighborhood(&self, loc: Loc) -> Neighborhood<'_, C> {
+        Neighborhood::new(&self.cells, loc, self.step).with_edge_mode(self.edge_mode)
+    }
+
     pub fn clear(&mut self) {
         self.cells.clear();
         self.next_cells.clear();
+        self.mark_all_changed();
     }
 
     pub fn size(&self) -> GridSize {
@@ -56,61 +567,357 @@ where
     }
 
     pub fn cell_mut(&mut self, loc: Loc) -> Option<&mut C> {
+        self.mark_all_changed();
+        if let Some(edit_before) = self.edit_before.as_mut()
+            && let Some(cell) = self.cells.cell(loc)
+        {
+            edit_before.push((loc, *cell));
+        }
         self.cells.cell_mut(loc)
     }
 
+    // The cell as of the last update, or an error if the loc is outside the grid.
+    pub fn try_cell(&self, loc: Loc) -> Result<&C, GridError> {
+        self.cells.try_cell(loc)
+    }
+
+    // Like cell_mut, but with an error if the loc is outside the grid.
+    pub fn try_cell_mut(&mut self, loc: Loc) -> Result<&mut C, GridError> {
+        let size = self.size;
+        self.cell_mut(loc)
+            .ok_or(GridError::OutOfBounds { loc, size })
+    }
+
+    // Flags for each cell whether it may have changed in the last update, e.g. so that a
+    // renderer can refresh only those cells. None if cells may also have been changed outside
+    // of an update since the update before it, so that the caller can't rely on its own
+    // state as of that update.
+    pub fn changed_cells(&self) -> Option<&[bool]> {
+        self.changed_cells.as_deref()
+    }
+
+    // Code that changes cells directly through the cells field, rather than via update,
+    // clear, or cell_mut, must call this.
+    pub fn mark_all_changed(&mut self) {
+        self.changed_cells = None;
+        self.changed_outside_update = true;
+        self.dirty_tiles = None;
+    }
+
+    // Whether cells have been changed some other way than by an update since the last one,
+    // e.g. by edits, which worlds that run their cells on other grids must pass on to them.
+    pub fn changed_outside_update(&self) -> bool {
+        self.changed_outside_update
+    }
+
     pub fn cells_iter(&self) -> Iter<'_, C> {
         self.cells.cells_iter()
     }
 
-    pub fn update<F>(&mut self, rand: &mut Option<Random>, mut other_update: F)
+    // The total amount of substance in the grid (see GridCell::substance_amount).
+    pub fn substance_mass(&self) -> f64 {
+        self.cells_iter()
+            .map(|cell| cell.substance_amount() as f64)
+            .sum()
+    }
+
+    pub fn update<F>(&mut self, rand: &mut Option<Random>, other_update: F)
+    where
+        F: FnMut(&mut Self),
+    {
+        self.update_with(rand, other_update, C::update);
+    }
+
+    // Like update, but updating each cell with cell_update instead of GridCell::update, e.g. to
+    // run only part of the cells' update, or to update them with more than their neighborhoods
+    // (see LayeredWorldGrid).
+    pub fn update_with<F, U>(&mut self, rand: &mut Option<Random>, other_update: F, cell_update: U)
     where
         F: FnMut(&mut Self),
+        U: Fn(&C, &Neighborhood<C>, &mut C, &mut Option<Random>) + Sync,
+    {
+        let active = vec![false; self.num_cells()];
+        self.update_with_active(rand, other_update, cell_update, active);
+    }
+
+    // Like update_with, but also updating the cells already flagged in active, which otherwise
+    // flags just the cells that aren't inert and their neighbors.
+    pub(crate) fn update_with_active<F, U>(
+        &mut self,
+        rand: &mut Option<Random>,
+        mut other_update: F,
+        cell_update: U,
+        active: Vec<bool>,
+    ) where
+        F: FnMut(&mut Self),
+        U: Fn(&C, &Neighborhood<C>, &mut C, &mut Option<Random>) + Sync,
     {
         self.next_cells.copy_from(&self.cells);
         other_update(self);
-        self.update_cells(rand);
+        match self.update_schedule {
+            UpdateSchedule::Synchronous => self.update_cells(active, 1.0, rand, &cell_update),
+            UpdateSchedule::Probabilistic(update_odds) => {
+                self.update_cells(active, update_odds, rand, &cell_update)
+            }
+            UpdateSchedule::RandomSequential => self.update_cells_sequentially(rand, &cell_update),
+        }
+        if let Some(population_cap) = self.population_cap {
+            self.enforce_population_cap(population_cap, rand);
+        }
+        for loc in &self.pinned_locs {
+            self.next_cells[*loc] = self.cells[*loc];
+        }
         mem::swap(&mut self.next_cells, &mut self.cells);
+        self.step += 1;
+    }
+
+    // Counts an update run elsewhere, e.g. on the layers of a LayeredWorldGrid, as an update
+    // of this grid: sets each cell flagged in changed, or every cell if that's None, via
+    // set_cell, which gets the cell's index, and takes a step. The grid's own pinned cells and
+    // population cap are left to the grids that ran the update.
+    pub fn record_update<F>(&mut self, changed: Option<Vec<bool>>, mut set_cell: F)
+    where
+        F: FnMut(usize, &mut C),
+    {
+        let cells = self.cells.cells.iter_mut().enumerate();
+        match &changed {
+            Some(changed) => cells
+                .zip(changed)
+                .filter(|(_, changed)| **changed)
+                .for_each(|((index, cell), _)| set_cell(index, cell)),
+            None => cells.for_each(|(index, cell)| set_cell(index, cell)),
+        }
+        self.changed_cells = changed.filter(|_| !self.changed_outside_update);
+        self.changed_outside_update = false;
+        self.dirty_tiles = None;
+        self.step += 1;
+    }
+
+    // Updates the cells in place in next_cells, in random order, or in row-major order without
+    // a random number stream.
+    fn update_cells_sequentially<U>(&mut self, rand: &mut Option<Random>, cell_update: &U)
+    where
+        U: Fn(&C, &Neighborhood<C>, &mut C, &mut Option<Random>),
+    {
+        let mut order: Vec<u32> = (0..self.num_cells() as u32).collect();
+        if let Some(rand) = rand.as_mut() {
+            rand.shuffle(&mut order);
+        }
+        let width = self.size.width;
+        for index in order {
+            let loc = Loc::new(index / width, index % width);
+            let cell = self.next_cells[loc];
+            let mut next_cell = cell;
+            cell_update(
+                &cell,
+                &Neighborhood::new(&self.next_cells, loc, self.step).with_edge_mode(self.edge_mode),
+                &mut next_cell,
+                rand,
+            );
+            self.next_cells[loc] = next_cell;
+        }
+        self.changed_cells = (!self.changed_outside_update).then(|| vec![true; self.num_cells()]);
+        self.changed_outside_update = false;
+        self.dirty_tiles = None;
     }
 
-    fn update_cells(&mut self, rand: &mut Option<Random>) {
+    fn update_cells<U>(
+        &mut self,
+        mut active: Vec<bool>,
+        update_odds: f64,
+        rand: &mut Option<Random>,
+        cell_update: &U,
+    ) where
+        U: Fn(&C, &Neighborhood<C>, &mut C, &mut Option<Random>) + Sync,
+    {
+        // Each row gets its own random number stream, forked up front, so the result doesn't
+        // depend on how rows are scheduled across threads (see Random).
+        let mut row_rands = Random::multi_fork_option(rand, self.size.height);
+        self.cells.mark_active(&mut active, self.edge_mode);
+        // Cells that other updates made non-inert have changed, and must update if their
+        // neighbors do.
+        self.next_cells.mark_active(&mut active, self.edge_mode);
+        // Cells that don't get their turn every step may change with no changes nearby, so
+        // tiles are tracked only while every cell gets its turn.
+        let skip_quiescent_tiles = self.skip_quiescent_tiles && update_odds >= 1.0;
+        if skip_quiescent_tiles && let Some(dirty_tiles) = &self.dirty_tiles {
+            dirty_tiles.deactivate_quiescent(&mut active, self.edge_mode);
+        }
+        self.update_rows(&active, update_odds, &mut row_rands, cell_update);
+        self.dirty_tiles = skip_quiescent_tiles.then(|| {
+            let mut dirty_tiles = self
+                .dirty_tiles
+                .take()
+                .unwrap_or_else(|| DirtyTiles::new(self.size));
+            dirty_tiles.record_changes(&self.cells, &self.next_cells, &active);
+            dirty_tiles
+        });
+        self.changed_cells = (!self.changed_outside_update).then_some(active);
+        self.changed_outside_update = false;
+    }
+
+    #[cfg(feature = "parallel")]
+    fn update_rows<U>(
+        &mut self,
+        active: &[bool],
+        update_odds: f64,
+        row_rands: &mut [Option<Random>],
+        cell_update: &U,
+    ) where
+        U: Fn(&C, &Neighborhood<C>, &mut C, &mut Option<Random>) + Sync,
+    {
+        let reference = self.verify_updates.then(|| {
+            self.serial_update_cells(active, update_odds, row_rands.to_vec(), cell_update)
+        });
+        let update_cell = Self::cell_updater(&self.cells, self.step, self.edge_mode, cell_update);
         self.next_cells
             .par_rows_mut()
-            .zip(Random::multi_fork_option(rand, self.size.width).par_iter_mut())
+            .zip(active.par_chunks_exact(self.size.width as usize))
+            .zip(row_rands.par_iter_mut())
+            .enumerate()
+            .for_each(|(row, ((row_next_cells, row_active), row_rand))| {
+                Self::update_row(
+                    row as u32,
+                    row_next_cells,
+                    row_active,
+                    update_odds,
+                    row_rand,
+                    &update_cell,
+                );
+            });
+        if let Some(reference) = reference {
+            self.assert_matches_reference(&reference);
+        }
+    }
+
+    // Without threads, the serial update is the update, so there's nothing to verify.
+    #[cfg(not(feature = "parallel"))]
+    fn update_rows<U>(
+        &mut self,
+        active: &[bool],
+        update_odds: f64,
+        row_rands: &mut [Option<Random>],
+        cell_update: &U,
+    ) where
+        U: Fn(&C, &Neighborhood<C>, &mut C, &mut Option<Random>),
+    {
+        self.next_cells =
+            self.serial_update_cells(active, update_odds, row_rands.to_vec(), cell_update);
+    }
+
+    // Removes the creatures born in next_cells, i.e. in cells that had none before the update,
+    // beyond the cap. Without a random number stream, the last births in row-major order go.
+    fn enforce_population_cap(&mut self, population_cap: usize, rand: &mut Option<Random>) {
+        let population = self
+            .next_cells
+            .cells_iter()
+            .filter(|cell| cell.genome().is_some())
+            .count();
+        if population <= population_cap {
+            return;
+        }
+        let mut births: Vec<usize> = self
+            .cells
+            .cells_iter()
+            .zip(self.next_cells.cells_iter())
             .enumerate()
-            .for_each(|(row, (row_next_cells, row_rand))| {
+            .filter(|(_, (cell, next_cell))| {
+                cell.genome().is_none() && next_cell.genome().is_some()
+            })
+            .map(|(index, _)| index)
+            .collect();
+        match rand.as_mut() {
+            Some(rand) => rand.shuffle(&mut births),
+            None => births.reverse(),
+        }
+        let width = self.size.width;
+        for index in births.into_iter().take(population - population_cap) {
+            let loc = Loc::new(index as u32 / width, index as u32 % width);
+            self.next_cells[loc].remove_creature();
+        }
+    }
+
+    fn serial_update_cells<U>(
+        &self,
+        active: &[bool],
+        update_odds: f64,
+        mut row_rands: Vec<Option<Random>>,
+        cell_update: &U,
+    ) -> WorldGridCells<C>
+    where
+        U: Fn(&C, &Neighborhood<C>, &mut C, &mut Option<Random>),
+    {
+        let mut result = self.next_cells.clone();
+        let update_cell = Self::cell_updater(&self.cells, self.step, self.edge_mode, cell_update);
+        result
+            .rows_mut()
+            .zip(active.chunks_exact(self.size.width as usize))
+            .zip(row_rands.iter_mut())
+            .enumerate()
+            .for_each(|(row, ((row_next_cells, row_active), row_rand))| {
                 Self::update_row(
                     row as u32,
-                    &self.cells,
                     row_next_cells,
-                    self.size.width,
+                    row_active,
+                    update_odds,
                     row_rand,
+                    &update_cell,
                 );
             });
+        result
     }
 
-    fn update_row(
+    #[cfg(feature = "parallel")]
+    fn assert_matches_reference(&self, reference: &WorldGridCells<C>) {
+        if let Err(GridDifference::Cell(loc)) = self.next_cells.compare_exact(reference) {
+            panic!(
+                "Parallel update of cell ({}, {}) doesn't match serial update: {:?} vs. {:?}",
+                loc.row, loc.col, self.next_cells[loc], reference[loc]
+            );
+        }
+    }
+
+    fn update_row<U>(
         row: u32,
-        cells: &WorldGridCells<C>,
         next_cells_row: &mut [C],
-        width: u32,
+        active_row: &[bool],
+        update_odds: f64,
         rand: &mut Option<Random>,
-    ) {
-        for col in 0..width {
-            Self::update_cell(Loc::new(row, col), cells, next_cells_row, rand);
+        update_cell: &U,
+    ) where
+        U: Fn(Loc, &mut C, &mut Option<Random>),
+    {
+        for (col, active) in active_row.iter().enumerate() {
+            // Inactive cells keep their current state, as already copied into next_cells, as
+            // do cells that don't get their turn to update. Without a random number stream,
+            // every cell gets its turn.
+            if *active
+                && (update_odds >= 1.0
+                    || rand.as_mut().is_none_or(|rand| rand.next_bool(update_odds)))
+            {
+                update_cell(Loc::new(row, col as u32), &mut next_cells_row[col], rand);
+            }
         }
     }
 
-    fn update_cell(
-        loc: Loc,
-        cells: &WorldGridCells<C>,
-        next_cells_row: &mut [C],
-        rand: &mut Option<Random>,
-    ) {
-        let cell = &cells[loc];
-        let neighborhood = Neighborhood::new(cells, loc);
-        let next_cell = &mut next_cells_row[loc.col as usize];
-        cell.update(&neighborhood, next_cell, rand);
+    // Updates the next state of the cell at a location from its neighborhood in cells.
+    fn cell_updater<'a, U>(
+        cells: &'a WorldGridCells<C>,
+        step: u64,
+        edge_mode: EdgeMode,
+        cell_update: &'a U,
+    ) -> impl Fn(Loc, &mut C, &mut Option<Random>) + 'a
+    where
+        U: Fn(&C, &Neighborhood<C>, &mut C, &mut Option<Random>),
+    {
+        move |loc, next_cell, rand| {
+            cell_update(
+                &cells[loc],
+                &Neighborhood::new(cells, loc, step).with_edge_mode(edge_mode),
+                next_cell,
+                rand,
+            )
+        }
     }
 
     pub fn debug_print(&self, row: u32, col: u32) {
@@ -132,17 +939,39 @@ where
     C: Clone + Copy + Default + GridCell,
 {
     pub fn new(size: GridSize) -> Self {
-        assert!(!size.is_empty());
-        Self {
+        Self::try_new(size).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_new(size: GridSize) -> Result<Self, GridError> {
+        if size.is_empty() {
+            return Err(GridError::EmptySize(size));
+        }
+        size.check_memory_budget(mem::size_of::<C>() as u64)?;
+        Ok(Self {
             size,
             cells: vec![C::default(); size.area()],
-        }
+        })
     }
 
     pub fn clear(&mut self) {
         self.cells.iter_mut().for_each(|cell| cell.clear());
     }
 
+    // Marks the cells that need updating: those that aren't inert, and their neighbors.
+    fn mark_active(&self, active: &mut [bool], edge_mode: EdgeMode) {
+        let width = self.size.width as usize;
+        for (index, cell) in self.cells.iter().enumerate() {
+            if !cell.is_inert() {
+                let center = Loc::new((index / width) as u32, (index % width) as u32);
+                Neighborhood::new(self, center, 0)
+                    .with_edge_mode(edge_mode)
+                    .for_block_locs(|_row_offset, _col_offset, loc| {
+                        active[loc.row as usize * width + loc.col as usize] = true;
+                    });
+            }
+        }
+    }
+
     pub fn size(&self) -> GridSize {
         self.size
     }
@@ -163,6 +992,7 @@ where
         self.cells.chunks_exact_mut(self.size.width as usize)
     }
 
+    #[cfg(feature = "parallel")]
     pub fn par_rows_mut(&mut self) -> rayon::slice::ChunksExactMut<'_, C> {
         self.cells.par_chunks_exact_mut(self.size.width as usize)
     }
@@ -176,6 +1006,16 @@ where
             .map(|index| &mut self.cells[index])
     }
 
+    pub fn try_cell(&self, loc: Loc) -> Result<&C, GridError> {
+        let index = loc.try_grid_index(self.size)?;
+        Ok(&self.cells[index])
+    }
+
+    pub fn try_cell_mut(&mut self, loc: Loc) -> Result<&mut C, GridError> {
+        let index = loc.try_grid_index(self.size)?;
+        Ok(&mut self.cells[index])
+    }
+
     pub fn copy_from(&mut self, source: &Self) {
         self.cells.copy_from_slice(&source.cells);
     }
@@ -188,8 +1028,8 @@ where
     type Output = C;
 
     fn index(&self, loc: Loc) -> &Self::Output {
-        self.cell(loc)
-            .unwrap_or_else(|| panic!("Index indices {}, {} out of bounds", loc.row, loc.col))
+        self.try_cell(loc)
+            .unwrap_or_else(|err| panic!("Index: {}", err))
     }
 }
 
@@ -198,16 +1038,27 @@ where
     C: Clone + Copy + Default + GridCell,
 {
     fn index_mut(&mut self, loc: Loc) -> &mut Self::Output {
-        self.cell_mut(loc)
-            .unwrap_or_else(|| panic!("Index_mut indices {}, {} out of bounds", loc.row, loc.col))
+        self.try_cell_mut(loc)
+            .unwrap_or_else(|err| panic!("Index_mut: {}", err))
     }
 }
 
+// Updates a cell, as GridCell::update does.
+pub type CellUpdate<C> = fn(&C, &Neighborhood<C>, &mut C, &mut Option<Random>);
+
 pub trait GridCell
 where
-    Self: Copy + Debug + Default + Send + Sync,
+    Self: Copy + Debug + Default + Send + Sync + CellCodec,
 {
     fn color_rgba(&self) -> [u8; 4];
+
+    // The cell's color with the hues of its backdrop, e.g. its substance, rotated by the given
+    // number of turns of the color wheel, for palette cycling. Only the display sees the
+    // shifted colors. Cells without a backdrop keep their colors.
+    fn hue_shifted_color_rgba(&self, _turns: f32) -> [u8; 4] {
+        self.color_rgba()
+    }
+
     fn clear(&mut self);
     fn update(
         &self,
@@ -216,40 +1067,234 @@ where
         rand: &mut Option<Random>,
     );
     fn debug_print(&self, row: u32, col: u32);
+
+    // Whether the cell is guaranteed not to change in an update as long as all its neighbors
+    // are inert too, e.g. an empty cell in a world where only creatures act. Updates skip
+    // inert cells with only inert neighbors.
+    fn is_inert(&self) -> bool {
+        false
+    }
+
+    // Names of the numeric fields exported per cell, e.g. for analysis in NumPy. Fields that
+    // don't apply to a cell (e.g. a gene value in an empty cell) export as -1.
+    fn field_names() -> &'static [&'static str] {
+        &[]
+    }
+
+    fn field_value(&self, _field: usize) -> f32 {
+        -1.0
+    }
+
+    // How far apart a field's values can be and still count as equal in approximate grid
+    // comparisons. Discrete fields should stay exact.
+    fn field_tolerance(_field: usize) -> f32 {
+        0.0
+    }
+
+    // The genes of the cell's creature packed into an integer, if the cell has a creature.
+    // Used to detect births and deaths.
+    fn genome(&self) -> Option<u64> {
+        None
+    }
+
+    // The amount of substance in the cell, e.g. for totaling a world's substance mass. Cells
+    // without substances have none.
+    fn substance_amount(&self) -> f32 {
+        0.0
+    }
+
+    // Replaces the cell's creature with a new one with the given packed genes (see genome).
+    // Cells without creatures ignore this.
+    fn set_genome(&mut self, _genome: u64) {}
+
+    // Removes the cell's creature, if any, leaving the rest of the cell as is.
+    fn remove_creature(&mut self) {}
+
+    // How many states the cell has for patterns (see Pattern): just dead and live, i.e. with
+    // and without a creature, unless the cell has more, e.g. Wireworld's.
+    fn num_pattern_states() -> u8 {
+        2
+    }
+
+    fn pattern_state(&self) -> u8 {
+        self.genome().is_some() as u8
+    }
+
+    // Sets the cell to a multistate pattern's state for it, from 0 to num_pattern_states - 1.
+    // Cells with just two states give live cells creatures with the given genome.
+    fn set_pattern_state(&mut self, state: u8, genome: u64) {
+        if state == 0 {
+            self.remove_creature();
+        } else {
+            self.set_genome(genome);
+        }
+    }
+
+    // The creature's 8-bit genes within genome(), e.g. for probing which gene values an
+    // environment favors and for showing them in the inspector.
+    fn genes() -> &'static [GeneDescriptor] {
+        &[]
+    }
 }
 
+// The row and column offsets from a neighborhood's center of its neighbors, in row-major
+// order.
+const NEIGHBOR_OFFSETS: [(i32, i32); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
 pub struct Neighborhood<'a, C>
 where
     C: Clone + Copy + Default + GridCell,
 {
     center: Loc,
     cells: &'a WorldGridCells<C>,
+    step: u64,
+    edge_mode: EdgeMode,
 }
 
 impl<'a, C> Neighborhood<'a, C>
 where
     C: Clone + Copy + Default + GridCell,
 {
-    pub fn new(cells: &'a WorldGridCells<C>, center: Loc) -> Self {
-        Self { center, cells }
+    pub fn new(cells: &'a WorldGridCells<C>, center: Loc, step: u64) -> Self {
+        Self {
+            center,
+            cells,
+            step,
+            edge_mode: EdgeMode::default(),
+        }
+    }
+
+    pub fn with_edge_mode(mut self, edge_mode: EdgeMode) -> Self {
+        self.edge_mode = edge_mode;
+        self
     }
 
-    pub fn for_neighbor_cells<F>(&self, mut f: F)
+    // The grid's update step that this neighborhood is being updated for.
+    pub fn step(&self) -> u64 {
+        self.step
+    }
+
+    // Whether a cell component that updates only every period steps, e.g. a slow layer,
+    // updates on this step. Off its steps, a component should leave its next state as is,
+    // i.e. the same as its current state.
+    pub fn is_update_step(&self, period: u64) -> bool {
+        self.step.is_multiple_of(period.max(1))
+    }
+
+    pub fn for_neighbor_cells<F>(&self, f: F)
     where
         F: FnMut(&C),
     {
-        for row in Self::index_range(self.center.row, self.cells.size.height) {
-            for col in Self::index_range(self.center.col, self.cells.size.width) {
-                let loc = Loc::new(row, col);
-                if loc != self.center {
-                    f(&self.cells[loc]);
-                }
-            }
+        self.neighbors().for_each(f);
+    }
+
+    // Also passes each neighbor's row and column offsets from the center, from -1 to 1, for
+    // updates that depend on direction.
+    pub fn for_neighbor_cells_with_offsets<F>(&self, mut f: F)
+    where
+        F: FnMut(i32, i32, &C),
+    {
+        for (row_offset, col_offset, cell) in self.neighbors_with_offsets() {
+            f(row_offset, col_offset, cell);
         }
     }
 
-    fn index_range(center: u32, max: u32) -> Range<u32> {
-        center.saturating_sub(1)..(center + 2).min(max)
+    // The neighbor cells within the grid's edges, in row-major order, e.g. for counting them
+    // with filter or stopping at the first match with any.
+    pub fn neighbors(&self) -> impl Iterator<Item = &'a C> {
+        self.neighbors_with_offsets().map(|(_, _, cell)| cell)
+    }
+
+    // The cell at the given row and column offsets from the center, from -1 to 1, e.g. (-1, 1)
+    // for the cell to the northeast, for rules that care about direction. None for offsets
+    // beyond the 3x3 block, which updates mustn't depend on (see
+    // WorldGrid::set_skip_quiescent_tiles), and for cells beyond clamped edges.
+    pub fn cell_at(&self, row_offset: i32, col_offset: i32) -> Option<&'a C> {
+        if row_offset.abs() > 1 || col_offset.abs() > 1 {
+            return None;
+        }
+        let loc =
+            self.edge_mode
+                .offset_loc(self.center, row_offset, col_offset, self.cells.size)?;
+        Some(&self.cells[loc])
+    }
+
+    // Also yields each neighbor's row and column offsets, as for_neighbor_cells_with_offsets.
+    pub fn neighbors_with_offsets(&self) -> impl Iterator<Item = (i32, i32, &'a C)> {
+        let (cells, center, edge_mode) = (self.cells, self.center, self.edge_mode);
+        NEIGHBOR_OFFSETS
+            .iter()
+            .filter_map(move |&(row_offset, col_offset)| {
+                let loc = edge_mode.offset_loc(center, row_offset, col_offset, cells.size)?;
+                Some((row_offset, col_offset, &cells[loc]))
+            })
+    }
+
+    // The sum of each cell's value times its weight in the kernel, over the 3x3 block of cells
+    // centered on this neighborhood, including the center. The kernel is indexed by row and
+    // column offset plus one, so kernel[1][1] weighs the center, e.g. heavily for diffusion.
+    // Cells beyond clamped edges add nothing.
+    pub fn weighted_sum<F>(&self, kernel: &[[f32; 3]; 3], value: F) -> f32
+    where
+        F: Fn(&C) -> f32,
+    {
+        let mut result = 0.0;
+        self.for_block_locs(|row_offset, col_offset, loc| {
+            result += kernel[(row_offset + 1) as usize][(col_offset + 1) as usize]
+                * value(&self.cells[loc]);
+        });
+        result
+    }
+
+    // Packs which of the 3x3 block of cells centered on this neighborhood are on into the low
+    // 9 bits, in row-major order from bit 0 (top left) through bit 4 (center) to bit 8 (bottom
+    // right). Cells beyond clamped edges count as off. For rules that look up a cell's next
+    // state in a table indexed by its neighborhood.
+    pub fn state_code<F>(&self, is_on: F) -> u16
+    where
+        F: Fn(&C) -> bool,
+    {
+        let mut result = 0;
+        self.for_block_locs(|row_offset, col_offset, loc| {
+            if is_on(&self.cells[loc]) {
+                result |= 1 << ((row_offset + 1) * 3 + (col_offset + 1));
+            }
+        });
+        result
+    }
+
+    // Passes the offsets from the center and location of each cell of the 3x3 block centered
+    // on this neighborhood, including the center, that lies within the grid's edges.
+    fn for_block_locs<F>(&self, mut f: F)
+    where
+        F: FnMut(i32, i32, Loc),
+    {
+        let size = self.cells.size;
+        for row_offset in -1..=1 {
+            let Some(row) = self
+                .edge_mode
+                .offset_index(self.center.row, row_offset, size.height)
+            else {
+                continue;
+            };
+            for col_offset in -1..=1 {
+                if let Some(col) =
+                    self.edge_mode
+                        .offset_index(self.center.col, col_offset, size.width)
+                {
+                    f(row_offset, col_offset, Loc::new(row, col));
+                }
+            }
+        }
     }
 }
 
@@ -272,6 +1317,12 @@ impl Loc {
         }
     }
 
+    pub fn try_grid_index(&self, size: GridSize) -> Result<usize, GridError> {
+        self.grid_index(size)
+            .ok_or(GridError::OutOfBounds { loc: *self, size })
+    }
+
+    #[cfg(feature = "std")]
     pub fn distance(&self, loc: Loc) -> f64 {
         let row_diff = self.row.abs_diff(loc.row);
         let col_diff = self.col.abs_diff(loc.col);
@@ -297,6 +1348,21 @@ impl GridSize {
     pub fn area(&self) -> usize {
         self.width as usize * self.height as usize
     }
+
+    // Checks that a grid of this size, at the given bytes per cell, fits the memory budget
+    // (see set_grid_memory_budget) and the address space.
+    fn check_memory_budget(self, bytes_per_cell: u64) -> Result<(), GridError> {
+        let bytes = (self.width as u64 * self.height as u64).saturating_mul(bytes_per_cell);
+        let budget = grid_memory_budget().min(isize::MAX as u64);
+        if bytes > budget {
+            return Err(GridError::OverMemoryBudget {
+                size: self,
+                bytes,
+                budget,
+            });
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -316,9 +1382,23 @@ impl BitSet8Gene {
         }
         Self::new(bit_counts.as_bit_set(rand, mutation_odds))
     }
+
+    // The gene's value as read during an update, with each bit flipped with the given odds,
+    // for noisy gene expression. The gene itself, which is what gets inherited, is unchanged.
+    pub fn expressed(&self, flip_odds: f64, rand: &mut Random) -> BitSet8 {
+        let mut result = self.value;
+        if flip_odds > 0.0 {
+            for i in 0..8 {
+                if rand.next_bool(flip_odds) {
+                    result.flip_bit(i);
+                }
+            }
+        }
+        result
+    }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct BitSet8 {
     pub bits: u8,
 }
@@ -332,6 +1412,14 @@ impl BitSet8 {
         Self::new(0)
     }
 
+    pub fn from_indices(indices: &[usize]) -> Self {
+        let mut result = Self::empty();
+        for index in indices {
+            result.set_bit(*index);
+        }
+        result
+    }
+
     pub fn random(bit_odds: f64, rand: &mut Random) -> Self {
         let mut result = Self::empty();
         for i in 0..8 {
@@ -354,19 +1442,32 @@ impl BitSet8 {
         self.bits ^= 1 << index;
     }
 
-    pub fn count_set_bits(&self) -> usize {
-        let mut result = 0;
-        for i in 0..8 {
-            if self.is_bit_set(i) {
-                result += 1;
-            }
-        }
-        result
+    pub fn count_ones(&self) -> usize {
+        self.bits.count_ones() as usize
     }
 
     pub fn count_matching_bits(&self, other: Self) -> usize {
         let mismatched_bits = Self::new(self.bits ^ other.bits);
-        8 - mismatched_bits.count_set_bits()
+        8 - mismatched_bits.count_ones()
+    }
+
+    pub fn union(&self, other: Self) -> Self {
+        Self::new(self.bits | other.bits)
+    }
+
+    pub fn intersection(&self, other: Self) -> Self {
+        Self::new(self.bits & other.bits)
+    }
+
+    // The bits set here but not in other.
+    pub fn difference(&self, other: Self) -> Self {
+        Self::new(self.bits & !other.bits)
+    }
+
+    // The indices of the set bits, in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + use<> {
+        let bits = *self;
+        (0..8).filter(move |index| bits.is_bit_set(*index))
     }
 
     pub fn nybbles(&self) -> (u8, u8) {
@@ -419,17 +1520,22 @@ impl BitCountsMap {
         } else if num_zeros == 0 {
             true
         } else {
-            let odds = num_ones as f64 / (num_ones + num_zeros) as f64;
-            rand.as_mut().unwrap().next_bool(odds)
+            match rand {
+                Some(rand) => rand.next_bool(num_ones as f64 / (num_ones + num_zeros) as f64),
+                // Without randomness (e.g. mixed genomes during a world's warm-up), go with the majority.
+                None => num_ones >= num_zeros,
+            }
         }
     }
 }
 
+#[cfg(feature = "std")]
 #[derive(Clone, Copy, Debug, Default)]
 pub struct FractionGene {
     pub value: f32,
 }
 
+#[cfg(feature = "std")]
 impl FractionGene {
     pub fn new(value: f32) -> Self {
         debug_assert!((0.0..=1.0).contains(&value));
@@ -451,20 +1557,46 @@ impl FractionGene {
             Self::new(average_value)
         }
     }
+
+    // The gene's value as read during an update, perturbed by normally distributed noise with
+    // the given standard deviation but kept within 0 to 1, for noisy gene expression.
+    pub fn expressed(&self, noise_stdev: f64, rand: &mut Random) -> f32 {
+        if noise_stdev > 0.0 {
+            rand.next_truncated_normal(self.value as f64, noise_stdev, 0.0..=1.0) as f32
+        } else {
+            self.value
+        }
+    }
 }
 
-#[derive(Debug)]
+// The determinism contract for updates: given a world's random number stream in the same
+// state, the same grid size and contents, and the same update schedule, an update has the
+// same result however many threads it runs on. Cell updates draw only from their row's
+// stream, which the grid forks from the world's stream, one per row in row order, before
+// updating the rows in parallel; and they read only the previous cells, so rows can't see
+// each other's progress. Only updates that break this, e.g. by sharing state between cells
+// outside the grid, can depend on thread scheduling; WorldGrid::set_verify_updates checks
+// each update against a single-threaded one to catch them.
+#[derive(Clone, Debug)]
 pub struct Random {
     rng: SmallRng,
 }
 
 impl Random {
+    #[cfg(feature = "std")]
     pub fn new() -> Self {
         Self {
             rng: SmallRng::from_rng(&mut rand::rng()),
         }
     }
 
+    // For reproducible runs, which Randoms from new() aren't.
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            rng: SmallRng::seed_from_u64(seed),
+        }
+    }
+
     pub fn fork(&mut self) -> Self {
         Self {
             rng: SmallRng::from_rng(&mut self.rng),
@@ -483,11 +1615,29 @@ impl Random {
         self.rng.random_range(range)
     }
 
+    #[cfg(feature = "std")]
     pub fn next_normal(&mut self, mean: f64, stdev: f64) -> f64 {
+        if strict_math::strict_math() {
+            return mean + stdev * self.next_strict_standard_normal();
+        }
         let distr = Normal::new(mean, stdev).unwrap();
         distr.sample(&mut self.rng)
     }
 
+    // Marsaglia's polar method, which needs only a log and a square root (see strict_math).
+    #[cfg(feature = "std")]
+    fn next_strict_standard_normal(&mut self) -> f64 {
+        loop {
+            let u: f64 = self.next_in_range(-1.0..1.0);
+            let v: f64 = self.next_in_range(-1.0..1.0);
+            let s = u * u + v * v;
+            if s > 0.0 && s < 1.0 {
+                return u * (-2.0 * strict_math::ln(s) / s).sqrt();
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
     pub fn next_truncated_normal(
         &mut self,
         mean: f64,
@@ -502,6 +1652,10 @@ impl Random {
         }
     }
 
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        items.shuffle(&mut self.rng);
+    }
+
     pub fn shuffle_color_rgb(&mut self, mut color: [u8; 3]) -> [u8; 3] {
         color.shuffle(&mut self.rng);
         color
@@ -514,6 +1668,7 @@ impl Random {
     }
 }
 
+#[cfg(feature = "std")]
 impl Default for Random {
     fn default() -> Self {
         Self::new()
@@ -565,6 +1720,43 @@ pub fn alpha_blend_with_background(above: [u8; 4], below: [u8; 4]) -> [u8; 4] {
     color_as_bytes(result)
 }
 
+// The color with its hue rotated by the given number of turns around the color wheel, keeping
+// its saturation, value, and alpha.
+#[cfg(feature = "std")]
+pub fn rotate_hue(color: [u8; 4], turns: f32) -> [u8; 4] {
+    let turns = turns.rem_euclid(1.0);
+    if turns == 0.0 {
+        return color;
+    }
+    let [red, green, blue, alpha] = color_as_fractions(color);
+    let max = red.max(green).max(blue);
+    let chroma = max - red.min(green).min(blue);
+    if chroma == 0.0 {
+        // Grays have no hue.
+        return color;
+    }
+    // The hue in sixths of a turn.
+    let hue = if max == red {
+        ((green - blue) / chroma).rem_euclid(6.0)
+    } else if max == green {
+        (blue - red) / chroma + 2.0
+    } else {
+        (red - green) / chroma + 4.0
+    };
+    let hue = (hue + turns * 6.0).rem_euclid(6.0);
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (red, green, blue) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let min = max - chroma;
+    color_as_bytes([red + min, green + min, blue + min, alpha])
+}
+
 fn color_as_fractions(color: [u8; 4]) -> [f32; 4] {
     let mut result: [f32; 4] = [0.0, 0.0, 0.0, 0.0];
     for i in 0..=3 {
@@ -0,0 +1,117 @@
+use crate::{GridCell, GridSize, Loc, WorldGrid};
+use arrayvec::ArrayVec;
+use std::fmt::Debug;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LifeEventKind {
+    Birth,
+    Death,
+}
+
+impl LifeEventKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Birth => "birth",
+            Self::Death => "death",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LifeEvent {
+    pub generation: u64,
+    pub loc: Loc,
+    pub kind: LifeEventKind,
+    pub genome: u64,
+    // For births, the genomes of the creatures that neighbored the cell in the previous
+    // generation, i.e. the candidate parents. Empty for deaths.
+    pub parent_genomes: ArrayVec<u64, 8>,
+}
+
+// Finds births and deaths by comparing each cell's creature genome (see GridCell::genome)
+// with the previous generation's. A creature replaced by a different one in a single step
+// counts as a death followed by a birth.
+pub struct LifeEventDetector {
+    size: GridSize,
+    genomes: Vec<Option<u64>>,
+}
+
+impl LifeEventDetector {
+    pub fn new<C>(grid: &WorldGrid<C>) -> Self
+    where
+        C: Clone + Debug + GridCell,
+    {
+        Self {
+            size: grid.size(),
+            genomes: Self::genomes(grid),
+        }
+    }
+
+    pub fn detect<C>(&mut self, generation: u64, grid: &WorldGrid<C>) -> Vec<LifeEvent>
+    where
+        C: Clone + Debug + GridCell,
+    {
+        let genomes = Self::genomes(grid);
+        let mut result = vec![];
+        for (index, (previous, current)) in self.genomes.iter().zip(genomes.iter()).enumerate() {
+            let loc = Loc::new(
+                index as u32 / self.size.width,
+                index as u32 % self.size.width,
+            );
+            match (*previous, *current) {
+                (None, Some(genome)) => result.push(self.birth(generation, loc, genome)),
+                (Some(genome), None) => result.push(Self::death(generation, loc, genome)),
+                (Some(previous), Some(current)) if previous != current => {
+                    result.push(Self::death(generation, loc, previous));
+                    result.push(self.birth(generation, loc, current));
+                }
+                _ => (),
+            }
+        }
+        self.genomes = genomes;
+        result
+    }
+
+    fn genomes<C>(grid: &WorldGrid<C>) -> Vec<Option<u64>>
+    where
+        C: Clone + Debug + GridCell,
+    {
+        grid.cells_iter().map(|cell| cell.genome()).collect()
+    }
+
+    fn birth(&self, generation: u64, loc: Loc, genome: u64) -> LifeEvent {
+        LifeEvent {
+            generation,
+            loc,
+            kind: LifeEventKind::Birth,
+            genome,
+            parent_genomes: self.neighbor_genomes(loc),
+        }
+    }
+
+    fn death(generation: u64, loc: Loc, genome: u64) -> LifeEvent {
+        LifeEvent {
+            generation,
+            loc,
+            kind: LifeEventKind::Death,
+            genome,
+            parent_genomes: ArrayVec::new(),
+        }
+    }
+
+    fn neighbor_genomes(&self, center: Loc) -> ArrayVec<u64, 8> {
+        let mut result = ArrayVec::new();
+        for row in center.row.saturating_sub(1)..(center.row + 2).min(self.size.height) {
+            for col in center.col.saturating_sub(1)..(center.col + 2).min(self.size.width) {
+                let loc = Loc::new(row, col);
+                if loc != center
+                    && let Some(index) = loc.grid_index(self.size)
+                    && let Some(genome) = self.genomes[index]
+                {
+                    result.push(genome);
+                }
+            }
+        }
+        result
+    }
+}
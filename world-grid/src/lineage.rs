@@ -0,0 +1,274 @@
+use crate::{GridCell, LifeEvent, LifeEventKind, WorldGrid};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+use std::io::{self, Write};
+
+// The ancestry of the genomes (see GridCell::genome) seen in a run, built from its births (see
+// LifeEventDetector). Creatures aren't told apart from others with the same genome, so this
+// is a tree of genomes: each new genome descends from the candidate parent whose genome
+// differs from it in the fewest bits, and clonal births add nothing. For exporting as Newick
+// or DOT to view the run's phylogeny in standard tree tools.
+#[derive(Clone, Debug, Default)]
+pub struct LineageTree {
+    nodes: BTreeMap<u64, LineageNode>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineageNode {
+    // None for the genomes of the founding creatures, and of creatures born with no
+    // creatures around them.
+    pub parent: Option<u64>,
+    // The generation the genome first appeared in.
+    pub origin: u64,
+}
+
+enum NewickStep {
+    // Opens a genome's subtree, after a comma unless it's the first of its siblings.
+    Open(u64, bool),
+    Close(u64),
+}
+
+impl LineageTree {
+    // Starts with the genomes of the grid's creatures as of the given generation as roots.
+    pub fn new<C>(generation: u64, grid: &WorldGrid<C>) -> Self
+    where
+        C: Clone + Debug + GridCell,
+    {
+        let mut result = Self::default();
+        for genome in grid.cells_iter().filter_map(|cell| cell.genome()) {
+            result.nodes.entry(genome).or_insert(LineageNode {
+                parent: None,
+                origin: generation,
+            });
+        }
+        result
+    }
+
+    // Adds the new genomes among the births. Candidate parents that the tree hasn't seen are
+    // passed over, so that no genome can become its own ancestor.
+    pub fn record(&mut self, events: &[LifeEvent]) {
+        for event in events {
+            if event.kind != LifeEventKind::Birth || self.nodes.contains_key(&event.genome) {
+                continue;
+            }
+            let parent = event
+                .parent_genomes
+                .iter()
+                .filter(|parent| self.nodes.contains_key(parent))
+                .min_by_key(|parent| (*parent ^ event.genome).count_ones())
+                .copied();
+            self.nodes.insert(
+                event.genome,
+                LineageNode {
+                    parent,
+                    origin: event.generation,
+                },
+            );
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, genome: u64) -> Option<&LineageNode> {
+        self.nodes.get(&genome)
+    }
+
+    // The genome, if the tree has it, followed by its ancestors from its parent back to its
+    // root.
+    pub fn ancestry(&self, genome: u64) -> impl Iterator<Item = u64> + '_ {
+        let mut next = self.nodes.contains_key(&genome).then_some(genome);
+        std::iter::from_fn(move || {
+            let result = next?;
+            next = self.nodes.get(&result).and_then(|node| node.parent);
+            Some(result)
+        })
+    }
+
+    // The subtree holding only the tips' ancestries, e.g. of the genomes still alive. With a
+    // root, e.g. a tagged genome, only its descendants among the tips are kept, and their
+    // ancestries stop at it.
+    pub fn pruned(&self, tips: &[u64], root: Option<u64>) -> Self {
+        let mut kept = BTreeSet::new();
+        for tip in tips {
+            let ancestry: Vec<u64> = match root {
+                Some(root) => {
+                    let mut ancestry = vec![];
+                    for genome in self.ancestry(*tip) {
+                        ancestry.push(genome);
+                        if genome == root {
+                            break;
+                        }
+                    }
+                    if ancestry.last() != Some(&root) {
+                        continue;
+                    }
+                    ancestry
+                }
+                None => self.ancestry(*tip).collect(),
+            };
+            kept.extend(ancestry);
+        }
+        let nodes = kept
+            .into_iter()
+            .map(|genome| {
+                let mut node = self.nodes[&genome];
+                if Some(genome) == root {
+                    node.parent = None;
+                }
+                (genome, node)
+            })
+            .collect();
+        Self { nodes }
+    }
+
+    // The most recent common ancestor of the genomes, i.e. the latest genome in all of their
+    // ancestries, which may be one of the genomes themselves. None if they descend from
+    // different roots. Its origin is the generation the genomes' lineages had all merged by.
+    pub fn mrca(&self, genomes: &[u64]) -> Option<u64> {
+        let (first, rest) = genomes.split_first()?;
+        let ancestries: Vec<BTreeSet<u64>> = rest
+            .iter()
+            .map(|genome| self.ancestry(*genome).collect())
+            .collect();
+        self.ancestry(*first)
+            .find(|genome| ancestries.iter().all(|ancestry| ancestry.contains(genome)))
+    }
+
+    // The generations at which the lineages of the tips, e.g. the genomes still alive, merge
+    // going back in time, most recent first: one for each merge, or several for a genome where
+    // several lineages merge at once. With n distinct tips from one root, there are n - 1. As
+    // creatures with the same genome aren't told apart, lineages are taken to merge at the
+    // origin of the genome they merge in, the earliest they could have. For comparing against
+    // the coalescent times expected from population-genetics theory.
+    pub fn coalescence_generations(&self, tips: &[u64]) -> Vec<u64> {
+        let tips: BTreeSet<u64> = tips.iter().copied().collect();
+        let tips: Vec<u64> = tips.into_iter().collect();
+        let tree = self.pruned(&tips, None);
+        let children = tree.children();
+        let mut result = vec![];
+        for (genome, node) in &tree.nodes {
+            let num_lineages = children.get(genome).map_or(0, Vec::len)
+                + tips.binary_search(genome).map_or(0, |_| 1);
+            for _ in 1..num_lineages {
+                result.push(node.origin);
+            }
+        }
+        result.sort_unstable_by(|a, b| b.cmp(a));
+        result
+    }
+
+    // Writes the tree in Newick format, with each genome's branch length the generations
+    // between its parent's origin and its own. Several roots are joined under an unnamed one.
+    pub fn write_newick<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let children = self.children();
+        let roots = self.roots();
+        if roots.len() > 1 {
+            write!(writer, "(")?;
+        }
+        let mut stack: Vec<NewickStep> = roots
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, root)| NewickStep::Open(*root, index == 0))
+            .collect();
+        while let Some(step) = stack.pop() {
+            match step {
+                NewickStep::Open(genome, first) => {
+                    if !first {
+                        write!(writer, ",")?;
+                    }
+                    match children.get(&genome) {
+                        Some(genome_children) => {
+                            write!(writer, "(")?;
+                            stack.push(NewickStep::Close(genome));
+                            stack.extend(
+                                genome_children
+                                    .iter()
+                                    .enumerate()
+                                    .rev()
+                                    .map(|(index, child)| NewickStep::Open(*child, index == 0)),
+                            );
+                        }
+                        None => self.write_newick_label(&mut writer, genome)?,
+                    }
+                }
+                NewickStep::Close(genome) => {
+                    write!(writer, ")")?;
+                    self.write_newick_label(&mut writer, genome)?;
+                }
+            }
+        }
+        if roots.len() > 1 {
+            write!(writer, ")")?;
+        }
+        writeln!(writer, ";")
+    }
+
+    // Writes the tree as a GraphViz DOT digraph, labeling each genome with its origin and each
+    // edge with its branch length.
+    pub fn write_dot<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "digraph lineage {{")?;
+        for (genome, node) in &self.nodes {
+            writeln!(
+                writer,
+                "  {} [label=\"{}\\ngeneration {}\"];",
+                genome, genome, node.origin
+            )?;
+        }
+        for (genome, node) in &self.nodes {
+            if let Some(parent) = node.parent {
+                writeln!(
+                    writer,
+                    "  {} -> {} [label=\"{}\"];",
+                    parent,
+                    genome,
+                    self.branch_length(*genome).unwrap_or(0)
+                )?;
+            }
+        }
+        writeln!(writer, "}}")
+    }
+
+    fn write_newick_label<W: Write>(&self, writer: &mut W, genome: u64) -> io::Result<()> {
+        write!(writer, "{}", genome)?;
+        if let Some(length) = self.branch_length(genome) {
+            write!(writer, ":{}", length)?;
+        }
+        Ok(())
+    }
+
+    // The generations from the genome's parent's origin to its own, if it has a parent.
+    fn branch_length(&self, genome: u64) -> Option<u64> {
+        let node = self.nodes.get(&genome)?;
+        let parent = self.nodes.get(&node.parent?)?;
+        Some(node.origin.saturating_sub(parent.origin))
+    }
+
+    // The genomes with no parent in the tree.
+    fn roots(&self) -> Vec<u64> {
+        self.nodes
+            .iter()
+            .filter(|(_, node)| {
+                node.parent
+                    .is_none_or(|parent| !self.nodes.contains_key(&parent))
+            })
+            .map(|(genome, _)| *genome)
+            .collect()
+    }
+
+    fn children(&self) -> BTreeMap<u64, Vec<u64>> {
+        let mut result: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+        for (genome, node) in &self.nodes {
+            if let Some(parent) = node.parent.filter(|parent| self.nodes.contains_key(parent)) {
+                result.entry(parent).or_default().push(*genome);
+            }
+        }
+        result
+    }
+}
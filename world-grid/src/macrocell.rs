@@ -0,0 +1,282 @@
+use crate::GridSize;
+use crate::pattern::{MAX_PATTERN_AREA, Pattern};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const HEADER: &str = "[M2] (evo-grid)";
+const LEAF_LEVEL: u32 = 3;
+const LEAF_SIZE: u32 = 1 << LEAF_LEVEL;
+
+// The smallest rectangle holding a node's live cells, relative to its top left, as the
+// inclusive [top, left, bottom, right], or None if it has none.
+type LiveBounds = Option<[u64; 4]>;
+
+// A node of a macrocell file's quadtree, referred to by later nodes by its 1-based line
+// number among the nodes, with 0 for an empty node of any size.
+enum Node {
+    // An 8x8 leaf, as the offsets of its live cells.
+    Leaf(Vec<(u64, u64)>),
+    // A 2x2 node of cell states, as in Golly's multistate files.
+    Cells([bool; 4]),
+    // Nodes of the next level down, in the order nw, ne, sw, se.
+    Quad(u32, [usize; 4]),
+}
+
+impl Node {
+    fn level(&self) -> u32 {
+        match self {
+            Self::Leaf(_) => LEAF_LEVEL,
+            Self::Cells(_) => 1,
+            Self::Quad(level, _) => *level,
+        }
+    }
+
+    // From the bounds of the nodes before it, so that a pattern's size is known without
+    // expanding its tree, which shared subtrees can make huge.
+    fn live_bounds(&self, node_bounds: &[LiveBounds]) -> LiveBounds {
+        let live_locs: Vec<(u64, u64)> = match self {
+            Self::Leaf(live) => live.clone(),
+            Self::Cells(states) => (0..4)
+                .filter(|quadrant| states[*quadrant])
+                .map(|quadrant| (quadrant as u64 / 2, quadrant as u64 % 2))
+                .collect(),
+            Self::Quad(level, children) => {
+                let half = 1 << (level - 1);
+                let mut corners = vec![];
+                for (quadrant, child) in children.iter().enumerate() {
+                    if let Some(Some([top, left, bottom, right])) =
+                        child.checked_sub(1).map(|index| node_bounds[index])
+                    {
+                        let top_offset = half * (quadrant as u64 / 2);
+                        let left_offset = half * (quadrant as u64 % 2);
+                        corners.push((top + top_offset, left + left_offset));
+                        corners.push((bottom + top_offset, right + left_offset));
+                    }
+                }
+                corners
+            }
+        };
+        Some([
+            live_locs.iter().map(|(row, _)| *row).min()?,
+            live_locs.iter().map(|(_, col)| *col).min()?,
+            live_locs.iter().map(|(row, _)| *row).max()?,
+            live_locs.iter().map(|(_, col)| *col).max()?,
+        ])
+    }
+}
+
+// Golly's macrocell format (.mc), which stores a pattern as a quadtree with identical
+// subtrees shared, so that large sparse patterns stay small. Loading one expands it into a
+// Pattern's full rectangle of cells, trimmed to the live cells.
+impl Pattern {
+    pub fn parse_macrocell(text: &str) -> Result<Self, String> {
+        let mut nodes: Vec<Node> = vec![];
+        let mut node_bounds = vec![];
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('[') || line.starts_with('#') {
+                continue;
+            }
+            let node = Self::parse_node(line, &nodes)
+                .map_err(|message| format!("line {}: {}", line_index + 1, message))?;
+            node_bounds.push(node.live_bounds(&node_bounds));
+            nodes.push(node);
+        }
+        // Checked before expanding the tree, so that the cells expanded are bounded too.
+        let Some(Some([top, left, bottom, right])) = node_bounds.last().copied() else {
+            return Ok(Self::new(GridSize::new(0, 0), vec![]));
+        };
+        let (width, height) = (right + 1 - left, bottom + 1 - top);
+        if width.saturating_mul(height) > MAX_PATTERN_AREA as u64 {
+            return Err(format!("{}x{} is too large to load", width, height));
+        }
+        let mut live = vec![false; (width * height) as usize];
+        let mut expansion = Expansion {
+            nodes: &nodes,
+            node_bounds: &node_bounds,
+            origin: (top, left),
+            width,
+            live: &mut live,
+        };
+        expansion.set_live(nodes.len(), 0, 0);
+        Ok(Self::new(GridSize::new(width as u32, height as u32), live))
+    }
+
+    fn parse_node(line: &str, nodes: &[Node]) -> Result<Node, String> {
+        if line.starts_with(['.', '*', '$']) {
+            let mut live = vec![];
+            let (mut row, mut col) = (0, 0);
+            for c in line.chars() {
+                match c {
+                    '.' => col += 1,
+                    '*' => {
+                        if row >= LEAF_SIZE as u64 || col >= LEAF_SIZE as u64 {
+                            return Err("leaf larger than 8x8".to_string());
+                        }
+                        live.push((row, col));
+                        col += 1;
+                    }
+                    '$' => {
+                        row += 1;
+                        col = 0;
+                    }
+                    _ => return Err(format!("unexpected '{}' in leaf", c)),
+                }
+                if row > LEAF_SIZE as u64 || col > LEAF_SIZE as u64 {
+                    return Err("leaf larger than 8x8".to_string());
+                }
+            }
+            return Ok(Node::Leaf(live));
+        }
+        let fields: Vec<u64> = line
+            .split_whitespace()
+            .map(|field| {
+                field
+                    .parse()
+                    .map_err(|_| format!("invalid field {}", field))
+            })
+            .collect::<Result<_, _>>()?;
+        let [level, nw, ne, sw, se] = fields[..] else {
+            return Err("expected a level and four children".to_string());
+        };
+        let children = [nw, ne, sw, se].map(|child| child as usize);
+        match level {
+            1 => Ok(Node::Cells(children.map(|state| state != 0))),
+            2..=63 => {
+                for child in children.into_iter().filter(|child| *child != 0) {
+                    match nodes.get(child - 1) {
+                        Some(node) if node.level() == level as u32 - 1 => (),
+                        _ => return Err(format!("bad child {} for level {}", child, level)),
+                    }
+                }
+                Ok(Node::Quad(level as u32, children))
+            }
+            _ => Err(format!("unsupported level {}", level)),
+        }
+    }
+
+    pub fn to_macrocell(&self) -> String {
+        let size = self.size();
+        let mut level = LEAF_LEVEL;
+        while (1u64 << level) < size.width.max(size.height) as u64 {
+            level += 1;
+        }
+        let mut encoder = MacrocellEncoder::default();
+        let root = encoder.encode(self, level, 0, 0);
+        // Without a rule line, Golly runs the pattern under Conway's rules.
+        let mut result = format!("{}\n", HEADER);
+        if let Some(name) = &self.name {
+            writeln!(result, "#C {}", name).unwrap();
+        }
+        for line in &encoder.lines {
+            writeln!(result, "{}", line).unwrap();
+        }
+        if root == 0 {
+            // Golly expects at least one node.
+            writeln!(result, "{} 0 0 0 0", LEAF_LEVEL + 1).unwrap();
+        }
+        result
+    }
+}
+
+// Expands a macrocell tree's live cells into the rectangle of cells bounding them.
+struct Expansion<'a> {
+    nodes: &'a [Node],
+    node_bounds: &'a [LiveBounds],
+    // The rectangle's top left, in the tree's coordinates.
+    origin: (u64, u64),
+    width: u64,
+    live: &'a mut [bool],
+}
+
+impl Expansion<'_> {
+    // Skips nodes without live cells, so the time taken is bounded by the live cell count.
+    fn set_live(&mut self, index: usize, top: u64, left: u64) {
+        if index == 0 || self.node_bounds[index - 1].is_none() {
+            return;
+        }
+        match &self.nodes[index - 1] {
+            Node::Leaf(live) => {
+                for (row, col) in live {
+                    self.set_cell(top + row, left + col);
+                }
+            }
+            Node::Cells(states) => {
+                for (quadrant, state) in states.iter().enumerate() {
+                    if *state {
+                        self.set_cell(top + quadrant as u64 / 2, left + quadrant as u64 % 2);
+                    }
+                }
+            }
+            Node::Quad(level, children) => {
+                let half = 1 << (level - 1);
+                for (quadrant, child) in children.iter().enumerate() {
+                    let top = top + half * (quadrant as u64 / 2);
+                    let left = left + half * (quadrant as u64 % 2);
+                    self.set_live(*child, top, left);
+                }
+            }
+        }
+    }
+
+    fn set_cell(&mut self, row: u64, col: u64) {
+        let (top, left) = self.origin;
+        self.live[((row - top) * self.width + col - left) as usize] = true;
+    }
+}
+
+// Writes each distinct non-empty node once, children before their parents.
+#[derive(Default)]
+struct MacrocellEncoder {
+    lines: Vec<String>,
+    indexes: HashMap<String, usize>,
+}
+
+impl MacrocellEncoder {
+    // Returns the node's index, or 0 if it's empty.
+    fn encode(&mut self, pattern: &Pattern, level: u32, top: u32, left: u32) -> usize {
+        let line = if level == LEAF_LEVEL {
+            match Self::leaf_line(pattern, top, left) {
+                Some(line) => line,
+                None => return 0,
+            }
+        } else {
+            let half = 1 << (level - 1);
+            let children = [(0, 0), (0, half), (half, 0), (half, half)]
+                .map(|(row, col)| self.encode(pattern, level - 1, top + row, left + col));
+            if children == [0; 4] {
+                return 0;
+            }
+            let [nw, ne, sw, se] = children;
+            format!("{} {} {} {} {}", level, nw, ne, sw, se)
+        };
+        if let Some(index) = self.indexes.get(&line) {
+            return *index;
+        }
+        self.lines.push(line.clone());
+        self.indexes.insert(line, self.lines.len());
+        self.lines.len()
+    }
+
+    // Rows of '.' and '*', each ending in '$', without trailing dead cells or empty rows.
+    fn leaf_line(pattern: &Pattern, top: u32, left: u32) -> Option<String> {
+        let size = pattern.size();
+        let is_live =
+            |row: u32, col: u32| row < size.height && col < size.width && pattern.is_live(row, col);
+        let rows: Vec<String> = (top..top + LEAF_SIZE)
+            .map(|row| {
+                let line: String = (left..left + LEAF_SIZE)
+                    .map(|col| if is_live(row, col) { '*' } else { '.' })
+                    .collect();
+                line.trim_end_matches('.').to_string()
+            })
+            .collect();
+        let num_rows = rows.iter().rposition(|row| !row.is_empty())? + 1;
+        Some(
+            rows[..num_rows]
+                .iter()
+                .map(|row| format!("{}$", row))
+                .collect(),
+        )
+    }
+}
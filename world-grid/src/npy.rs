@@ -0,0 +1,63 @@
+use crate::{GridCell, WorldGrid};
+use std::fmt::Debug;
+use std::io::{self, Write};
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+const NPY_HEADER_ALIGNMENT: usize = 64;
+
+impl<C> WorldGrid<C>
+where
+    C: Clone + Debug + GridCell,
+{
+    // Writes one of the cell type's export fields (see GridCell::field_names) as a
+    // height x width float32 array in NumPy .npy format.
+    pub fn write_field_npy<W: Write>(&self, field: usize, writer: W) -> io::Result<()> {
+        let values: Vec<f32> = self
+            .cells_iter()
+            .map(|cell| cell.field_value(field))
+            .collect();
+        let size = self.size();
+        write_npy_f32(
+            writer,
+            &[size.height as usize, size.width as usize],
+            &values,
+        )
+    }
+}
+
+// Format described at https://numpy.org/doc/stable/reference/generated/numpy.lib.format.html
+pub fn write_npy_f32<W: Write>(mut writer: W, shape: &[usize], values: &[f32]) -> io::Result<()> {
+    debug_assert_eq!(shape.iter().product::<usize>(), values.len());
+
+    let shape_text = match shape {
+        [len] => format!("({},)", len),
+        _ => format!(
+            "({})",
+            shape
+                .iter()
+                .map(|len| len.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut header = format!(
+        "{{'descr': '<f4', 'fortran_order': False, 'shape': {}, }}",
+        shape_text
+    );
+    // Pad with spaces so that the data starts on an aligned boundary, ending with a newline.
+    let preamble_len = NPY_MAGIC.len() + 2 + 2;
+    let unpadded_len = preamble_len + header.len() + 1;
+    let padding =
+        (NPY_HEADER_ALIGNMENT - unpadded_len % NPY_HEADER_ALIGNMENT) % NPY_HEADER_ALIGNMENT;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+
+    writer.write_all(NPY_MAGIC)?;
+    writer.write_all(&[1, 0])?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    for value in values {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    writer.flush()
+}
@@ -0,0 +1,280 @@
+use crate::{GridCell, GridSize, Loc, WorldGrid};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const LIVE_CHAR: char = 'O';
+const DEAD_CHAR: char = '.';
+// The most cells a loaded pattern may have, so that a malformed or malicious pattern file is
+// rejected rather than exhausting memory.
+pub(crate) const MAX_PATTERN_AREA: usize = 1 << 26;
+
+// A rectangle of live and dead cells in the Life plaintext format (.cells), e.g.
+//
+//     !Name: Glider
+//     .O.
+//     ..O
+//     OOO
+//
+// Lines starting with '!' are comments, and short lines are padded with dead cells. A cell is
+// live if it has a creature (see GridCell::genome).
+//
+// Multistate patterns, e.g. Wireworld circuits, hold a state per cell instead, from 0 for
+// dead or empty up (see GridCell::pattern_state). They load and save as RLE (see parse_rle),
+// and other formats see them as their live cells, i.e. those in states other than 0.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    pub name: Option<String>,
+    size: GridSize,
+    // Row-major, and only 0 for dead or 1 for live unless multistate.
+    states: Vec<u8>,
+    multistate: bool,
+}
+
+impl Pattern {
+    pub(crate) fn new(size: GridSize, live: Vec<bool>) -> Self {
+        Self {
+            name: None,
+            size,
+            states: live.into_iter().map(u8::from).collect(),
+            multistate: false,
+        }
+    }
+
+    pub fn size(&self) -> GridSize {
+        self.size
+    }
+
+    pub fn is_multistate(&self) -> bool {
+        self.multistate
+    }
+
+    pub fn state(&self, row: u32, col: u32) -> u8 {
+        self.states[(row * self.size.width + col) as usize]
+    }
+
+    pub fn is_live(&self, row: u32, col: u32) -> bool {
+        self.state(row, col) != 0
+    }
+
+    pub fn num_live(&self) -> usize {
+        self.states.iter().filter(|state| **state != 0).count()
+    }
+
+    // Files ending in .mc are read as macrocells (see parse_macrocell), files ending in .rle
+    // as RLE (see parse_rle), and others as plaintext.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let pattern = match Self::extension(path) {
+            Some("mc") => Self::parse_macrocell(&text),
+            Some("rle") => Self::parse_rle(&text),
+            _ => Self::parse(&text),
+        };
+        pattern.map_err(|message| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid pattern file {}: {}", path.display(), message),
+            )
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let text = match Self::extension(path) {
+            Some("mc") => self.to_macrocell(),
+            Some("rle") => self.to_rle(),
+            _ => self.to_string(),
+        };
+        fs::write(path, text)
+    }
+
+    fn extension(path: &Path) -> Option<&str> {
+        path.extension().and_then(|extension| extension.to_str())
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut name = None;
+        let mut rows: Vec<Vec<u8>> = vec![];
+        for (line_index, line) in text.lines().enumerate() {
+            if let Some(comment) = line.strip_prefix('!') {
+                if let Some(pattern_name) = comment.strip_prefix("Name:") {
+                    name = Some(pattern_name.trim().to_string());
+                }
+                continue;
+            }
+            let row = line
+                .trim_end()
+                .chars()
+                .map(|c| match c {
+                    // Some files mark live cells with '*', from the older Life 1.05 style.
+                    LIVE_CHAR | '*' => Ok(1),
+                    DEAD_CHAR => Ok(0),
+                    _ => Err(format!("line {}: unexpected '{}'", line_index + 1, c)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.push(row);
+        }
+        let mut result = Self::from_rows(&rows, false)?;
+        result.name = name;
+        Ok(result)
+    }
+
+    // Pads short rows with dead cells.
+    pub(crate) fn from_rows(rows: &[Vec<u8>], multistate: bool) -> Result<Self, String> {
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        Self::check_size(width, rows.len())?;
+        let mut states = Vec::with_capacity(width * rows.len());
+        for row in rows {
+            states.extend(row);
+            states.extend((row.len()..width).map(|_| 0));
+        }
+        Ok(Self {
+            name: None,
+            size: GridSize::new(width as u32, rows.len() as u32),
+            states,
+            multistate,
+        })
+    }
+
+    // The rows with cells, as (index, cells), within a pattern of the given size whose other
+    // cells are dead, e.g. for formats that skip runs of empty rows.
+    pub(crate) fn from_sparse_rows(
+        size: GridSize,
+        rows: &[(usize, Vec<u8>)],
+        multistate: bool,
+    ) -> Result<Self, String> {
+        let (width, height) = (size.width as usize, size.height as usize);
+        Self::check_size(width, height)?;
+        let mut states = vec![0; width * height];
+        for (index, cells) in rows {
+            let start = index * width;
+            states[start..start + cells.len()].copy_from_slice(cells);
+        }
+        Ok(Self {
+            name: None,
+            size,
+            states,
+            multistate,
+        })
+    }
+
+    pub(crate) fn check_size(width: usize, height: usize) -> Result<(), String> {
+        if width.max(height) > MAX_PATTERN_AREA || width * height > MAX_PATTERN_AREA {
+            return Err(format!("{}x{} is too large to load", width, height));
+        }
+        Ok(())
+    }
+
+    // The smallest rectangle holding all the live cells.
+    pub fn trimmed(&self) -> Self {
+        let live_locs = || {
+            (0..self.size.height)
+                .flat_map(|row| (0..self.size.width).map(move |col| (row, col)))
+                .filter(|(row, col)| self.is_live(*row, *col))
+        };
+        let (Some(min_row), Some(max_row), Some(min_col), Some(max_col)) = (
+            live_locs().map(|(row, _)| row).min(),
+            live_locs().map(|(row, _)| row).max(),
+            live_locs().map(|(_, col)| col).min(),
+            live_locs().map(|(_, col)| col).max(),
+        ) else {
+            return Self {
+                name: self.name.clone(),
+                size: GridSize::new(0, 0),
+                states: vec![],
+                multistate: self.multistate,
+            };
+        };
+        let size = GridSize::new(max_col + 1 - min_col, max_row + 1 - min_row);
+        Self {
+            name: self.name.clone(),
+            size,
+            states: (min_row..=max_row)
+                .flat_map(|row| (min_col..=max_col).map(move |col| self.state(row, col)))
+                .collect(),
+            multistate: self.multistate,
+        }
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.name {
+            writeln!(f, "!Name: {}", name)?;
+        }
+        for row in 0..self.size.height {
+            let line: String = (0..self.size.width)
+                .map(|col| {
+                    if self.is_live(row, col) {
+                        LIVE_CHAR
+                    } else {
+                        DEAD_CHAR
+                    }
+                })
+                .collect();
+            writeln!(f, "{}", line.trim_end_matches(DEAD_CHAR))?;
+        }
+        Ok(())
+    }
+}
+
+impl<C> WorldGrid<C>
+where
+    C: Clone + Debug + GridCell,
+{
+    // The cells of the rectangle with the given top left and size, clipped to the grid.
+    pub fn extract_pattern(&self, top_left: Loc, size: GridSize) -> Pattern {
+        let grid_size = self.size();
+        let size = GridSize::new(
+            size.width.min(grid_size.width.saturating_sub(top_left.col)),
+            size.height
+                .min(grid_size.height.saturating_sub(top_left.row)),
+        );
+        let cells = self.cells_iter().as_slice();
+        let states = (0..size.height)
+            .flat_map(|row| (0..size.width).map(move |col| (row, col)))
+            .map(|(row, col)| {
+                let loc = Loc::new(top_left.row + row, top_left.col + col);
+                cells[loc.grid_index(grid_size).unwrap()].pattern_state()
+            })
+            .collect();
+        Pattern {
+            name: None,
+            size,
+            states,
+            multistate: C::num_pattern_states() > 2,
+        }
+    }
+
+    // Places the pattern centered on center, clipped to the grid, giving its live cells
+    // creatures with the given genome and removing the creatures from its dead cells, or, for a
+    // multistate pattern, setting each cell to its state (see GridCell::set_pattern_state).
+    // Returns the number of live cells placed.
+    pub fn place_pattern(&mut self, pattern: &Pattern, center: Loc, genome: u64) -> usize {
+        let size = pattern.size();
+        let top = center.row as i64 - (size.height / 2) as i64;
+        let left = center.col as i64 - (size.width / 2) as i64;
+        let mut placed = 0;
+        for row in 0..size.height {
+            for col in 0..size.width {
+                let (grid_row, grid_col) = (top + row as i64, left + col as i64);
+                if grid_row < 0 || grid_col < 0 {
+                    continue;
+                }
+                let Some(cell) = self.cell_mut(Loc::new(grid_row as u32, grid_col as u32)) else {
+                    continue;
+                };
+                let state = pattern.state(row, col);
+                if pattern.is_multistate() {
+                    cell.set_pattern_state(state, genome);
+                } else if state != 0 {
+                    cell.set_genome(genome);
+                } else {
+                    cell.remove_creature();
+                }
+                placed += (state != 0) as usize;
+            }
+        }
+        placed
+    }
+}
@@ -0,0 +1,18 @@
+use std::io::{self, Write};
+
+// Writes an image's 8-bit pixels, row by row, as a PNG: RGBA with four bytes per pixel, or
+// RGB with three, e.g. for frames of a video.
+pub fn write_png<W: Write>(writer: W, width: u32, height: u32, pixels: &[u8]) -> io::Result<()> {
+    let num_pixels = width as usize * height as usize;
+    let color_type = if pixels.len() == 4 * num_pixels {
+        png::ColorType::Rgba
+    } else {
+        assert_eq!(pixels.len(), 3 * num_pixels, "Wrong number of pixel bytes");
+        png::ColorType::Rgb
+    };
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(color_type);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(pixels)?;
+    Ok(())
+}
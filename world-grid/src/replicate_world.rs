@@ -0,0 +1,221 @@
+use crate::snapshot::{CellCodec, SnapshotError, SnapshotReader, SnapshotWriter};
+use crate::{
+    GridCell, GridSettings, GridSize, Loc, Neighborhood, Random, StepStats, UpdateSchedule, World,
+    WorldGrid, WorldMetric,
+};
+
+// Cells between the panels.
+const PANEL_GAP: u32 = 2;
+const DIVERGED_COLOR: [u8; 4] = [0xff, 0x00, 0xff, 0xff];
+// How much the diff panel dims the cells where the replicates still agree.
+const AGREED_DIMMING: u8 = 4;
+
+// Runs two replicates of a world from the same state, including the state of their random
+// number generators, that differ only in their update schedules, and shows them side by side
+// with a third panel marking the cells where their states have diverged. The world params are
+// shared by the whole process, so they can't differ between the replicates. As with
+// LayeredWorld, the grid is only a copy of the replicates' cells, so they can't be edited.
+pub struct ReplicateWorld<W: World + Clone> {
+    original: W,
+    variant: W,
+    variant_schedule: UpdateSchedule,
+    grid: WorldGrid<ReplicateCell>,
+    step: u64,
+    first_divergence_step: Option<u64>,
+}
+
+impl<W: World + Clone> ReplicateWorld<W> {
+    pub fn new(original: W, variant_schedule: UpdateSchedule) -> Self {
+        let size = original.grid().size();
+        let grid_size = GridSize::new(3 * size.width + 2 * PANEL_GAP, size.height);
+        let mut result = Self {
+            variant: Self::variant_of(&original, variant_schedule),
+            original,
+            variant_schedule,
+            grid: WorldGrid::new(grid_size),
+            step: 0,
+            first_divergence_step: None,
+        };
+        result.composite();
+        result
+    }
+
+    fn variant_of(original: &W, variant_schedule: UpdateSchedule) -> W {
+        let mut result = original.clone();
+        result.apply_grid_settings(&GridSettings {
+            update_schedule: Some(variant_schedule),
+            ..GridSettings::default()
+        });
+        result
+    }
+
+    pub fn original(&self) -> &W {
+        &self.original
+    }
+
+    pub fn variant(&self) -> &W {
+        &self.variant
+    }
+
+    // The number of cells whose states differ between the replicates.
+    pub fn num_diverged(&self) -> usize {
+        self.original
+            .grid()
+            .cells_iter()
+            .zip(self.variant.grid().cells_iter())
+            .filter(|(original, variant)| Self::diverged(*original, *variant))
+            .count()
+    }
+
+    fn diverged<C: GridCell>(original: &C, variant: &C) -> bool {
+        original.genome() != variant.genome() || original.color_rgba() != variant.color_rgba()
+    }
+
+    fn composite(&mut self) {
+        let width = self.original.grid().size().width;
+        let diff_col = 2 * (width + PANEL_GAP);
+        for (index, (original, variant)) in self
+            .original
+            .grid()
+            .cells_iter()
+            .zip(self.variant.grid().cells_iter())
+            .enumerate()
+        {
+            let loc = Loc::new(index as u32 / width, index as u32 % width);
+            let diff_color = if Self::diverged(original, variant) {
+                DIVERGED_COLOR
+            } else {
+                let [red, green, blue, _] = original.color_rgba();
+                [
+                    red / AGREED_DIMMING,
+                    green / AGREED_DIMMING,
+                    blue / AGREED_DIMMING,
+                    0xff,
+                ]
+            };
+            for (col, color) in [
+                (loc.col, original.color_rgba()),
+                (width + PANEL_GAP + loc.col, variant.color_rgba()),
+                (diff_col + loc.col, diff_color),
+            ] {
+                self.grid.cells[Loc::new(loc.row, col)].color = color;
+            }
+        }
+        self.grid.mark_all_changed();
+    }
+
+    fn check_divergence(&mut self) {
+        if self.first_divergence_step.is_none() && self.num_diverged() > 0 {
+            self.first_divergence_step = Some(self.step);
+            println!("Replicates diverged at step {}", self.step);
+        }
+    }
+}
+
+impl<W: World + Clone> World for ReplicateWorld<W> {
+    fn grid(&self) -> &WorldGrid<impl GridCell> {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut WorldGrid<impl GridCell> {
+        &mut self.grid
+    }
+
+    fn update(&mut self) {
+        self.original.update();
+        self.variant.update();
+        self.step += 1;
+        self.check_divergence();
+        self.composite();
+    }
+
+    fn reset(&mut self) {
+        self.original.reset();
+        self.variant = Self::variant_of(&self.original, self.variant_schedule);
+        self.step = 0;
+        self.first_divergence_step = None;
+        self.composite();
+    }
+
+    fn metrics(&self) -> Vec<WorldMetric> {
+        let mut result = self.original.metrics();
+        result.extend(self.variant.metrics());
+        let num_cells = self.original.grid().num_cells();
+        result.push(WorldMetric::new(
+            "diverged_fraction",
+            self.num_diverged() as f64 / num_cells as f64,
+        ));
+        result
+    }
+
+    fn generation(&self) -> u64 {
+        self.step
+    }
+
+    // The original's stats, since the replicates only differ once they diverge.
+    fn step_stats(&self) -> StepStats {
+        StepStats {
+            generation: self.step,
+            ..self.original.step_stats()
+        }
+    }
+
+    // The variant keeps its own schedule.
+    fn apply_grid_settings(&mut self, settings: &GridSettings) {
+        self.original.apply_grid_settings(settings);
+        self.variant.apply_grid_settings(&GridSettings {
+            update_schedule: Some(self.variant_schedule),
+            ..settings.clone()
+        });
+    }
+
+    fn is_editable(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ReplicateCell {
+    color: [u8; 4],
+}
+
+impl CellCodec for ReplicateCell {
+    const FORMAT_NAME: &'static str = "replicate";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_bytes(&self.color);
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        Ok(Self {
+            color: reader.read_array()?,
+        })
+    }
+}
+
+impl GridCell for ReplicateCell {
+    fn color_rgba(&self) -> [u8; 4] {
+        self.color
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    // The replicates update themselves; see ReplicateWorld::update.
+    fn update(
+        &self,
+        _neighborhood: &Neighborhood<ReplicateCell>,
+        _next_cell: &mut ReplicateCell,
+        _rand: &mut Option<Random>,
+    ) {
+    }
+
+    fn debug_print(&self, row: u32, col: u32) {
+        println!("({}, {}): Color: {:?}", row, col, self.color);
+    }
+
+    fn is_inert(&self) -> bool {
+        true
+    }
+}
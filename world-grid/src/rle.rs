@@ -0,0 +1,205 @@
+use crate::GridSize;
+use crate::pattern::{MAX_PATTERN_AREA, Pattern};
+use std::fmt::Write;
+use std::mem;
+
+// Golly and most pattern websites wrap RLE lines at this length.
+const MAX_LINE_LENGTH: usize = 70;
+// The highest state with a single-letter tag in multistate RLE, X.
+const MAX_MULTISTATE: u8 = 24;
+
+// The run-length encoded format (.rle) that Golly and most pattern collections use, e.g.
+//
+//     #N Glider
+//     x = 3, y = 3, rule = B3/S23
+//     bo$2bo$3o!
+//
+// where each run is an optional count and a tag: b for dead cells, o for live ones, and $ for
+// the ends of rows. Multistate patterns, as Golly writes them for rules like WireWorld, tag
+// cells in state 0 with . and those in states 1 through 24 with A through X. Any other letter
+// is read as a live cell.
+impl Pattern {
+    pub fn parse_rle(text: &str) -> Result<Self, String> {
+        let mut name = None;
+        let mut header_size = GridSize::new(0, 0);
+        // Only the rows with cells, as (index, cells), so that long runs of empty rows take no
+        // memory until the pattern is built.
+        let mut rows: Vec<(usize, Vec<u8>)> = vec![];
+        let mut row = (0, vec![]);
+        let mut width = 0;
+        let mut multistate = false;
+        let mut count: Option<usize> = None;
+        'lines: for (line_index, line) in text.lines().enumerate() {
+            let at_line = |message| format!("line {}: {}", line_index + 1, message);
+            let line = line.trim();
+            if let Some(comment) = line.strip_prefix('#') {
+                if let Some(pattern_name) = comment.strip_prefix('N') {
+                    name = Some(pattern_name.trim().to_string());
+                }
+                continue;
+            }
+            if line.starts_with('x') {
+                header_size = Self::parse_rle_header(line)
+                    .ok_or_else(|| format!("line {}: invalid header", line_index + 1))?;
+                Self::check_size(header_size.width as usize, header_size.height as usize)
+                    .map_err(at_line)?;
+                continue;
+            }
+            for c in line.chars() {
+                match c {
+                    '0'..='9' => {
+                        let digit = c as usize - '0' as usize;
+                        let run = count.unwrap_or(0) * 10 + digit;
+                        if run > MAX_PATTERN_AREA {
+                            return Err(at_line("run too long".to_string()));
+                        }
+                        count = Some(run);
+                        continue;
+                    }
+                    '!' => break 'lines,
+                    '$' => {
+                        let next_index = row.0 + count.unwrap_or(1);
+                        Self::check_size(width, next_index + 1).map_err(at_line)?;
+                        let (index, cells) = mem::replace(&mut row, (next_index, vec![]));
+                        if !cells.is_empty() {
+                            rows.push((index, cells));
+                        }
+                    }
+                    c if c.is_ascii_alphabetic() || c == '.' => {
+                        let state = match c {
+                            'b' | '.' => 0,
+                            'A'..='X' => {
+                                multistate = true;
+                                c as u8 - b'A' + 1
+                            }
+                            _ => 1,
+                        };
+                        width = width.max(row.1.len() + count.unwrap_or(1));
+                        Self::check_size(width, row.0 + 1).map_err(at_line)?;
+                        row.1.extend((0..count.unwrap_or(1)).map(|_| state));
+                    }
+                    c if c.is_whitespace() => continue,
+                    _ => return Err(at_line(format!("unexpected '{}'", c))),
+                }
+                count = None;
+            }
+        }
+        let height = row.0 + 1;
+        if !row.1.is_empty() {
+            rows.push(row);
+        }
+        // Keeps the trailing dead rows and columns that the header declares.
+        let size = GridSize::new(
+            width.max(header_size.width as usize) as u32,
+            height.max(header_size.height as usize) as u32,
+        );
+        let mut result = Self::from_sparse_rows(size, &rows, multistate)?;
+        result.name = name;
+        Ok(result)
+    }
+
+    // E.g. "x = 3, y = 3, rule = B3/S23".
+    fn parse_rle_header(line: &str) -> Option<GridSize> {
+        let mut width = None;
+        let mut height = None;
+        for field in line.split(',') {
+            let (key, value) = field.split_once('=')?;
+            match key.trim() {
+                "x" => width = value.trim().parse().ok(),
+                "y" => height = value.trim().parse().ok(),
+                _ => (),
+            }
+        }
+        Some(GridSize::new(width?, height?))
+    }
+
+    pub fn to_rle(&self) -> String {
+        let size = self.size();
+        let mut result = String::new();
+        if let Some(name) = &self.name {
+            writeln!(result, "#N {}", name).unwrap();
+        }
+        // Multistate patterns don't say which rule they're for.
+        if self.is_multistate() {
+            writeln!(result, "x = {}, y = {}", size.width, size.height).unwrap();
+        } else {
+            writeln!(
+                result,
+                "x = {}, y = {}, rule = B3/S23",
+                size.width, size.height
+            )
+            .unwrap();
+        }
+        let mut line = String::new();
+        for run in self.rle_runs() {
+            if line.len() + run.len() > MAX_LINE_LENGTH {
+                writeln!(result, "{}", line).unwrap();
+                line.clear();
+            }
+            line.push_str(&run);
+        }
+        writeln!(result, "{}", line).unwrap();
+        result
+    }
+
+    // The runs without the header or line breaks, e.g. for a single line of text. Reads back
+    // with parse_rle, except for any trailing dead rows and columns.
+    pub fn rle_body(&self) -> String {
+        self.rle_runs().concat()
+    }
+
+    fn rle_runs(&self) -> Vec<String> {
+        let size = self.size();
+        let mut result = vec![];
+        let mut pending_rows = 0;
+        for row in 0..size.height {
+            let row_runs = self.row_runs(row);
+            if row_runs.is_empty() {
+                pending_rows += 1;
+                continue;
+            }
+            if pending_rows > 0 {
+                result.push(Self::run(pending_rows, '$'));
+            }
+            result.extend(row_runs);
+            pending_rows = 1;
+        }
+        result.push("!".to_string());
+        result
+    }
+
+    // The runs of the row, leaving off its trailing dead cells.
+    fn row_runs(&self, row: u32) -> Vec<String> {
+        let mut result = vec![];
+        let mut col = 0;
+        let width = self.size().width;
+        while col < width {
+            let state = self.state(row, col);
+            let start = col;
+            while col < width && self.state(row, col) == state {
+                col += 1;
+            }
+            if state != 0 || col < width {
+                result.push(Self::run(col - start, self.tag(state)));
+            }
+        }
+        result
+    }
+
+    fn tag(&self, state: u8) -> char {
+        match (self.is_multistate(), state) {
+            (false, 0) => 'b',
+            (false, _) => 'o',
+            (true, 0) => '.',
+            (true, state) => (b'A' + state.min(MAX_MULTISTATE) - 1) as char,
+        }
+    }
+
+    fn run(count: u32, tag: char) -> String {
+        if count == 1 {
+            tag.to_string()
+        } else {
+            format!("{}{}", count, tag)
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use crate::{GridCell, Loc, SnapshotError, WorldGrid};
+use std::fmt::Debug;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// A single creature's genome, saved so it can be reinjected into another run. Stored as a
+// one-line text file holding the cell format name and the genome in hex, e.g. "evo-conway 0x604".
+#[derive(Clone, Debug, PartialEq)]
+pub struct Seed {
+    pub format_name: String,
+    pub genome: u64,
+}
+
+impl Seed {
+    pub fn new(format_name: &str, genome: u64) -> Self {
+        Self {
+            format_name: format_name.to_string(),
+            genome,
+        }
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("{}-{:x}.seed", self.format_name, self.genome)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, format!("{} {:#x}\n", self.format_name, self.genome))
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid seed file {}", path.display()),
+            )
+        })
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let (format_name, genome) = text.trim().split_once(' ')?;
+        let genome = u64::from_str_radix(genome.trim().strip_prefix("0x")?, 16).ok()?;
+        Some(Self::new(format_name, genome))
+    }
+}
+
+impl<C> WorldGrid<C>
+where
+    C: Clone + Debug + GridCell,
+{
+    pub fn extract_seed(&self, loc: Loc) -> Option<Seed> {
+        let index = loc.grid_index(self.size())?;
+        let genome = self.cells_iter().nth(index)?.genome()?;
+        Some(Seed::new(C::FORMAT_NAME, genome))
+    }
+
+    // Places up to count copies of the seed's creature in the cells nearest to center,
+    // replacing whatever creatures were there. Returns the number placed.
+    pub fn inject_seed(
+        &mut self,
+        seed: &Seed,
+        center: Loc,
+        count: usize,
+    ) -> Result<usize, SnapshotError> {
+        if seed.format_name != C::FORMAT_NAME {
+            return Err(SnapshotError::WrongCellFormat(seed.format_name.clone()));
+        }
+        let size = self.size();
+        let max_radius = size.width.max(size.height);
+        let mut placed = 0;
+        for radius in 0..=max_radius {
+            for loc in Self::ring_locs(center, radius) {
+                if placed == count {
+                    return Ok(placed);
+                }
+                if let Some(cell) = self.cell_mut(loc) {
+                    cell.set_genome(seed.genome);
+                    placed += 1;
+                }
+            }
+        }
+        Ok(placed)
+    }
+
+    // The locs at a Chebyshev distance of radius from center, some possibly off the grid.
+    fn ring_locs(center: Loc, radius: u32) -> Vec<Loc> {
+        let radius = radius as i64;
+        let mut result = vec![];
+        for row in -radius..=radius {
+            for col in -radius..=radius {
+                if row.abs() == radius || col.abs() == radius {
+                    let row = center.row as i64 + row;
+                    let col = center.col as i64 + col;
+                    if row >= 0 && col >= 0 && row <= u32::MAX as i64 && col <= u32::MAX as i64 {
+                        result.push(Loc::new(row as u32, col as u32));
+                    }
+                }
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{GridCell, GridSize, Loc, Random, WorldGrid};
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+
+// A temporary fitness modifier imposed from outside a world's own rules, to test how its
+// creatures respond to selection, e.g. "creatures with genome bit 3 set get +20% survival in
+// this rectangle for 100 steps". Works on any world with creatures (see GridCell::genome).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SelectionPressure {
+    pub top_left: Loc,
+    pub size: GridSize,
+    // Only creatures whose genomes have all these bits set are affected, so 0 affects all.
+    pub genome_mask: u64,
+    // From -1 to 1. A positive bonus is the odds that an affected creature that dies in a step
+    // is revived, and a negative one the odds that one that survives is removed.
+    pub survival_bonus: f64,
+    pub steps: u64,
+}
+
+impl SelectionPressure {
+    fn contains(&self, loc: Loc) -> bool {
+        (self.top_left.row..self.top_left.row + self.size.height).contains(&loc.row)
+            && (self.top_left.col..self.top_left.col + self.size.width).contains(&loc.col)
+    }
+
+    fn affects(&self, genome: u64) -> bool {
+        genome & self.genome_mask == self.genome_mask
+    }
+}
+
+impl FromStr for SelectionPressure {
+    type Err = String;
+
+    // Parses "row,col,height,width,genome_mask,survival_bonus,steps", with the mask in hex,
+    // e.g. "10,20,50,50,8,0.2,100".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid selection pressure: {}", s);
+        let fields: Vec<&str> = s.split(',').map(str::trim).collect();
+        let [row, col, height, width, genome_mask, survival_bonus, steps] = fields[..] else {
+            return Err(invalid());
+        };
+        let survival_bonus: f64 = survival_bonus.parse().map_err(|_| invalid())?;
+        if !(-1.0..=1.0).contains(&survival_bonus) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            top_left: Loc::new(
+                row.parse().map_err(|_| invalid())?,
+                col.parse().map_err(|_| invalid())?,
+            ),
+            size: GridSize::new(
+                width.parse().map_err(|_| invalid())?,
+                height.parse().map_err(|_| invalid())?,
+            ),
+            genome_mask: u64::from_str_radix(genome_mask, 16).map_err(|_| invalid())?,
+            survival_bonus,
+            steps: steps.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl fmt::Display for SelectionPressure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{:x},{},{}",
+            self.top_left.row,
+            self.top_left.col,
+            self.size.height,
+            self.size.width,
+            self.genome_mask,
+            self.survival_bonus,
+            self.steps
+        )
+    }
+}
+
+// Applies selection pressures around each of a world's updates until they expire.
+pub struct SelectionProbes {
+    // Each with its steps remaining.
+    pressures: Vec<(SelectionPressure, u64)>,
+    // The affected creatures as of before the update, and the pressure affecting each.
+    affected: Vec<(Loc, u64, usize)>,
+    rand: Random,
+}
+
+impl SelectionProbes {
+    pub fn new(rand: Random) -> Self {
+        Self {
+            pressures: vec![],
+            affected: vec![],
+            rand,
+        }
+    }
+
+    pub fn add(&mut self, pressure: SelectionPressure) {
+        self.pressures.push((pressure, pressure.steps));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pressures.is_empty()
+    }
+
+    pub fn before_update<C>(&mut self, grid: &WorldGrid<C>)
+    where
+        C: Clone + Debug + GridCell,
+    {
+        self.affected.clear();
+        for (index, (pressure, _)) in self.pressures.iter().enumerate() {
+            for row in pressure.top_left.row..pressure.top_left.row + pressure.size.height {
+                for col in pressure.top_left.col..pressure.top_left.col + pressure.size.width {
+                    let loc = Loc::new(row, col);
+                    if let Some(genome) = grid.cells.cell(loc).and_then(|cell| cell.genome())
+                        && pressure.affects(genome)
+                        // Overlapping pressures apply only the first.
+                        && !self.pressures[..index]
+                            .iter()
+                            .any(|(earlier, _)| earlier.contains(loc) && earlier.affects(genome))
+                    {
+                        self.affected.push((loc, genome, index));
+                    }
+                }
+            }
+        }
+    }
+
+    // Revives or removes the affected creatures according to their pressures, then counts
+    // down the pressures' steps. Returns the pressures that just expired.
+    pub fn after_update<C>(&mut self, grid: &mut WorldGrid<C>) -> Vec<SelectionPressure>
+    where
+        C: Clone + Debug + GridCell,
+    {
+        let mut changed = false;
+        for &(loc, genome, index) in &self.affected {
+            let survival_bonus = self.pressures[index].0.survival_bonus;
+            let Some(cell) = grid.cells.cell_mut(loc) else {
+                continue;
+            };
+            match cell.genome() {
+                None if survival_bonus > 0.0 && self.rand.next_bool(survival_bonus) => {
+                    cell.set_genome(genome);
+                    changed = true;
+                }
+                Some(next_genome)
+                    if next_genome == genome
+                        && survival_bonus < 0.0
+                        && self.rand.next_bool(-survival_bonus) =>
+                {
+                    cell.remove_creature();
+                    changed = true;
+                }
+                _ => (),
+            }
+        }
+        if changed {
+            grid.mark_all_changed();
+        }
+        self.affected.clear();
+        let mut expired = vec![];
+        self.pressures.retain_mut(|(pressure, steps_remaining)| {
+            *steps_remaining = steps_remaining.saturating_sub(1);
+            if *steps_remaining == 0 {
+                expired.push(*pressure);
+            }
+            *steps_remaining > 0
+        });
+        expired
+    }
+}
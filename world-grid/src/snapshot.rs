@@ -0,0 +1,340 @@
+use crate::{GridCell, GridError, GridSize, WorldGrid, WorldGridCells};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{self, Debug, Display, Formatter};
+
+const SNAPSHOT_MAGIC: &[u8; 4] = b"EVGS";
+const SNAPSHOT_VERSION: u16 = 1;
+// FNV-1a, which unlike std's hashers is specified to stay the same across platforms and Rust
+// versions.
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+// Compact binary encoding of a single grid cell. Each cell type chooses its own layout (of at
+// least one byte) and names it via FORMAT_NAME, so that a snapshot from one world can't be
+// loaded into another.
+pub trait CellCodec: Sized {
+    const FORMAT_NAME: &'static str;
+
+    fn encode(&self, writer: &mut SnapshotWriter);
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError>;
+}
+
+impl<C> WorldGrid<C>
+where
+    C: Clone + Debug + GridCell,
+{
+    // Layout: magic, version, cell format name, width, height, then each cell in row-major order.
+    pub fn encode_snapshot(&self) -> Vec<u8> {
+        let mut writer = SnapshotWriter::with_capacity(self.num_cells());
+        writer.write_bytes(SNAPSHOT_MAGIC);
+        writer.write_u16(SNAPSHOT_VERSION);
+        writer.write_str(C::FORMAT_NAME);
+        writer.write_u32(self.size().width);
+        writer.write_u32(self.size().height);
+        for cell in self.cells_iter() {
+            cell.encode(&mut writer);
+        }
+        writer.into_bytes()
+    }
+
+    // A hash of the grid's snapshot encoding, which is little-endian throughout, so that
+    // runs on different platforms can be compared step by step (see strict_math).
+    pub fn checksum(&self) -> u64 {
+        self.encode_snapshot()
+            .iter()
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+            })
+    }
+
+    pub fn decode_snapshot(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let mut reader = SnapshotReader::new(bytes);
+        let format_name = read_snapshot_header(&mut reader)?;
+        if format_name != C::FORMAT_NAME {
+            return Err(SnapshotError::WrongCellFormat(format_name));
+        }
+        let size = GridSize::new(reader.read_u32()?, reader.read_u32()?);
+        if size.is_empty() {
+            return Err(SnapshotError::InvalidValue);
+        }
+        // Reject sizes the remaining bytes can't possibly hold before allocating the grid.
+        if reader.remaining() < size.area() {
+            return Err(SnapshotError::Truncated);
+        }
+
+        let mut result = Self::try_new(size).map_err(SnapshotError::Grid)?;
+        for cell in result.cells.cells_iter_mut() {
+            *cell = C::decode(&mut reader)?;
+        }
+        if reader.remaining() != 0 {
+            return Err(SnapshotError::TrailingBytes);
+        }
+        Ok(result)
+    }
+
+    // Replaces the cells with those of a snapshot of a grid of the same size, keeping the
+    // grid's settings, e.g. its update schedule and pinned cells.
+    pub fn load_snapshot(&mut self, bytes: &[u8]) -> Result<(), SnapshotError> {
+        let snapshot = Self::decode_snapshot(bytes)?;
+        if snapshot.size() != self.size() {
+            return Err(SnapshotError::WrongSize(snapshot.size()));
+        }
+        self.cells = snapshot.cells;
+        self.mark_all_changed();
+        Ok(())
+    }
+
+    // Checkpoints the grid in memory, e.g. before experimenting with interactive edits, for
+    // rolling back to with restore. Unlike encode_snapshot, this copies the cells as they are,
+    // so it works for any cell type, and keeps the step count too.
+    pub fn snapshot(&self) -> GridSnapshot<C> {
+        GridSnapshot {
+            cells: self.cells.clone(),
+            step: self.step,
+        }
+    }
+
+    // Rolls the grid back to a snapshot of a grid of the same size, keeping the grid's
+    // settings as load_snapshot does. The snapshot can be restored again later.
+    pub fn restore(&mut self, snapshot: &GridSnapshot<C>) -> Result<(), SnapshotError> {
+        if snapshot.cells.size() != self.size() {
+            return Err(SnapshotError::WrongSize(snapshot.cells.size()));
+        }
+        self.cells.clone_from(&snapshot.cells);
+        self.step = snapshot.step;
+        self.mark_all_changed();
+        Ok(())
+    }
+}
+
+// A grid's cells and step count, as of WorldGrid::snapshot.
+#[derive(Clone, Debug)]
+pub struct GridSnapshot<C>
+where
+    C: Clone + GridCell,
+{
+    cells: WorldGridCells<C>,
+    step: u64,
+}
+
+impl<C> GridSnapshot<C>
+where
+    C: Clone + GridCell,
+{
+    pub fn step(&self) -> u64 {
+        self.step
+    }
+}
+
+// The cell format name (see CellCodec::FORMAT_NAME) of a snapshot, e.g. to pick the cell
+// type to decode it as.
+pub fn snapshot_format_name(bytes: &[u8]) -> Result<String, SnapshotError> {
+    read_snapshot_header(&mut SnapshotReader::new(bytes))
+}
+
+// Reads up to and including the cell format name, which it returns.
+fn read_snapshot_header(reader: &mut SnapshotReader) -> Result<String, SnapshotError> {
+    if reader.read_bytes(SNAPSHOT_MAGIC.len())? != SNAPSHOT_MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    let version = reader.read_u16()?;
+    if version != SNAPSHOT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    reader.read_str()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnapshotError {
+    BadMagic,
+    UnsupportedVersion(u16),
+    WrongCellFormat(String),
+    WrongSize(GridSize),
+    InvalidValue,
+    Truncated,
+    TrailingBytes,
+    // The snapshot's grid couldn't be made, e.g. it's over the memory budget.
+    Grid(GridError),
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadMagic => write!(f, "not a grid snapshot"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            Self::WrongCellFormat(name) => write!(f, "snapshot holds {} cells", name),
+            Self::WrongSize(size) => {
+                write!(f, "snapshot is of a {}x{} grid", size.width, size.height)
+            }
+            Self::InvalidValue => write!(f, "invalid value in snapshot"),
+            Self::Truncated => write!(f, "snapshot is truncated"),
+            Self::TrailingBytes => write!(f, "snapshot has trailing bytes"),
+            Self::Grid(err) => write!(f, "snapshot grid: {}", err),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+pub struct SnapshotWriter {
+    bytes: Vec<u8>,
+}
+
+impl SnapshotWriter {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            bytes: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_u8(value as u8);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    pub fn write_str(&mut self, value: &str) {
+        self.write_u16(value.len() as u16);
+        self.write_bytes(value.as_bytes());
+    }
+
+    pub fn write_option<T, F>(&mut self, value: &Option<T>, write_value: F)
+    where
+        F: FnOnce(&mut Self, &T),
+    {
+        self.write_bool(value.is_some());
+        if let Some(value) = value {
+            write_value(self, value);
+        }
+    }
+}
+
+pub struct SnapshotReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> SnapshotReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn unread_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.bytes.len() < len {
+            return Err(SnapshotError::Truncated);
+        }
+        let (result, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(result)
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], SnapshotError> {
+        Ok(self.read_bytes(N)?.try_into().unwrap())
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, SnapshotError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(SnapshotError::InvalidValue),
+        }
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, SnapshotError> {
+        Ok(u16::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, SnapshotError> {
+        Ok(f32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_fraction(&mut self) -> Result<f32, SnapshotError> {
+        let value = self.read_f32()?;
+        if (0.0..=1.0).contains(&value) {
+            Ok(value)
+        } else {
+            Err(SnapshotError::InvalidValue)
+        }
+    }
+
+    pub fn read_str(&mut self) -> Result<String, SnapshotError> {
+        let len = self.read_u16()? as usize;
+        String::from_utf8(self.read_bytes(len)?.to_vec()).map_err(|_| SnapshotError::InvalidValue)
+    }
+
+    pub fn read_option<T, F>(&mut self, read_value: F) -> Result<Option<T>, SnapshotError>
+    where
+        F: FnOnce(&mut Self) -> Result<T, SnapshotError>,
+    {
+        if self.read_bool()? {
+            Ok(Some(read_value(self)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
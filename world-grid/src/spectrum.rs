@@ -0,0 +1,177 @@
+use crate::npy::write_npy_f32;
+use crate::{GridCell, GridSize, WorldGrid};
+use std::f64::consts::PI;
+use std::fmt::Debug;
+use std::io::{self, Write};
+
+// The 2D power spectrum of one of the cell fields (see GridCell::field_names), for finding
+// characteristic length scales in patterns. The FFT needs power-of-two sizes, so only the
+// largest such region at the center of the grid is analyzed. The field's mean is subtracted
+// first, so that the zero frequency doesn't swamp everything else.
+#[derive(Clone, Debug)]
+pub struct PowerSpectrum {
+    // Power-of-two width and height.
+    pub size: GridSize,
+    // Row-major, shifted so that zero frequency is at (height / 2, width / 2).
+    pub power: Vec<f32>,
+}
+
+impl<C> WorldGrid<C>
+where
+    C: Clone + Debug + GridCell,
+{
+    pub fn power_spectrum(&self, field: usize) -> PowerSpectrum {
+        let grid_size = self.size();
+        let size = GridSize::new(
+            largest_power_of_two(grid_size.width),
+            largest_power_of_two(grid_size.height),
+        );
+        let (first_row, first_col) = (
+            (grid_size.height - size.height) / 2,
+            (grid_size.width - size.width) / 2,
+        );
+        let cells = self.cells.cells_iter().as_slice();
+        // Fields that don't apply to a cell are negative.
+        let values: Vec<f64> = (first_row..first_row + size.height)
+            .flat_map(|row| {
+                (first_col..first_col + size.width).map(move |col| {
+                    (cells[(row * grid_size.width + col) as usize].field_value(field) as f64)
+                        .max(0.0)
+                })
+            })
+            .collect();
+        let mean = values.iter().sum::<f64>() / values.len().max(1) as f64;
+        let mut data: Vec<(f64, f64)> = values.iter().map(|value| (value - mean, 0.0)).collect();
+        fft_2d(&mut data, size);
+
+        let (width, height) = (size.width as usize, size.height as usize);
+        let mut power = vec![0.0; data.len()];
+        for row in 0..height {
+            for col in 0..width {
+                let (re, im) = data[row * width + col];
+                let shifted_row = (row + height / 2) % height;
+                let shifted_col = (col + width / 2) % width;
+                power[shifted_row * width + shifted_col] = (re * re + im * im) as f32;
+            }
+        }
+        PowerSpectrum { size, power }
+    }
+}
+
+impl PowerSpectrum {
+    // Mean power in rings of equal spatial frequency, in steps of 1 / max(width, height)
+    // cycles per cell, starting at zero frequency.
+    pub fn radial_profile(&self) -> Vec<f32> {
+        let (width, height) = (self.size.width as usize, self.size.height as usize);
+        let max_len = width.max(height) as f64;
+        let mut sums = vec![0.0; max_len as usize / 2 + 1];
+        let mut counts = vec![0; sums.len()];
+        for row in 0..height {
+            for col in 0..width {
+                let freq_row = (row as f64 - (height / 2) as f64) / height as f64;
+                let freq_col = (col as f64 - (width / 2) as f64) / width as f64;
+                let ring = (freq_row.hypot(freq_col) * max_len).round() as usize;
+                if ring < sums.len() {
+                    sums[ring] += self.power[row * width + col] as f64;
+                    counts[ring] += 1;
+                }
+            }
+        }
+        sums.iter()
+            .zip(counts)
+            .map(|(sum, count)| (sum / count.max(1) as f64) as f32)
+            .collect()
+    }
+
+    // The wavelength, in cells, of the ring with the most power, ignoring zero frequency. None
+    // if the field is uniform.
+    pub fn peak_wavelength(&self) -> Option<f64> {
+        let profile = self.radial_profile();
+        let (ring, power) = profile
+            .iter()
+            .enumerate()
+            .skip(1)
+            .max_by(|(_, power1), (_, power2)| power1.total_cmp(power2))?;
+        (*power > 0.0).then(|| self.size.width.max(self.size.height) as f64 / ring as f64)
+    }
+
+    pub fn write_npy<W: Write>(&self, writer: W) -> io::Result<()> {
+        write_npy_f32(
+            writer,
+            &[self.size.height as usize, self.size.width as usize],
+            &self.power,
+        )
+    }
+
+    // Writes a grayscale image in binary PGM format, log scaled so that weak structure shows.
+    pub fn write_pgm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let log_power: Vec<f32> = self.power.iter().map(|power| power.ln_1p()).collect();
+        let max = log_power.iter().copied().fold(0.0, f32::max);
+        let scale = if max > 0.0 { 255.0 / max } else { 0.0 };
+        write!(
+            writer,
+            "P5\n{} {}\n255\n",
+            self.size.width, self.size.height
+        )?;
+        let pixels: Vec<u8> = log_power
+            .iter()
+            .map(|value| (value * scale).round() as u8)
+            .collect();
+        writer.write_all(&pixels)
+    }
+}
+
+fn largest_power_of_two(value: u32) -> u32 {
+    if value == 0 { 0 } else { 1 << value.ilog2() }
+}
+
+// Transforms the rows, then the columns.
+fn fft_2d(data: &mut [(f64, f64)], size: GridSize) {
+    let (width, height) = (size.width as usize, size.height as usize);
+    for row in data.chunks_exact_mut(width) {
+        fft(row);
+    }
+    let mut column = vec![(0.0, 0.0); height];
+    for col in 0..width {
+        for row in 0..height {
+            column[row] = data[row * width + col];
+        }
+        fft(&mut column);
+        for row in 0..height {
+            data[row * width + col] = column[row];
+        }
+    }
+}
+
+// In-place iterative radix-2 Cooley-Tukey FFT. The length must be a power of two.
+fn fft(data: &mut [(f64, f64)]) {
+    let len = data.len();
+    if len <= 1 {
+        return;
+    }
+    debug_assert!(len.is_power_of_two());
+
+    let bits = len.trailing_zeros();
+    for index in 0..len {
+        let reversed = index.reverse_bits() >> (usize::BITS - bits);
+        if index < reversed {
+            data.swap(index, reversed);
+        }
+    }
+
+    let mut half_len = 1;
+    while half_len < len {
+        let angle = -PI / half_len as f64;
+        for start in (0..len).step_by(half_len * 2) {
+            for offset in 0..half_len {
+                let (sin, cos) = (angle * offset as f64).sin_cos();
+                let (re, im) = data[start + offset + half_len];
+                let twiddled = (re * cos - im * sin, re * sin + im * cos);
+                let even = data[start + offset];
+                data[start + offset] = (even.0 + twiddled.0, even.1 + twiddled.1);
+                data[start + offset + half_len] = (even.0 - twiddled.0, even.1 - twiddled.1);
+            }
+        }
+        half_len *= 2;
+    }
+}
@@ -0,0 +1,95 @@
+use std::f64::consts::LN_2;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Terms of the series below, enough for full f64 precision over their reduced ranges.
+const LN_SERIES_TERMS: u32 = 12;
+const EXP_SERIES_TERMS: u32 = 20;
+
+static STRICT_MATH: AtomicBool = AtomicBool::new(false);
+
+// When set, the world updates' transcendental math (normal sampling in Random and powf here)
+// is computed from only +, -, *, /, and sqrt, which IEEE 754 requires to be correctly rounded,
+// rather than from the platform's libm, whose last bits differ between OSes. The rest of the
+// substance and FractionGene math already is: sums run in a fixed order, rows never reduce
+// across threads, and Rust never fuses a multiply and add on its own. So runs in strict math
+// mode from the same snapshot and random seed match bit for bit across platforms. Strict math
+// draws random numbers differently, so a run only matches others in the same mode.
+pub fn set_strict_math(strict_math: bool) {
+    STRICT_MATH.store(strict_math, Ordering::Relaxed);
+}
+
+pub fn strict_math() -> bool {
+    STRICT_MATH.load(Ordering::Relaxed)
+}
+
+// base.powf(exponent), computed portably in strict math mode.
+pub fn powf(base: f64, exponent: f64) -> f64 {
+    if !strict_math() {
+        return base.powf(exponent);
+    }
+    if exponent == 0.0 || base == 1.0 {
+        1.0
+    } else if base == 0.0 {
+        if exponent > 0.0 { 0.0 } else { f64::INFINITY }
+    } else {
+        exp(exponent * ln(base))
+    }
+}
+
+// The natural log, from x = m * 2^e with m within a factor of sqrt(2) of 1, and
+// ln(m) = 2 atanh((m - 1) / (m + 1)).
+pub(crate) fn ln(x: f64) -> f64 {
+    if x.is_nan() || x < 0.0 {
+        return f64::NAN;
+    }
+    if x == 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if x.is_infinite() {
+        return x;
+    }
+    let (x, extra_exponent) = if x < f64::MIN_POSITIVE {
+        // Normalizes a subnormal.
+        (x * (1u64 << 54) as f64, -54)
+    } else {
+        (x, 0)
+    };
+    let bits = x.to_bits();
+    let mut exponent = ((bits >> 52) & 0x7ff) as i64 - 1023 + extra_exponent;
+    let mut mantissa = f64::from_bits((bits & !(0x7ffu64 << 52)) | (1023u64 << 52));
+    if mantissa > std::f64::consts::SQRT_2 {
+        mantissa /= 2.0;
+        exponent += 1;
+    }
+    let s = (mantissa - 1.0) / (mantissa + 1.0);
+    let s_squared = s * s;
+    let mut term = s;
+    let mut sum = 0.0;
+    for n in 0..LN_SERIES_TERMS {
+        sum += term / (2 * n + 1) as f64;
+        term *= s_squared;
+    }
+    exponent as f64 * LN_2 + 2.0 * sum
+}
+
+// e^x, from x = k ln(2) + r with |r| <= ln(2) / 2, and e^x = 2^k e^r.
+fn exp(x: f64) -> f64 {
+    if x.is_nan() {
+        return x;
+    }
+    let k = (x / LN_2).round();
+    if k > 1023.0 {
+        return f64::INFINITY;
+    }
+    if k < -1022.0 {
+        return 0.0;
+    }
+    let r = x - k * LN_2;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for n in 1..EXP_SERIES_TERMS {
+        term *= r / n as f64;
+        sum += term;
+    }
+    sum * f64::from_bits(((k as i64 + 1023) as u64) << 52)
+}
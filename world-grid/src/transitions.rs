@@ -0,0 +1,94 @@
+use crate::{GridCell, WorldGrid};
+use std::fmt::Debug;
+
+// How many cells made each kind of state transition in one generation. A cell's state here is
+// whether it holds a creature (see GridCell::genome), which one, and the cell's color, which
+// also covers other layers such as substances without needing per-world code.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TransitionCounts {
+    pub empty_to_empty: u64,
+    pub empty_to_creature: u64,
+    pub creature_to_empty: u64,
+    pub creature_to_creature: u64,
+    // Creatures replaced in a single step by one with a different genome. Also counted in
+    // creature_to_creature.
+    pub creature_replaced: u64,
+    // Cells whose color changed, for whatever reason.
+    pub color_changed: u64,
+}
+
+impl TransitionCounts {
+    pub fn names() -> [&'static str; 6] {
+        [
+            "empty_to_empty",
+            "empty_to_creature",
+            "creature_to_empty",
+            "creature_to_creature",
+            "creature_replaced",
+            "color_changed",
+        ]
+    }
+
+    pub fn values(&self) -> [u64; 6] {
+        [
+            self.empty_to_empty,
+            self.empty_to_creature,
+            self.creature_to_empty,
+            self.creature_to_creature,
+            self.creature_replaced,
+            self.color_changed,
+        ]
+    }
+}
+
+// Counts transitions by comparing each cell's state with the previous generation's.
+pub struct TransitionCounter {
+    states: Vec<(Option<u64>, [u8; 4])>,
+}
+
+impl TransitionCounter {
+    pub fn new<C>(grid: &WorldGrid<C>) -> Self
+    where
+        C: Clone + Debug + GridCell,
+    {
+        Self {
+            states: Self::states(grid),
+        }
+    }
+
+    pub fn count<C>(&mut self, grid: &WorldGrid<C>) -> TransitionCounts
+    where
+        C: Clone + Debug + GridCell,
+    {
+        let states = Self::states(grid);
+        let mut result = TransitionCounts::default();
+        for ((previous_genome, previous_color), (genome, color)) in self.states.iter().zip(&states)
+        {
+            match (previous_genome, genome) {
+                (None, None) => result.empty_to_empty += 1,
+                (None, Some(_)) => result.empty_to_creature += 1,
+                (Some(_), None) => result.creature_to_empty += 1,
+                (Some(previous_genome), Some(genome)) => {
+                    result.creature_to_creature += 1;
+                    if previous_genome != genome {
+                        result.creature_replaced += 1;
+                    }
+                }
+            }
+            if previous_color != color {
+                result.color_changed += 1;
+            }
+        }
+        self.states = states;
+        result
+    }
+
+    fn states<C>(grid: &WorldGrid<C>) -> Vec<(Option<u64>, [u8; 4])>
+    where
+        C: Clone + Debug + GridCell,
+    {
+        grid.cells_iter()
+            .map(|cell| (cell.genome(), cell.color_rgba()))
+            .collect()
+    }
+}
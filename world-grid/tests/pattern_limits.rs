@@ -0,0 +1,50 @@
+// Checks that pattern files that are short but describe huge patterns are rejected, or loaded
+// without allocating for their empty space, rather than exhausting memory.
+
+use world_grid::Pattern;
+
+// Just under the most cells a loaded pattern may have, 1 << 26.
+const HUGE_SIDE: usize = 67_108_000;
+
+#[test]
+fn rle_runs_of_empty_rows_take_no_memory() {
+    let pattern = Pattern::parse_rle(&format!("{}$!", HUGE_SIDE)).unwrap();
+    assert_eq!(pattern.size().height as usize, HUGE_SIDE + 1);
+    assert_eq!(pattern.num_live(), 0);
+}
+
+#[test]
+fn rle_header_of_empty_rows_takes_no_memory() {
+    let pattern = Pattern::parse_rle(&format!("x = 0, y = {}\n!", HUGE_SIDE)).unwrap();
+    assert_eq!(pattern.size().height as usize, HUGE_SIDE);
+}
+
+#[test]
+fn rle_too_large_is_rejected() {
+    assert!(Pattern::parse_rle(&format!("2o{}$o!", HUGE_SIDE)).is_err());
+    assert!(Pattern::parse_rle("x = 100000, y = 100000\n!").is_err());
+}
+
+// Each level's node repeats the one below it in its nw and se quadrants, doubling its live
+// cells and the side of the rectangle bounding them, so a few dozen lines describe 2^25 live
+// cells spread across a square 2^28 cells on a side.
+#[test]
+fn macrocell_too_large_is_rejected_before_expanding() {
+    let mut text = String::from("[M2] (evo-grid)\n*$\n");
+    for level in 4..=28 {
+        let child = level - 3;
+        text.push_str(&format!("{} {} 0 0 {}\n", level, child, child));
+    }
+    let err = Pattern::parse_macrocell(&text).unwrap_err();
+    assert!(err.contains("too large"), "{}", err);
+}
+
+#[test]
+fn macrocell_within_bounds_loads_trimmed() {
+    let text = "[M2] (evo-grid)\n*$\n4 1 0 0 1\n";
+    let pattern = Pattern::parse_macrocell(text).unwrap();
+    assert_eq!(pattern.size().width, 9);
+    assert_eq!(pattern.size().height, 9);
+    assert_eq!(pattern.num_live(), 2);
+    assert!(pattern.is_live(0, 0) && pattern.is_live(8, 8));
+}
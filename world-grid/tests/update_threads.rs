@@ -0,0 +1,115 @@
+#![cfg(feature = "parallel")]
+
+// Checks the determinism contract for updates (see Random): a seeded grid comes out the same
+// after a run of updates however many threads they run on, under each update schedule.
+
+use rayon::ThreadPoolBuilder;
+use world_grid::{
+    CellCodec, GridCell, GridSize, Loc, Neighborhood, Random, SnapshotError, SnapshotReader,
+    SnapshotWriter, UpdateSchedule, WorldGrid,
+};
+
+const SEED: u64 = 0x5eed;
+const STEPS: usize = 25;
+const THREAD_COUNTS: [usize; 3] = [2, 4, 8];
+
+// A cell whose update mixes its neighbors' values with a draw from its row's random number
+// stream, so that any dependence on which thread updates which row shows up in the checksum.
+#[derive(Clone, Copy, Debug, Default)]
+struct MixingCell {
+    value: u8,
+}
+
+impl CellCodec for MixingCell {
+    const FORMAT_NAME: &'static str = "mixing";
+
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write_u8(self.value);
+    }
+
+    fn decode(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        Ok(Self {
+            value: reader.read_u8()?,
+        })
+    }
+}
+
+impl GridCell for MixingCell {
+    fn color_rgba(&self) -> [u8; 4] {
+        [self.value, self.value, self.value, 0xff]
+    }
+
+    fn clear(&mut self) {
+        self.value = 0;
+    }
+
+    fn update(
+        &self,
+        neighborhood: &Neighborhood<Self>,
+        next_cell: &mut Self,
+        rand: &mut Option<Random>,
+    ) {
+        let sum = neighborhood
+            .neighbors()
+            .fold(self.value, |sum, cell| sum.wrapping_add(cell.value));
+        let noise = rand
+            .as_mut()
+            .map_or(0, |rand| rand.next_in_range(0..=u8::MAX));
+        next_cell.value = sum.wrapping_add(noise);
+    }
+
+    fn debug_print(&self, row: u32, col: u32) {
+        println!("({}, {}): {}", row, col, self.value);
+    }
+}
+
+fn checksum_after_updates(num_threads: usize, update_schedule: UpdateSchedule) -> u64 {
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build()
+        .unwrap();
+    pool.install(|| {
+        let size = GridSize::new(37, 29);
+        let mut rand = Some(Random::from_seed(SEED));
+        let mut grid = WorldGrid::<MixingCell>::new(size);
+        grid.set_update_schedule(update_schedule);
+        for row in 0..size.height {
+            for col in 0..size.width {
+                let value = rand.as_mut().unwrap().next_in_range(0..=u8::MAX);
+                grid.cell_mut(Loc::new(row, col)).unwrap().value = value;
+            }
+        }
+        for _ in 0..STEPS {
+            grid.update(&mut rand, |_grid| {});
+        }
+        grid.checksum()
+    })
+}
+
+fn assert_same_for_all_thread_counts(update_schedule: UpdateSchedule) {
+    let expected = checksum_after_updates(1, update_schedule);
+    for num_threads in THREAD_COUNTS {
+        assert_eq!(
+            checksum_after_updates(num_threads, update_schedule),
+            expected,
+            "{:?} updates on {} threads",
+            update_schedule,
+            num_threads
+        );
+    }
+}
+
+#[test]
+fn synchronous_updates_match_across_thread_counts() {
+    assert_same_for_all_thread_counts(UpdateSchedule::Synchronous);
+}
+
+#[test]
+fn probabilistic_updates_match_across_thread_counts() {
+    assert_same_for_all_thread_counts(UpdateSchedule::Probabilistic(0.5));
+}
+
+#[test]
+fn random_sequential_updates_match_across_thread_counts() {
+    assert_same_for_all_thread_counts(UpdateSchedule::RandomSequential);
+}